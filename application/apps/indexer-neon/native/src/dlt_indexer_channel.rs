@@ -77,6 +77,7 @@ fn index_dlt_file_with_progress(
         &tx,
         shutdown_receiver,
         fibex_metadata,
+        false,
     ) {
         Err(why) => {
             error!("create_index_and_mapping_dlt: couldn't process: {}", why);
@@ -5,14 +5,27 @@ use indexer_base::chunks::ChunkResults;
 use indexer_base::config::SectionConfig;
 use indexer_base::export::export_file_line_based;
 use neon::prelude::*;
+use serde::Deserialize;
 use std::path;
 use std::sync::{Arc, Mutex};
 use std::thread;
 
 static DLT_SESSION_ID: &str = "session";
 static DLT_SOURCE_FILE: &str = "file";
+/// a dlt file that is exported as pcapng (one udp packet per message)
+static DLT_SOURCE_FILE_AS_PCAPNG: &str = "file_pcapng";
 static LINE_BASED_SOURCE_FILE: &str = "lines";
 
+/// the export parameters that are not part of the `SectionConfig`
+#[derive(Deserialize, Debug, Default)]
+struct ExportOptions {
+    #[serde(default)]
+    pcap: Option<dlt::dlt_pcap::PcapExportOptions>,
+    /// the session was indexed with record grouping
+    #[serde(default)]
+    grouped_records: bool,
+}
+
 pub struct ExporterEventEmitter {
     pub event_receiver: Arc<Mutex<cc::Receiver<ChunkResults>>>,
     pub shutdown_sender: async_std::sync::Sender<()>,
@@ -27,6 +40,8 @@ impl ExporterEventEmitter {
         destination_path: path::PathBuf,
         sections_config: SectionConfig,
         was_session_file: bool,
+        pcap_options: Option<dlt::dlt_pcap::PcapExportOptions>,
+        grouped_records: bool,
         shutdown_rx: async_std::sync::Receiver<()>,
         chunk_result_sender: cc::Sender<ChunkResults>,
     ) -> Result<(), Error> {
@@ -68,6 +83,25 @@ impl ExporterEventEmitter {
                 debug!("back after DLT export finished!");
             }));
             Ok(())
+        } else if source_type == DLT_SOURCE_FILE_AS_PCAPNG {
+            let dlt_file_path = path::PathBuf::from(source);
+            let pcap_config = pcap_options.unwrap_or_default().to_config()?;
+
+            self.task_thread = Some(thread::spawn(move || {
+                match dlt::dlt_pcap::export_as_pcapng(
+                    &dlt_file_path,
+                    &destination_path,
+                    sections_config,
+                    &pcap_config,
+                    chunk_result_sender,
+                    None,
+                ) {
+                    Ok(_) => {}
+                    Err(e) => warn!("error exporting dlt messages as pcapng: {}", e),
+                }
+                debug!("back after pcapng export finished!");
+            }));
+            Ok(())
         } else if source_type == LINE_BASED_SOURCE_FILE {
             let file_path = path::PathBuf::from(source);
 
@@ -77,6 +111,7 @@ impl ExporterEventEmitter {
                     destination_path,
                     sections_config,
                     was_session_file,
+                    grouped_records,
                     chunk_result_sender,
                 ) {
                     Ok(_) => {}
@@ -105,6 +140,7 @@ declare_types! {
             i += 1;
             let arg_sections_conf = cx.argument::<JsValue>(i)?;
             let sections_conf: SectionConfig = neon_serde::from_value(&mut cx, arg_sections_conf)?;
+            let export_options: ExportOptions = neon_serde::from_value(&mut cx, arg_sections_conf)?;
             i += 1;
             let was_session_file = cx.argument::<JsBoolean>(i)?.value();
 
@@ -122,11 +158,13 @@ declare_types! {
                 destination_path,
                 sections_conf,
                 was_session_file,
+                export_options.pcap,
+                export_options.grouped_records,
                 shutdown_channel.1,
                 tx,
             ) {
                 Ok(()) => Ok(emitter),
-                Err(_) => Err(neon::result::Throw)
+                Err(e) => cx.throw_error(format!("{}", e)),
             }
         }
 
@@ -4,6 +4,7 @@ use indexer_base::chunks::ChunkResults;
 use indexer_base::config::IndexingConfig;
 use indexer_base::progress::{Notification, Severity};
 use neon::prelude::*;
+use processor::grouping::RecordStart;
 use std::path;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -20,6 +21,7 @@ impl IndexingEventEmitter {
         chunk_result_sender: cc::Sender<ChunkResults>,
         append: bool,
         chunk_size: usize,
+        record_start: Option<RecordStart>,
         thread_conf: IndexingThreadConfig,
     ) {
         info!("call event_thread with chunk size: {}", chunk_size);
@@ -35,6 +37,7 @@ impl IndexingEventEmitter {
                     append,
                 },
                 thread_conf.timestamps,
+                record_start,
                 chunk_result_sender.clone(),
                 Some(shutdown_rx),
             );
@@ -46,6 +49,7 @@ impl IndexingEventEmitter {
 fn index_file_with_progress(
     config: IndexingConfig,
     timestamps: bool,
+    record_start: Option<RecordStart>,
     tx: cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) {
@@ -66,6 +70,9 @@ fn index_file_with_progress(
         config,
         source_file_size,
         timestamps,
+        record_start,
+        None,
+        None,
         tx.clone(),
         shutdown_receiver,
     ) {
@@ -91,6 +98,11 @@ declare_types! {
             let append: bool = cx.argument::<JsBoolean>(3)?.value();
             let timestamps: bool = cx.argument::<JsBoolean>(4)?.value();
             let chunk_size: usize = cx.argument::<JsNumber>(5)?.value() as usize;
+            // lines that start a new record ("timestamp" or { pattern: REGEX }), no grouping if missing
+            let record_start: Option<RecordStart> = match cx.argument_opt(6) {
+                Some(arg) => neon_serde::from_value(&mut cx, arg)?,
+                None => None,
+            };
             let (shutdown_sender, shutdown_receiver) = cc::unbounded();
 
             let file_path = path::PathBuf::from(file);
@@ -104,6 +116,7 @@ declare_types! {
                 chunk_result_sender,
                 append,
                 chunk_size,
+                record_start,
                 IndexingThreadConfig {
                     in_file: file_path,
                     out_path,
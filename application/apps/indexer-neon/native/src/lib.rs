@@ -21,6 +21,7 @@ mod fibex_utils;
 mod indexer_channel;
 mod logging;
 mod merger_channel;
+mod text_stats_channel;
 mod timestamp_detector_channel;
 use crate::dlt_pcap_channel::JsDltPcapEventEmitter;
 use concatenator_channel::JsConcatenatorEmitter;
@@ -30,6 +31,7 @@ use dlt_socket_channel::JsDltSocketEventEmitter;
 
 use dlt_stats_channel::JsDltStatsEventEmitter;
 use export_channel::JsExporterEventEmitter;
+use indexer_base::archive::{list_archive, ArchiveEntry};
 use indexer_base::progress::{IndexingProgress, IndexingResults, Notification, Severity};
 use indexer_channel::JsIndexerEventEmitter;
 use log::LevelFilter;
@@ -39,6 +41,7 @@ use log4rs::encode::pattern::PatternEncoder;
 use merger_channel::JsMergerEmitter;
 use neon::prelude::*;
 use processor::parse::{self, timespan_in_files, DiscoverItem, TimestampFormatResult};
+use text_stats_channel::JsTextStatsEventEmitter;
 use timestamp_detector_channel::JsTimestampFormatDetectionEmitter;
 
 #[no_mangle]
@@ -101,7 +104,8 @@ pub fn init_logging() -> Result<(), std::io::Error> {
 fn detect_timestamp_in_string(mut cx: FunctionContext) -> JsResult<JsNumber> {
     let input: String = cx.argument::<JsString>(0)?.value();
     match parse::detect_timestamp_in_string(input.as_str(), None) {
-        Ok((timestamp, _, _)) => Ok(cx.number((timestamp) as f64)),
+        // milliseconds, the sub-millisecond part as fraction
+        Ok((timestamp, _, _)) => Ok(cx.number(timestamp as f64 / 1_000_000.0)),
         Err(e) => cx.throw_type_error(format!("{}", e)),
     }
 }
@@ -113,12 +117,17 @@ fn detect_timestamp_format_in_file(mut cx: FunctionContext) -> JsResult<JsValue>
     ) = cc::unbounded();
     let items: Vec<DiscoverItem> = vec![DiscoverItem {
         path: file_name.clone(),
+        encoding: None,
+        regex: None,
     }];
     let err_timestamp_result = TimestampFormatResult {
         path: file_name,
         format: None,
+        regex: None,
         min_time: None,
         max_time: None,
+        encoding: None,
+        discovery: None,
     };
     let js_err_value = neon_serde::to_value(&mut cx, &err_timestamp_result)?;
     match timespan_in_files(items, &tx) {
@@ -162,6 +171,36 @@ fn detect_timestamp_format_in_file(mut cx: FunctionContext) -> JsResult<JsValue>
     }
 }
 
+/// an archive entry with the path that opens it like a normal file
+#[derive(serde::Serialize)]
+struct ListedArchiveEntry {
+    #[serde(flatten)]
+    entry: ArchiveEntry,
+    source: String,
+}
+
+/// lists the files of a tar (also compressed) or zip archive
+///
+/// # Arguments
+///
+/// * `archive` - path of the archive
+fn list_archive_entries(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let archive_path = std::path::PathBuf::from(cx.argument::<JsString>(0)?.value());
+    match list_archive(&archive_path) {
+        Ok(entries) => {
+            let listed: Vec<ListedArchiveEntry> = entries
+                .into_iter()
+                .map(|entry| ListedArchiveEntry {
+                    source: entry.source_path(&archive_path).to_string_lossy().to_string(),
+                    entry,
+                })
+                .collect();
+            Ok(neon_serde::to_value(&mut cx, &listed)?)
+        }
+        Err(e) => cx.throw_error(format!("could not list {:?}: {}", archive_path, e)),
+    }
+}
+
 register_module!(mut cx, {
     init_logging().expect("logging has to be cofigured");
     // handle_discover_subcommand
@@ -170,10 +209,12 @@ register_module!(mut cx, {
         "detectTimestampFormatInFile",
         detect_timestamp_format_in_file,
     )?;
+    cx.export_function("listArchive", list_archive_entries)?;
     cx.export_class::<JsIndexerEventEmitter>("RustIndexerEventEmitter")?;
     cx.export_class::<JsDltIndexerEventEmitter>("RustDltIndexerEventEmitter")?;
     cx.export_class::<JsDltPcapEventEmitter>("RustDltPcapEventEmitter")?;
     cx.export_class::<JsDltStatsEventEmitter>("RustDltStatsEventEmitter")?;
+    cx.export_class::<JsTextStatsEventEmitter>("RustTextStatsEventEmitter")?;
     cx.export_class::<JsDltSocketEventEmitter>("RustDltSocketEventEmitter")?;
     cx.export_class::<JsTimestampFormatDetectionEmitter>("RustTimestampFormatDetectionEmitter")?;
     cx.export_class::<JsConcatenatorEmitter>("RustConcatenatorEmitter")?;
@@ -107,6 +107,10 @@ pub class JsMergerEmitter for MergerEmitter {
                 year: i.year,
                 tag: i.tag,
                 format: i.format,
+                regex: i.regex,
+                json: i.json,
+                records: i.records,
+                encoding: i.encoding,
             }
         }).collect();
         emitter.start_merging_in_thread(
@@ -0,0 +1,115 @@
+use crate::channels::EventEmitterTask;
+use crossbeam_channel as cc;
+use indexer_base::progress::{Notification, Severity};
+use neon::prelude::*;
+use processor::stats::{TextStatisticsResults, TextStatsConfig};
+use std::path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub struct TextStatsEventEmitter {
+    pub event_receiver: Arc<Mutex<cc::Receiver<TextStatisticsResults>>>,
+    pub shutdown_sender: cc::Sender<()>,
+    pub task_thread: Option<std::thread::JoinHandle<()>>,
+}
+impl TextStatsEventEmitter {
+    pub fn start_text_stats_in_thread(
+        self: &mut TextStatsEventEmitter,
+        source_file: path::PathBuf,
+        config: TextStatsConfig,
+        shutdown_rx: cc::Receiver<()>,
+        chunk_result_sender: cc::Sender<TextStatisticsResults>,
+    ) {
+        // Spawn a thread to continue running after this method has returned.
+        self.task_thread = Some(thread::spawn(move || {
+            text_stats_with_progress(
+                source_file,
+                config,
+                chunk_result_sender.clone(),
+                Some(shutdown_rx),
+            );
+            debug!("back after collecting text statistics finished!",);
+        }));
+    }
+}
+
+fn text_stats_with_progress(
+    source_file: path::PathBuf,
+    config: TextStatsConfig,
+    tx: cc::Sender<TextStatisticsResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) {
+    trace!("calling text stats with progress");
+    match processor::stats::get_text_file_info(&source_file, &config, &tx, shutdown_receiver) {
+        Err(why) => {
+            error!("couldn't collect statistics: {}", why);
+            match tx.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: format!("couldn't collect statistics: {}", why),
+                line: None,
+            })) {
+                Ok(()) => (),
+                Err(_) => warn!("could not communicate errors to js"),
+            }
+        }
+        Ok(_) => trace!("get_text_file_info returned ok"),
+    }
+}
+
+// interface of the Rust code for js, exposes the `poll` and `shutdown` methods
+declare_types! {
+    pub class JsTextStatsEventEmitter for TextStatsEventEmitter {
+        init(mut cx) {
+            trace!("Rust: JsTextStatsEventEmitter");
+            let file_name = cx.argument::<JsString>(0)?.value();
+            let file_path = path::PathBuf::from(file_name);
+            let arg_config = cx.argument::<JsValue>(1)?;
+            let config: TextStatsConfig = neon_serde::from_value(&mut cx, arg_config)?;
+            let chunk_result_channel: (cc::Sender<TextStatisticsResults>, cc::Receiver<TextStatisticsResults>) = cc::unbounded();
+            let shutdown_channel = cc::unbounded();
+            let mut emitter = TextStatsEventEmitter {
+                event_receiver: Arc::new(Mutex::new(chunk_result_channel.1)),
+                shutdown_sender: shutdown_channel.0,
+                task_thread: None,
+            };
+            emitter.start_text_stats_in_thread(
+                file_path,
+                config,
+                shutdown_channel.1,
+                chunk_result_channel.0
+            );
+            Ok(emitter)
+        }
+
+        // will be called by JS to receive data in a loop, but care should be taken to only call it once at a time.
+        method poll(mut cx) {
+            // The callback to be executed when data is available
+            let cb = cx.argument::<JsFunction>(0)?;
+            let this = cx.this();
+
+            // Create an asynchronously `EventEmitterTask` to receive data
+            let events = cx.borrow(&this, |emitter| Arc::clone(&emitter.event_receiver));
+            let emitter = EventEmitterTask::new(events);
+
+            // Schedule the task on the `libuv` thread pool
+            emitter.schedule(cb);
+            Ok(JsUndefined::new().upcast())
+        }
+
+        // The shutdown method may be called to stop the Rust thread. It
+        // will error if the thread has already been destroyed.
+        method shutdown(mut cx) {
+            trace!("shutdown called");
+            let this = cx.this();
+
+            // Unwrap the shutdown channel and send a shutdown command
+            cx.borrow(&this, |emitter| {
+                match emitter.shutdown_sender.send(()) {
+                    Err(e) => trace!("error happened when sending: {}", e),
+                    Ok(()) => trace!("sent command Shutdown")
+                }
+            });
+            Ok(JsUndefined::new().upcast())
+        }
+    }
+}
@@ -41,17 +41,35 @@ export interface IDltSocketParams {
 	statusUpdates: boolean;
 }
 
-export interface ISocketConfig {
+export interface IUdpEndpoint {
 	multicast_addr?: IMulticastInfo;
 	bind_addr: string;
 	port: string;
 }
+/// every line of a live session ends with a column that names the endpoint
+/// (`<address>:<port>`) the message was received on
+export interface ISocketConfig {
+	endpoints: IUdpEndpoint[];
+	reorder_window_ms?: number;
+	ring_buffer?: IRingBufferConfig;
+}
+/// limits the disk space of a long running capture, segments are listed in the
+/// manifest next to the session file (`<session file>.segments.json`)
+/// the lines of dropped segments are removed from the start of the session file,
+/// a notification tells by how many lines the line numbers are offset then
+export interface IRingBufferConfig {
+	max_segment_bytes?: number;
+	max_segment_duration_s?: number;
+	max_segments: number;
+	archive_dir?: string;
+}
 /// Multicast config information.
 /// `multiaddr` address must be a valid multicast address
 /// `interface` is the address of the local interface with which the
 /// system should join the
 /// multicast group. If it's equal to `INADDR_ANY` then an appropriate
 /// interface is chosen by the system.
+/// For IPv6 groups `interface` is the index of the interface.
 export interface IMulticastInfo {
 	multiaddr: string;
 	interface?: string;
@@ -130,7 +148,7 @@ export type TDltFileAsyncEventObject = TDltFileAsyncEventProgress;
 
 export function exportDltFile(
 	source: string,
-	sourceType: 'session' | 'file',
+	sourceType: 'session' | 'file' | 'file_pcapng',
 	targetFile: string,
 	params: IFileSaveParams
 ): CancelablePromise<void, void, TDltFileAsyncEvents, TDltFileAsyncEventObject> {
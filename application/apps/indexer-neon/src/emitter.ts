@@ -8,6 +8,7 @@ const {
     RustTimestampFormatDetectionEmitter: RustTimestampChannel,
     RustConcatenatorEmitter: RustConcatenatorChannel,
     RustMergerEmitter: RustMergerChannel,
+    RustTextStatsEventEmitter: RustTextStatsChannel,
 } = require("../../../../native/index.node");
 const { EventEmitter } = require("events");
 export {
@@ -21,6 +22,7 @@ export {
     RustTimestampChannel,
     RustConcatenatorChannel,
     RustMergerChannel,
+    RustTextStatsChannel,
 };
 const { promisify } = require("util");
 import { log } from "./logging";
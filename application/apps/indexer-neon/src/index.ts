@@ -18,7 +18,9 @@ export default {
 	indexAsync: Processor.indexAsync,
 	detectTimestampInString: Processor.detectTimestampInString,
 	detectTimestampFormatInFile: Processor.detectTimestampFormatInFile,
+	listArchive: Processor.listArchive,
 	discoverTimespanAsync: Processor.discoverTimespanAsync,
+	textStatsAsync: Processor.textStatsAsync,
 	exportLineBased: Processor.exportLineBased,
 	// Merging
 	mergeFilesAsync: Merge.mergeFilesAsync,
@@ -7,9 +7,17 @@ import {
 	INeonNotification,
 	ITimestampFormatResult,
 	IDiscoverItem,
-	IChunk
+	IChunk,
+	ITextStatsConfig,
+	ITextStatisticInfo
 } from './progress';
-import { NativeEventEmitter, RustIndexerChannel, RustTimestampChannel, RustExportFileChannel } from './emitter';
+import {
+	NativeEventEmitter,
+	RustIndexerChannel,
+	RustTimestampChannel,
+	RustExportFileChannel,
+	RustTextStatsChannel
+} from './emitter';
 import { TimeUnit } from './units';
 import { CancelablePromise } from './promise';
 import { IFileSaveParams } from '../../../common/interfaces/index';
@@ -28,15 +36,19 @@ export interface IFilePath {
 	path: string;
 }
 
+// lines that start a new record, all following lines belong to the record (e.g. stack traces)
+export type TRecordStart = 'timestamp' | { pattern: string };
 export interface IIndexOptions {
 	chunkSize?: number;
 	append?: boolean;
 	timestamps?: boolean;
+	recordStart?: TRecordStart;
 }
 export interface IIndexOptionsChecked {
 	chunkSize: number;
 	append: boolean;
 	timestamps: boolean;
+	recordStart?: TRecordStart;
 }
 
 export type TFileAsyncEvents = 'progress' | 'notification';
@@ -178,6 +190,67 @@ export function discoverTimespanAsync(
 	});
 }
 
+export type TTextStatsEvents = 'stats' | 'progress' | 'notification';
+export type TTextStatsEventStats = (event: ITextStatisticInfo) => void;
+export type TTextStatsEventProgress = (event: ITicks) => void;
+export type TTextStatsEventNotification = (event: INeonNotification) => void;
+export type TTextStatsEventObject = TTextStatsEventStats | TTextStatsEventProgress | TTextStatsEventNotification;
+
+export function textStatsAsync(
+	file: string,
+	config?: ITextStatsConfig
+): CancelablePromise<void, void, TTextStatsEvents, TTextStatsEventObject> {
+	return new CancelablePromise<
+		void,
+		void,
+		TTextStatsEvents,
+		TTextStatsEventObject
+	>((resolve, reject, cancel, refCancelCB, self) => {
+		try {
+			// Add cancel callback
+			refCancelCB(() => {
+				// Cancelation is started, but not canceled
+				log(`Get command "break" operation. Starting breaking.`);
+				emitter.requestShutdown();
+			});
+			const channel = new RustTextStatsChannel(file, config !== undefined ? config : {});
+			const emitter = new NativeEventEmitter(channel);
+			let total: number = 1;
+			emitter.on(NativeEventEmitter.EVENTS.GotItem, (chunk: ITextStatisticInfo) => {
+				self.emit('stats', chunk);
+			});
+			emitter.on(NativeEventEmitter.EVENTS.Progress, (ticks: ITicks) => {
+				total = ticks.total;
+				self.emit('progress', ticks);
+			});
+			emitter.on(NativeEventEmitter.EVENTS.Stopped, () => {
+				emitter.shutdownAcknowledged(() => {
+					cancel();
+				});
+			});
+			emitter.on(NativeEventEmitter.EVENTS.Notification, (notification: INeonNotification) => {
+				self.emit('notification', notification);
+			});
+			emitter.on(NativeEventEmitter.EVENTS.Finished, () => {
+				emitter.shutdownAcknowledged(() => {
+					self.emit('progress', { ellapsed: total, total });
+					resolve();
+				});
+			});
+		} catch (err) {
+			if (!(err instanceof Error)) {
+				log(`operation is stopped. Error isn't valid:`);
+				log(err);
+				err = new Error(`operation is stopped. Error isn't valid.`);
+			} else {
+				log(`operation is stopped due error: ${err.message}`);
+			}
+			// Operation is rejected
+			reject(err);
+		}
+	});
+}
+
 export type TIndexAsyncEvents = 'chunk' | 'progress' | 'notification';
 export type TIndexAsyncEventChunk = (event: IChunk) => void;
 export type TIndexAsyncEventProgress = (event: ITicks) => void;
@@ -211,7 +284,8 @@ export function indexAsync(
 				outPath,
 				opt.append,
 				opt.timestamps,
-				opt.chunkSize
+				opt.chunkSize,
+				opt.recordStart
 			);
 			const emitter = new NativeEventEmitter(channel);
 			let totalTicks = 1;
@@ -271,6 +345,25 @@ export function detectTimestampFormatInFile(input: string): string {
 	return getNativeModule().detectTimestampFormatInFile(input);
 }
 
+// a file inside of a tar or zip archive
+export interface IArchiveEntry {
+	// path inside of the archive
+	name: string;
+	// size after the archive is unpacked
+	size: number;
+	// size inside of the archive (differs for compressed zip entries)
+	stored_size: number;
+	compression: 'none' | 'gzip' | 'zstd' | 'xz' | 'bzip2' | 'deflate';
+	file_type: 'dlt' | 'pcap' | 'pcapng' | 'text' | 'archive' | 'binary';
+	// opens the entry like a normal file (e.g. as file of indexAsync or indexDltAsync)
+	source: string;
+}
+
+// lists the files of a tar (also compressed) or zip archive, throws if the file is no archive
+export function listArchive(archive: string): Array<IArchiveEntry> {
+	return getNativeModule().listArchive(archive);
+}
+
 function getDefaultProcessorOptions(options: IIndexOptions | undefined): IIndexOptionsChecked {
 	if (typeof options !== 'object' || options === null) {
 		options = {};
@@ -22,6 +22,48 @@ export interface ITimestampFormatResult {
     format?: string,
     minTime?: string,
     maxTime?: string,
+    discovery?: IFormatDiscovery,
+}
+export interface IDiscoveryCandidate {
+    value: string,
+    confidence: number,
+}
+export interface IFormatDiscovery {
+    kind: "dlt" | "dlt_stream" | "pcap" | "pcap_ng" | "json_lines" | "syslog" | "logcat" | "text" | "archive" | "binary",
+    confidence: number,
+    compression: string,
+    timestamp_formats: Array<IDiscoveryCandidate>,
+    level_fields: Array<IDiscoveryCandidate>,
+    record_grouping?: IDiscoveryCandidate,
+}
+export interface ITextStatsConfig {
+    level_pattern?: string,
+    source_pattern?: string,
+    format?: string,
+    regex?: string,
+    encoding?: string,
+    top_sources?: number,
+}
+export interface ITextLevelDistribution {
+    no_level: number,
+    log_fatal: number,
+    log_error: number,
+    log_warning: number,
+    log_info: number,
+    log_debug: number,
+    log_verbose: number,
+}
+export interface ITextStatisticInfo {
+    lines: number,
+    empty_lines: number,
+    lines_with_timestamp: number,
+    format?: string,
+    min_time?: string,
+    max_time?: string,
+    encoding: string,
+    levels: ITextLevelDistribution,
+    source_count: number,
+    top_sources: Array<[string, ITextLevelDistribution]>,
 }
 export interface IConcatenatorResult {
     file_cnt: number,
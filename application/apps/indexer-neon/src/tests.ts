@@ -588,9 +588,13 @@ export function testSocketDlt(outPath: string) {
 			interface: undefined
 		};
 		const sockConf: ISocketConfig = {
-			multicast_addr: multicastInfo,
-			bind_addr: '0.0.0.0',
-			port: '8888'
+			endpoints: [
+				{
+					multicast_addr: multicastInfo,
+					bind_addr: '0.0.0.0',
+					port: '8888'
+				}
+			]
 		};
 		const session_id = `dlt_${new Date().toISOString()}`;
 		const promise = dltOverSocket(
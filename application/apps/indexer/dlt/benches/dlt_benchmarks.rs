@@ -3,7 +3,7 @@ extern crate criterion;
 extern crate dlt;
 
 // use dlt::dlt::*;
-use bytes::{BytesMut};
+use bytes::BytesMut;
 use criterion::Criterion;
 
 fn dlt_benchmark(c: &mut Criterion) {
@@ -73,5 +73,36 @@ fn dlt_parse_benchmark(c: &mut Criterion) {
     });
 }
 
+fn dlt_generated_benchmark(c: &mut Criterion) {
+    let mut generator =
+        dlt::dlt_generator::DltGenerator::new(dlt::dlt_generator::DltGeneratorConfig {
+            target_size: 1024 * 1024,
+            ..Default::default()
+        })
+        .expect("could not create generator");
+    let mut content: Vec<u8> = vec![];
+    generator
+        .generate(&mut content)
+        .expect("could not generate messages");
+    c.bench_function("parse 1 MB of generated messages", move |b| {
+        b.iter(|| {
+            let mut input = &content[..];
+            let mut index = 0;
+            while let Ok((rest, _)) =
+                dlt::dlt_parse::dlt_message(input, None, index, None, None, true)
+            {
+                input = rest;
+                index += 1;
+            }
+            index
+        })
+    });
+}
+
+criterion_group! {
+    name = generated;
+    config = Criterion::default().sample_size(10);
+    targets = dlt_generated_benchmark
+}
 criterion_group!(benches, dlt_benchmark, dlt_parse_benchmark);
-criterion_main!(benches);
+criterion_main!(benches, generated);
@@ -52,9 +52,13 @@ impl DltTimeStamp {
     pub fn from_us(us: u64) -> Self {
         DltTimeStamp {
             seconds: (us / (1000 * 1000)) as u32,
-            microseconds: (us % (1000 * 1000)) as u32 * 1000 * 1000,
+            microseconds: (us % (1000 * 1000)) as u32,
         }
     }
+    /// the storage header has a resolution of microseconds
+    pub fn from_ns(ns: u64) -> Self {
+        DltTimeStamp::from_us(ns / 1000)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Arbitrary, Serialize)]
@@ -1265,10 +1269,37 @@ pub struct Message {
     #[serde(skip_serializing)]
     pub fibex_metadata: Option<Rc<FibexMetadata>>,
 }
-pub const DLT_COLUMN_SENTINAL: char = '\u{0004}';
+pub use indexer_base::utils::COLUMN_SENTINAL as DLT_COLUMN_SENTINAL;
 pub const DLT_ARGUMENT_SENTINAL: char = '\u{0005}';
 pub const DLT_NEWLINE_SENTINAL_SLICE: &[u8] = &[0x6];
 
+/// the columns of a message as they are shown in the ui
+/// (see `Message::displayed_fields`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageFields {
+    /// time of the storage header
+    pub time: String,
+    pub ecu: String,
+    pub app: String,
+    pub context: String,
+    /// log level or message type
+    pub message_type: String,
+    /// the arguments of the payload, resolved with the fibex for non-verbose messages
+    pub arguments: Vec<String>,
+}
+
+impl MessageFields {
+    /// the arguments separated by spaces
+    pub fn payload(&self) -> String {
+        self.arguments
+            .iter()
+            .map(|a| a.trim())
+            .filter(|a| !a.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ExtendedHeaderConfig {
     pub message_type: MessageType,
@@ -1390,8 +1421,7 @@ impl Message {
                 let since_the_epoch = now
                     .duration_since(UNIX_EPOCH)
                     .unwrap_or(std::time::Duration::from_secs(0));
-                let in_ms = since_the_epoch.as_millis();
-                DltTimeStamp::from_ms(in_ms as u64)
+                DltTimeStamp::from_us(since_the_epoch.as_micros() as u64)
             }
         };
         let ecu_id = self
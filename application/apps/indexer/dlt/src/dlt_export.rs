@@ -0,0 +1,490 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::{Message, MessageFields, PayloadContent, STORAGE_HEADER_LENGTH};
+use crate::dlt_file::{session_file_path, FileMessageProducer};
+use crate::dlt_parse::{DltParseError, ParsedMessage};
+use crate::dlt_redaction::{redact_message, report_dropped, MessageRedaction};
+use crate::dlt_split::SequentialCopier;
+use crate::fibex::FibexMetadata;
+use crate::filtering::ProcessedDltExportFilter;
+use crate::ring_buffer::{manifest_path, read_segment_manifest};
+use crossbeam_channel as cc;
+use failure::{err_msg, Error};
+use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::{input_exists, input_size};
+use indexer_base::config::{IndexSection, SectionConfig};
+use indexer_base::progress::{IndexingProgress, Notification, ProgressReporter, Severity};
+use indexer_base::redaction::Redactor;
+use indexer_base::utils;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// a column of the csv export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvColumn {
+    /// time of the storage header
+    Time,
+    Ecu,
+    App,
+    Context,
+    Level,
+    /// all arguments (resolved with the fibex for non-verbose messages)
+    Payload,
+}
+
+pub const ALL_CSV_COLUMNS: [CsvColumn; 6] = [
+    CsvColumn::Time,
+    CsvColumn::Ecu,
+    CsvColumn::App,
+    CsvColumn::Context,
+    CsvColumn::Level,
+    CsvColumn::Payload,
+];
+
+impl CsvColumn {
+    pub fn name(self) -> &'static str {
+        match self {
+            CsvColumn::Time => "time",
+            CsvColumn::Ecu => "ecu",
+            CsvColumn::App => "app",
+            CsvColumn::Context => "context",
+            CsvColumn::Level => "level",
+            CsvColumn::Payload => "payload",
+        }
+    }
+}
+
+/// parses a comma separated list of column names, e.g. "time,ecu,payload"
+pub fn parse_csv_columns(input: &str) -> Result<Vec<CsvColumn>, Error> {
+    let columns = input
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            ALL_CSV_COLUMNS
+                .iter()
+                .find(|c| c.name() == s.to_lowercase())
+                .copied()
+                .ok_or_else(|| err_msg(format!("unknown csv column: {}", s)))
+        })
+        .collect::<Result<Vec<CsvColumn>, Error>>()?;
+    if columns.is_empty() {
+        return Err(err_msg("need at least one csv column"));
+    }
+    Ok(columns)
+}
+
+/// how exported messages are written
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportFormat {
+    /// one json object per line with the complete decoded message
+    JsonLines,
+    /// a header line followed by one line with the selected columns per message
+    Csv(Vec<CsvColumn>),
+}
+
+/// writes messages one by one, nothing is kept in memory
+pub struct MessageExportWriter<W: Write> {
+    out: W,
+    format: ExportFormat,
+    header_written: bool,
+    written: usize,
+}
+
+impl<W: Write> MessageExportWriter<W> {
+    pub fn new(out: W, format: ExportFormat) -> Self {
+        MessageExportWriter {
+            out,
+            format,
+            header_written: false,
+            written: 0,
+        }
+    }
+
+    /// writes the message, `false` if it was skipped because its payload could
+    /// not be formatted (a non-verbose payload that does not fit its fibex frame)
+    pub fn write_message(&mut self, msg: &Message) -> Result<bool, Error> {
+        let fields = match msg.displayed_fields() {
+            Ok(fields) => fields,
+            Err(_) => return Ok(false),
+        };
+        match &self.format {
+            ExportFormat::JsonLines => {
+                let mut json = serde_json::to_value(msg)?;
+                // like in the ui the fibex is used instead of showing raw bytes
+                if let PayloadContent::NonVerbose(id, _) = &msg.payload.payload_content {
+                    json["payload"]["payload_content"] = serde_json::json!({
+                        "NonVerbose": { "id": id, "arguments": fields.arguments }
+                    });
+                }
+                serde_json::to_writer(&mut self.out, &json)?;
+                self.out.write_all(b"\n")?;
+            }
+            ExportFormat::Csv(columns) => {
+                if !self.header_written {
+                    let names: Vec<&str> = columns.iter().map(|c| c.name()).collect();
+                    writeln!(self.out, "{}", names.join(","))?;
+                    self.header_written = true;
+                }
+                let line: Vec<String> = columns
+                    .iter()
+                    .map(|c| csv_escape(csv_field(&fields, *c)))
+                    .collect();
+                writeln!(self.out, "{}", line.join(","))?;
+            }
+        }
+        self.written += 1;
+        Ok(true)
+    }
+
+    /// number of messages written so far
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// flushes and returns the underlying writer
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let ExportFormat::Csv(columns) = &self.format {
+            if !self.header_written {
+                let names: Vec<&str> = columns.iter().map(|c| c.name()).collect();
+                writeln!(self.out, "{}", names.join(","))?;
+            }
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn csv_field(fields: &MessageFields, column: CsvColumn) -> String {
+    match column {
+        CsvColumn::Time => fields.time.clone(),
+        CsvColumn::Ecu => fields.ecu.clone(),
+        CsvColumn::App => fields.app.clone(),
+        CsvColumn::Context => fields.context.clone(),
+        CsvColumn::Level => fields.message_type.clone(),
+        CsvColumn::Payload => fields.payload(),
+    }
+}
+
+fn csv_escape(field: String) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// keeps track of the sections while the messages are streamed by in order
+pub(crate) struct SectionTracker {
+    sections: Vec<IndexSection>,
+    current: usize,
+}
+
+impl SectionTracker {
+    pub(crate) fn new(config: SectionConfig) -> Self {
+        SectionTracker {
+            sections: config.sections,
+            current: 0,
+        }
+    }
+    pub(crate) fn contains(&mut self, index: usize) -> bool {
+        if self.sections.is_empty() {
+            return true;
+        }
+        while self.current < self.sections.len() && self.sections[self.current].last_line < index {
+            self.current += 1;
+        }
+        self.current < self.sections.len() && self.sections[self.current].first_line <= index
+    }
+    pub(crate) fn is_done(&self) -> bool {
+        !self.sections.is_empty() && self.current >= self.sections.len()
+    }
+}
+
+/// exports the messages of the sections of a dlt file (all messages if
+/// there are no sections) in a structured format, redacted if a `redactor`
+/// is given
+///
+/// the messages are streamed from the file, so it works for files of any size
+#[allow(clippy::too_many_arguments)]
+pub fn export_dlt_file_structured(
+    dlt_file_path: &Path,
+    destination_path: &Path,
+    sections: SectionConfig,
+    format: ExportFormat,
+    fibex_metadata: Option<FibexMetadata>,
+    mut redactor: Option<&mut Redactor>,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    trace!(
+        "export_dlt_file_structured {:?} to file: {:?} as {:?}, exporting {:?}",
+        dlt_file_path,
+        destination_path,
+        format,
+        sections
+    );
+    if !input_exists(dlt_file_path) {
+        let reason = format!("couln't find file to export: {:?}", dlt_file_path);
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: reason.clone(),
+            line: None,
+        }));
+        return Err(err_msg(reason));
+    }
+    let in_path = dlt_file_path.to_path_buf();
+    let source_file_size = input_size(&in_path)? as usize;
+    let mut message_producer = FileMessageProducer::new(
+        &in_path,
+        None,
+        update_channel.clone(),
+        true,
+        fibex_metadata.map(Rc::new),
+    )?;
+    let mut writer =
+        MessageExportWriter::new(BufWriter::new(fs::File::create(destination_path)?), format);
+    let mut tracker = SectionTracker::new(sections);
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(message_producer.compressed_input.clone());
+    let mut msg_index = 0usize;
+    let mut stopped = false;
+    loop {
+        let (consumed, next) = message_producer.produce_next_message();
+        if consumed == 0 {
+            break;
+        }
+        progress_reporter.make_progress(consumed);
+        match next {
+            Ok(ParsedMessage::Item(mut msg)) => {
+                if tracker.contains(msg_index) {
+                    let dropped = match redactor.as_mut() {
+                        Some(redactor) => {
+                            redact_message(&mut msg, redactor) == MessageRedaction::Dropped
+                        }
+                        None => false,
+                    };
+                    if !dropped && !writer.write_message(&msg)? {
+                        let _ = update_channel.send(Err(Notification {
+                            severity: Severity::WARNING,
+                            content: "export: skipped message with a payload that does not fit its fibex frame".to_string(),
+                            line: Some(msg_index),
+                        }));
+                    }
+                }
+                msg_index += 1;
+                if tracker.is_done() {
+                    break;
+                }
+            }
+            Ok(ParsedMessage::Invalid) | Ok(ParsedMessage::FilteredOut) => (),
+            Err(DltParseError::ParsingHickup { reason }) => {
+                warn!("export: skip message that could not be parsed: {}", reason);
+            }
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("export: cannot continue parsing: {}", e),
+                    line: Some(msg_index),
+                }));
+                break;
+            }
+        }
+        if utils::check_if_stop_was_requested(&shutdown_receiver, "dlt export") {
+            stopped = true;
+            break;
+        }
+    }
+    let written = writer.written();
+    writer.finish()?;
+    if let Some(redactor) = redactor {
+        report_dropped(redactor, &update_channel);
+    }
+    let _ = update_channel.send(Ok(if stopped {
+        IndexingProgress::Stopped
+    } else {
+        IndexingProgress::Finished
+    }));
+    Ok(written)
+}
+
+/// copies the messages of a dlt file that pass the filter (header filters,
+/// payload regexes and time range) byte by byte into a new dlt file
+///
+/// returns the number of exported messages
+pub fn export_filtered_dlt_file(
+    dlt_file_path: &Path,
+    destination_path: &Path,
+    filter: &ProcessedDltExportFilter,
+    fibex_metadata: Option<FibexMetadata>,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    export_filtered(
+        &[dlt_file_path.to_path_buf()],
+        destination_path,
+        filter,
+        fibex_metadata,
+        update_channel,
+        shutdown_receiver,
+    )
+}
+
+/// like `export_filtered_dlt_file` for all segments of a segment manifest
+pub fn export_filtered_segments(
+    manifest_path: &Path,
+    destination_path: &Path,
+    filter: &ProcessedDltExportFilter,
+    fibex_metadata: Option<FibexMetadata>,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    let manifest = read_segment_manifest(manifest_path)?;
+    let segment_files: Vec<PathBuf> = manifest
+        .segments
+        .into_iter()
+        .map(|segment| segment.dlt_file)
+        .collect();
+    export_filtered(
+        &segment_files,
+        destination_path,
+        filter,
+        fibex_metadata,
+        update_channel,
+        shutdown_receiver,
+    )
+}
+
+/// like `export_filtered_dlt_file` for the recorded messages of a session
+pub fn export_filtered_session_file(
+    session_id: &str,
+    destination_path: &Path,
+    filter: &ProcessedDltExportFilter,
+    fibex_metadata: Option<FibexMetadata>,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    let session_file = session_file_path(session_id)?;
+    let segments_manifest = manifest_path(&session_file);
+    if segments_manifest.exists() {
+        export_filtered_segments(
+            &segments_manifest,
+            destination_path,
+            filter,
+            fibex_metadata,
+            update_channel,
+            shutdown_receiver,
+        )
+    } else {
+        export_filtered_dlt_file(
+            &session_file,
+            destination_path,
+            filter,
+            fibex_metadata,
+            update_channel,
+            shutdown_receiver,
+        )
+    }
+}
+
+fn export_filtered(
+    dlt_files: &[PathBuf],
+    destination_path: &Path,
+    filter: &ProcessedDltExportFilter,
+    fibex_metadata: Option<FibexMetadata>,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    trace!(
+        "export_filtered {:?} to file: {:?}",
+        dlt_files,
+        destination_path
+    );
+    let mut total_size = 0usize;
+    for dlt_file in dlt_files {
+        if !input_exists(dlt_file) {
+            let reason = format!("couln't find file to export: {:?}", dlt_file);
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: reason.clone(),
+                line: None,
+            }));
+            return Err(err_msg(reason));
+        }
+        total_size += input_size(dlt_file)? as usize;
+    }
+    let fibex_metadata = fibex_metadata.map(Rc::new);
+    let mut out_writer = BufWriter::new(fs::File::create(destination_path)?);
+    let mut progress_reporter = ProgressReporter::new(total_size, update_channel.clone());
+    let mut written = 0usize;
+    let mut stopped = false;
+    'files: for dlt_file in dlt_files {
+        let mut message_producer = FileMessageProducer::new(
+            dlt_file,
+            Some(filter.header.clone()),
+            update_channel.clone(),
+            true,
+            fibex_metadata.clone(),
+        )?;
+        let mut copier = SequentialCopier::new(dlt_file)?;
+        let mut offset = 0u64;
+        loop {
+            let (consumed, next) = message_producer.produce_next_message();
+            if consumed == 0 {
+                break;
+            }
+            progress_reporter.make_progress(consumed);
+            let end = offset + consumed as u64;
+            match next {
+                Ok(ParsedMessage::Item(msg)) => {
+                    if filter.matches(&msg) {
+                        let length = std::cmp::min(
+                            (STORAGE_HEADER_LENGTH + msg.header.overall_length() as usize) as u64,
+                            consumed as u64,
+                        );
+                        copier.copy_to(end - length, length, &mut out_writer)?;
+                        written += 1;
+                    }
+                }
+                Ok(ParsedMessage::Invalid) | Ok(ParsedMessage::FilteredOut) => (),
+                Err(DltParseError::ParsingHickup { reason }) => {
+                    warn!(
+                        "filtered export: skip message that could not be parsed: {}",
+                        reason
+                    );
+                }
+                Err(e) => {
+                    let _ = update_channel.send(Err(Notification {
+                        severity: Severity::ERROR,
+                        content: format!("filtered export: cannot continue parsing: {}", e),
+                        line: None,
+                    }));
+                    break;
+                }
+            }
+            offset = end;
+            if utils::check_if_stop_was_requested(&shutdown_receiver, "filtered dlt export") {
+                stopped = true;
+                break 'files;
+            }
+        }
+    }
+    out_writer.flush()?;
+    let _ = update_channel.send(Ok(if stopped {
+        IndexingProgress::Stopped
+    } else {
+        IndexingProgress::Finished
+    }));
+    Ok(written)
+}
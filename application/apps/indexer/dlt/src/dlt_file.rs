@@ -17,6 +17,9 @@ use crate::dlt_parse::{
     DLT_READER_CAPACITY,
 };
 use crate::filtering;
+use crate::lifecycle::{scan_lifecycles, LifecycleTimeCorrection, MessageWithCorrectedTime};
+use crate::message_counter::{counter_sequence_intact, MessageCounterTracker};
+use crate::ring_buffer::{manifest_path, read_segment_manifest};
 use buf_redux::policy::MinBuffered;
 use buf_redux::BufReader as ReduxReader;
 use crossbeam_channel as cc;
@@ -25,6 +28,7 @@ use failure::{err_msg, Error};
 use futures::stream::StreamExt;
 use indexer_base::{
     chunks::{ChunkFactory, ChunkResults},
+    compression::{input_size, open_input, ConsumedBytes, InputReader},
     config::*,
     progress::*,
     utils,
@@ -42,7 +46,7 @@ pub async fn parse_dlt_file(
     fibex_metadata: Option<Rc<FibexMetadata>>,
 ) -> Result<Vec<Message>, Error> {
     trace!("parse_dlt_file");
-    let source_file_size = fs::metadata(&in_file)?.len() as usize;
+    let source_file_size = input_size(&in_file)? as usize;
     let (update_channel, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
     let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
     let mut messages: Vec<Message> = Vec::new();
@@ -53,6 +57,7 @@ pub async fn parse_dlt_file(
         true,
         fibex_metadata,
     )?;
+    progress_reporter.track_read_bytes(message_stream.compressed_input.clone());
     // type Item = Result<Option<Message>, DltParseError>;
     while let Some(msg_result) = message_stream.next().await {
         trace!("got message from stream: {:?}", msg_result);
@@ -80,8 +85,17 @@ pub fn create_index_and_mapping_dlt(
     update_channel: &cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
     fibex_metadata: Option<FibexMetadata>,
+    with_corrected_time: bool,
 ) -> Result<(), Error> {
     trace!("create_index_and_mapping_dlt");
+    // the corrected time can only be calculated when we know the whole lifecycle,
+    // so we need to scan the file upfront
+    let time_correction = if with_corrected_time {
+        let lifecycles = scan_lifecycles(&config.in_file)?;
+        Some(LifecycleTimeCorrection::new(lifecycles))
+    } else {
+        None
+    };
     let filter_config: Option<filtering::ProcessedDltFilterConfig> =
         dlt_filter.map(filtering::process_filter_config);
     let mut message_producer = FileMessageProducer::new(
@@ -98,6 +112,7 @@ pub fn create_index_and_mapping_dlt(
         update_channel,
         shutdown_receiver,
         &mut message_producer,
+        time_correction,
     )
 }
 
@@ -107,7 +122,9 @@ pub struct MessageStats {
     no_parse: usize,
 }
 pub struct FileMessageProducer {
-    reader: ReduxReader<fs::File, MinBuffered>,
+    reader: ReduxReader<InputReader, MinBuffered>,
+    /// for progress reporting of compressed files
+    pub(crate) compressed_input: Option<ConsumedBytes>,
     filter_config: Option<filtering::ProcessedDltFilterConfig>,
     stats: MessageStats,
     update_channel: cc::Sender<ChunkResults>,
@@ -116,14 +133,14 @@ pub struct FileMessageProducer {
 }
 
 impl FileMessageProducer {
-    fn new(
+    pub(crate) fn new(
         in_path: &PathBuf,
         filter_config: Option<filtering::ProcessedDltFilterConfig>,
         update_channel: cc::Sender<ChunkResults>,
         with_storage_header: bool,
         fibex_metadata: Option<Rc<FibexMetadata>>,
     ) -> Result<FileMessageProducer, Error> {
-        let f = match fs::File::open(&in_path) {
+        let f = match open_input(in_path) {
             Ok(file) => file,
             Err(e) => {
                 warn!("could not open {:?}", in_path);
@@ -135,10 +152,12 @@ impl FileMessageProducer {
                 return Err(err_msg(format!("could not open file ({})", e)));
             }
         };
+        let compressed_input = f.compressed_input();
         let reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
             .set_policy(MinBuffered(DLT_MIN_BUFFER_SPACE));
         Ok(FileMessageProducer {
             reader,
+            compressed_input,
             filter_config,
             stats: MessageStats {
                 parsed: 0,
@@ -151,7 +170,7 @@ impl FileMessageProducer {
     }
 }
 impl FileMessageProducer {
-    fn produce_next_message(&mut self) -> (usize, Result<ParsedMessage, DltParseError>) {
+    pub(crate) fn produce_next_message(&mut self) -> (usize, Result<ParsedMessage, DltParseError>) {
         #[allow(clippy::never_loop)]
         let consume_and_parse_result = loop {
             match self.reader.fill_buf() {
@@ -268,6 +287,7 @@ pub fn index_dlt_content(
     update_channel: &cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
     message_producer: &mut FileMessageProducer,
+    mut time_correction: Option<LifecycleTimeCorrection>,
 ) -> Result<(), Error> {
     trace!("index_dlt_file {:?}", config);
     let (out_file, current_out_file_size) =
@@ -286,9 +306,16 @@ pub fn index_dlt_content(
     let mut buf_writer = BufWriter::with_capacity(DLT_READER_CAPACITY, out_file);
 
     let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(message_producer.compressed_input.clone());
 
     let mut stopped = false;
     let mut skipped = 0usize;
+    let mut counter_tracker = if counter_sequence_intact(message_producer.filter_config.as_ref()) {
+        Some(MessageCounterTracker::new())
+    } else {
+        debug!("filter drops messages of a sender, no message counter tracking");
+        None
+    };
     loop {
         if stopped {
             info!("we were stopped in dlt-indexer",);
@@ -303,8 +330,30 @@ pub fn index_dlt_content(
         match next {
             Ok(ParsedMessage::Item(msg)) => {
                 trace!("[line:{}] next was Ok(ParsedMessage::Item(msg))", line_nr);
-                let written_bytes_len =
-                    utils::create_tagged_line_d(config.tag, &mut buf_writer, &msg, line_nr, true)?;
+                if let Some(tracker) = counter_tracker.as_mut() {
+                    if let Some(notification) = tracker.track_message(&msg, line_nr) {
+                        update_channel.send(Err(notification))?;
+                    }
+                }
+                let written_bytes_len = match time_correction.as_mut() {
+                    Some(correction) => utils::create_tagged_line_d(
+                        config.tag,
+                        &mut buf_writer,
+                        MessageWithCorrectedTime {
+                            corrected_time: correction.corrected_time(&msg),
+                            msg: &msg,
+                        },
+                        line_nr,
+                        true,
+                    )?,
+                    None => utils::create_tagged_line_d(
+                        config.tag,
+                        &mut buf_writer,
+                        &msg,
+                        line_nr,
+                        true,
+                    )?,
+                };
                 // tmp_writer.write_all(&msg.as_bytes())?;
                 line_nr += 1;
                 if let Some(chunk) = chunk_factory.add_bytes(line_nr, written_bytes_len) {
@@ -399,6 +448,15 @@ pub fn export_session_file(
         sections
     );
     let session_file_path = session_file_path(&session_id)?;
+    let segments_manifest = manifest_path(&session_file_path);
+    if segments_manifest.exists() {
+        return export_segments_as_dlt(
+            &segments_manifest,
+            destination_path,
+            sections,
+            update_channel,
+        );
+    }
     export_as_dlt_file(
         session_file_path,
         destination_path,
@@ -413,8 +471,6 @@ pub fn export_as_dlt_file(
     sections: SectionConfig,
     update_channel: cc::Sender<ChunkResults>,
 ) -> Result<(), Error> {
-    use std::io::Read;
-    use std::io::Seek;
     trace!(
         "export_as_dlt_file {:?} to file: {:?}, exporting {:?}",
         dlt_file_path,
@@ -423,21 +479,10 @@ pub fn export_as_dlt_file(
     );
     if dlt_file_path.exists() {
         trace!("found file to export: {:?}", &dlt_file_path);
-        let f = fs::File::open(&dlt_file_path)?;
-        let mut reader = &mut std::io::BufReader::new(f);
         let out_file = std::fs::File::create(destination_path)?;
         trace!("created out_file: {:?}", &out_file);
-        let partitioner = FilePartitioner::new(&dlt_file_path, sections)?;
         let mut out_writer = BufWriter::new(out_file);
-
-        for part in partitioner.get_parts() {
-            trace!("copy part {:?}", part);
-            reader.seek(std::io::SeekFrom::Start(part.offset as u64))?;
-            let mut take = reader.take(part.length as u64);
-            std::io::copy(&mut take, &mut out_writer)?;
-            reader = take.into_inner();
-            out_writer.flush()?;
-        }
+        copy_sections(&dlt_file_path, sections, &mut out_writer)?;
         let _ = update_channel.send(Ok(IndexingProgress::Finished));
         Ok(())
     } else {
@@ -451,7 +496,54 @@ pub fn export_as_dlt_file(
     }
 }
 
-pub(crate) fn session_file_path(session_id: &str) -> Result<PathBuf, Error> {
+/// exports the retained segments of a ring buffer capture as one dlt file,
+/// the sections use the line numbers of the capture
+pub fn export_segments_as_dlt(
+    manifest_path: &std::path::Path,
+    destination_path: PathBuf,
+    sections: SectionConfig,
+    update_channel: cc::Sender<ChunkResults>,
+) -> Result<(), Error> {
+    trace!(
+        "export_segments_as_dlt {:?} to file: {:?}, exporting {:?}",
+        manifest_path,
+        destination_path,
+        sections
+    );
+    let manifest = read_segment_manifest(manifest_path)?;
+    let out_file = std::fs::File::create(destination_path)?;
+    let mut out_writer = BufWriter::new(out_file);
+    for segment in &manifest.segments {
+        if let Some(local_sections) = segment.local_sections(&sections) {
+            copy_sections(&segment.dlt_file, local_sections, &mut out_writer)?;
+        }
+    }
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(())
+}
+
+fn copy_sections(
+    dlt_file_path: &PathBuf,
+    sections: SectionConfig,
+    out_writer: &mut BufWriter<fs::File>,
+) -> Result<(), Error> {
+    use std::io::Read;
+    use std::io::Seek;
+    let f = fs::File::open(dlt_file_path)?;
+    let mut reader = &mut std::io::BufReader::new(f);
+    let partitioner = FilePartitioner::new(dlt_file_path, sections)?;
+    for part in partitioner.get_parts() {
+        trace!("copy part {:?}", part);
+        reader.seek(std::io::SeekFrom::Start(part.offset as u64))?;
+        let mut take = reader.take(part.length as u64);
+        std::io::copy(&mut take, out_writer)?;
+        reader = take.into_inner();
+        out_writer.flush()?;
+    }
+    Ok(())
+}
+
+pub fn session_file_path(session_id: &str) -> Result<PathBuf, Error> {
     let home_dir = dirs::home_dir().ok_or_else(|| err_msg("couldn't get home directory"))?;
     let tmp_file_name = format!("{}.dlt", session_id);
     Ok(home_dir
@@ -226,270 +226,357 @@ impl fmt::Display for LogLevel {
     }
 }
 
+/// a non-verbose message decoded with its frame from the fibex
+pub(crate) struct NonVerboseFrame {
+    pub(crate) app_id: String,
+    pub(crate) context_id: String,
+    pub(crate) message_type: Option<MessageType>,
+    pub(crate) arguments: Vec<Argument>,
+    /// without pdus there is nothing to decode
+    pub(crate) has_pdus: bool,
+}
+
 impl Message {
+    /// decodes the payload of a non-verbose message with the fibex, `None` if
+    /// there is no frame for it and an error if the payload does not fit the frame
+    pub(crate) fn decode_nonverbose(
+        &self,
+        id: u32,
+        data: &[u8],
+    ) -> Result<Option<NonVerboseFrame>, fmt::Error> {
+        let fibex_metadata = match &self.fibex_metadata {
+            Some(fibex_metadata) => fibex_metadata,
+            None => return Ok(None),
+        };
+        let id_text = format!("ID_{}", id);
+        let frame_metadata = if let Some(extended_header) = &self.extended_header {
+            fibex_metadata.frame_map_with_key.get(&(
+                ContextId(extended_header.context_id.clone()),
+                ApplicationId(extended_header.application_id.clone()),
+                FrameId(id_text),
+            )) // TODO: avoid cloning here (Cow or Borrow)
+        } else {
+            fibex_metadata.frame_map.get(&FrameId(id_text))
+        };
+        let frame_metadata = match frame_metadata {
+            Some(frame_metadata) => frame_metadata,
+            None => return Ok(None),
+        };
+        let FrameMetadata {
+            application_id,
+            context_id,
+            message_info,
+            ..
+        } = &**frame_metadata;
+        let message_type = message_info
+            .as_ref()
+            .and_then(|mi| MessageType::try_new_from_fibex_message_info(&*mi))
+            .or_else(|| {
+                self.extended_header
+                    .as_ref()
+                    .map(|h| h.message_type.clone())
+            });
+        let mut arguments = vec![];
+        let mut offset = 0;
+        for pdu in &frame_metadata.pdus {
+            if let Some(description) = &pdu.description {
+                arguments.push(Argument {
+                    type_info: TypeInfo {
+                        kind: TypeInfoKind::StringType,
+                        coding: StringCoding::UTF8,
+                        has_trace_info: false,
+                        has_variable_info: false,
+                    },
+                    name: None,
+                    unit: None,
+                    fixed_point: None,
+                    value: Value::StringVal(description.to_string()),
+                });
+            } else {
+                for signal_type in &pdu.signal_types {
+                    let mut fixed_point = None;
+                    let value = match signal_type.kind {
+                        TypeInfoKind::StringType | TypeInfoKind::Raw => {
+                            if data.len() < offset + 2 {
+                                return Err(fmt::Error);
+                            }
+                            let length = if self.header.endianness == Endianness::Big {
+                                BigEndian::read_u16(&data[offset..offset + 2]) as usize
+                            } else {
+                                LittleEndian::read_u16(&data[offset..offset + 2]) as usize
+                            };
+                            offset += 2;
+                            if data.len() < offset + length {
+                                return Err(fmt::Error);
+                            }
+                            let v = if signal_type.kind == TypeInfoKind::StringType {
+                                Value::StringVal(
+                                    String::from_utf8(data[offset..offset + length].to_vec())
+                                        .map_err(|_| fmt::Error)?,
+                                )
+                            } else {
+                                Value::Raw(Vec::from(&data[offset..offset + length]))
+                            };
+                            offset += length;
+                            v
+                        }
+                        TypeInfoKind::Bool => {
+                            offset += 1;
+                            if data.len() < offset {
+                                return Err(fmt::Error);
+                            }
+                            Value::Bool(data[offset - 1])
+                        }
+                        TypeInfoKind::Float(width) => {
+                            let length = width as usize / 8;
+                            if data.len() < offset + length {
+                                return Err(fmt::Error);
+                            }
+                            let v = if self.header.endianness == Endianness::Big {
+                                dlt_fint::<BigEndian>(width)(&data[offset..offset + length])
+                            } else {
+                                dlt_fint::<LittleEndian>(width)(&data[offset..offset + length])
+                            }
+                            .map_err(|_| fmt::Error)?
+                            .1;
+                            offset += length;
+                            v
+                        }
+                        TypeInfoKind::Signed(length) => {
+                            let byte_length = length as usize / 8;
+                            if data.len() < offset + byte_length {
+                                return Err(fmt::Error);
+                            }
+                            let value_offset = &data[offset..];
+                            let (_, v) = if self.header.endianness == Endianness::Big {
+                                dlt_sint::<BigEndian>(length)(value_offset)
+                            } else {
+                                dlt_sint::<LittleEndian>(length)(value_offset)
+                            }
+                            .map_err(|_| fmt::Error)?;
+                            offset += byte_length;
+                            v
+                        }
+                        TypeInfoKind::SignedFixedPoint(length) => {
+                            let byte_length = length as usize / 8;
+                            if data.len() < offset + byte_length {
+                                return Err(fmt::Error);
+                            }
+                            let (value_offset, fp) = if self.header.endianness == Endianness::Big {
+                                dlt_fixed_point::<BigEndian>(
+                                    &data[offset..offset + byte_length],
+                                    length,
+                                )
+                            } else {
+                                dlt_fixed_point::<LittleEndian>(
+                                    &data[offset..offset + byte_length],
+                                    length,
+                                )
+                            }
+                            .map_err(|_| fmt::Error)?;
+                            fixed_point = Some(fp);
+                            let (_, v) = if self.header.endianness == Endianness::Big {
+                                dlt_sint::<BigEndian>(float_width_to_type_length(length))(
+                                    value_offset,
+                                )
+                            } else {
+                                dlt_sint::<LittleEndian>(float_width_to_type_length(length))(
+                                    value_offset,
+                                )
+                            }
+                            .map_err(|_| fmt::Error)?;
+                            offset += byte_length;
+                            v
+                        }
+                        TypeInfoKind::Unsigned(length) => {
+                            let byte_length = length as usize / 8;
+                            if data.len() < offset + byte_length {
+                                return Err(fmt::Error);
+                            }
+                            let value_offset = &data[offset..];
+                            let (_, v) = if self.header.endianness == Endianness::Big {
+                                dlt_uint::<BigEndian>(length)(value_offset)
+                            } else {
+                                dlt_uint::<LittleEndian>(length)(value_offset)
+                            }
+                            .map_err(|_| fmt::Error)?;
+                            offset += byte_length;
+                            v
+                        }
+                        TypeInfoKind::UnsignedFixedPoint(length) => {
+                            let byte_length = length as usize / 8;
+                            if data.len() < offset + byte_length {
+                                return Err(fmt::Error);
+                            }
+                            let value_offset = {
+                                let (r, fp) = if self.header.endianness == Endianness::Big {
+                                    dlt_fixed_point::<BigEndian>(
+                                        &data[offset..offset + byte_length],
+                                        length,
+                                    )
+                                } else {
+                                    dlt_fixed_point::<LittleEndian>(
+                                        &data[offset..offset + byte_length],
+                                        length,
+                                    )
+                                }
+                                .map_err(|_| fmt::Error)?;
+                                fixed_point = Some(fp);
+                                r
+                            };
+                            let (_, v) = if self.header.endianness == Endianness::Big {
+                                dlt_uint::<BigEndian>(float_width_to_type_length(length))(
+                                    value_offset,
+                                )
+                            } else {
+                                dlt_uint::<LittleEndian>(float_width_to_type_length(length))(
+                                    value_offset,
+                                )
+                            }
+                            .map_err(|_| fmt::Error)?;
+                            offset += byte_length;
+                            v
+                        }
+                    };
+                    arguments.push(Argument {
+                        type_info: signal_type.clone(),
+                        name: None,
+                        unit: None,
+                        fixed_point,
+                        value,
+                    });
+                }
+            }
+        }
+        Ok(Some(NonVerboseFrame {
+            app_id: application_id
+                .as_ref()
+                .map(|id| &**id)
+                .or_else(|| {
+                    self.extended_header
+                        .as_ref()
+                        .map(|h| h.application_id.as_ref())
+                })
+                .unwrap_or("-")
+                .to_string(),
+            context_id: context_id
+                .as_ref()
+                .map(|id| &**id)
+                .or_else(|| self.extended_header.as_ref().map(|h| h.context_id.as_ref()))
+                .unwrap_or("-")
+                .to_string(),
+            message_type,
+            arguments,
+            has_pdus: !frame_metadata.pdus.is_empty(),
+        }))
+    }
+
+    /// the arguments that are shown for a non-verbose message without a
+    /// (decodable) fibex frame
+    pub(crate) fn nonverbose_fallback(&self, id: u32) -> [String; 2] {
+        [
+            format!("[{}]", id),
+            get_message_type_string(&self.extended_header).to_string(),
+        ]
+    }
+
+    /// the fields of the message like `Display` formats them, fails (like
+    /// `Display`) for non-verbose payloads that do not fit their fibex frame
+    pub fn displayed_fields(&self) -> Result<MessageFields, fmt::Error> {
+        let (app, context, message_type) = match &self.extended_header {
+            Some(ext) => (
+                ext.application_id.clone(),
+                ext.context_id.clone(),
+                ext.message_type.to_string(),
+            ),
+            None => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        let ecu = match (&self.header.ecu_id, &self.storage_header) {
+            (Some(id), _) if !id.is_empty() => id.clone(),
+            (_, Some(storage_header)) => storage_header.ecu_id.clone(),
+            _ => String::new(),
+        };
+        let mut fields = MessageFields {
+            time: self
+                .storage_header
+                .as_ref()
+                .map(|h| h.timestamp.to_string())
+                .unwrap_or_default(),
+            ecu,
+            app,
+            context,
+            message_type,
+            arguments: vec![],
+        };
+        match &self.payload.payload_content {
+            PayloadContent::Verbose(arguments) => {
+                fields.arguments = arguments.iter().map(|arg| arg.to_string()).collect();
+            }
+            PayloadContent::NonVerbose(id, data) => {
+                let has_pdus = match self.decode_nonverbose(*id, data)? {
+                    Some(frame) => {
+                        fields.app = frame.app_id;
+                        fields.context = frame.context_id;
+                        fields.message_type = frame
+                            .message_type
+                            .map(|t| t.to_string())
+                            .unwrap_or_else(|| "-".to_string());
+                        fields.arguments = frame.arguments.iter().map(|a| a.to_string()).collect();
+                        frame.has_pdus
+                    }
+                    None => false,
+                };
+                if !has_pdus {
+                    fields
+                        .arguments
+                        .extend(self.nonverbose_fallback(*id).iter().cloned());
+                }
+            }
+            PayloadContent::ControlMsg(ctrl_id, _data) => {
+                fields.arguments = vec![match SERVICE_ID_MAPPING.get(&ctrl_id.value()) {
+                    Some((name, _desc)) => format!("[{}]", name),
+                    None => "[Unknown CtrlCommand]".to_string(),
+                }];
+            }
+        }
+        Ok(fields)
+    }
+
     pub(crate) fn format_nonverbose_data(
         &self,
         id: u32,
         data: &[u8],
         f: &mut fmt::Formatter,
     ) -> fmt::Result {
-        let mut is_written = false;
-        if let Some(fibex_metadata) = &self.fibex_metadata {
-            let id_text = format!("ID_{}", id);
-            let frame_metadata = if let Some(extended_header) = &self.extended_header {
-                fibex_metadata.frame_map_with_key.get(&(
-                    ContextId(extended_header.context_id.clone()),
-                    ApplicationId(extended_header.application_id.clone()),
-                    FrameId(id_text),
-                )) // TODO: avoid cloning here (Cow or Borrow)
-            } else {
-                fibex_metadata.frame_map.get(&FrameId(id_text))
-            };
-            if let Some(frame_metadata) = frame_metadata {
-                let FrameMetadata {
-                    application_id,
-                    context_id,
-                    message_info,
-                    ..
-                } = &**frame_metadata;
+        let has_pdus = match self.decode_nonverbose(id, data)? {
+            Some(frame) => {
                 write!(
                     f,
                     "{}{}{}{}",
-                    application_id
-                        .as_ref()
-                        .map(|id| &**id)
-                        .or_else(|| self
-                            .extended_header
-                            .as_ref()
-                            .map(|h| h.application_id.as_ref()))
-                        .unwrap_or("-"),
-                    DLT_COLUMN_SENTINAL,
-                    context_id
-                        .as_ref()
-                        .map(|id| &**id)
-                        .or_else(|| self.extended_header.as_ref().map(|h| h.context_id.as_ref()))
-                        .unwrap_or("-"),
-                    DLT_COLUMN_SENTINAL
+                    frame.app_id, DLT_COLUMN_SENTINAL, frame.context_id, DLT_COLUMN_SENTINAL
                 )?;
-                if let Some(v) = message_info
-                    .as_ref()
-                    .and_then(|mi| MessageType::try_new_from_fibex_message_info(&*mi))
-                {
-                    write!(f, "{}", v)?;
-                } else if let Some(message_type) =
-                    self.extended_header.as_ref().map(|h| &h.message_type)
-                {
-                    write!(f, "{}", message_type)?;
-                } else {
-                    write!(f, "-")?;
+                match &frame.message_type {
+                    Some(message_type) => write!(f, "{}", message_type)?,
+                    None => write!(f, "-")?,
                 }
                 write!(f, "{}", DLT_COLUMN_SENTINAL)?;
-                let mut offset = 0;
-                for pdu in &frame_metadata.pdus {
-                    if let Some(description) = &pdu.description {
-                        let arg = Argument {
-                            type_info: TypeInfo {
-                                kind: TypeInfoKind::StringType,
-                                coding: StringCoding::UTF8,
-                                has_trace_info: false,
-                                has_variable_info: false,
-                            },
-                            name: None,
-                            unit: None,
-                            fixed_point: None,
-                            value: Value::StringVal(description.to_string()),
-                        };
-                        write!(f, "{}{} ", DLT_ARGUMENT_SENTINAL, arg)?;
-                    } else {
-                        for signal_type in &pdu.signal_types {
-                            let mut fixed_point = None;
-                            let value = match signal_type.kind {
-                                TypeInfoKind::StringType | TypeInfoKind::Raw => {
-                                    if data.len() < offset + 2 {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let length = if self.header.endianness == Endianness::Big {
-                                        BigEndian::read_u16(&data[offset..offset + 2]) as usize
-                                    } else {
-                                        LittleEndian::read_u16(&data[offset..offset + 2]) as usize
-                                    };
-                                    offset += 2;
-                                    if data.len() < offset + length {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let v = if signal_type.kind == TypeInfoKind::StringType {
-                                        Value::StringVal(
-                                            String::from_utf8(
-                                                data[offset..offset + length].to_vec(),
-                                            )
-                                            .map_err(|_| fmt::Error)?,
-                                        )
-                                    } else {
-                                        Value::Raw(Vec::from(&data[offset..offset + length]))
-                                    };
-                                    offset += length;
-                                    v
-                                }
-                                TypeInfoKind::Bool => {
-                                    offset += 1;
-                                    if data.len() < offset {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    Value::Bool(data[offset - 1])
-                                }
-                                TypeInfoKind::Float(width) => {
-                                    let length = width as usize / 8;
-                                    if data.len() < offset + length {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let v = if self.header.endianness == Endianness::Big {
-                                        dlt_fint::<BigEndian>(width)(&data[offset..offset + length])
-                                    } else {
-                                        dlt_fint::<LittleEndian>(width)(
-                                            &data[offset..offset + length],
-                                        )
-                                    }
-                                    .map_err(|_| fmt::Error)?
-                                    .1;
-                                    offset += length;
-                                    v
-                                }
-                                TypeInfoKind::Signed(length) => {
-                                    let byte_length = length as usize / 8;
-                                    if data.len() < offset + byte_length {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let value_offset = &data[offset..];
-                                    let (_, v) = if self.header.endianness == Endianness::Big {
-                                        dlt_sint::<BigEndian>(length)(value_offset)
-                                    } else {
-                                        dlt_sint::<LittleEndian>(length)(value_offset)
-                                    }
-                                    .map_err(|_| fmt::Error)?;
-                                    offset += byte_length;
-                                    v
-                                }
-                                TypeInfoKind::SignedFixedPoint(length) => {
-                                    let byte_length = length as usize / 8;
-                                    if data.len() < offset + byte_length {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let (value_offset, fp) =
-                                        if self.header.endianness == Endianness::Big {
-                                            dlt_fixed_point::<BigEndian>(
-                                                &data[offset..offset + byte_length],
-                                                length,
-                                            )
-                                        } else {
-                                            dlt_fixed_point::<LittleEndian>(
-                                                &data[offset..offset + byte_length],
-                                                length,
-                                            )
-                                        }
-                                        .map_err(|_| fmt::Error)?;
-                                    fixed_point = Some(fp);
-                                    let (_, v) =
-                                        if self.header.endianness == Endianness::Big {
-                                            dlt_sint::<BigEndian>(float_width_to_type_length(
-                                                length,
-                                            ))(
-                                                value_offset
-                                            )
-                                        } else {
-                                            dlt_sint::<LittleEndian>(float_width_to_type_length(
-                                                length,
-                                            ))(
-                                                value_offset
-                                            )
-                                        }
-                                        .map_err(|_| fmt::Error)?;
-                                    offset += byte_length;
-                                    v
-                                }
-                                TypeInfoKind::Unsigned(length) => {
-                                    let byte_length = length as usize / 8;
-                                    if data.len() < offset + byte_length {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let value_offset = &data[offset..];
-                                    let (_, v) = if self.header.endianness == Endianness::Big {
-                                        dlt_uint::<BigEndian>(length)(value_offset)
-                                    } else {
-                                        dlt_uint::<LittleEndian>(length)(value_offset)
-                                    }
-                                    .map_err(|_| fmt::Error)?;
-                                    offset += byte_length;
-                                    v
-                                }
-                                TypeInfoKind::UnsignedFixedPoint(length) => {
-                                    let byte_length = length as usize / 8;
-                                    if data.len() < offset + byte_length {
-                                        return fmt::Result::Err(fmt::Error);
-                                    }
-                                    let value_offset = {
-                                        let (r, fp) =
-                                            if self.header.endianness == Endianness::Big {
-                                                dlt_fixed_point::<BigEndian>(
-                                                    &data[offset..offset + byte_length],
-                                                    length,
-                                                )
-                                            } else {
-                                                dlt_fixed_point::<LittleEndian>(
-                                                    &data[offset..offset + byte_length],
-                                                    length,
-                                                )
-                                            }
-                                            .map_err(|_| fmt::Error)?;
-                                        fixed_point = Some(fp);
-                                        r
-                                    };
-                                    let (_, v) =
-                                        if self.header.endianness == Endianness::Big {
-                                            dlt_uint::<BigEndian>(float_width_to_type_length(
-                                                length,
-                                            ))(
-                                                value_offset
-                                            )
-                                        } else {
-                                            dlt_uint::<LittleEndian>(float_width_to_type_length(
-                                                length,
-                                            ))(
-                                                value_offset
-                                            )
-                                        }
-                                        .map_err(|_| fmt::Error)?;
-                                    offset += byte_length;
-                                    v
-                                }
-                            };
-                            let arg = Argument {
-                                type_info: signal_type.clone(),
-                                name: None,
-                                unit: None,
-                                fixed_point,
-                                value,
-                            };
-                            write!(f, "{}{} ", DLT_ARGUMENT_SENTINAL, arg)?;
-                        }
-                    };
-                    is_written = true;
+                for arg in &frame.arguments {
+                    write!(f, "{}{} ", DLT_ARGUMENT_SENTINAL, arg)?;
                 }
-            } else {
+                frame.has_pdus
+            }
+            None => {
                 self.write_app_id_context_id_and_message_type(f)?;
+                false
             }
-        } else {
-            self.write_app_id_context_id_and_message_type(f)?;
-        }
-        if !is_written {
-            if let Err(_e) = f.write_str(
-                &format!(
-                    "{}[{}]{} {}",
-                    DLT_ARGUMENT_SENTINAL,
-                    id,
-                    DLT_ARGUMENT_SENTINAL,
-                    get_message_type_string(&self.extended_header),
-                )[..],
-            ) {}
+        };
+        if !has_pdus {
+            let [id_text, message_type] = self.nonverbose_fallback(id);
+            write!(
+                f,
+                "{}{}{} {}",
+                DLT_ARGUMENT_SENTINAL, id_text, DLT_ARGUMENT_SENTINAL, message_type
+            )?;
         }
         Ok(())
     }
@@ -0,0 +1,238 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::STORAGE_HEADER_LENGTH;
+use crate::dlt_parse::{
+    dlt_message, forward_to_next_storage_header, DltParseError, ParsedMessage, DLT_PATTERN_SIZE,
+    DLT_READER_CAPACITY,
+};
+use crate::fibex::FibexMetadata;
+use crate::filtering;
+use crossbeam_channel as cc;
+use failure::Error;
+use indexer_base::chunks::{ChunkFactory, ChunkResults};
+use indexer_base::config::IndexingConfig;
+use indexer_base::follow::{wait_for_more, FollowedFile, SourceChange};
+use indexer_base::progress::{IndexingProgress, Notification, Severity};
+use indexer_base::utils;
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+const FOLLOW_READ_SIZE: usize = 64 * 1024;
+/// storage header and the standard header up to the length field
+const LENGTH_END: usize = STORAGE_HEADER_LENGTH + 4;
+
+/// how many bytes the next message in `input` needs
+enum NextMessage {
+    /// complete message, `skipped` bytes of garbage are in front of it
+    Complete { skipped: usize, length: usize },
+    /// the rest of the message was not written yet
+    Incomplete,
+    /// no storage header, the bytes can be dropped
+    Garbage(usize),
+}
+
+fn next_message(input: &[u8]) -> NextMessage {
+    match forward_to_next_storage_header(input) {
+        Some((dropped, rest)) => {
+            if rest.len() < LENGTH_END {
+                return NextMessage::Incomplete;
+            }
+            let length = u16::from_be_bytes([rest[LENGTH_END - 2], rest[LENGTH_END - 1]]) as usize;
+            if rest.len() < STORAGE_HEADER_LENGTH + length {
+                NextMessage::Incomplete
+            } else {
+                NextMessage::Complete {
+                    skipped: dropped,
+                    length: dropped + STORAGE_HEADER_LENGTH + length,
+                }
+            }
+        }
+        // the end could be the start of a storage header
+        None => NextMessage::Garbage(input.len().saturating_sub(DLT_PATTERN_SIZE - 1)),
+    }
+}
+
+/// writes the messages of a followed dlt file and reports the chunks
+struct FollowedMessages<'a> {
+    tag: &'a str,
+    filter_config: Option<filtering::ProcessedDltFilterConfig>,
+    fibex_metadata: Option<Rc<FibexMetadata>>,
+    writer: BufWriter<fs::File>,
+    chunk_factory: ChunkFactory,
+    line_nr: usize,
+    message_nr: usize,
+    update_channel: cc::Sender<ChunkResults>,
+}
+
+impl<'a> FollowedMessages<'a> {
+    /// parses all complete messages, returns the number of consumed bytes
+    fn write_messages(&mut self, input: &[u8]) -> Result<usize, Error> {
+        let mut consumed = 0;
+        loop {
+            let (skipped, length) = match next_message(&input[consumed..]) {
+                NextMessage::Complete { skipped, length } => (skipped, length),
+                NextMessage::Incomplete => break,
+                NextMessage::Garbage(length) => {
+                    consumed += length;
+                    break;
+                }
+            };
+            let message = &input[consumed..consumed + length];
+            let parsed = dlt_message(
+                message,
+                self.filter_config.as_ref(),
+                self.message_nr,
+                Some(&self.update_channel),
+                self.fibex_metadata.clone(),
+                true,
+            );
+            self.message_nr += 1;
+            match parsed {
+                Ok((_, ParsedMessage::Item(msg))) => {
+                    let written_bytes_len = utils::create_tagged_line_d(
+                        self.tag,
+                        &mut self.writer,
+                        &msg,
+                        self.line_nr,
+                        true,
+                    )?;
+                    self.line_nr += 1;
+                    if let Some(chunk) = self
+                        .chunk_factory
+                        .add_bytes(self.line_nr, written_bytes_len)
+                    {
+                        self.writer.flush()?;
+                        self.update_channel
+                            .send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+                    }
+                    consumed += length;
+                }
+                Ok(_) => consumed += length,
+                Err(DltParseError::ParsingHickup { reason })
+                | Err(DltParseError::Unrecoverable { cause: reason }) => {
+                    warn!("follow: skip message that could not be parsed: {}", reason);
+                    // look for the next message after this storage header
+                    consumed += skipped + DLT_PATTERN_SIZE;
+                }
+                Err(DltParseError::IncompleteParse { .. }) => consumed += length,
+            }
+        }
+        Ok(consumed)
+    }
+
+    /// reports the lines that are not yet part of a chunk
+    fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        if let Some(chunk) = self.chunk_factory.create_pending_chunk(self.line_nr) {
+            self.update_channel
+                .send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+        }
+        Ok(())
+    }
+}
+
+/// like `dlt_file::create_index_and_mapping_dlt` but the file is followed after
+/// its end was reached: appended messages are indexed as they come in and a
+/// chunk is reported whenever the file has no new content. A message that is
+/// only partly written is parsed when it is complete. Truncated or rotated
+/// files are read again from the start. Runs until a stop is requested.
+pub fn follow_dlt_file(
+    config: IndexingConfig,
+    dlt_filter: Option<filtering::DltFilterConfig>,
+    fibex_metadata: Option<FibexMetadata>,
+    poll_interval: Duration,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<(), Error> {
+    trace!("follow_dlt_file {:?}", config);
+    let line_nr = if config.append {
+        utils::next_line_nr(config.out_path)?
+    } else {
+        0
+    };
+    let (out_file, current_out_file_size) =
+        utils::get_out_file_and_size(config.append, config.out_path)?;
+    let mut followed = match FollowedFile::open(&config.in_file) {
+        Ok(followed) => followed,
+        Err(e) => {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: format!("could not follow file ({})", e),
+                line: None,
+            }));
+            return Err(e);
+        }
+    };
+    let mut messages = FollowedMessages {
+        tag: config.tag,
+        filter_config: dlt_filter.map(filtering::process_filter_config),
+        fibex_metadata: fibex_metadata.map(Rc::new),
+        writer: BufWriter::with_capacity(DLT_READER_CAPACITY, out_file),
+        chunk_factory: ChunkFactory::new(config.chunk_size, current_out_file_size),
+        line_nr,
+        message_nr: 0,
+        update_channel: update_channel.clone(),
+    };
+    // bytes that do not form a complete message yet
+    let mut pending: Vec<u8> = vec![];
+    let mut read_buf = vec![0u8; FOLLOW_READ_SIZE];
+    loop {
+        let len = followed.read(&mut read_buf)?;
+        if len > 0 {
+            pending.extend_from_slice(&read_buf[..len]);
+            let consumed = messages.write_messages(&pending)?;
+            pending.drain(..consumed);
+            continue;
+        }
+        // reached the current end of the file
+        messages.flush()?;
+        match followed.check_source()? {
+            SourceChange::Unchanged => {
+                if wait_for_more(poll_interval, &shutdown_receiver) {
+                    break;
+                }
+                continue;
+            }
+            SourceChange::Truncated => (),
+            SourceChange::Rotated { rest } => {
+                pending.extend_from_slice(&rest);
+                let consumed = messages.write_messages(&pending)?;
+                pending.drain(..consumed);
+            }
+        }
+        if !pending.is_empty() {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::WARNING,
+                content: format!(
+                    "dropped {} bytes of an incomplete message at the end of the old file",
+                    pending.len()
+                ),
+                line: Some(messages.line_nr),
+            }));
+            pending.clear();
+        }
+        messages.flush()?;
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "{:?} was truncated or replaced, reading it from the start",
+                config.in_file
+            ),
+            line: Some(messages.line_nr),
+        }));
+    }
+    messages.flush()?;
+    update_channel.send(Ok(IndexingProgress::Stopped))?;
+    Ok(())
+}
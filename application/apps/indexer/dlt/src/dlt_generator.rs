@@ -0,0 +1,445 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::*;
+use failure::{err_msg, Error};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rustc_hash::FxHashMap;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// log levels in the order of `DltGeneratorConfig::level_weights`
+pub const GENERATED_LEVELS: [LogLevel; 6] = [
+    LogLevel::Fatal,
+    LogLevel::Error,
+    LogLevel::Warn,
+    LogLevel::Info,
+    LogLevel::Debug,
+    LogLevel::Verbose,
+];
+
+/// non-verbose message ids start here, one id per (app, context, level)
+const FIRST_FRAME_ID: u32 = 1000;
+
+/// describes what kind of dlt file should be generated
+#[derive(Debug, Clone)]
+pub struct DltGeneratorConfig {
+    /// stop after this many bytes have been written
+    pub target_size: u64,
+    pub ecus: Vec<String>,
+    pub apps: Vec<String>,
+    pub contexts: Vec<String>,
+    /// relative frequency of fatal, error, warn, info, debug and verbose messages
+    pub level_weights: [u32; 6],
+    /// fraction (0.0 - 1.0) of messages that are sent non-verbose
+    pub non_verbose_ratio: f64,
+    /// fraction (0.0 - 1.0) of messages that are damaged
+    pub corruption_ratio: f64,
+    /// storage time of the first message (seconds since epoch)
+    pub start_time: u32,
+    /// the same seed always produces the same file
+    pub seed: u64,
+}
+
+impl Default for DltGeneratorConfig {
+    fn default() -> Self {
+        DltGeneratorConfig {
+            target_size: 1024 * 1024,
+            ecus: vec!["ECU1".to_string(), "ECU2".to_string()],
+            apps: vec![
+                "APP1".to_string(),
+                "APP2".to_string(),
+                "DIAG".to_string(),
+                "NAV".to_string(),
+            ],
+            contexts: vec!["CTX1".to_string(), "CTX2".to_string(), "MAIN".to_string()],
+            level_weights: [1, 5, 10, 60, 20, 4],
+            non_verbose_ratio: 0.0,
+            corruption_ratio: 0.0,
+            start_time: 1_580_000_000,
+            seed: 0,
+        }
+    }
+}
+
+/// what was actually written
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeneratorStats {
+    pub messages: usize,
+    pub non_verbose: usize,
+    pub corrupted: usize,
+    pub bytes: u64,
+}
+
+/// a non-verbose message: a description followed by one value
+struct FrameTemplate {
+    description: &'static str,
+    signal: &'static str,
+    byte_length: usize,
+}
+
+const FRAME_TEMPLATES: [FrameTemplate; 3] = [
+    FrameTemplate {
+        description: "speed:",
+        signal: "S_UINT32",
+        byte_length: 4,
+    },
+    FrameTemplate {
+        description: "temperature:",
+        signal: "S_SINT16",
+        byte_length: 2,
+    },
+    FrameTemplate {
+        description: "state changed to",
+        signal: "S_UINT8",
+        byte_length: 1,
+    },
+];
+
+struct Frame {
+    id: u32,
+    app: String,
+    context: String,
+    level: LogLevel,
+    template: &'static FrameTemplate,
+}
+
+const VERBOSE_TEXTS: [&str; 8] = [
+    "connection established",
+    "received request",
+    "cache miss for key",
+    "retrying operation",
+    "timeout waiting for response",
+    "state machine transition",
+    "buffer usage",
+    "configuration reloaded",
+];
+
+/// writes random but realistic dlt messages (with storage headers)
+pub struct DltGenerator {
+    config: DltGeneratorConfig,
+    rng: StdRng,
+    frames: Vec<Frame>,
+    uptimes: FxHashMap<String, u32>,
+    /// message counters are kept per ECU and app
+    counters: FxHashMap<(String, String), u8>,
+    /// storage time in us
+    time_us: u64,
+}
+
+impl DltGenerator {
+    pub fn new(config: DltGeneratorConfig) -> Result<Self, Error> {
+        if config.ecus.is_empty() || config.apps.is_empty() || config.contexts.is_empty() {
+            return Err(err_msg("need at least one ECU, app and context"));
+        }
+        if config.level_weights.iter().all(|w| *w == 0) {
+            return Err(err_msg("at least one log level needs a weight"));
+        }
+        let frames = create_frames(&config);
+        Ok(DltGenerator {
+            rng: StdRng::seed_from_u64(config.seed),
+            time_us: u64::from(config.start_time) * 1_000_000,
+            uptimes: FxHashMap::default(),
+            counters: FxHashMap::default(),
+            frames,
+            config,
+        })
+    }
+
+    /// FIBEX that describes the generated non-verbose messages
+    pub fn fibex(&self) -> String {
+        generate_fibex(&self.frames)
+    }
+
+    pub fn generate<W: Write>(&mut self, out: &mut W) -> Result<GeneratorStats, Error> {
+        let mut stats = GeneratorStats::default();
+        while stats.bytes < self.config.target_size {
+            let non_verbose = self.rng.gen_bool(self.config.non_verbose_ratio);
+            let msg = self.next_message(non_verbose);
+            let mut bytes = msg.as_bytes();
+            if self.rng.gen_bool(self.config.corruption_ratio) {
+                bytes = self.corrupt(bytes);
+                stats.corrupted += 1;
+            }
+            out.write_all(&bytes)?;
+            stats.bytes += bytes.len() as u64;
+            stats.messages += 1;
+            if non_verbose {
+                stats.non_verbose += 1;
+            }
+        }
+        out.flush()?;
+        Ok(stats)
+    }
+
+    fn pick_level(&mut self) -> LogLevel {
+        let total: u32 = self.config.level_weights.iter().sum();
+        let mut n = self.rng.gen_range(0, total);
+        for (i, weight) in self.config.level_weights.iter().enumerate() {
+            if n < *weight {
+                return GENERATED_LEVELS[i];
+            }
+            n -= weight;
+        }
+        LogLevel::Info
+    }
+
+    fn next_message(&mut self, non_verbose: bool) -> Message {
+        let ecu = pick(&mut self.rng, &self.config.ecus).clone();
+        self.time_us += self.rng.gen_range(0, 2_000);
+        let delta = self.rng.gen_range(0, 20);
+        let uptime = self.uptimes.entry(ecu.clone()).or_insert(0);
+        *uptime = uptime.wrapping_add(delta);
+        let uptime = *uptime;
+
+        let level = self.pick_level();
+        let (app, context, payload_content) = if non_verbose {
+            let candidates: Vec<usize> = (0..self.frames.len())
+                .filter(|i| self.frames[*i].level == level)
+                .collect();
+            let rng = &mut self.rng;
+            let frame = &self.frames[candidates[rng.gen_range(0, candidates.len())]];
+            let value: Vec<u8> = (0..frame.template.byte_length).map(|_| rng.gen()).collect();
+            (
+                frame.app.clone(),
+                frame.context.clone(),
+                PayloadContent::NonVerbose(frame.id, value),
+            )
+        } else {
+            let app = pick(&mut self.rng, &self.config.apps).clone();
+            let context = pick(&mut self.rng, &self.config.contexts).clone();
+            let text = VERBOSE_TEXTS[self.rng.gen_range(0, VERBOSE_TEXTS.len())];
+            let value: u32 = self.rng.gen_range(0, 100_000);
+            (
+                app,
+                context,
+                PayloadContent::Verbose(vec![string_argument(text), u32_argument(value)]),
+            )
+        };
+        let next_counter = self.counters.entry((ecu.clone(), app.clone())).or_insert(0);
+        let counter = *next_counter;
+        *next_counter = counter.wrapping_add(1);
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some(ecu),
+                session_id: None,
+                timestamp: Some(uptime),
+                payload: Payload2 { payload_content },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(level),
+                    app_id: app,
+                    context_id: context,
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds: (self.time_us / 1_000_000) as u32,
+            microseconds: (self.time_us % 1_000_000) as u32,
+        }))
+    }
+
+    /// truncates the message, overwrites some bytes or adds garbage after it
+    fn corrupt(&mut self, mut bytes: Vec<u8>) -> Vec<u8> {
+        match self.rng.gen_range(0, 3) {
+            0 => {
+                let keep = self.rng.gen_range(1, bytes.len());
+                bytes.truncate(keep);
+            }
+            1 => {
+                for _ in 0..self.rng.gen_range(1, 4) {
+                    let i = self.rng.gen_range(0, bytes.len());
+                    bytes[i] = self.rng.gen();
+                }
+            }
+            _ => {
+                for _ in 0..self.rng.gen_range(1, 16) {
+                    bytes.push(self.rng.gen());
+                }
+            }
+        }
+        bytes
+    }
+}
+
+/// writes a generated dlt file and, if requested, the FIBEX for its
+/// non-verbose messages
+pub fn generate_dlt_file(
+    config: DltGeneratorConfig,
+    out_path: &Path,
+    fibex_path: Option<&Path>,
+) -> Result<GeneratorStats, Error> {
+    let mut generator = DltGenerator::new(config)?;
+    if let Some(path) = fibex_path {
+        fs::write(path, generator.fibex())?;
+    }
+    let mut out = BufWriter::new(fs::File::create(out_path)?);
+    generator.generate(&mut out)
+}
+
+fn pick<'a>(rng: &mut StdRng, items: &'a [String]) -> &'a String {
+    &items[rng.gen_range(0, items.len())]
+}
+
+fn string_argument(text: &str) -> Argument {
+    Argument {
+        type_info: TypeInfo {
+            kind: TypeInfoKind::StringType,
+            coding: StringCoding::UTF8,
+            has_variable_info: false,
+            has_trace_info: false,
+        },
+        name: None,
+        unit: None,
+        fixed_point: None,
+        value: Value::StringVal(text.to_string()),
+    }
+}
+
+fn u32_argument(value: u32) -> Argument {
+    Argument {
+        type_info: TypeInfo {
+            kind: TypeInfoKind::Unsigned(TypeLength::BitLength32),
+            coding: StringCoding::ASCII,
+            has_variable_info: false,
+            has_trace_info: false,
+        },
+        name: None,
+        unit: None,
+        fixed_point: None,
+        value: Value::U32(value),
+    }
+}
+
+fn create_frames(config: &DltGeneratorConfig) -> Vec<Frame> {
+    let mut frames = vec![];
+    for app in &config.apps {
+        for context in &config.contexts {
+            for (i, level) in GENERATED_LEVELS.iter().enumerate() {
+                if config.level_weights[i] == 0 {
+                    continue;
+                }
+                let id = FIRST_FRAME_ID + frames.len() as u32;
+                frames.push(Frame {
+                    id,
+                    app: app.clone(),
+                    context: context.clone(),
+                    level: *level,
+                    template: &FRAME_TEMPLATES[id as usize % FRAME_TEMPLATES.len()],
+                });
+            }
+        }
+    }
+    frames
+}
+
+fn fibex_message_info(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Fatal => "DLT_LOG_FATAL",
+        LogLevel::Error => "DLT_LOG_ERROR",
+        LogLevel::Warn => "DLT_LOG_WARN",
+        LogLevel::Info => "DLT_LOG_INFO",
+        LogLevel::Debug => "DLT_LOG_DEBUG",
+        _ => "DLT_LOG_VERBOSE",
+    }
+}
+
+fn generate_fibex(frames: &[Frame]) -> String {
+    let mut pdus = String::new();
+    let mut frame_elements = String::new();
+    for frame in frames {
+        let desc_pdu = format!("PDU_{}_0", frame.id);
+        let value_pdu = format!("PDU_{}_1", frame.id);
+        let _ = write!(
+            pdus,
+            r#"
+            <fx:PDU ID="{desc_pdu}">
+                <ho:SHORT-NAME>{desc_pdu}</ho:SHORT-NAME>
+                <ho:DESC>{desc}</ho:DESC>
+                <fx:BYTE-LENGTH>0</fx:BYTE-LENGTH>
+                <fx:PDU-TYPE>OTHER</fx:PDU-TYPE>
+            </fx:PDU>
+            <fx:PDU ID="{value_pdu}">
+                <ho:SHORT-NAME>{value_pdu}</ho:SHORT-NAME>
+                <fx:BYTE-LENGTH>{len}</fx:BYTE-LENGTH>
+                <fx:PDU-TYPE>OTHER</fx:PDU-TYPE>
+                <fx:SIGNAL-INSTANCES>
+                    <fx:SIGNAL-INSTANCE ID="SI_{id}">
+                        <fx:SEQUENCE-NUMBER>0</fx:SEQUENCE-NUMBER>
+                        <fx:SIGNAL-REF ID-REF="{signal}"/>
+                    </fx:SIGNAL-INSTANCE>
+                </fx:SIGNAL-INSTANCES>
+            </fx:PDU>"#,
+            desc_pdu = desc_pdu,
+            value_pdu = value_pdu,
+            desc = frame.template.description,
+            len = frame.template.byte_length,
+            id = frame.id,
+            signal = frame.template.signal,
+        );
+        let _ = write!(
+            frame_elements,
+            r#"
+            <fx:FRAME ID="ID_{id}">
+                <ho:SHORT-NAME>{desc}</ho:SHORT-NAME>
+                <fx:BYTE-LENGTH>{len}</fx:BYTE-LENGTH>
+                <fx:FRAME-TYPE>OTHER</fx:FRAME-TYPE>
+                <fx:PDU-INSTANCES>
+                    <fx:PDU-INSTANCE ID="PI_{id}_0">
+                        <fx:PDU-REF ID-REF="{desc_pdu}"/>
+                        <fx:SEQUENCE-NUMBER>0</fx:SEQUENCE-NUMBER>
+                    </fx:PDU-INSTANCE>
+                    <fx:PDU-INSTANCE ID="PI_{id}_1">
+                        <fx:PDU-REF ID-REF="{value_pdu}"/>
+                        <fx:SEQUENCE-NUMBER>1</fx:SEQUENCE-NUMBER>
+                    </fx:PDU-INSTANCE>
+                </fx:PDU-INSTANCES>
+                <fx:MANUFACTURER-EXTENSION>
+                    <MESSAGE_TYPE>DLT_TYPE_LOG</MESSAGE_TYPE>
+                    <MESSAGE_INFO>{info}</MESSAGE_INFO>
+                    <APPLICATION_ID>{app}</APPLICATION_ID>
+                    <CONTEXT_ID>{context}</CONTEXT_ID>
+                </fx:MANUFACTURER-EXTENSION>
+            </fx:FRAME>"#,
+            id = frame.id,
+            desc = frame.template.description,
+            len = frame.template.byte_length,
+            desc_pdu = desc_pdu,
+            value_pdu = value_pdu,
+            info = fibex_message_info(frame.level),
+            app = frame.app,
+            context = frame.context,
+        );
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<fx:FIBEX xmlns:ho="http://www.asam.net/xml" xmlns:fx="http://www.asam.net/xml/fbx">
+    <fx:PROJECT ID="Generated">
+        <ho:SHORT-NAME>Generated</ho:SHORT-NAME>
+    </fx:PROJECT>
+    <fx:ELEMENTS>
+        <fx:PDUS>{}
+        </fx:PDUS>
+        <fx:FRAMES>{}
+        </fx:FRAMES>
+    </fx:ELEMENTS>
+</fx:FIBEX>
+"#,
+        pdus, frame_elements
+    )
+}
@@ -1,23 +1,28 @@
 extern crate dirs;
 use crate::dlt::*;
-use crate::dlt_file::create_dlt_session_file;
+use crate::dlt_file::{create_dlt_session_file, session_file_path};
 use crate::dlt_parse::dlt_message;
 use crate::dlt_parse::*;
 use crate::fibex::FibexMetadata;
 use crate::filtering;
-use async_std::net::{Ipv4Addr, UdpSocket};
+use crate::reorder::ReorderBuffer;
+use crate::ring_buffer::SegmentRing;
+use async_std::net::{IpAddr, Ipv4Addr, UdpSocket};
 use crossbeam_channel as cc;
 use failure::err_msg;
 use futures::stream::StreamExt;
 use futures::FutureExt;
 use indexer_base::chunks::Chunk;
 use indexer_base::chunks::{ChunkFactory, ChunkResults};
-use indexer_base::config::SocketConfig;
+use indexer_base::config::{SocketConfig, UdpEndpoint};
 use indexer_base::progress::*;
 use indexer_base::utils;
+use std::borrow::Borrow;
+use std::fmt;
 use std::io::{BufWriter, Write};
 use std::net::SocketAddr;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Fail)]
 pub enum ConnectionError {
@@ -63,39 +68,41 @@ pub async fn index_from_socket2(
 ) -> Result<(), ConnectionError> {
     debug!("index_from_socket: with socket conf: {:?}", socket_config);
     let (out_file, current_out_file_size) = utils::get_out_file_and_size(true, out_path)?;
-    let tmp_dlt_file = create_dlt_session_file(&session_id)?;
-
-    let mut tmp_writer = BufWriter::new(tmp_dlt_file);
-    let mut chunk_factory = ChunkFactory::new(0, current_out_file_size);
-    let mut line_nr = initial_line_nr;
-    let mut buf_writer = BufWriter::with_capacity(10 * 1024 * 1024, out_file);
-    let s = format!("{}:{}", socket_config.bind_addr, socket_config.port);
-    let bind_addr_and_port: SocketAddr = s.parse()?;
-    debug!("create UDP socket by binding to: {}", bind_addr_and_port);
-    let socket = UdpSocket::bind(bind_addr_and_port).await.map_err(|e| {
-        warn!("error trying to bind to {}: {}", bind_addr_and_port, e);
-        err_msg(format!(
-            "you cannot not bind a UDP socket to {}",
-            socket_config.bind_addr
-        ))
-    })?;
-    if let Some(multicast_info) = socket_config.multicast_addr {
-        let multi_addr = multicast_info.multiaddr.parse()?;
-        let inter = match multicast_info.interface {
-            Some(s) => s.parse()?,
-            None => Ipv4Addr::new(0, 0, 0, 0),
-        };
-        debug!(
-            "joining UDP multicast group: {} on interface: {}",
-            multi_addr, inter
-        );
-        if let Err(e) = socket.join_multicast_v4(multi_addr, inter) {
-            return Err(ConnectionError::UnableToConnect {
-                reason: format!("error joining multicast group: {}", e),
-            });
+    // in ring buffer mode the raw messages go to segment files, the text
+    // output is always written to the index file (which the ring trims)
+    let (ring, tmp_dlt_file) = match socket_config.ring_buffer.clone() {
+        Some(ring_config) => {
+            let (ring, dlt_file) = SegmentRing::start(
+                ring_config,
+                session_file_path(&session_id)?,
+                out_path.clone(),
+                initial_line_nr,
+                Instant::now(),
+            )?;
+            (Some(ring), dlt_file)
         }
+        None => (None, create_dlt_session_file(&session_id)?),
+    };
+
+    if socket_config.endpoints.is_empty() {
+        return Err(ConnectionError::WrongConfiguration {
+            cause: "no UDP endpoint configured".to_string(),
+        });
     }
-    trace!("created socket...");
+    let fibex_metadata = fibex_metadata.map(Rc::new);
+    let mut producers: Vec<UdpMessageProducer> = vec![];
+    for endpoint in &socket_config.endpoints {
+        producers.push(UdpMessageProducer {
+            socket: bind_udp_endpoint(endpoint).await?,
+            update_channel: update_channel.clone(),
+            fibex_metadata: fibex_metadata.clone(),
+            filter_config: filter_config.clone(),
+        });
+    }
+    // the last column tells where a message came from (also for a single
+    // endpoint so the layout does not depend on the configuration)
+    let source_labels: Vec<String> = socket_config.endpoints.iter().map(endpoint_label).collect();
+    trace!("created {} socket(s)...", producers.len());
     // send (0,0),(0,0) to indicate connection established
     let _ = update_channel.send(Ok(IndexingProgress::GotItem {
         item: Chunk {
@@ -103,26 +110,50 @@ pub async fn index_from_socket2(
             b: (0, 0),
         },
     }));
-    let udp_msg_producer = UdpMessageProducer {
-        socket,
-        update_channel: update_channel.clone(),
-        fibex_metadata: fibex_metadata.map(Rc::new),
-        filter_config,
+    let mut session_writer = SessionWriter {
+        tmp_writer: BufWriter::new(tmp_dlt_file),
+        buf_writer: BufWriter::with_capacity(SESSION_WRITER_CAPACITY, out_file),
+        out_path,
+        chunk_factory: ChunkFactory::new(0, current_out_file_size),
+        line_nr: initial_line_nr,
+        tag,
+        source_labels,
+        ring,
+        update_channel: &update_channel,
     };
+    let mut reorder_buffer = socket_config
+        .reorder_window_ms
+        .map(|ms| ReorderBuffer::new(Duration::from_millis(ms)));
     // listen for both a shutdown request and incomming messages
     // to do this we need to select over streams of the same type
     // the type we use to unify is this Event enum
     enum Event {
         Shutdown,
-        Msg(Result<Option<Vec<Message>>, DltParseError>),
+        Tick,
+        Msg(usize, Result<Option<Vec<Message>>, DltParseError>),
     }
     let shutdown_stream = shutdown_receiver.map(|_| {
         debug!("shutdown_receiver event");
         Event::Shutdown
     });
-    let message_stream: futures::stream::Map<UdpMessageProducer, _> =
-        udp_msg_producer.map(Event::Msg);
-    let mut event_stream = futures::stream::select(message_stream, shutdown_stream);
+    // with a reorder buffer we have to check regularly if messages are due,
+    // without one there is no timer at all
+    let tick_stream = match socket_config.reorder_window_ms {
+        Some(ms) => async_std::stream::interval(Duration::from_millis(std::cmp::max(ms / 4, 1)))
+            .map(|_| Event::Tick)
+            .boxed_local(),
+        None => futures::stream::pending().boxed_local(),
+    };
+    let message_stream = futures::stream::select_all(
+        producers
+            .into_iter()
+            .enumerate()
+            .map(|(source, producer)| producer.map(move |res| Event::Msg(source, res))),
+    );
+    let mut event_stream = futures::stream::select(
+        futures::stream::select(message_stream, shutdown_stream),
+        tick_stream,
+    );
     while let Some(event) = event_stream.next().await {
         let maybe_msgs = match event {
             Event::Shutdown => {
@@ -130,8 +161,18 @@ pub async fn index_from_socket2(
                 let _ = update_channel.send(Ok(IndexingProgress::Stopped));
                 break;
             }
-            Event::Msg(Ok(maybe_msg)) => maybe_msg,
-            Event::Msg(Err(DltParseError::ParsingHickup { reason })) => {
+            Event::Tick => {
+                if let Some(buffer) = reorder_buffer.as_mut() {
+                    session_writer.write(buffer.pop_ready(Instant::now()))?;
+                }
+                continue;
+            }
+            Event::Msg(source, Ok(maybe_msg)) => maybe_msg.map(|msgs| {
+                msgs.into_iter()
+                    .map(|msg| SourcedMessage { msg, source })
+                    .collect::<Vec<SourcedMessage>>()
+            }),
+            Event::Msg(_, Err(DltParseError::ParsingHickup { reason })) => {
                 let _ = update_channel.send(Err(Notification {
                     severity: Severity::WARNING,
                     content: format!("parsing faild for one message: {}", reason),
@@ -139,21 +180,21 @@ pub async fn index_from_socket2(
                 }));
                 None
             }
-            Event::Msg(Err(DltParseError::Unrecoverable { .. })) => break,
-            Event::Msg(Err(DltParseError::IncompleteParse { .. })) => break,
+            Event::Msg(_, Err(DltParseError::Unrecoverable { .. })) => break,
+            Event::Msg(_, Err(DltParseError::IncompleteParse { .. })) => break,
         };
         match maybe_msgs {
             Some(msgs) => {
                 trace!("socket: got {} messages ...", msgs.len());
-                for m in msgs {
-                    tmp_writer.write_all(&m.as_bytes())?;
-                    let written_bytes_len =
-                        utils::create_tagged_line_d(tag, &mut buf_writer, &m, line_nr, true)?;
-                    line_nr += 1;
-                    if let Some(chunk) = chunk_factory.add_bytes(line_nr, written_bytes_len) {
-                        buf_writer.flush()?;
-                        let _ = update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }));
+                match reorder_buffer.as_mut() {
+                    Some(buffer) => {
+                        let now = Instant::now();
+                        for m in msgs {
+                            buffer.push(m, now);
+                        }
+                        session_writer.write(buffer.pop_ready(now))?;
                     }
+                    None => session_writer.write(msgs)?,
                 }
             }
             None => {
@@ -161,10 +202,183 @@ pub async fn index_from_socket2(
             }
         }
     }
-    tmp_writer.flush()?;
+    if let Some(buffer) = reorder_buffer.as_mut() {
+        session_writer.write(buffer.flush())?;
+    }
+    session_writer.flush()?;
     Ok(())
 }
 
+/// a received message together with the index of the endpoint it came from
+pub struct SourcedMessage {
+    pub msg: Message,
+    pub source: usize,
+}
+
+impl Borrow<Message> for SourcedMessage {
+    fn borrow(&self) -> &Message {
+        &self.msg
+    }
+}
+
+/// formats a message with an additional trailing column for its source
+pub struct MessageWithSource<'a> {
+    pub msg: &'a Message,
+    pub source: &'a str,
+}
+
+impl<'a> fmt::Display for MessageWithSource<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.msg, DLT_COLUMN_SENTINAL, self.source)
+    }
+}
+
+/// how an endpoint is named in the source column
+pub fn endpoint_label(endpoint: &UdpEndpoint) -> String {
+    match &endpoint.multicast_addr {
+        Some(multicast_info) => format!("{}:{}", multicast_info.multiaddr, endpoint.port),
+        None => format!("{}:{}", endpoint.bind_addr, endpoint.port),
+    }
+}
+
+/// binds a socket for the endpoint and joins its multicast group (IPv4 or IPv6)
+pub async fn bind_udp_endpoint(endpoint: &UdpEndpoint) -> Result<UdpSocket, ConnectionError> {
+    let bind_ip: IpAddr = endpoint.bind_addr.parse()?;
+    let port: u16 = endpoint
+        .port
+        .parse()
+        .map_err(|e| ConnectionError::WrongConfiguration {
+            cause: format!("invalid port {} ({})", endpoint.port, e),
+        })?;
+    let bind_addr_and_port = SocketAddr::new(bind_ip, port);
+    debug!("create UDP socket by binding to: {}", bind_addr_and_port);
+    let socket = UdpSocket::bind(bind_addr_and_port).await.map_err(|e| {
+        warn!("error trying to bind to {}: {}", bind_addr_and_port, e);
+        err_msg(format!(
+            "you cannot not bind a UDP socket to {}",
+            bind_addr_and_port
+        ))
+    })?;
+    if let Some(multicast_info) = &endpoint.multicast_addr {
+        let multi_addr: IpAddr = multicast_info.multiaddr.parse()?;
+        debug!(
+            "joining UDP multicast group: {} on interface: {:?}",
+            multi_addr, multicast_info.interface
+        );
+        let joined = match multi_addr {
+            IpAddr::V4(addr) => {
+                let inter = match &multicast_info.interface {
+                    Some(s) => s.parse()?,
+                    None => Ipv4Addr::new(0, 0, 0, 0),
+                };
+                socket.join_multicast_v4(addr, inter)
+            }
+            IpAddr::V6(addr) => {
+                // for IPv6 the interface is given by its index, 0 lets the system choose
+                let inter: u32 = match &multicast_info.interface {
+                    Some(s) => s.parse().map_err(|e| ConnectionError::WrongConfiguration {
+                        cause: format!("invalid IPv6 interface index {} ({})", s, e),
+                    })?,
+                    None => 0,
+                };
+                socket.join_multicast_v6(&addr, inter)
+            }
+        };
+        if let Err(e) = joined {
+            return Err(ConnectionError::UnableToConnect {
+                reason: format!("error joining multicast group: {}", e),
+            });
+        }
+    }
+    Ok(socket)
+}
+
+const SESSION_WRITER_CAPACITY: usize = 10 * 1024 * 1024;
+
+/// writes live messages to the session file (raw dlt) and to the
+/// index file, line numbers are assigned in the order of writing
+struct SessionWriter<'a> {
+    tmp_writer: BufWriter<std::fs::File>,
+    buf_writer: BufWriter<std::fs::File>,
+    out_path: &'a std::path::PathBuf,
+    chunk_factory: ChunkFactory,
+    line_nr: usize,
+    tag: &'a str,
+    source_labels: Vec<String>,
+    ring: Option<SegmentRing>,
+    update_channel: &'a cc::Sender<ChunkResults>,
+}
+
+impl<'a> SessionWriter<'a> {
+    fn write(&mut self, msgs: Vec<SourcedMessage>) -> Result<(), ConnectionError> {
+        for SourcedMessage { msg: m, source } in msgs {
+            self.rotate_if_needed()?;
+            let msg_bytes = m.as_bytes();
+            self.tmp_writer.write_all(&msg_bytes)?;
+            let written_bytes_len = utils::create_tagged_line_d(
+                self.tag,
+                &mut self.buf_writer,
+                MessageWithSource {
+                    msg: &m,
+                    source: &self.source_labels[source],
+                },
+                self.line_nr,
+                true,
+            )?;
+            if let Some(ring) = self.ring.as_mut() {
+                ring.add_message(msg_bytes.len(), written_bytes_len);
+            }
+            self.line_nr += 1;
+            if let Some(chunk) = self
+                .chunk_factory
+                .add_bytes(self.line_nr, written_bytes_len)
+            {
+                self.buf_writer.flush()?;
+                // the lines removed by the ring are no longer in the index file
+                let item = match &self.ring {
+                    Some(ring) => ring.manifest().index_chunk(chunk),
+                    None => chunk,
+                };
+                let _ = self
+                    .update_channel
+                    .send(Ok(IndexingProgress::GotItem { item }));
+            }
+        }
+        Ok(())
+    }
+
+    fn rotate_if_needed(&mut self) -> Result<(), ConnectionError> {
+        let now = Instant::now();
+        let ring = match self.ring.as_mut() {
+            Some(ring) if ring.is_full(now) => ring,
+            _ => return Ok(()),
+        };
+        // the ring cuts the lines of dropped segments from the index file
+        self.buf_writer.flush()?;
+        self.tmp_writer.flush()?;
+        if let Some((dlt_file, notifications)) = ring.rotate_if_needed(now)? {
+            self.tmp_writer = BufWriter::new(dlt_file);
+            let out_file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(self.out_path)?;
+            self.buf_writer = BufWriter::with_capacity(SESSION_WRITER_CAPACITY, out_file);
+            for notification in notifications {
+                let _ = self.update_channel.send(Err(notification));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), ConnectionError> {
+        self.buf_writer.flush()?;
+        self.tmp_writer.flush()?;
+        if let Some(ring) = &self.ring {
+            ring.finish()?;
+        }
+        Ok(())
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub async fn create_index_and_mapping_dlt_from_socket(
     session_id: String,
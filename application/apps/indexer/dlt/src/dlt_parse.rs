@@ -11,8 +11,16 @@
 // from E.S.R.Labs.
 use crate::dlt::*;
 use crate::filtering;
+use crate::lifecycle::{lifecycle_ecu_id, Lifecycle, LifecycleDetector};
+use crate::message_counter::{CounterKey, LostMessagesInfo, MessageCounterTracker};
 use crossbeam_channel as cc;
-use indexer_base::{chunks::ChunkResults, error_reporter::*, progress::*, utils};
+use indexer_base::{
+    chunks::ChunkResults,
+    compression::{input_size, open_input},
+    error_reporter::*,
+    progress::*,
+    utils,
+};
 use serde::Serialize;
 
 use buf_redux::policy::MinBuffered;
@@ -22,7 +30,6 @@ use failure::{err_msg, Error};
 use nom::bytes::streaming::{tag, take, take_while_m_n};
 use nom::{combinator::map, multi::count, number::streaming, sequence::tuple, IResult};
 use rustc_hash::FxHashMap;
-use std::fs;
 use std::io::{BufRead, Read};
 use std::rc::Rc;
 
@@ -958,18 +965,6 @@ fn validated_payload_length<T>(
     Some(message_length - headers_length)
 }
 
-fn skip_till_after_next_storage_header(input: &[u8]) -> Result<(&[u8], usize), DltParseError> {
-    match forward_to_next_storage_header(input) {
-        Some((consumed, rest)) => {
-            let (after_storage_header, skipped_bytes) = skip_storage_header(rest)?;
-            Ok((after_storage_header, consumed + skipped_bytes))
-        }
-        None => Err(DltParseError::ParsingHickup {
-            reason: "did not find another storage header".into(),
-        }),
-    }
-}
-
 /// check if the DLT_PATTERN next and just skip the storage header if so
 /// returns a slice where the storage header was removed
 pub(crate) fn skip_storage_header(input: &[u8]) -> Result<(&[u8], usize), DltParseError> {
@@ -990,11 +985,17 @@ pub fn dlt_statistic_row_info<'a, T>(
     update_channel: Option<&cc::Sender<IndexingResults<T>>>,
 ) -> Result<(&'a [u8], StatisticRowInfo), DltParseError> {
     let update_channel_ref = update_channel;
-    // let (after_storage_header, _) = skip_till_after_next_storage_header(input)?;
-    let (after_storage_header, _) = if with_storage_header {
-        skip_till_after_next_storage_header(input)?
+    let (after_storage_header, storage_header) = if with_storage_header {
+        match forward_to_next_storage_header(input) {
+            Some((_, rest)) => dlt_storage_header::<T>(rest, index, None)?,
+            None => {
+                return Err(DltParseError::ParsingHickup {
+                    reason: "did not find another storage header".into(),
+                })
+            }
+        }
     } else {
-        (input, 0)
+        (input, None)
     };
     let (after_storage_and_normal_header, header) = dlt_standard_header(after_storage_header)?;
 
@@ -1006,6 +1007,10 @@ pub fn dlt_statistic_row_info<'a, T>(
                 StatisticRowInfo {
                     app_id_context_id: None,
                     ecu_id: header.ecu_id,
+                    session_id: header.session_id,
+                    message_counter: header.message_counter,
+                    uptime: header.timestamp,
+                    storage_header,
                     level: None,
                     verbose: false,
                 },
@@ -1020,6 +1025,10 @@ pub fn dlt_statistic_row_info<'a, T>(
             StatisticRowInfo {
                 app_id_context_id: None,
                 ecu_id: header.ecu_id,
+                session_id: header.session_id,
+                message_counter: header.message_counter,
+                uptime: header.timestamp,
+                storage_header,
                 level: None,
                 verbose: false,
             },
@@ -1039,6 +1048,10 @@ pub fn dlt_statistic_row_info<'a, T>(
         StatisticRowInfo {
             app_id_context_id: Some((extended_header.application_id, extended_header.context_id)),
             ecu_id: header.ecu_id,
+            session_id: header.session_id,
+            message_counter: header.message_counter,
+            uptime: header.timestamp,
+            storage_header,
             level,
             verbose: extended_header.verbose,
         },
@@ -1195,6 +1208,8 @@ pub struct StatisticInfo {
     context_ids: Vec<(String, LevelDistribution)>,
     ecu_ids: Vec<(String, LevelDistribution)>,
     contained_non_verbose: bool,
+    lost_messages: Vec<(String, LostMessagesInfo)>,
+    lifecycles: Vec<Lifecycle>,
 }
 pub type StatisticsResults = std::result::Result<IndexingProgress<StatisticInfo>, Notification>;
 pub fn get_dlt_file_info(
@@ -1202,15 +1217,16 @@ pub fn get_dlt_file_info(
     update_channel: &cc::Sender<StatisticsResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) -> Result<(), Error> {
-    let f = match fs::File::open(in_file) {
+    let f = match open_input(in_file) {
         Ok(file) => file,
         Err(e) => {
             error!("could not open {:?}", in_file);
             return Err(err_msg(format!("could not open {:?} ({})", in_file, e)));
         }
     };
+    let compressed_input = f.compressed_input();
 
-    let source_file_size: usize = fs::metadata(&in_file)?.len() as usize;
+    let source_file_size: usize = input_size(in_file)? as usize;
     let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
         .set_policy(MinBuffered(DLT_MIN_BUFFER_SPACE));
 
@@ -1220,6 +1236,10 @@ pub fn get_dlt_file_info(
     let mut index = 0usize;
     let mut processed_bytes = 0usize;
     let mut contained_non_verbose = false;
+    let mut counter_tracker = MessageCounterTracker::new();
+    let mut lifecycle_detector = LifecycleDetector::new();
+    // only successfully parsed messages are counted (like lines in the index)
+    let mut message_nr = 0usize;
     loop {
         match read_one_dlt_message_info(&mut reader, Some(index), true, Some(update_channel)) {
             Ok(Some((
@@ -1227,12 +1247,31 @@ pub fn get_dlt_file_info(
                 StatisticRowInfo {
                     app_id_context_id: Some((app_id, context_id)),
                     ecu_id: ecu,
+                    session_id,
+                    message_counter,
+                    uptime,
+                    storage_header,
                     level,
                     verbose,
                 },
             ))) => {
                 contained_non_verbose = contained_non_verbose || !verbose;
                 reader.consume(consumed);
+                lifecycle_detector.track(
+                    message_nr,
+                    lifecycle_ecu_id(ecu.as_ref(), storage_header.as_ref().map(|h| &h.ecu_id)),
+                    uptime,
+                    storage_header.as_ref().map(|h| &h.timestamp),
+                );
+                message_nr += 1;
+                counter_tracker.track(
+                    CounterKey {
+                        ecu_id: ecu.clone(),
+                        session_id,
+                        app_id: Some(app_id.clone()),
+                    },
+                    message_counter,
+                );
                 add_for_level(level, &mut app_ids, app_id);
                 add_for_level(level, &mut context_ids, context_id);
                 match ecu {
@@ -1246,12 +1285,31 @@ pub fn get_dlt_file_info(
                 StatisticRowInfo {
                     app_id_context_id: None,
                     ecu_id: ecu,
+                    session_id,
+                    message_counter,
+                    uptime,
+                    storage_header,
                     level,
                     verbose,
                 },
             ))) => {
                 contained_non_verbose = contained_non_verbose || !verbose;
                 reader.consume(consumed);
+                lifecycle_detector.track(
+                    message_nr,
+                    lifecycle_ecu_id(ecu.as_ref(), storage_header.as_ref().map(|h| &h.ecu_id)),
+                    uptime,
+                    storage_header.as_ref().map(|h| &h.timestamp),
+                );
+                message_nr += 1;
+                counter_tracker.track(
+                    CounterKey {
+                        ecu_id: ecu.clone(),
+                        session_id,
+                        app_id: None,
+                    },
+                    message_counter,
+                );
                 add_for_level(level, &mut app_ids, "NONE".to_string());
                 add_for_level(level, &mut context_ids, "NONE".to_string());
                 match ecu {
@@ -1310,8 +1368,12 @@ pub fn get_dlt_file_info(
                 update_channel.send(Ok(IndexingProgress::Stopped))?;
                 break;
             }
+            let progress = match &compressed_input {
+                Some(read_bytes) => read_bytes.get(),
+                None => processed_bytes,
+            };
             update_channel.send(Ok(IndexingProgress::Progress {
-                ticks: (processed_bytes, source_file_size),
+                ticks: (progress, source_file_size),
             }))?;
         }
     }
@@ -1326,6 +1388,8 @@ pub fn get_dlt_file_info(
             .into_iter()
             .collect::<Vec<(String, LevelDistribution)>>(),
         contained_non_verbose,
+        lost_messages: counter_tracker.lost_messages(),
+        lifecycles: lifecycle_detector.finish(),
     };
 
     update_channel.send(Ok(IndexingProgress::GotItem { item: res }))?;
@@ -1337,10 +1401,28 @@ pub fn get_dlt_file_info(
 pub struct StatisticRowInfo {
     app_id_context_id: Option<(String, String)>,
     ecu_id: Option<String>,
+    session_id: Option<u32>,
+    message_counter: u8,
+    uptime: Option<u32>,
+    storage_header: Option<StorageHeader>,
     level: Option<LogLevel>,
     verbose: bool,
 }
-fn read_one_dlt_message_info<T: Read>(
+impl StatisticRowInfo {
+    pub(crate) fn ecu_id(&self) -> Option<&String> {
+        self.ecu_id.as_ref()
+    }
+    pub(crate) fn storage_ecu_id(&self) -> Option<&String> {
+        self.storage_header.as_ref().map(|h| &h.ecu_id)
+    }
+    pub(crate) fn uptime(&self) -> Option<u32> {
+        self.uptime
+    }
+    pub(crate) fn storage_time(&self) -> Option<&DltTimeStamp> {
+        self.storage_header.as_ref().map(|h| &h.timestamp)
+    }
+}
+pub(crate) fn read_one_dlt_message_info<T: Read>(
     reader: &mut ReduxReader<T, MinBuffered>,
     index: Option<usize>,
     with_storage_header: bool,
@@ -1,5 +1,8 @@
 use crate::dlt::*;
+use crate::dlt_export::SectionTracker;
+use crate::dlt_file::FileMessageProducer;
 use crate::dlt_parse::*;
+use crate::dlt_split::SequentialCopier;
 use crate::fibex::FibexMetadata;
 use crate::filtering;
 use async_std::task;
@@ -8,14 +11,19 @@ use etherparse::*;
 use failure::{err_msg, Error};
 use futures::stream::StreamExt;
 use indexer_base::chunks::{ChunkFactory, ChunkResults};
-use indexer_base::config::IndexingConfig;
+use indexer_base::compression::{input_exists, input_size, open_input, ConsumedBytes, InputReader};
+use indexer_base::config::{IndexingConfig, SectionConfig};
 use indexer_base::progress::*;
 use indexer_base::utils;
 use pcap_parser::traits::PcapReaderIterator;
 use pcap_parser::PcapNGReader;
 use pcap_parser::*;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
 use std::fs::*;
 use std::io::{BufWriter, Write};
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::Path;
 use std::rc::Rc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -60,29 +68,47 @@ pub fn convert_to_dlt_file(
 }
 
 struct PcapMessageProducer {
-    reader: PcapNGReader<File>,
+    reader: PcapNGReader<InputReader>,
+    /// bytes read from the (maybe compressed) file
+    read_bytes: ConsumedBytes,
     update_channel: cc::Sender<ChunkResults>,
     index: usize,
     fibex_metadata: Option<Rc<FibexMetadata>>,
     filter_config: Option<filtering::ProcessedDltFilterConfig>,
+    /// timestamp resolution and offset of the interfaces in the current section
+    interfaces: Vec<(u8, u64)>,
+}
+
+/// microseconds since the epoch of a packet, the resolution is defined by
+/// its interface (microseconds if not specified)
+pub(crate) fn packet_time_us(epb: &EnhancedPacketBlock, interfaces: &[(u8, u64)]) -> u64 {
+    let (ts_resol, ts_offset) = interfaces
+        .get(epb.if_id as usize)
+        .copied()
+        .unwrap_or((6, 0));
+    let (seconds, fraction, unit) = build_ts(epb.ts_high, epb.ts_low, ts_offset, ts_resol);
+    u64::from(seconds) * 1_000_000 + u64::from(fraction) * 1_000_000 / unit
 }
 
 impl PcapMessageProducer {
     #![allow(dead_code)]
     pub fn new(
-        pcap_path: &std::path::PathBuf,
+        pcap_path: &Path,
         update_channel: cc::Sender<ChunkResults>,
         fibex_metadata: Option<Rc<FibexMetadata>>,
         filter_config: Option<filtering::ProcessedDltFilterConfig>,
     ) -> Result<Self, Error> {
-        let pcap_file = File::open(&pcap_path)?;
+        let pcap_file = open_input(pcap_path)?;
+        let read_bytes = pcap_file.consumed();
         match PcapNGReader::new(65536, pcap_file) {
             Ok(reader) => Ok(PcapMessageProducer {
                 reader,
+                read_bytes,
                 index: 0,
                 update_channel,
                 fibex_metadata,
                 filter_config,
+                interfaces: vec![],
             }),
             Err(e) => Err(err_msg(format!("{:?}", e))),
         }
@@ -102,27 +128,35 @@ impl futures::Stream for PcapMessageProducer {
         mut self: std::pin::Pin<&mut Self>,
         _cx: &mut std::task::Context,
     ) -> futures::task::Poll<Option<Self::Item>> {
+        let this = &mut *self;
         let mut consumed = 0usize;
-        let update_channel = self.update_channel.clone();
-        let filter_config = self.filter_config.clone();
-        let fibex = self.fibex_metadata.clone();
-        let index = self.index;
-        self.index += 1;
+        let update_channel = this.update_channel.clone();
+        let filter_config = this.filter_config.clone();
+        let fibex = this.fibex_metadata.clone();
+        let index = this.index;
+        this.index += 1;
         let now = SystemTime::now();
         let since_the_epoch = now
             .duration_since(UNIX_EPOCH)
             .unwrap_or(std::time::Duration::from_secs(0));
-        let mut last_in_ms = since_the_epoch.as_millis() as i64;
-        let res = match self.reader.next() {
+        let mut last_in_us = since_the_epoch.as_micros() as u64;
+        let res = match this.reader.next() {
             Ok((offset, block)) => {
                 // trace!("got new block (offset: {})", offset);
                 consumed = offset;
                 let data = match block {
                     PcapBlockOwned::NG(Block::EnhancedPacket(ref epb)) => {
-                        let ts_us: i64 = (epb.ts_high as i64) << 32 | epb.ts_low as i64;
-                        last_in_ms = ts_us / 1000;
+                        last_in_us = packet_time_us(epb, &this.interfaces);
                         Some(epb.data)
                     }
+                    PcapBlockOwned::NG(Block::SectionHeader(_)) => {
+                        this.interfaces.clear();
+                        None
+                    }
+                    PcapBlockOwned::NG(Block::InterfaceDescription(ref idb)) => {
+                        this.interfaces.push((idb.if_tsresol, idb.if_tsoffset));
+                        None
+                    }
                     PcapBlockOwned::NG(Block::SimplePacket(ref spb)) => Some(spb.data),
                     PcapBlockOwned::NG(_) => None,
                     PcapBlockOwned::Legacy(_) | PcapBlockOwned::LegacyHeader(_) => None,
@@ -148,7 +182,7 @@ impl futures::Stream for PcapMessageProducer {
                             ) {
                                 Ok((_, ParsedMessage::Item(m))) => {
                                     let msg_with_storage_header = m.add_storage_header(Some(
-                                        DltTimeStamp::from_ms(last_in_ms as u64),
+                                        DltTimeStamp::from_us(last_in_us),
                                     ));
                                     futures::task::Poll::Ready(Some(Ok(MessageStreamItem::Item(
                                         msg_with_storage_header,
@@ -189,7 +223,8 @@ impl futures::Stream for PcapMessageProducer {
                         }
                     }
                 } else {
-                    futures::task::Poll::Pending
+                    // blocks without packet data (e.g. the interface description)
+                    futures::task::Poll::Ready(Some(Ok(MessageStreamItem::Skipped)))
                 }
             }
             Err(PcapError::Eof) => {
@@ -198,7 +233,7 @@ impl futures::Stream for PcapMessageProducer {
             }
             Err(PcapError::Incomplete) => {
                 trace!("Pcap: Incomplete");
-                let _ = self.reader.refill();
+                let _ = this.reader.refill();
                 futures::task::Poll::Ready(Some(Ok(MessageStreamItem::Incomplete)))
             }
             Err(e) => {
@@ -208,7 +243,7 @@ impl futures::Stream for PcapMessageProducer {
                 })))
             }
         };
-        self.reader.consume(consumed);
+        this.reader.consume(consumed);
         res
     }
 }
@@ -235,6 +270,9 @@ pub fn index_from_pcap<'a>(
         fibex_metadata,
         filter_config,
     )?;
+    let source_file_size = input_size(&config.in_file)? as usize;
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(Some(pcap_msg_producer.read_bytes.clone()));
     // listen for both a shutdown request and incomming messages
     // to do this we need to select over streams of the same type
     // the type we use to unify is this Event enum
@@ -253,6 +291,10 @@ pub fn index_from_pcap<'a>(
     task::block_on(async {
         while let Some(event) = event_stream.next().await {
             // trace!("received event: {:?}", event);
+            if let Event::Msg(_) = event {
+                // the bytes are counted while reading
+                progress_reporter.make_progress(0);
+            }
             match event {
                 Event::Shutdown => {
                     debug!("received shutdown through future channel");
@@ -364,3 +406,285 @@ pub fn create_index_and_mapping_dlt_from_pcap<'a>(
         }
     }
 }
+
+/// the DLT port that is used by default for exported packets
+pub const DLT_UDP_PORT: u16 = 3490;
+
+const PCAPNG_SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const PCAPNG_INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const PCAPNG_ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const LINKTYPE_ETHERNET: u16 = 1;
+
+/// where the udp packets of the exported messages come from and go to
+#[derive(Debug, Clone)]
+pub struct PcapExportConfig {
+    /// source address and port for the messages of an ECU
+    pub ecu_sources: FxHashMap<String, SocketAddrV4>,
+    /// source of messages from ECUs that are not in `ecu_sources`
+    pub default_source: SocketAddrV4,
+    pub destination: SocketAddrV4,
+}
+
+impl Default for PcapExportConfig {
+    fn default() -> Self {
+        PcapExportConfig {
+            ecu_sources: FxHashMap::default(),
+            default_source: SocketAddrV4::new(Ipv4Addr::new(192, 168, 0, 1), DLT_UDP_PORT),
+            destination: SocketAddrV4::new(Ipv4Addr::new(239, 255, 42, 99), DLT_UDP_PORT),
+        }
+    }
+}
+
+impl PcapExportConfig {
+    fn source_for(&self, ecu_id: Option<&String>) -> &SocketAddrV4 {
+        ecu_id
+            .and_then(|id| self.ecu_sources.get(id))
+            .unwrap_or(&self.default_source)
+    }
+}
+
+/// the addresses of a pcapng export as they are passed from the ui
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct PcapExportOptions {
+    /// udp sources of ECUs like "ECU1=192.168.0.10:3490"
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+    /// udp destination of all messages like "239.255.42.99:3490"
+    pub destination: Option<String>,
+}
+
+impl PcapExportOptions {
+    pub fn to_config(&self) -> Result<PcapExportConfig, Error> {
+        let mut config = PcapExportConfig::default();
+        for endpoint in &self.endpoints {
+            let (ecu, address) = parse_ecu_endpoint(endpoint)?;
+            config.ecu_sources.insert(ecu, address);
+        }
+        if let Some(destination) = &self.destination {
+            config.destination = destination
+                .trim()
+                .parse()
+                .map_err(|e| err_msg(format!("invalid destination {}: {}", destination, e)))?;
+        }
+        Ok(config)
+    }
+}
+
+/// parses an ECU endpoint like "ECU1=192.168.0.10:3490"
+pub fn parse_ecu_endpoint(input: &str) -> Result<(String, SocketAddrV4), Error> {
+    let mut parts = input.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(ecu), Some(address)) if !ecu.trim().is_empty() => {
+            let address: SocketAddrV4 = address
+                .trim()
+                .parse()
+                .map_err(|e| err_msg(format!("invalid address {}: {}", address, e)))?;
+            Ok((ecu.trim().to_string(), address))
+        }
+        _ => Err(err_msg(format!(
+            "invalid endpoint {}, expected ECU=IP:PORT",
+            input
+        ))),
+    }
+}
+
+/// locally administered mac address for an ip address (multicast addresses
+/// are mapped to their multicast mac)
+fn mac_for(ip: &Ipv4Addr) -> [u8; 6] {
+    let o = ip.octets();
+    if ip.is_multicast() {
+        [0x01, 0x00, 0x5e, o[1] & 0x7f, o[2], o[3]]
+    } else {
+        [0x02, 0x00, o[0], o[1], o[2], o[3]]
+    }
+}
+
+/// writes a pcapng file with one ethernet interface, packet timestamps are
+/// in microseconds (the default resolution)
+pub struct PcapNgWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> PcapNgWriter<W> {
+    pub fn new(mut out: W) -> Result<Self, Error> {
+        // section header block
+        write_u32(&mut out, PCAPNG_SECTION_HEADER_BLOCK)?;
+        write_u32(&mut out, 28)?;
+        write_u32(&mut out, PCAPNG_BYTE_ORDER_MAGIC)?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        // section length is not specified
+        out.write_all(&(-1i64).to_le_bytes())?;
+        write_u32(&mut out, 28)?;
+        // interface description block
+        write_u32(&mut out, PCAPNG_INTERFACE_DESCRIPTION_BLOCK)?;
+        write_u32(&mut out, 20)?;
+        out.write_all(&LINKTYPE_ETHERNET.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        // no snap length
+        write_u32(&mut out, 0)?;
+        write_u32(&mut out, 20)?;
+        Ok(PcapNgWriter { out })
+    }
+
+    pub fn write_packet(&mut self, timestamp_us: u64, data: &[u8]) -> Result<(), Error> {
+        let padding = (4 - data.len() % 4) % 4;
+        let block_length = (32 + data.len() + padding) as u32;
+        write_u32(&mut self.out, PCAPNG_ENHANCED_PACKET_BLOCK)?;
+        write_u32(&mut self.out, block_length)?;
+        // interface id
+        write_u32(&mut self.out, 0)?;
+        write_u32(&mut self.out, (timestamp_us >> 32) as u32)?;
+        write_u32(&mut self.out, timestamp_us as u32)?;
+        write_u32(&mut self.out, data.len() as u32)?;
+        write_u32(&mut self.out, data.len() as u32)?;
+        self.out.write_all(data)?;
+        self.out.write_all(&[0u8; 3][..padding])?;
+        write_u32(&mut self.out, block_length)?;
+        Ok(())
+    }
+
+    /// writes one dlt message (without storage header) as udp packet
+    pub fn write_udp_packet(
+        &mut self,
+        timestamp_us: u64,
+        source: &SocketAddrV4,
+        destination: &SocketAddrV4,
+        payload: &[u8],
+    ) -> Result<(), Error> {
+        let builder = PacketBuilder::ethernet2(mac_for(source.ip()), mac_for(destination.ip()))
+            .ipv4(source.ip().octets(), destination.ip().octets(), 64)
+            .udp(source.port(), destination.port());
+        let mut packet: Vec<u8> = Vec::with_capacity(builder.size(payload.len()));
+        builder
+            .write(&mut packet, payload)
+            .map_err(|e| err_msg(format!("could not create udp packet: {:?}", e)))?;
+        self.write_packet(timestamp_us, &packet)
+    }
+
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+fn write_u32<W: Write>(out: &mut W, v: u32) -> Result<(), Error> {
+    out.write_all(&v.to_le_bytes())?;
+    Ok(())
+}
+
+/// writes the messages in the sections of a dlt file (all if there are no
+/// sections) to a pcapng file, each message becomes one udp packet with
+/// the time of the storage header
+///
+/// this is the reverse of `convert_to_dlt_file`
+pub fn export_as_pcapng(
+    dlt_file_path: &Path,
+    destination_path: &Path,
+    sections: SectionConfig,
+    config: &PcapExportConfig,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    trace!(
+        "export_as_pcapng {:?} to file: {:?}, exporting {:?}",
+        dlt_file_path,
+        destination_path,
+        sections
+    );
+    if !input_exists(dlt_file_path) {
+        let reason = format!("couln't find file to export: {:?}", dlt_file_path);
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: reason.clone(),
+            line: None,
+        }));
+        return Err(err_msg(reason));
+    }
+    let in_path = dlt_file_path.to_path_buf();
+    let source_file_size = input_size(&in_path)? as usize;
+    let mut message_producer =
+        FileMessageProducer::new(&in_path, None, update_channel.clone(), true, None)?;
+    let mut copier = SequentialCopier::new(&in_path)?;
+    let mut writer = PcapNgWriter::new(BufWriter::new(File::create(destination_path)?))?;
+    let mut tracker = SectionTracker::new(sections);
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(message_producer.compressed_input.clone());
+    let mut offset = 0u64;
+    let mut msg_index = 0usize;
+    let mut written = 0usize;
+    let mut stopped = false;
+    let mut raw: Vec<u8> = Vec::new();
+    loop {
+        let (consumed, next) = message_producer.produce_next_message();
+        if consumed == 0 {
+            break;
+        }
+        progress_reporter.make_progress(consumed);
+        let end = offset + consumed as u64;
+        match next {
+            Ok(ParsedMessage::Item(msg)) => {
+                if tracker.contains(msg_index) {
+                    let length = std::cmp::min(
+                        (STORAGE_HEADER_LENGTH + msg.header.overall_length() as usize) as u64,
+                        consumed as u64,
+                    );
+                    raw.clear();
+                    copier.copy_to(end - length, length, &mut raw)?;
+                    let timestamp_us = msg.storage_header.as_ref().map_or(0, |h| {
+                        u64::from(h.timestamp.seconds) * 1_000_000
+                            + u64::from(h.timestamp.microseconds)
+                    });
+                    let source = config.source_for(msg.header.ecu_id.as_ref());
+                    match writer.write_udp_packet(
+                        timestamp_us,
+                        source,
+                        &config.destination,
+                        &raw[STORAGE_HEADER_LENGTH.min(raw.len())..],
+                    ) {
+                        Ok(()) => written += 1,
+                        Err(e) => {
+                            let _ = update_channel.send(Err(Notification {
+                                severity: Severity::WARNING,
+                                content: format!("message not exported: {}", e),
+                                line: Some(msg_index),
+                            }));
+                        }
+                    }
+                }
+                msg_index += 1;
+                if tracker.is_done() {
+                    break;
+                }
+            }
+            Ok(ParsedMessage::Invalid) | Ok(ParsedMessage::FilteredOut) => (),
+            Err(DltParseError::ParsingHickup { reason }) => {
+                warn!(
+                    "pcapng export: skip message that could not be parsed: {}",
+                    reason
+                );
+            }
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("pcapng export: cannot continue parsing: {}", e),
+                    line: Some(msg_index),
+                }));
+                break;
+            }
+        }
+        offset = end;
+        if utils::check_if_stop_was_requested(&shutdown_receiver, "pcapng export") {
+            stopped = true;
+            break;
+        }
+    }
+    writer.finish()?;
+    let _ = update_channel.send(Ok(if stopped {
+        IndexingProgress::Stopped
+    } else {
+        IndexingProgress::Finished
+    }));
+    Ok(written)
+}
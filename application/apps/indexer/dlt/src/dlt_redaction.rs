@@ -0,0 +1,211 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::*;
+use crate::dlt_export::SectionTracker;
+use crate::dlt_file::FileMessageProducer;
+use crate::dlt_parse::{DltParseError, ParsedMessage};
+use crate::dlt_split::SequentialCopier;
+use byteorder::{BigEndian, LittleEndian};
+use crossbeam_channel as cc;
+use failure::{err_msg, Error};
+use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::{input_exists, input_size};
+use indexer_base::config::SectionConfig;
+use indexer_base::progress::{IndexingProgress, Notification, ProgressReporter, Severity};
+use indexer_base::redaction::Redactor;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageRedaction {
+    Unchanged,
+    /// at least one argument was replaced, the payload length is updated
+    Redacted,
+    /// only the arguments of verbose messages can be redacted, non-verbose and
+    /// control messages have to be left out
+    Dropped,
+}
+
+fn payload_length(msg: &Message) -> usize {
+    if msg.header.endianness == Endianness::Big {
+        msg.payload.as_bytes::<BigEndian>().len()
+    } else {
+        msg.payload.as_bytes::<LittleEndian>().len()
+    }
+}
+
+fn string_argument(original: &Argument, value: String) -> Argument {
+    Argument {
+        type_info: TypeInfo {
+            kind: TypeInfoKind::StringType,
+            coding: StringCoding::UTF8,
+            has_variable_info: original.type_info.has_variable_info,
+            has_trace_info: original.type_info.has_trace_info,
+        },
+        name: original.name.clone(),
+        unit: None,
+        fixed_point: None,
+        value: Value::StringVal(value),
+    }
+}
+
+/// replaces the arguments of a verbose message that match the rules of the
+/// redactor, redacted arguments become strings. Other messages are `Dropped`
+/// (their payload could contain anything).
+pub fn redact_message(msg: &mut Message, redactor: &mut Redactor) -> MessageRedaction {
+    let app_id = msg
+        .extended_header
+        .as_ref()
+        .map(|h| h.application_id.clone());
+    let context_id = msg.extended_header.as_ref().map(|h| h.context_id.clone());
+    let arguments = match &mut msg.payload.payload_content {
+        PayloadContent::Verbose(arguments) => arguments,
+        _ => {
+            redactor.drop_message();
+            return MessageRedaction::Dropped;
+        }
+    };
+    redactor.start_message();
+    let mut changed: Vec<usize> = vec![];
+    for (i, arg) in arguments.iter_mut().enumerate() {
+        let text = match &arg.value {
+            Value::StringVal(s) => s.clone(),
+            v => v.to_string(),
+        };
+        if let Some(redacted) =
+            redactor.redact_argument(app_id.as_deref(), context_id.as_deref(), i, &text)
+        {
+            *arg = string_argument(arg, redacted);
+            changed.push(i);
+        }
+    }
+    redactor.finish_message(!changed.is_empty());
+    if changed.is_empty() {
+        return MessageRedaction::Unchanged;
+    }
+    let headers_length = msg.header.overall_length() as usize - msg.header.payload_length as usize;
+    if headers_length + payload_length(msg) > u16::MAX as usize {
+        // pseudonyms made the message too long, only the mask fits
+        let mask = redactor.mask().to_string();
+        if let PayloadContent::Verbose(arguments) = &mut msg.payload.payload_content {
+            for i in changed {
+                arguments[i] = string_argument(&arguments[i], mask.clone());
+            }
+        }
+    }
+    msg.header.payload_length = payload_length(msg) as u16;
+    MessageRedaction::Redacted
+}
+
+/// like `dlt_file::export_as_dlt_file` but messages are redacted, messages
+/// without a replacement are copied unchanged and messages that cannot be
+/// redacted are left out
+pub fn export_as_dlt_file_redacted(
+    dlt_file_path: &Path,
+    destination_path: &Path,
+    sections: SectionConfig,
+    redactor: &mut Redactor,
+    update_channel: cc::Sender<ChunkResults>,
+) -> Result<(), Error> {
+    trace!(
+        "export_as_dlt_file_redacted {:?} to file: {:?}, exporting {:?}",
+        dlt_file_path,
+        destination_path,
+        sections
+    );
+    if !input_exists(dlt_file_path) {
+        let reason = format!("couln't find file to export: {:?}", dlt_file_path);
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::ERROR,
+            content: reason.clone(),
+            line: None,
+        }));
+        return Err(err_msg(reason));
+    }
+    let in_path = dlt_file_path.to_path_buf();
+    let source_file_size = input_size(&in_path)? as usize;
+    let mut message_producer =
+        FileMessageProducer::new(&in_path, None, update_channel.clone(), true, None)?;
+    let mut copier = SequentialCopier::new(&in_path)?;
+    let mut out_writer = BufWriter::new(fs::File::create(destination_path)?);
+    let mut tracker = SectionTracker::new(sections);
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(message_producer.compressed_input.clone());
+    let mut offset = 0u64;
+    let mut msg_index = 0usize;
+    loop {
+        let (consumed, next) = message_producer.produce_next_message();
+        if consumed == 0 {
+            break;
+        }
+        progress_reporter.make_progress(consumed);
+        let end = offset + consumed as u64;
+        match next {
+            Ok(ParsedMessage::Item(mut msg)) => {
+                if tracker.contains(msg_index) {
+                    match redact_message(&mut msg, redactor) {
+                        MessageRedaction::Redacted => out_writer.write_all(&msg.as_bytes())?,
+                        MessageRedaction::Unchanged => {
+                            let length = std::cmp::min(
+                                (STORAGE_HEADER_LENGTH + msg.header.overall_length() as usize)
+                                    as u64,
+                                consumed as u64,
+                            );
+                            copier.copy_to(end - length, length, &mut out_writer)?;
+                        }
+                        MessageRedaction::Dropped => (),
+                    }
+                }
+                msg_index += 1;
+                if tracker.is_done() {
+                    break;
+                }
+            }
+            Ok(ParsedMessage::Invalid) | Ok(ParsedMessage::FilteredOut) => (),
+            Err(DltParseError::ParsingHickup { reason }) => {
+                warn!(
+                    "redacted export: skip message that could not be parsed: {}",
+                    reason
+                );
+            }
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("redacted export: cannot continue parsing: {}", e),
+                    line: Some(msg_index),
+                }));
+                break;
+            }
+        }
+        offset = end;
+    }
+    out_writer.flush()?;
+    report_dropped(redactor, &update_channel);
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(())
+}
+
+/// warns about messages that were left out because they cannot be redacted
+pub(crate) fn report_dropped(redactor: &Redactor, update_channel: &cc::Sender<ChunkResults>) {
+    let dropped = redactor.summary().dropped;
+    if dropped > 0 {
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "{} non-verbose or control messages were left out because they cannot be redacted",
+                dropped
+            ),
+            line: None,
+        }));
+    }
+}
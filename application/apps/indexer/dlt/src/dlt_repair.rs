@@ -0,0 +1,303 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::{calculate_all_headers_length, STORAGE_HEADER_LENGTH};
+use crate::dlt_parse::{
+    dlt_message, forward_to_next_storage_header, ParsedMessage, DLT_PATTERN, DLT_PATTERN_SIZE,
+    DLT_READER_CAPACITY,
+};
+use buf_redux::policy::MinBuffered;
+use buf_redux::BufReader as ReduxReader;
+use byteorder::{BigEndian, ByteOrder};
+use crossbeam_channel as cc;
+use failure::Error;
+use indexer_base::chunks::ChunkResults;
+use indexer_base::progress::{IndexingProgress, Notification, ProgressReporter, Severity};
+use indexer_base::utils;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+
+/// a message can have at most 64 KiB, we need to see the complete message
+/// and the beginning of the next one to decide if it was damaged
+const REPAIR_MIN_BUFFER_SPACE: usize = 4 * 0x1_0000;
+
+/// storage header and the first 4 bytes of the standard header
+const MIN_MESSAGE_LENGTH: usize = STORAGE_HEADER_LENGTH + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DamageKind {
+    /// bytes that are not part of any message
+    Garbage,
+    /// a DLT pattern that is not followed by a plausible header
+    BogusStorageHeader,
+    /// the length in the standard header is shorter than the headers
+    InvalidPayloadLength,
+    /// a message that was cut off by the next storage header
+    TruncatedMessage,
+    /// an incomplete message at the end of the file
+    TruncatedTail,
+    /// the message has a plausible frame but the content cannot be parsed
+    UnparsableMessage,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamagedRange {
+    pub offset: u64,
+    pub length: u64,
+    pub kind: DamageKind,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RepairReport {
+    pub source_size: u64,
+    pub valid_messages: usize,
+    pub written_bytes: u64,
+    pub damaged_bytes: u64,
+    pub damaged: Vec<DamagedRange>,
+}
+
+impl RepairReport {
+    /// adjacent garbage is reported as one range
+    fn add_damage(&mut self, offset: u64, length: u64, kind: DamageKind, reason: String) {
+        self.damaged_bytes += length;
+        if let Some(last) = self.damaged.last_mut() {
+            if kind == DamageKind::Garbage
+                && last.kind == DamageKind::Garbage
+                && last.offset + last.length == offset
+            {
+                last.length += length;
+                return;
+            }
+        }
+        self.damaged.push(DamagedRange {
+            offset,
+            length,
+            kind,
+            reason,
+        });
+    }
+}
+
+/// result of looking at the beginning of the input
+#[derive(Debug, PartialEq)]
+pub(crate) enum Inspection {
+    /// a complete message with this length
+    Valid(usize),
+    /// this many bytes have to be dropped
+    Damaged(usize, DamageKind, String),
+}
+
+/// decides if the input starts with a valid message (including storage header).
+/// `at_eof` tells if the input contains everything till the end of the file
+pub(crate) fn inspect_message(input: &[u8], at_eof: bool) -> Inspection {
+    // keep a possible incomplete pattern at the end of the input
+    let rest_len = if at_eof {
+        input.len()
+    } else {
+        input.len().saturating_sub(DLT_PATTERN_SIZE - 1)
+    };
+    if !input.starts_with(DLT_PATTERN) {
+        let dropped = match forward_to_next_storage_header(input) {
+            Some((dropped, _)) => dropped,
+            None => rest_len,
+        };
+        return Inspection::Damaged(
+            dropped,
+            DamageKind::Garbage,
+            "no storage header".to_string(),
+        );
+    }
+    let next_pattern = forward_to_next_storage_header(&input[1..]).map(|(dropped, _)| dropped + 1);
+    let till_next = next_pattern.unwrap_or(rest_len);
+    if input.len() < MIN_MESSAGE_LENGTH {
+        return Inspection::Damaged(
+            till_next,
+            DamageKind::TruncatedTail,
+            format!("only {} bytes left", input.len()),
+        );
+    }
+    if let Some(next) = next_pattern {
+        if next < MIN_MESSAGE_LENGTH && starts_with_complete_message(&input[next..], at_eof) {
+            return Inspection::Damaged(
+                next,
+                DamageKind::TruncatedMessage,
+                format!("headers cut off after {} bytes", next),
+            );
+        }
+    }
+    let total = match message_length(input) {
+        Ok(total) => total,
+        Err((kind, reason)) => return Inspection::Damaged(till_next, kind, reason),
+    };
+    if total > input.len() {
+        return match next_pattern {
+            Some(next) => Inspection::Damaged(
+                next,
+                DamageKind::TruncatedMessage,
+                format!("message of {} bytes cut off after {} bytes", total, next),
+            ),
+            None => Inspection::Damaged(
+                input.len(),
+                DamageKind::TruncatedTail,
+                format!(
+                    "message of {} bytes but only {} bytes left",
+                    total,
+                    input.len()
+                ),
+            ),
+        };
+    }
+    if let Some(next) = next_pattern {
+        // a storage header inside of the message could also be part of the payload,
+        // we only trust it if a complete message follows
+        if next < total && starts_with_complete_message(&input[next..], at_eof) {
+            return Inspection::Damaged(
+                next,
+                DamageKind::TruncatedMessage,
+                format!("message of {} bytes cut off after {} bytes", total, next),
+            );
+        }
+    }
+    match dlt_message(&input[..total], None, 0, None, None, true) {
+        Ok((_, ParsedMessage::Item(_))) => Inspection::Valid(total),
+        Ok(_) => Inspection::Damaged(
+            total,
+            DamageKind::InvalidPayloadLength,
+            "invalid payload length".to_string(),
+        ),
+        Err(e) => {
+            let length = if ends_cleanly(input, total, at_eof) {
+                total
+            } else {
+                till_next
+            };
+            Inspection::Damaged(length, DamageKind::UnparsableMessage, e.to_string())
+        }
+    }
+}
+
+/// length of the message (including storage header) or the reason why
+/// the headers make no sense
+fn message_length(input: &[u8]) -> Result<usize, (DamageKind, String)> {
+    let ecu_id = &input[12..16];
+    if ecu_id.iter().any(|b| *b != 0 && !(0x20..=0x7e).contains(b)) {
+        return Err((
+            DamageKind::BogusStorageHeader,
+            format!("invalid ecu id {:?}", ecu_id),
+        ));
+    }
+    let header_type = input[STORAGE_HEADER_LENGTH];
+    let version = header_type >> 5 & 0b111;
+    if version != 1 {
+        return Err((
+            DamageKind::BogusStorageHeader,
+            format!("unsupported version {}", version),
+        ));
+    }
+    let overall_length =
+        BigEndian::read_u16(&input[STORAGE_HEADER_LENGTH + 2..STORAGE_HEADER_LENGTH + 4]);
+    let headers_length = calculate_all_headers_length(header_type);
+    if overall_length < headers_length {
+        return Err((
+            DamageKind::InvalidPayloadLength,
+            format!(
+                "message length {} is shorter than its headers ({})",
+                overall_length, headers_length
+            ),
+        ));
+    }
+    Ok(STORAGE_HEADER_LENGTH + overall_length as usize)
+}
+
+fn ends_cleanly(input: &[u8], end: usize, at_eof: bool) -> bool {
+    (end == input.len() && at_eof) || input[end..].starts_with(DLT_PATTERN)
+}
+
+fn starts_with_complete_message(input: &[u8], at_eof: bool) -> bool {
+    if input.len() < MIN_MESSAGE_LENGTH {
+        return false;
+    }
+    match message_length(input) {
+        Ok(total) => total <= input.len() && ends_cleanly(input, total, at_eof),
+        Err(_) => false,
+    }
+}
+
+/// writes all valid messages of a damaged dlt file to `out_file` and reports
+/// every byte range that had to be dropped
+pub fn repair_dlt_file(
+    in_file: &Path,
+    out_file: &Path,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<RepairReport, Error> {
+    trace!("repair_dlt_file {:?} -> {:?}", in_file, out_file);
+    let source_size = fs::metadata(in_file)?.len();
+    let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, fs::File::open(in_file)?)
+        .set_policy(MinBuffered(REPAIR_MIN_BUFFER_SPACE));
+    let mut writer = BufWriter::new(fs::File::create(out_file)?);
+    let mut progress_reporter = ProgressReporter::new(source_size as usize, update_channel.clone());
+    let mut report = RepairReport {
+        source_size,
+        ..Default::default()
+    };
+    let mut offset = 0u64;
+    loop {
+        let content = reader.fill_buf()?;
+        if content.is_empty() {
+            break;
+        }
+        let at_eof = content.len() < REPAIR_MIN_BUFFER_SPACE;
+        let consumed = match inspect_message(content, at_eof) {
+            Inspection::Valid(length) => {
+                writer.write_all(&content[..length])?;
+                report.valid_messages += 1;
+                report.written_bytes += length as u64;
+                length
+            }
+            Inspection::Damaged(length, kind, reason) => {
+                report.add_damage(offset, length as u64, kind, reason);
+                length
+            }
+        };
+        offset += consumed as u64;
+        reader.consume(consumed);
+        progress_reporter.make_progress(consumed);
+        if utils::check_if_stop_was_requested(&shutdown_receiver, "dlt repair") {
+            writer.flush()?;
+            let _ = update_channel.send(Ok(IndexingProgress::Stopped));
+            return Ok(report);
+        }
+    }
+    writer.flush()?;
+    if !report.damaged.is_empty() {
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "dropped {} damaged ranges ({} bytes)",
+                report.damaged.len(),
+                report.damaged_bytes
+            ),
+            line: None,
+        }));
+    }
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(report)
+}
+
+pub fn write_repair_report(report: &RepairReport, path: &Path) -> Result<(), Error> {
+    let writer = BufWriter::new(fs::File::create(path)?);
+    serde_json::to_writer_pretty(writer, report)?;
+    Ok(())
+}
@@ -0,0 +1,200 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::{DltTimeStamp, Message};
+use crate::dlt_file::FileMessageProducer;
+use crate::dlt_parse::{DltParseError, ParsedMessage};
+use async_std::io::prelude::WriteExt;
+use async_std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use crossbeam_channel as cc;
+use failure::{err_msg, Error};
+use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::input_size;
+use indexer_base::progress::{IndexingProgress, Notification, ProgressReporter, Severity};
+use indexer_base::utils;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// how fast messages are sent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayTiming {
+    /// keep the distance between the storage header timestamps
+    Original,
+    /// like `Original`, but faster (> 1.0) or slower (< 1.0), has to be a
+    /// positive number
+    Speed(f64),
+    /// send messages as fast as possible
+    AsFastAsPossible,
+}
+
+/// where the messages are sent to
+#[derive(Debug, Clone)]
+pub enum ReplayTarget {
+    /// send one message per datagram to this address (unicast or multicast)
+    Udp(SocketAddr),
+    /// listen on this address and stream the messages to the first
+    /// client that connects (like a dlt-daemon)
+    Tcp(SocketAddr),
+}
+
+/// calculates when a message should be sent relative to the start of the replay
+pub struct ReplayClock {
+    speed: Option<f64>,
+    first_timestamp_us: Option<i64>,
+    last_offset: Duration,
+}
+
+impl ReplayClock {
+    pub fn new(timing: ReplayTiming) -> Result<Self, Error> {
+        let speed = match timing {
+            ReplayTiming::Original => Some(1.0),
+            ReplayTiming::Speed(s) if s.is_finite() && s > 0.0 => Some(s),
+            ReplayTiming::Speed(s) => {
+                return Err(err_msg(format!(
+                    "invalid replay speed {} (has to be a positive number)",
+                    s
+                )))
+            }
+            ReplayTiming::AsFastAsPossible => None,
+        };
+        Ok(ReplayClock {
+            speed,
+            first_timestamp_us: None,
+            last_offset: Duration::from_secs(0),
+        })
+    }
+
+    /// messages without a storage header (or with an older timestamp then the
+    /// message before) are sent right after the previous one
+    pub fn send_offset(&mut self, timestamp: Option<&DltTimeStamp>) -> Duration {
+        let (speed, ts) = match (self.speed, timestamp) {
+            (Some(speed), Some(ts)) => (speed, ts),
+            _ => return self.last_offset,
+        };
+        let us = i64::from(ts.seconds) * 1_000_000 + i64::from(ts.microseconds);
+        let first = *self.first_timestamp_us.get_or_insert(us);
+        if us > first {
+            let offset = Duration::from_micros(((us - first) as f64 / speed) as u64);
+            if offset > self.last_offset {
+                self.last_offset = offset;
+            }
+        }
+        self.last_offset
+    }
+}
+
+enum Connection {
+    Udp(UdpSocket, SocketAddr),
+    Tcp(TcpStream),
+}
+
+impl Connection {
+    async fn open(target: &ReplayTarget) -> Result<Self, Error> {
+        match target {
+            ReplayTarget::Udp(addr) => {
+                let local: SocketAddr = if addr.is_ipv4() {
+                    "0.0.0.0:0".parse()?
+                } else {
+                    "[::]:0".parse()?
+                };
+                let socket = UdpSocket::bind(local).await?;
+                Ok(Connection::Udp(socket, *addr))
+            }
+            ReplayTarget::Tcp(addr) => {
+                // binding does not block, so we can use the std listener here
+                let listener: TcpListener = std::net::TcpListener::bind(addr)
+                    .map_err(|e| err_msg(format!("could not listen on {} ({})", addr, e)))?
+                    .into();
+                debug!("replay: waiting for a client on {}", addr);
+                let (stream, peer) = listener.accept().await?;
+                info!("replay: client {} connected", peer);
+                Ok(Connection::Tcp(stream))
+            }
+        }
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        match self {
+            Connection::Udp(socket, addr) => {
+                socket.send_to(bytes, *addr).await?;
+            }
+            Connection::Tcp(stream) => stream.write_all(bytes).await?,
+        }
+        Ok(())
+    }
+}
+
+/// sends the messages of a dlt file like an ECU would do (without storage
+/// headers), returns the number of messages that were sent
+pub async fn replay_dlt_file(
+    in_file: PathBuf,
+    target: ReplayTarget,
+    timing: ReplayTiming,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<usize, Error> {
+    trace!(
+        "replay_dlt_file {:?} to {:?} ({:?})",
+        in_file,
+        target,
+        timing
+    );
+    let mut clock = ReplayClock::new(timing)?;
+    let source_file_size = input_size(&in_file)? as usize;
+    let mut message_producer =
+        FileMessageProducer::new(&in_file, None, update_channel.clone(), true, None)?;
+    let mut connection = Connection::open(&target).await?;
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(message_producer.compressed_input.clone());
+    let start = Instant::now();
+    let mut sent = 0usize;
+    loop {
+        let (consumed, next) = message_producer.produce_next_message();
+        if consumed == 0 {
+            break;
+        }
+        progress_reporter.make_progress(consumed);
+        match next {
+            Ok(ParsedMessage::Item(msg)) => {
+                let offset = clock.send_offset(msg.storage_header.as_ref().map(|h| &h.timestamp));
+                let elapsed = start.elapsed();
+                if offset > elapsed {
+                    async_std::task::sleep(offset - elapsed).await;
+                }
+                connection.send(&without_storage_header(msg)).await?;
+                sent += 1;
+            }
+            Ok(ParsedMessage::Invalid) | Ok(ParsedMessage::FilteredOut) => (),
+            Err(DltParseError::ParsingHickup { reason }) => {
+                warn!("replay: skip message that could not be parsed: {}", reason);
+            }
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("replay: cannot continue parsing: {}", e),
+                    line: None,
+                }));
+                break;
+            }
+        }
+        if utils::check_if_stop_was_requested(&shutdown_receiver, "dlt replay") {
+            let _ = update_channel.send(Ok(IndexingProgress::Stopped));
+            return Ok(sent);
+        }
+    }
+    let _ = update_channel.send(Ok(IndexingProgress::Finished));
+    Ok(sent)
+}
+
+fn without_storage_header(mut msg: Message) -> Vec<u8> {
+    msg.storage_header = None;
+    msg.as_bytes()
+}
@@ -0,0 +1,413 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::{Message, STORAGE_HEADER_LENGTH};
+use crate::dlt_file::FileMessageProducer;
+use crate::dlt_parse::{DltParseError, ParsedMessage, DLT_READER_CAPACITY};
+use crate::lifecycle::{lifecycle_ecu_id, scan_lifecycles, LifecycleTimeCorrection};
+use crossbeam_channel as cc;
+use failure::{err_msg, Error};
+use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::{input_size, open_input, InputReader};
+use indexer_base::progress::{IndexingProgress, Notification, ProgressReporter, Severity};
+use indexer_base::utils;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub const SPLIT_MANIFEST_SUFFIX: &str = ".split.json";
+/// files that are kept open at the same time, the least recently used one is
+/// closed (and later reopened for appending) when a new one is needed
+pub const MAX_OPEN_SPLIT_FILES: usize = 64;
+
+/// how messages are distributed to the output files
+#[derive(Debug, Clone, PartialEq)]
+pub enum SplitCriterion {
+    /// start a new file before it grows beyond this many bytes
+    Size(u64),
+    /// start a new file when the storage time leaves the interval (in seconds)
+    Interval(u64),
+    /// one file per ECU
+    EcuId,
+    /// one file per application
+    AppId,
+    /// one file per ECU lifecycle
+    Lifecycle,
+}
+
+impl SplitCriterion {
+    fn name(&self) -> String {
+        match self {
+            SplitCriterion::Size(max) => format!("size ({} bytes)", max),
+            SplitCriterion::Interval(s) => format!("interval ({} s)", s),
+            SplitCriterion::EcuId => "ecu".to_string(),
+            SplitCriterion::AppId => "app".to_string(),
+            SplitCriterion::Lifecycle => "lifecycle".to_string(),
+        }
+    }
+}
+
+/// one of the written files, message numbers are the indices in the source file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SplitFile {
+    pub file_name: String,
+    /// running number, ecu id, app id or lifecycle id
+    pub key: String,
+    pub messages: usize,
+    pub bytes: u64,
+    pub first_message: usize,
+    pub last_message: usize,
+    /// storage time (in us since epoch) of the first and last message
+    pub first_time_us: Option<i64>,
+    pub last_time_us: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SplitManifest {
+    pub source: PathBuf,
+    pub criterion: String,
+    pub files: Vec<SplitFile>,
+}
+
+/// `<out_dir>/<source file stem>.split.json`
+pub fn split_manifest_path(in_file: &Path, out_dir: &Path) -> PathBuf {
+    out_dir.join(format!("{}{}", file_stem(in_file), SPLIT_MANIFEST_SUFFIX))
+}
+
+pub fn read_split_manifest(path: &Path) -> Result<SplitManifest, Error> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| err_msg(format!("invalid split manifest {:?}: {}", path, e)))
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "split".to_string())
+}
+
+/// ids can contain anything, we only keep what is safe in a file name
+fn file_name_part(id: &str) -> String {
+    let part: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if part.is_empty() {
+        "NONE".to_string()
+    } else {
+        part
+    }
+}
+
+fn storage_time_us(msg: &Message) -> Option<i64> {
+    msg.storage_header
+        .as_ref()
+        .map(|h| i64::from(h.timestamp.seconds) * 1_000_000 + i64::from(h.timestamp.microseconds))
+}
+
+/// reads the source a second time to copy the bytes of the parsed messages,
+/// the byte ranges have to be requested in ascending order
+pub(crate) struct SequentialCopier {
+    reader: BufReader<InputReader>,
+    position: u64,
+}
+
+impl SequentialCopier {
+    pub(crate) fn new(path: &Path) -> Result<Self, Error> {
+        Ok(SequentialCopier {
+            reader: BufReader::with_capacity(DLT_READER_CAPACITY, open_input(path)?),
+            position: 0,
+        })
+    }
+
+    pub(crate) fn copy_to<W: Write>(
+        &mut self,
+        start: u64,
+        length: u64,
+        out: &mut W,
+    ) -> Result<(), Error> {
+        if start > self.position {
+            let skip = start - self.position;
+            std::io::copy(&mut (&mut self.reader).take(skip), &mut std::io::sink())?;
+        }
+        let copied = std::io::copy(&mut (&mut self.reader).take(length), out)?;
+        if copied != length {
+            return Err(err_msg(format!(
+                "could only copy {} of {} bytes at offset {}",
+                copied, length, start
+            )));
+        }
+        self.position = start + length;
+        Ok(())
+    }
+}
+
+struct Splitter {
+    criterion: SplitCriterion,
+    out_dir: PathBuf,
+    stem: String,
+    files: Vec<SplitFile>,
+    /// key -> index in files
+    file_indices: FxHashMap<String, usize>,
+    /// key -> (last use, writer) of the open files
+    open: FxHashMap<String, (usize, BufWriter<fs::File>)>,
+    max_open: usize,
+    uses: usize,
+    /// start (in us) of the interval of the current file
+    interval_start: Option<i64>,
+    lifecycles: Option<LifecycleTimeCorrection>,
+}
+
+impl Splitter {
+    /// the key of the file a message belongs to
+    fn key_for(&mut self, msg: &Message, length: u64) -> String {
+        let current = self.files.len().checked_sub(1);
+        match &self.criterion {
+            SplitCriterion::Size(max) => match current {
+                Some(i) if self.files[i].bytes + length <= *max => self.files[i].key.clone(),
+                _ => self.files.len().to_string(),
+            },
+            SplitCriterion::Interval(seconds) => {
+                let interval = *seconds as i64 * 1_000_000;
+                match (current, storage_time_us(msg), self.interval_start) {
+                    (Some(i), Some(t), Some(start)) if t < start + interval => {
+                        self.files[i].key.clone()
+                    }
+                    (Some(i), None, _) => self.files[i].key.clone(),
+                    (_, t, _) => {
+                        self.interval_start = t.map(|t| t - t.rem_euclid(interval));
+                        self.files.len().to_string()
+                    }
+                }
+            }
+            SplitCriterion::EcuId => lifecycle_ecu_id(
+                msg.header.ecu_id.as_ref(),
+                msg.storage_header.as_ref().map(|h| &h.ecu_id),
+            ),
+            SplitCriterion::AppId => msg
+                .extended_header
+                .as_ref()
+                .map(|h| h.application_id.clone())
+                .unwrap_or_else(|| "NONE".to_string()),
+            SplitCriterion::Lifecycle => self
+                .lifecycles
+                .as_mut()
+                .and_then(|l| l.lifecycle_of(msg))
+                .map(|l| l.id.to_string())
+                .unwrap_or_else(|| "NONE".to_string()),
+        }
+    }
+
+    fn file_name(&self, key: &str) -> String {
+        match self.criterion {
+            SplitCriterion::Size(_) | SplitCriterion::Interval(_) => {
+                format!("{}_{:04}.dlt", self.stem, self.files.len())
+            }
+            SplitCriterion::EcuId => format!("{}_ecu_{}.dlt", self.stem, file_name_part(key)),
+            SplitCriterion::AppId => format!("{}_app_{}.dlt", self.stem, file_name_part(key)),
+            SplitCriterion::Lifecycle => match key.parse::<usize>() {
+                Ok(id) => format!("{}_lifecycle_{:04}.dlt", self.stem, id),
+                Err(_) => format!("{}_lifecycle_{}.dlt", self.stem, key),
+            },
+        }
+    }
+
+    /// the index of the file for `key` and its writer, the file is created or reopened
+    fn writer_for(
+        &mut self,
+        key: String,
+        msg_index: usize,
+    ) -> Result<(usize, &mut BufWriter<fs::File>), Error> {
+        self.uses += 1;
+        let file_index = match self.file_indices.get(&key) {
+            Some(index) => *index,
+            None => {
+                if let SplitCriterion::Size(_) | SplitCriterion::Interval(_) = self.criterion {
+                    // only the latest file is written to
+                    for (_, (_, mut writer)) in self.open.drain() {
+                        writer.flush()?;
+                    }
+                }
+                let file_name = self.file_name(&key);
+                fs::File::create(self.out_dir.join(&file_name))?;
+                self.files.push(SplitFile {
+                    file_name,
+                    key: key.clone(),
+                    messages: 0,
+                    bytes: 0,
+                    first_message: msg_index,
+                    last_message: msg_index,
+                    first_time_us: None,
+                    last_time_us: None,
+                });
+                self.file_indices.insert(key.clone(), self.files.len() - 1);
+                self.files.len() - 1
+            }
+        };
+        if !self.open.contains_key(&key) {
+            if self.open.len() >= self.max_open {
+                self.close_least_recently_used()?;
+            }
+            let file = fs::OpenOptions::new()
+                .append(true)
+                .open(self.out_dir.join(&self.files[file_index].file_name))?;
+            self.open.insert(key.clone(), (0, BufWriter::new(file)));
+        }
+        let uses = self.uses;
+        let (last_use, writer) = self
+            .open
+            .get_mut(&key)
+            .ok_or_else(|| err_msg("split file not open"))?;
+        *last_use = uses;
+        Ok((file_index, writer))
+    }
+
+    fn close_least_recently_used(&mut self) -> Result<(), Error> {
+        let oldest = self
+            .open
+            .iter()
+            .min_by_key(|(_, (last_use, _))| *last_use)
+            .map(|(key, _)| key.clone());
+        if let Some((_, mut writer)) = oldest.and_then(|key| self.open.remove(&key)) {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        for (_, (_, mut writer)) in self.open.drain() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// copies the messages of a dlt file into several smaller files and
+/// writes a manifest that lists them (see `split_manifest_path`)
+pub fn split_dlt_file(
+    in_file: &Path,
+    out_dir: &Path,
+    criterion: SplitCriterion,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<SplitManifest, Error> {
+    trace!(
+        "split_dlt_file {:?} into {:?} ({:?})",
+        in_file,
+        out_dir,
+        criterion
+    );
+    match criterion {
+        SplitCriterion::Size(0) | SplitCriterion::Interval(0) => {
+            return Err(err_msg("size and interval have to be greater than 0"));
+        }
+        _ => (),
+    }
+    let in_path = in_file.to_path_buf();
+    let source_file_size = input_size(&in_path)? as usize;
+    fs::create_dir_all(out_dir)?;
+    let lifecycles = if criterion == SplitCriterion::Lifecycle {
+        Some(LifecycleTimeCorrection::new(scan_lifecycles(&in_path)?))
+    } else {
+        None
+    };
+    let mut message_producer =
+        FileMessageProducer::new(&in_path, None, update_channel.clone(), true, None)?;
+    let mut copier = SequentialCopier::new(&in_path)?;
+    let mut splitter = Splitter {
+        criterion: criterion.clone(),
+        out_dir: out_dir.to_path_buf(),
+        stem: file_stem(in_file),
+        files: vec![],
+        file_indices: FxHashMap::default(),
+        open: FxHashMap::default(),
+        max_open: MAX_OPEN_SPLIT_FILES,
+        uses: 0,
+        interval_start: None,
+        lifecycles,
+    };
+    let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
+    progress_reporter.track_read_bytes(message_producer.compressed_input.clone());
+    let mut offset = 0u64;
+    let mut msg_index = 0usize;
+    let mut stopped = false;
+    loop {
+        let (consumed, next) = message_producer.produce_next_message();
+        if consumed == 0 {
+            break;
+        }
+        progress_reporter.make_progress(consumed);
+        let end = offset + consumed as u64;
+        match next {
+            Ok(ParsedMessage::Item(msg)) => {
+                // skipped bytes in front of the message are not copied
+                let length = std::cmp::min(
+                    (STORAGE_HEADER_LENGTH + msg.header.overall_length() as usize) as u64,
+                    consumed as u64,
+                );
+                let key = splitter.key_for(&msg, length);
+                let (file_index, writer) = splitter.writer_for(key, msg_index)?;
+                copier.copy_to(end - length, length, writer)?;
+                let split_file = &mut splitter.files[file_index];
+                split_file.messages += 1;
+                split_file.bytes += length;
+                split_file.last_message = msg_index;
+                let time = storage_time_us(&msg);
+                if split_file.first_time_us.is_none() {
+                    split_file.first_time_us = time;
+                }
+                if time.is_some() {
+                    split_file.last_time_us = time;
+                }
+                msg_index += 1;
+            }
+            Ok(ParsedMessage::Invalid) | Ok(ParsedMessage::FilteredOut) => (),
+            Err(DltParseError::ParsingHickup { reason }) => {
+                warn!("split: skip message that could not be parsed: {}", reason);
+            }
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::ERROR,
+                    content: format!("split: cannot continue parsing: {}", e),
+                    line: Some(msg_index),
+                }));
+                break;
+            }
+        }
+        offset = end;
+        if utils::check_if_stop_was_requested(&shutdown_receiver, "dlt split") {
+            stopped = true;
+            break;
+        }
+    }
+    splitter.finish()?;
+    let manifest = SplitManifest {
+        source: in_path,
+        criterion: criterion.name(),
+        files: splitter.files,
+    };
+    let manifest_file = fs::File::create(split_manifest_path(in_file, out_dir))?;
+    serde_json::to_writer_pretty(BufWriter::new(manifest_file), &manifest)?;
+    let _ = update_channel.send(Ok(if stopped {
+        IndexingProgress::Stopped
+    } else {
+        IndexingProgress::Finished
+    }));
+    Ok(manifest)
+}
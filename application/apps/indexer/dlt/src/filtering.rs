@@ -13,7 +13,10 @@ use serde::{Deserialize, Serialize};
 use std::io::{Read};
 use std::fs;
 use std::collections::HashSet;
+use std::path::Path;
 use crate::dlt;
+use failure::err_msg;
+use regex::Regex;
 use std::iter::FromIterator;
 
 /// only select log entries with level MIN_LEVEL and more severe
@@ -23,7 +26,7 @@ use std::iter::FromIterator;
 ///  4 => INFO
 ///  5 => DEBUG
 ///  6 => VERBOSE
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct DltFilterConfig {
     pub min_log_level: Option<u8>,
     pub app_ids: Option<Vec<String>>,
@@ -53,3 +56,80 @@ pub fn read_filter_options(f: &mut fs::File) -> Result<DltFilterConfig, failure:
     let v: DltFilterConfig = serde_json::from_str(&contents[..])?;
     Ok(v)
 }
+
+/// selects the messages of a filtered export, the header filters are the
+/// ones of `DltFilterConfig` (so a filter file for indexing can be used as is)
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DltExportFilterConfig {
+    #[serde(flatten)]
+    pub header: DltFilterConfig,
+    /// a message is selected if its payload matches at least one regex
+    pub payload_regexes: Option<Vec<String>>,
+    /// storage header time in ms since epoch, inclusive
+    pub from_ms: Option<u64>,
+    /// storage header time in ms since epoch, inclusive
+    pub to_ms: Option<u64>,
+}
+
+#[derive(Clone)]
+pub struct ProcessedDltExportFilter {
+    /// applied by the parser (messages are `FilteredOut`)
+    pub header: ProcessedDltFilterConfig,
+    pub payload_regexes: Vec<Regex>,
+    pub from_ms: Option<u64>,
+    pub to_ms: Option<u64>,
+}
+
+impl ProcessedDltExportFilter {
+    /// checks the payload and the time of a message that passed the header filter
+    pub fn matches(&self, msg: &dlt::Message) -> bool {
+        if self.from_ms.is_some() || self.to_ms.is_some() {
+            let time_ms = match &msg.storage_header {
+                Some(h) => {
+                    u64::from(h.timestamp.seconds) * 1000
+                        + u64::from(h.timestamp.microseconds) / 1000
+                }
+                None => return false,
+            };
+            if self.from_ms.map(|from| time_ms < from).unwrap_or(false)
+                || self.to_ms.map(|to| time_ms > to).unwrap_or(false)
+            {
+                return false;
+            }
+        }
+        if self.payload_regexes.is_empty() {
+            return true;
+        }
+        // a payload that cannot be formatted does not match
+        match msg.displayed_fields() {
+            Ok(fields) => {
+                let payload = fields.payload();
+                self.payload_regexes.iter().any(|r| r.is_match(&payload))
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+pub fn process_export_filter_config(
+    cfg: DltExportFilterConfig,
+) -> Result<ProcessedDltExportFilter, failure::Error> {
+    let payload_regexes = cfg
+        .payload_regexes
+        .unwrap_or_default()
+        .iter()
+        .map(|p| Regex::new(p).map_err(|e| err_msg(format!("invalid payload regex {}: {}", p, e))))
+        .collect::<Result<Vec<Regex>, failure::Error>>()?;
+    Ok(ProcessedDltExportFilter {
+        header: process_filter_config(cfg.header),
+        payload_regexes,
+        from_ms: cfg.from_ms,
+        to_ms: cfg.to_ms,
+    })
+}
+
+pub fn read_export_filter_options(path: &Path) -> Result<DltExportFilterConfig, failure::Error> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| err_msg(format!("could not parse export filter {:?}: {}", path, e)))
+}
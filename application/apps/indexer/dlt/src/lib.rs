@@ -20,14 +20,25 @@ extern crate log;
 extern crate failure;
 
 pub mod dlt;
+pub mod dlt_export;
 pub mod dlt_file;
 pub mod dlt_fmt;
+pub mod dlt_follow;
+pub mod dlt_generator;
 pub mod dlt_net;
 pub mod dlt_parse;
 pub mod dlt_pcap;
+pub mod dlt_redaction;
+pub mod dlt_repair;
+pub mod dlt_replay;
+pub mod dlt_split;
 pub mod fibex;
 pub mod filtering;
+pub mod lifecycle;
+pub mod message_counter;
 pub mod proptest_strategies;
+pub mod reorder;
+pub mod ring_buffer;
 pub mod service_id;
 
 #[cfg(test)]
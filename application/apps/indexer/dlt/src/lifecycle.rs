@@ -0,0 +1,314 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::{DltTimeStamp, Message, DLT_COLUMN_SENTINAL};
+use crate::dlt_parse::{
+    read_one_dlt_message_info, DltParseError, StatisticsResults, DLT_MIN_BUFFER_SPACE,
+    DLT_READER_CAPACITY,
+};
+use buf_redux::policy::MinBuffered;
+use buf_redux::BufReader as ReduxReader;
+use failure::{err_msg, Error};
+use indexer_base::compression::open_input;
+use indexer_base::config::{IndexSection, SectionConfig};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fmt;
+use std::io::BufRead;
+
+/// the uptime of an ECU has to go back by more then this (in 0.1 ms) before
+/// we consider it a restart. Smaller backward steps happen all the time because
+/// messages of different applications are not sent in strict order
+pub const LIFECYCLE_RESTART_TOLERANCE: u32 = 10_000;
+
+/// a lifecycle spans all messages of one ECU between two restarts
+///
+/// `first_line` and `last_line` are message indices in the dlt file (same as
+/// the line numbers of an unfiltered index). Since messages of other ECUs can
+/// be interleaved, the section between them might contain other messages, too
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Lifecycle {
+    pub id: usize,
+    pub ecu_id: String,
+    pub first_line: usize,
+    pub last_line: usize,
+    pub message_count: usize,
+    /// uptime (in 0.1 ms) of the first and last message
+    pub start_uptime: u32,
+    pub end_uptime: u32,
+    /// estimated wallclock time (in us since epoch) when the uptime of
+    /// the ECU was 0, only available if the messages had a storage header
+    pub start_time_us: Option<i64>,
+    #[serde(skip)]
+    has_uptime: bool,
+}
+
+impl Lifecycle {
+    fn new(ecu_id: String, line_nr: usize) -> Self {
+        Lifecycle {
+            id: 0,
+            ecu_id,
+            first_line: line_nr,
+            last_line: line_nr,
+            message_count: 0,
+            start_uptime: 0,
+            end_uptime: 0,
+            start_time_us: None,
+            has_uptime: false,
+        }
+    }
+
+    fn add(&mut self, line_nr: usize, uptime: Option<u32>, storage_time: Option<&DltTimeStamp>) {
+        self.last_line = line_nr;
+        self.message_count += 1;
+        if let Some(uptime) = uptime {
+            if self.has_uptime {
+                self.end_uptime = std::cmp::max(self.end_uptime, uptime);
+            } else {
+                self.start_uptime = uptime;
+                self.end_uptime = uptime;
+                self.has_uptime = true;
+            }
+            if let Some(ts) = storage_time {
+                // the storage time is taken when the message was received, so the
+                // smallest difference is the closest we get to the real start time
+                let boot_time = timestamp_as_us(ts) - i64::from(uptime) * 100;
+                self.start_time_us = Some(match self.start_time_us {
+                    Some(t) => std::cmp::min(t, boot_time),
+                    None => boot_time,
+                });
+            }
+        }
+    }
+
+    /// the estimated absolute time for an uptime in this lifecycle
+    pub fn corrected_time(&self, uptime: u32) -> Option<DltTimeStamp> {
+        let us = self.start_time_us? + i64::from(uptime) * 100;
+        if us < 0 {
+            return None;
+        }
+        Some(DltTimeStamp {
+            seconds: (us / 1_000_000) as u32,
+            microseconds: (us % 1_000_000) as u32,
+        })
+    }
+
+    pub fn as_index_section(&self) -> IndexSection {
+        IndexSection {
+            first_line: self.first_line,
+            last_line: self.last_line,
+        }
+    }
+}
+
+fn timestamp_as_us(ts: &DltTimeStamp) -> i64 {
+    i64::from(ts.seconds) * 1_000_000 + i64::from(ts.microseconds)
+}
+
+fn is_restart(max_uptime: u32, uptime: u32) -> bool {
+    uptime.saturating_add(LIFECYCLE_RESTART_TOLERANCE) < max_uptime
+}
+
+/// ecu id of a message, the storage header is used as a fallback
+pub fn lifecycle_ecu_id(header_ecu_id: Option<&String>, storage_ecu_id: Option<&String>) -> String {
+    header_ecu_id
+        .or(storage_ecu_id)
+        .cloned()
+        .unwrap_or_else(|| "NONE".to_string())
+}
+
+/// splits a sequence of messages into lifecycles per ECU
+#[derive(Default)]
+pub struct LifecycleDetector {
+    current: FxHashMap<String, Lifecycle>,
+    finished: Vec<Lifecycle>,
+}
+
+impl LifecycleDetector {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn track(
+        &mut self,
+        line_nr: usize,
+        ecu_id: String,
+        uptime: Option<u32>,
+        storage_time: Option<&DltTimeStamp>,
+    ) {
+        if let (Some(lifecycle), Some(u)) = (self.current.get(&ecu_id), uptime) {
+            if lifecycle.has_uptime && is_restart(lifecycle.end_uptime, u) {
+                debug!(
+                    "ECU {} restarted in line {} (uptime {} after {})",
+                    ecu_id, line_nr, u, lifecycle.end_uptime
+                );
+                if let Some(ended) = self.current.remove(&ecu_id) {
+                    self.finished.push(ended);
+                }
+            }
+        }
+        self.current
+            .entry(ecu_id)
+            .or_insert_with_key(|id| Lifecycle::new(id.clone(), line_nr))
+            .add(line_nr, uptime, storage_time);
+    }
+
+    /// all detected lifecycles, ordered by their first line
+    pub fn finish(self) -> Vec<Lifecycle> {
+        let mut lifecycles = self.finished;
+        lifecycles.extend(self.current.into_values());
+        lifecycles.sort_by(|a, b| {
+            a.first_line
+                .cmp(&b.first_line)
+                .then_with(|| a.ecu_id.cmp(&b.ecu_id))
+        });
+        for (i, lifecycle) in lifecycles.iter_mut().enumerate() {
+            lifecycle.id = i;
+        }
+        lifecycles
+    }
+}
+
+/// section config that covers the selected lifecycles. Overlapping
+/// sections are merged since the export expects them in order
+pub fn lifecycle_sections(lifecycles: &[Lifecycle], ids: &[usize]) -> SectionConfig {
+    let mut ranges: Vec<(usize, usize)> = lifecycles
+        .iter()
+        .filter(|l| ids.contains(&l.id))
+        .map(|l| (l.first_line, l.last_line))
+        .collect();
+    ranges.sort();
+    let mut sections: Vec<IndexSection> = vec![];
+    for (first_line, last_line) in ranges {
+        match sections.last_mut() {
+            Some(last) if first_line <= last.last_line + 1 => {
+                last.last_line = std::cmp::max(last.last_line, last_line);
+            }
+            _ => sections.push(IndexSection {
+                first_line,
+                last_line,
+            }),
+        }
+    }
+    SectionConfig { sections }
+}
+
+/// collect the lifecycles of a dlt file (with storage headers)
+pub fn scan_lifecycles(in_file: &std::path::PathBuf) -> Result<Vec<Lifecycle>, Error> {
+    let f = match open_input(in_file) {
+        Ok(file) => file,
+        Err(e) => {
+            return Err(err_msg(format!("could not open {:?} ({})", in_file, e)));
+        }
+    };
+    let mut reader = ReduxReader::with_capacity(DLT_READER_CAPACITY, f)
+        .set_policy(MinBuffered(DLT_MIN_BUFFER_SPACE));
+    let mut detector = LifecycleDetector::new();
+    let mut line_nr = 0usize;
+    loop {
+        let no_channel: Option<&crossbeam_channel::Sender<StatisticsResults>> = None;
+        match read_one_dlt_message_info(&mut reader, Some(line_nr), true, no_channel) {
+            Ok(Some((consumed, row))) => {
+                reader.consume(consumed);
+                detector.track(
+                    line_nr,
+                    lifecycle_ecu_id(row.ecu_id(), row.storage_ecu_id()),
+                    row.uptime(),
+                    row.storage_time(),
+                );
+                line_nr += 1;
+            }
+            Ok(None) => break,
+            Err(DltParseError::ParsingHickup { reason }) => {
+                debug!("lifecycle scan: skip broken message: {}", reason);
+                reader.consume(4);
+            }
+            Err(e) => {
+                warn!("lifecycle scan: cannot continue parsing: {}", e);
+                break;
+            }
+        }
+    }
+    Ok(detector.finish())
+}
+
+/// assigns messages to previously detected lifecycles to
+/// calculate the absolute time from the uptime
+pub struct LifecycleTimeCorrection {
+    per_ecu: FxHashMap<String, EcuLifecycles>,
+}
+
+struct EcuLifecycles {
+    lifecycles: Vec<Lifecycle>,
+    current: usize,
+    max_uptime: Option<u32>,
+}
+
+impl LifecycleTimeCorrection {
+    pub fn new(lifecycles: Vec<Lifecycle>) -> Self {
+        let mut per_ecu: FxHashMap<String, EcuLifecycles> = FxHashMap::default();
+        for lifecycle in lifecycles {
+            per_ecu
+                .entry(lifecycle.ecu_id.clone())
+                .or_insert_with(|| EcuLifecycles {
+                    lifecycles: vec![],
+                    current: 0,
+                    max_uptime: None,
+                })
+                .lifecycles
+                .push(lifecycle);
+        }
+        LifecycleTimeCorrection { per_ecu }
+    }
+
+    /// the lifecycle of a message, messages have to be passed in the same
+    /// order as during the detection
+    pub fn lifecycle_of(&mut self, msg: &Message) -> Option<&Lifecycle> {
+        let ecu_id = lifecycle_ecu_id(
+            msg.header.ecu_id.as_ref(),
+            msg.storage_header.as_ref().map(|h| &h.ecu_id),
+        );
+        let ecu = self.per_ecu.get_mut(&ecu_id)?;
+        if let Some(uptime) = msg.header.timestamp {
+            match ecu.max_uptime {
+                Some(max) if is_restart(max, uptime) && ecu.current + 1 < ecu.lifecycles.len() => {
+                    ecu.current += 1;
+                    ecu.max_uptime = Some(uptime);
+                }
+                Some(max) => ecu.max_uptime = Some(std::cmp::max(max, uptime)),
+                None => ecu.max_uptime = Some(uptime),
+            }
+        }
+        ecu.lifecycles.get(ecu.current)
+    }
+
+    /// messages have to be passed in the same order as during the detection
+    pub fn corrected_time(&mut self, msg: &Message) -> Option<DltTimeStamp> {
+        let uptime = msg.header.timestamp?;
+        self.lifecycle_of(msg)?.corrected_time(uptime)
+    }
+}
+
+/// formats a message with an additional trailing column for the corrected time
+pub struct MessageWithCorrectedTime<'a> {
+    pub msg: &'a Message,
+    pub corrected_time: Option<DltTimeStamp>,
+}
+
+impl<'a> fmt::Display for MessageWithCorrectedTime<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.msg, DLT_COLUMN_SENTINAL)?;
+        match &self.corrected_time {
+            Some(ts) => write!(f, "{}", ts),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::{LogLevel, Message};
+use crate::filtering;
+use indexer_base::progress::{Notification, Severity};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fmt;
+
+/// the message counter of a dlt message is only meaningful within the
+/// sequence of messages of one sender. We identify a sender by its
+/// ECU, session and application id
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CounterKey {
+    pub ecu_id: Option<String>,
+    pub session_id: Option<u32>,
+    pub app_id: Option<String>,
+}
+
+impl CounterKey {
+    pub fn from_message(msg: &Message) -> CounterKey {
+        CounterKey {
+            ecu_id: msg.header.ecu_id.clone(),
+            session_id: msg.header.session_id,
+            app_id: msg
+                .extended_header
+                .as_ref()
+                .map(|h| h.application_id.clone()),
+        }
+    }
+}
+
+impl fmt::Display for CounterKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}",
+            self.ecu_id.as_deref().unwrap_or("NONE"),
+            self.app_id.as_deref().unwrap_or("NONE")
+        )?;
+        if let Some(session_id) = self.session_id {
+            write!(f, " (session {})", session_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CounterEvent {
+    /// the counter jumped ahead, `lost` messages are missing in between
+    Gap {
+        expected: u8,
+        received: u8,
+        lost: usize,
+    },
+    /// the same counter value was seen twice in a row
+    Duplicate { counter: u8 },
+}
+
+/// accumulated counter anomalies (usually per ECU)
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct LostMessagesInfo {
+    pub lost: usize,
+    pub gaps: usize,
+    pub duplicates: usize,
+    pub wraparounds: usize,
+}
+
+impl LostMessagesInfo {
+    fn add(&mut self, event: &CounterEvent) {
+        match event {
+            CounterEvent::Gap { lost, .. } => {
+                self.lost += lost;
+                self.gaps += 1;
+            }
+            CounterEvent::Duplicate { .. } => self.duplicates += 1,
+        }
+    }
+}
+
+/// keeps track of the last seen message counter for every sender
+/// and detects gaps and duplicates. Wraparounds are only counted
+#[derive(Default)]
+pub struct MessageCounterTracker {
+    last_counters: FxHashMap<CounterKey, u8>,
+    per_ecu: FxHashMap<String, LostMessagesInfo>,
+}
+
+impl MessageCounterTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// register the next message counter for a sender. The first message of
+    /// a sender only initializes the sequence, a regular overflow from 255 to 0
+    /// is no event
+    pub fn track(&mut self, key: CounterKey, counter: u8) -> Option<CounterEvent> {
+        let last = self.last_counters.insert(key.clone(), counter)?;
+        let expected = last.wrapping_add(1);
+        let ecu = key.ecu_id.unwrap_or_else(|| "NONE".to_string());
+        if counter < last {
+            self.per_ecu.entry(ecu.clone()).or_default().wraparounds += 1;
+        }
+        let event = if counter == expected {
+            return None;
+        } else if counter == last {
+            CounterEvent::Duplicate { counter }
+        } else {
+            CounterEvent::Gap {
+                expected,
+                received: counter,
+                lost: counter.wrapping_sub(expected) as usize,
+            }
+        };
+        self.per_ecu.entry(ecu).or_default().add(&event);
+        Some(event)
+    }
+
+    /// convenience function that tracks a message and creates a warning
+    /// for anything unusual
+    pub fn track_message(&mut self, msg: &Message, line_nr: usize) -> Option<Notification> {
+        let key = CounterKey::from_message(msg);
+        self.track(key.clone(), msg.header.message_counter)
+            .map(|event| counter_event_notification(&key, &event, line_nr))
+    }
+
+    pub fn lost_messages(&self) -> Vec<(String, LostMessagesInfo)> {
+        let mut res: Vec<(String, LostMessagesInfo)> = self
+            .per_ecu
+            .iter()
+            .map(|(ecu, info)| (ecu.clone(), info.clone()))
+            .collect();
+        res.sort_by(|a, b| a.0.cmp(&b.0));
+        res
+    }
+}
+
+pub fn counter_event_notification(
+    key: &CounterKey,
+    event: &CounterEvent,
+    line_nr: usize,
+) -> Notification {
+    let content = match event {
+        CounterEvent::Gap {
+            expected,
+            received,
+            lost,
+        } => format!(
+            "message counter gap for {}: expected {} but got {}, {} message(s) lost",
+            key, expected, received, lost
+        ),
+        CounterEvent::Duplicate { counter } => {
+            format!("duplicated message counter {} for {}", counter, key)
+        }
+    };
+    Notification {
+        severity: Severity::WARNING,
+        content,
+        line: Some(line_nr),
+    }
+}
+
+/// a filter that drops single messages of a sender (log level or context id)
+/// would make the counter sequence look broken, so in that case we cannot
+/// say anything about lost messages
+pub fn counter_sequence_intact(
+    filter_config: Option<&filtering::ProcessedDltFilterConfig>,
+) -> bool {
+    match filter_config {
+        None => true,
+        Some(cfg) => {
+            let keeps_all_levels = match cfg.min_log_level {
+                None | Some(LogLevel::Verbose) => true,
+                Some(_) => false,
+            };
+            keeps_all_levels && cfg.context_ids.is_none()
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::dlt::Message;
+use crate::message_counter::CounterKey;
+use rustc_hash::FxHashMap;
+use std::borrow::Borrow;
+use std::time::{Duration, Instant};
+
+struct Pending<T> {
+    arrival: Instant,
+    seq: u64,
+    /// uptime used for sorting, messages without a timestamp keep
+    /// the last uptime we saw for their sender so they stay in place
+    uptime: u32,
+    msg: T,
+}
+
+struct SenderQueue<T> {
+    pending: Vec<Pending<T>>,
+    last_uptime: u32,
+}
+
+impl<T> Default for SenderQueue<T> {
+    fn default() -> Self {
+        SenderQueue {
+            pending: vec![],
+            last_uptime: 0,
+        }
+    }
+}
+
+/// Buffers live messages for a limited time to bring them into order
+///
+/// Message counters are only continuous per sender (see `CounterKey`), so
+/// messages are only sorted per sender (by uptime and message counter).
+/// Messages of different senders stay in the order in which they arrived.
+/// Items can be anything that contains a message (e.g. a message together
+/// with its source).
+/// A message is released at the latest `window` after it arrived, together
+/// with all messages of the same sender that belong before it
+pub struct ReorderBuffer<T: Borrow<Message>> {
+    window: Duration,
+    queues: FxHashMap<CounterKey, SenderQueue<T>>,
+    next_seq: u64,
+}
+
+impl<T: Borrow<Message>> ReorderBuffer<T> {
+    pub fn new(window: Duration) -> Self {
+        ReorderBuffer {
+            window,
+            queues: FxHashMap::default(),
+            next_seq: 0,
+        }
+    }
+
+    pub fn push(&mut self, item: T, arrival: Instant) {
+        let msg: &Message = item.borrow();
+        let mut key = CounterKey::from_message(msg);
+        if key.ecu_id.is_none() {
+            key.ecu_id = msg.storage_header.as_ref().map(|h| h.ecu_id.clone());
+        }
+        let queue = self.queues.entry(key).or_default();
+        let uptime = match msg.header.timestamp {
+            Some(t) => {
+                queue.last_uptime = t;
+                t
+            }
+            None => queue.last_uptime,
+        };
+        queue.pending.push(Pending {
+            arrival,
+            seq: self.next_seq,
+            uptime,
+            msg: item,
+        });
+        self.next_seq += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queues.values().all(|q| q.pending.is_empty())
+    }
+
+    /// all messages that have waited for the complete window (plus the ones
+    /// that have to go before them), in the order they should be written
+    pub fn pop_ready(&mut self, now: Instant) -> Vec<T> {
+        let window = self.window;
+        self.release(|p| now.duration_since(p.arrival) >= window)
+    }
+
+    /// release everything, e.g. when the session ends
+    pub fn flush(&mut self) -> Vec<T> {
+        self.release(|_| true)
+    }
+
+    fn release<F: Fn(&Pending<T>) -> bool>(&mut self, expired: F) -> Vec<T> {
+        let mut released: Vec<Vec<Pending<T>>> = vec![];
+        for queue in self.queues.values_mut() {
+            if !queue.pending.iter().any(&expired) {
+                continue;
+            }
+            sort_pending(&mut queue.pending);
+            let last_expired = queue.pending.iter().rposition(&expired).unwrap_or(0);
+            let rest = queue.pending.split_off(last_expired + 1);
+            released.push(std::mem::replace(&mut queue.pending, rest));
+        }
+        merge_by_arrival(released)
+    }
+}
+
+fn sort_pending<T: Borrow<Message>>(pending: &mut [Pending<T>]) {
+    // message counters wrap around, so they are compared by their distance
+    // to the counter of the oldest message in the queue (which can be
+    // negative for messages that were sent before it)
+    let reference = match pending.iter().min_by_key(|p| p.seq) {
+        Some(p) => p.msg.borrow().header.message_counter,
+        None => return,
+    };
+    pending.sort_by_key(|p| {
+        (
+            p.uptime,
+            p.msg
+                .borrow()
+                .header
+                .message_counter
+                .wrapping_sub(reference) as i8,
+            p.seq,
+        )
+    });
+}
+
+/// combines the sorted per sender lists, across senders the arrival order is kept
+fn merge_by_arrival<T>(lists: Vec<Vec<Pending<T>>>) -> Vec<T> {
+    let total = lists.iter().map(|l| l.len()).sum();
+    let mut res: Vec<T> = Vec::with_capacity(total);
+    let mut iters: Vec<std::iter::Peekable<std::vec::IntoIter<Pending<T>>>> = lists
+        .into_iter()
+        .map(|l| l.into_iter().peekable())
+        .collect();
+    loop {
+        let next = iters
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, it)| it.peek().map(|p| (p.seq, i)))
+            .min();
+        match next {
+            Some((_, i)) => {
+                if let Some(p) = iters[i].next() {
+                    res.push(p.msg);
+                }
+            }
+            None => break,
+        }
+    }
+    res
+}
@@ -0,0 +1,378 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use failure::{err_msg, Error};
+use indexer_base::chunks::Chunk;
+use indexer_base::config::{IndexSection, RingBufferConfig, SectionConfig};
+use indexer_base::progress::{Notification, Severity};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+pub const SEGMENT_MANIFEST_SUFFIX: &str = ".segments.json";
+
+/// one segment of the raw dlt messages of a ring buffer capture
+///
+/// the line numbers count from the start of the capture, they include the
+/// lines that were already removed from the index file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SegmentInfo {
+    pub index: usize,
+    pub first_line: usize,
+    /// number of messages in the segment
+    pub line_count: usize,
+    pub dlt_bytes: u64,
+    /// bytes of the lines of the segment in the index file
+    #[serde(default)]
+    pub text_bytes: u64,
+    pub dlt_file: PathBuf,
+}
+
+impl SegmentInfo {
+    /// the parts of `sections` (global line numbers) that are in this segment,
+    /// with line numbers relative to the segment file.
+    /// No sections means everything, `None` if nothing is in this segment
+    pub fn local_sections(&self, sections: &SectionConfig) -> Option<SectionConfig> {
+        if sections.sections.is_empty() {
+            return Some(SectionConfig { sections: vec![] });
+        }
+        if self.line_count == 0 {
+            return None;
+        }
+        let last_line = self.first_line + self.line_count - 1;
+        let local: Vec<IndexSection> = sections
+            .sections
+            .iter()
+            .filter(|s| s.first_line <= last_line && s.last_line >= self.first_line)
+            .map(|s| IndexSection {
+                first_line: std::cmp::max(s.first_line, self.first_line) - self.first_line,
+                last_line: std::cmp::min(s.last_line, last_line) - self.first_line,
+            })
+            .collect();
+        if local.is_empty() {
+            None
+        } else {
+            Some(SectionConfig { sections: local })
+        }
+    }
+}
+
+/// describes the segments that are still available
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct SegmentManifest {
+    pub segments: Vec<SegmentInfo>,
+    pub dropped_segments: usize,
+    pub dropped_lines: usize,
+    #[serde(default)]
+    pub dropped_text_bytes: u64,
+}
+
+impl SegmentManifest {
+    /// where the lines of `chunk` (counted from the start of the capture) are
+    /// in the index file after the lines of the dropped segments were removed
+    pub fn index_chunk(&self, chunk: Chunk) -> Chunk {
+        let dropped_bytes = self.dropped_text_bytes as usize;
+        Chunk {
+            r: (
+                chunk.r.0 - self.dropped_lines,
+                chunk.r.1 - self.dropped_lines,
+            ),
+            b: (chunk.b.0 - dropped_bytes, chunk.b.1 - dropped_bytes),
+        }
+    }
+}
+
+/// the manifest is stored next to the dlt session file
+pub fn manifest_path(dlt_base: &Path) -> PathBuf {
+    let mut name = dlt_base.as_os_str().to_owned();
+    name.push(SEGMENT_MANIFEST_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// `capture.dlt` => `capture_000003.dlt`
+pub fn segment_path(base: &Path, index: usize) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match base.extension() {
+        Some(ext) => format!("{}_{:06}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}_{:06}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+pub fn read_segment_manifest(path: &Path) -> Result<SegmentManifest, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| err_msg(format!("could not read manifest {:?} ({})", path, e)))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Splits the raw dlt messages of a live capture into numbered segment files
+/// and keeps the index file (the text output) in step with them.
+///
+/// A new segment is started once the dlt content or the text output of the
+/// current one reached `max_segment_bytes` or it is older then
+/// `max_segment_duration_s`. When more then `max_segments` exist, the oldest
+/// one is deleted (or moved to the archive directory) and its lines are cut
+/// from the start of the index file. Content the index file had before
+/// the capture started is kept.
+pub struct SegmentRing {
+    config: RingBufferConfig,
+    dlt_base: PathBuf,
+    index_file: PathBuf,
+    /// size of the index file before the capture
+    index_start: u64,
+    manifest_path: PathBuf,
+    manifest: SegmentManifest,
+    segment_started: Instant,
+    next_index: usize,
+}
+
+impl SegmentRing {
+    pub fn start(
+        config: RingBufferConfig,
+        dlt_base: PathBuf,
+        index_file: PathBuf,
+        first_line: usize,
+        now: Instant,
+    ) -> Result<(Self, fs::File), Error> {
+        if config.max_segments == 0 {
+            return Err(err_msg("ring buffer needs at least one segment"));
+        }
+        if config.max_segment_bytes.is_none() && config.max_segment_duration_s.is_none() {
+            return Err(err_msg(
+                "ring buffer needs a maximum segment size or duration",
+            ));
+        }
+        if let Some(dir) = &config.archive_dir {
+            fs::create_dir_all(dir)?;
+        }
+        let manifest_path = manifest_path(&dlt_base);
+        let index_start = fs::metadata(&index_file).map_or(0, |m| m.len());
+        let mut ring = SegmentRing {
+            config,
+            dlt_base,
+            index_file,
+            index_start,
+            manifest_path,
+            manifest: SegmentManifest::default(),
+            segment_started: now,
+            next_index: 0,
+        };
+        let file = ring.open_segment(first_line, now)?;
+        ring.write_manifest()?;
+        Ok((ring, file))
+    }
+
+    pub fn manifest(&self) -> &SegmentManifest {
+        &self.manifest
+    }
+
+    /// has to be called for every message written to the current segment
+    /// with the size of its line in the index file
+    pub fn add_message(&mut self, dlt_bytes: usize, text_bytes: usize) {
+        if let Some(current) = self.manifest.segments.last_mut() {
+            current.line_count += 1;
+            current.dlt_bytes += dlt_bytes as u64;
+            current.text_bytes += text_bytes as u64;
+        }
+    }
+
+    pub fn is_full(&self, now: Instant) -> bool {
+        let current = match self.manifest.segments.last() {
+            Some(current) => current,
+            None => return false,
+        };
+        if current.line_count == 0 {
+            return false;
+        }
+        let too_big = match self.config.max_segment_bytes {
+            Some(max) => current.dlt_bytes >= max || current.text_bytes >= max,
+            None => false,
+        };
+        let too_old = match self.config.max_segment_duration_s {
+            Some(secs) => now.duration_since(self.segment_started) >= Duration::from_secs(secs),
+            None => false,
+        };
+        too_big || too_old
+    }
+
+    /// starts a new segment if the current one is full. Has to be called
+    /// between messages with everything written to the index file, the
+    /// returned file replaces the current one. The index file is replaced
+    /// when segments were dropped, so it has to be opened again
+    pub fn rotate_if_needed(
+        &mut self,
+        now: Instant,
+    ) -> Result<Option<(fs::File, Vec<Notification>)>, Error> {
+        if !self.is_full(now) {
+            return Ok(None);
+        }
+        let first_line = match self.manifest.segments.last() {
+            Some(s) => s.first_line + s.line_count,
+            None => 0,
+        };
+        let file = self.open_segment(first_line, now)?;
+        let mut notifications = vec![];
+        let dropped_lines = self.manifest.dropped_lines;
+        while self.manifest.segments.len() > self.config.max_segments {
+            let dropped = self.manifest.segments.remove(0);
+            self.remove_lines(&dropped)?;
+            self.manifest.dropped_segments += 1;
+            self.manifest.dropped_lines += dropped.line_count;
+            self.manifest.dropped_text_bytes += dropped.text_bytes;
+            notifications.push(self.remove_segment(&dropped));
+        }
+        if self.manifest.dropped_lines > dropped_lines {
+            notifications.push(Notification {
+                severity: Severity::WARNING,
+                content: format!(
+                    "removed {} lines from the start of the session, line numbers are offset by {}",
+                    self.manifest.dropped_lines - dropped_lines,
+                    self.manifest.dropped_lines
+                ),
+                line: None,
+            });
+        }
+        self.write_manifest()?;
+        Ok(Some((file, notifications)))
+    }
+
+    /// writes the final state of the manifest
+    pub fn finish(&self) -> Result<(), Error> {
+        self.write_manifest()
+    }
+
+    fn open_segment(&mut self, first_line: usize, now: Instant) -> Result<fs::File, Error> {
+        let index = self.next_index;
+        self.next_index += 1;
+        let dlt_path = segment_path(&self.dlt_base, index);
+        debug!("start segment {}: {:?}", index, dlt_path);
+        let file = fs::File::create(&dlt_path)?;
+        self.manifest.segments.push(SegmentInfo {
+            index,
+            first_line,
+            line_count: 0,
+            dlt_bytes: 0,
+            text_bytes: 0,
+            dlt_file: dlt_path,
+        });
+        self.segment_started = now;
+        Ok(file)
+    }
+
+    /// cuts the lines of the segment from the index file (they directly
+    /// follow the content from before the capture)
+    fn remove_lines(&self, segment: &SegmentInfo) -> Result<(), Error> {
+        let archive = self
+            .config
+            .archive_dir
+            .as_ref()
+            .map(|dir| {
+                archive_target(
+                    &segment_path(&self.index_file, segment.index),
+                    Path::new(dir),
+                )
+            })
+            .transpose()?;
+        cut_file(
+            &self.index_file,
+            self.index_start,
+            segment.text_bytes,
+            archive.as_deref(),
+        )
+        .map_err(|e| {
+            err_msg(format!(
+                "could not remove lines of segment {} from {:?} ({})",
+                segment.index, self.index_file, e
+            ))
+        })
+    }
+
+    fn remove_segment(&self, segment: &SegmentInfo) -> Notification {
+        let action = match &self.config.archive_dir {
+            Some(dir) => {
+                if let Err(e) = archive_file(&segment.dlt_file, &PathBuf::from(dir)) {
+                    warn!("could not archive {:?}: {}", segment.dlt_file, e);
+                }
+                "archived"
+            }
+            None => {
+                if let Err(e) = fs::remove_file(&segment.dlt_file) {
+                    warn!("could not remove {:?}: {}", segment.dlt_file, e);
+                }
+                "dropped"
+            }
+        };
+        Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "{} dlt segment {} (lines {} - {})",
+                action,
+                segment.index,
+                segment.first_line,
+                (segment.first_line + segment.line_count).saturating_sub(1)
+            ),
+            line: None,
+        }
+    }
+
+    fn write_manifest(&self) -> Result<(), Error> {
+        let mut f = fs::File::create(&self.manifest_path)?;
+        f.write_all(serde_json::to_string(&self.manifest)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn archive_target(file: &Path, dir: &Path) -> Result<PathBuf, Error> {
+    let name = file
+        .file_name()
+        .ok_or_else(|| err_msg(format!("no file name in {:?}", file)))?;
+    Ok(dir.join(name))
+}
+
+fn archive_file(file: &Path, dir: &Path) -> Result<(), Error> {
+    let target = archive_target(file, dir)?;
+    if fs::rename(file, &target).is_err() {
+        // rename does not work across file systems
+        fs::copy(file, &target)?;
+        fs::remove_file(file)?;
+    }
+    Ok(())
+}
+
+/// removes `len` bytes at `start` from the file by writing the rest to a new
+/// file that replaces it, the removed bytes go to `removed_to` if given
+fn cut_file(path: &Path, start: u64, len: u64, removed_to: Option<&Path>) -> Result<(), Error> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".cut");
+    let tmp_path = PathBuf::from(tmp_path);
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+    io::copy(&mut (&mut reader).take(start), &mut writer)?;
+    let mut removed = (&mut reader).take(len);
+    match removed_to {
+        Some(target) => {
+            io::copy(&mut removed, &mut BufWriter::new(fs::File::create(target)?))?;
+        }
+        None => {
+            io::copy(&mut removed, &mut io::sink())?;
+        }
+    }
+    io::copy(&mut reader, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
@@ -0,0 +1,361 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_export::*;
+    use crate::fibex::{read_fibexes, FibexMetadata};
+    use crate::filtering::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use indexer_base::config::{IndexSection, SectionConfig};
+    use indexer_base::progress::Severity;
+    use pretty_assertions::assert_eq;
+    use std::path::{Path, PathBuf};
+    use tempdir::TempDir;
+
+    fn msg(app: &str, text: &str, seconds: u32) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Big,
+                ecu_id: Some("ECU1".to_string()),
+                session_id: None,
+                timestamp: Some(seconds * 10_000),
+                payload: Payload2 {
+                    payload_content: PayloadContent::Verbose(vec![Argument {
+                        type_info: TypeInfo {
+                            kind: TypeInfoKind::StringType,
+                            coding: StringCoding::UTF8,
+                            has_variable_info: false,
+                            has_trace_info: false,
+                        },
+                        name: None,
+                        unit: None,
+                        fixed_point: None,
+                        value: Value::StringVal(text.to_string()),
+                    }]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Warn),
+                    app_id: app.to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds,
+            microseconds: 0,
+        }))
+        .as_bytes()
+    }
+
+    /// a message for the frame with id 65 of the test fibex
+    fn non_verbose(data: Vec<u8>) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Big,
+                ecu_id: Some("ECU1".to_string()),
+                session_id: None,
+                timestamp: Some(0),
+                payload: Payload2 {
+                    payload_content: PayloadContent::NonVerbose(65, data),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Warn),
+                    app_id: "DR".to_string(),
+                    context_id: "CTX1".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds: 100,
+            microseconds: 0,
+        }))
+        .as_bytes()
+    }
+
+    fn test_fibex() -> FibexMetadata {
+        read_fibexes(vec![
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/dlt-messages.xml")
+        ])
+        .expect("can't parse fibex")
+    }
+
+    fn export(
+        messages: &[Vec<u8>],
+        sections: Vec<IndexSection>,
+        format: ExportFormat,
+        dir: &Path,
+    ) -> (usize, String) {
+        let in_path = dir.join("trace.dlt");
+        let out_path = dir.join("trace.out");
+        std::fs::write(&in_path, messages.concat()).unwrap();
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let written = export_dlt_file_structured(
+            &in_path,
+            &out_path,
+            SectionConfig { sections },
+            format,
+            None,
+            None,
+            tx,
+            None,
+        )
+        .expect("export failed");
+        (written, std::fs::read_to_string(&out_path).unwrap())
+    }
+
+    #[test]
+    fn test_export_json_lines() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> = (0..3).map(|i| msg("APP", "hello", 100 + i)).collect();
+        let (written, content) = export(&messages, vec![], ExportFormat::JsonLines, tmp_dir.path());
+        assert_eq!(3, written);
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|l| serde_json::from_str(l).expect("invalid json"))
+            .collect();
+        assert_eq!(3, lines.len());
+        assert_eq!(serde_json::json!("ECU1"), lines[0]["header"]["ecu_id"]);
+        assert_eq!(
+            serde_json::json!(101),
+            lines[1]["storage_header"]["timestamp"]["seconds"]
+        );
+        assert_eq!(
+            serde_json::json!({ "StringVal": "hello" }),
+            lines[2]["payload"]["payload_content"]["Verbose"][0]["value"]
+        );
+    }
+
+    #[test]
+    fn test_export_csv_columns() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let messages = vec![
+            msg("APP1", "plain", 100),
+            msg("APP2", "a \"quoted\", text", 101),
+        ];
+        let columns = parse_csv_columns("ecu, app,context,level,payload").unwrap();
+        let (written, content) = export(
+            &messages,
+            vec![],
+            ExportFormat::Csv(columns),
+            tmp_dir.path(),
+        );
+        assert_eq!(2, written);
+        assert_eq!(
+            vec![
+                "ecu,app,context,level,payload",
+                "ECU1,APP1,CTX,WARN,plain",
+                "ECU1,APP2,CTX,WARN,\"a \"\"quoted\"\", text\"",
+            ],
+            content.lines().collect::<Vec<&str>>()
+        );
+        let (_, with_time) = export(
+            &messages[0..1],
+            vec![],
+            ExportFormat::Csv(vec![CsvColumn::Time]),
+            tmp_dir.path(),
+        );
+        assert_eq!("time\n1970-01-01T00:01:40Z\n", with_time);
+    }
+
+    #[test]
+    fn test_export_csv_payload_with_sentinels() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let text = format!("a{}b{}c", DLT_COLUMN_SENTINAL, DLT_ARGUMENT_SENTINAL);
+        let columns = parse_csv_columns("app,context,payload").unwrap();
+        let (_, content) = export(
+            &[msg("APP1", &text, 100)],
+            vec![],
+            ExportFormat::Csv(columns),
+            tmp_dir.path(),
+        );
+        assert_eq!(format!("app,context,payload\nAPP1,CTX,{}\n", text), content);
+    }
+
+    #[test]
+    fn test_export_skips_malformed_non_verbose_payload() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        // the frame with id 65 needs 24 bytes of values, a single byte does not fit
+        let mut values = vec![0u8; 24];
+        values[3] = 42;
+        let messages = [non_verbose(values), non_verbose(vec![1])];
+        let in_path = tmp_dir.path().join("trace.dlt");
+        let out_path = tmp_dir.path().join("trace.out");
+        std::fs::write(&in_path, messages.concat()).unwrap();
+        let fibex = test_fibex();
+        for format in [
+            ExportFormat::JsonLines,
+            ExportFormat::Csv(vec![CsvColumn::Payload]),
+        ] {
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            let written = export_dlt_file_structured(
+                &in_path,
+                &out_path,
+                SectionConfig { sections: vec![] },
+                format,
+                Some(fibex.clone()),
+                None,
+                tx,
+                None,
+            )
+            .expect("export failed");
+            assert_eq!(1, written);
+            let warnings: Vec<Option<usize>> = rx
+                .try_iter()
+                .filter_map(|r| r.err())
+                .filter(|n| n.severity == Severity::WARNING)
+                .map(|n| n.line)
+                .collect();
+            assert_eq!(vec![Some(1)], warnings);
+        }
+        // json lines show the arguments resolved with the fibex instead of raw bytes
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        export_dlt_file_structured(
+            &in_path,
+            &out_path,
+            SectionConfig { sections: vec![] },
+            ExportFormat::JsonLines,
+            Some(fibex),
+            None,
+            tx,
+            None,
+        )
+        .expect("export failed");
+        let line: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&out_path).unwrap().trim()).unwrap();
+        let content = &line["payload"]["payload_content"]["NonVerbose"];
+        assert_eq!(serde_json::json!(65), content["id"]);
+        assert!(content["arguments"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("42")));
+    }
+
+    #[test]
+    fn test_export_sections() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> = (0..6)
+            .map(|i| msg("APP", &format!("msg{}", i), 100))
+            .collect();
+        let (written, content) = export(
+            &messages,
+            vec![
+                IndexSection {
+                    first_line: 1,
+                    last_line: 2,
+                },
+                IndexSection {
+                    first_line: 4,
+                    last_line: 4,
+                },
+            ],
+            ExportFormat::Csv(vec![CsvColumn::Payload]),
+            tmp_dir.path(),
+        );
+        assert_eq!(3, written);
+        assert_eq!("payload\nmsg1\nmsg2\nmsg4\n", content);
+    }
+
+    #[test]
+    fn test_parse_csv_columns() {
+        assert_eq!(
+            ALL_CSV_COLUMNS.to_vec(),
+            parse_csv_columns("time,ECU,app,context,level,payload").unwrap()
+        );
+        assert!(parse_csv_columns("time,foo").is_err());
+        assert!(parse_csv_columns("").is_err());
+    }
+
+    fn export_filtered(messages: &[Vec<u8>], filter: &str, dir: &Path) -> (usize, Vec<u8>) {
+        export_filtered_with_fibex(messages, filter, None, dir)
+    }
+
+    fn export_filtered_with_fibex(
+        messages: &[Vec<u8>],
+        filter: &str,
+        fibex: Option<FibexMetadata>,
+        dir: &Path,
+    ) -> (usize, Vec<u8>) {
+        let in_path = dir.join("trace.dlt");
+        let out_path = dir.join("filtered.dlt");
+        std::fs::write(&in_path, messages.concat()).unwrap();
+        let config: DltExportFilterConfig = serde_json::from_str(filter).expect("invalid filter");
+        let filter = process_export_filter_config(config).expect("could not process filter");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let written = export_filtered_dlt_file(&in_path, &out_path, &filter, fibex, tx, None)
+            .expect("export failed");
+        (written, std::fs::read(&out_path).unwrap())
+    }
+
+    #[test]
+    fn test_export_filtered_byte_exact() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let messages = vec![
+            msg("APP1", "door open", 100),
+            msg("APP2", "door open", 101),
+            msg("APP1", "door closed", 102),
+            msg("APP1", "window open", 103),
+            msg("APP1", "door open", 104),
+        ];
+        let (written, content) = export_filtered(
+            &messages,
+            r#"{"app_ids": ["APP1"], "payload_regexes": ["^door", "closed"], "to_ms": 103000}"#,
+            tmp_dir.path(),
+        );
+        assert_eq!(2, written);
+        assert_eq!([messages[0].clone(), messages[2].clone()].concat(), content);
+    }
+
+    #[test]
+    fn test_export_filtered_payload_that_cannot_be_formatted() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let mut values = vec![0u8; 24];
+        values[3] = 42;
+        // a single byte does not fit the frame and never matches
+        let messages = vec![non_verbose(values), non_verbose(vec![1])];
+        let (written, content) = export_filtered_with_fibex(
+            &messages,
+            r#"{"payload_regexes": [".*"]}"#,
+            Some(test_fibex()),
+            tmp_dir.path(),
+        );
+        assert_eq!(1, written);
+        assert_eq!(messages[0], content);
+    }
+
+    #[test]
+    fn test_export_filtered_time_range() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> = (0..5).map(|i| msg("APP", "tick", 100 + i)).collect();
+        let (written, content) = export_filtered(
+            &messages,
+            r#"{"from_ms": 101000, "to_ms": 102500}"#,
+            tmp_dir.path(),
+        );
+        assert_eq!(2, written);
+        assert_eq!(messages[1..3].concat(), content);
+        let (all, _) = export_filtered(&messages, "{}", tmp_dir.path());
+        assert_eq!(5, all);
+    }
+
+    #[test]
+    fn test_export_filter_config() {
+        let config: DltExportFilterConfig =
+            serde_json::from_str(r#"{"min_log_level": 3, "context_ids": ["CTX"]}"#).unwrap();
+        assert_eq!(Some(3), config.header.min_log_level);
+        assert_eq!(Some(vec!["CTX".to_string()]), config.header.context_ids);
+        assert_eq!(None, config.payload_regexes);
+        let invalid = DltExportFilterConfig {
+            payload_regexes: Some(vec!["(".to_string()]),
+            ..Default::default()
+        };
+        assert!(process_export_filter_config(invalid).is_err());
+    }
+}
@@ -32,6 +32,60 @@ mod tests {
             &tx,
             None,
             None,
+            false,
         );
     }
+
+    #[test]
+    fn test_index_compressed_dlt_file() {
+        use crate::dlt_generator::{DltGenerator, DltGeneratorConfig};
+        use std::io::Write;
+        let tmp_dir = tempdir::TempDir::new("compressed").expect("could not create temp dir");
+        let mut content: Vec<u8> = vec![];
+        DltGenerator::new(DltGeneratorConfig {
+            target_size: 50 * 1024,
+            seed: 7,
+            ..Default::default()
+        })
+        .unwrap()
+        .generate(&mut content)
+        .unwrap();
+        let plain_path = tmp_dir.path().join("trace.dlt");
+        let gz_path = tmp_dir.path().join("trace.dlt.gz");
+        std::fs::write(&plain_path, &content).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(&content).unwrap();
+        std::fs::write(&gz_path, encoder.finish().unwrap()).unwrap();
+
+        let index = |in_path: &PathBuf| -> String {
+            let out_path = PathBuf::from(format!("{}.out", in_path.display()));
+            let source_file_size = std::fs::metadata(in_path).unwrap().len() as usize;
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            create_index_and_mapping_dlt(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 500,
+                    in_file: in_path.clone(),
+                    out_path: &out_path,
+                    append: false,
+                },
+                source_file_size,
+                None,
+                &tx,
+                None,
+                None,
+                true,
+            )
+            .expect("indexing failed");
+            for result in rx.try_iter() {
+                if let Ok(indexer_base::progress::IndexingProgress::Progress { ticks }) = result {
+                    assert!(ticks.0 <= source_file_size && ticks.1 == source_file_size);
+                }
+            }
+            std::fs::read_to_string(out_path).unwrap()
+        };
+        let plain_index = index(&plain_path);
+        assert!(!plain_index.is_empty());
+        assert_eq!(plain_index, index(&gz_path));
+    }
 }
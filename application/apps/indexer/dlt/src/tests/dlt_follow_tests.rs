@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_follow::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::{Chunk, ChunkResults};
+    use indexer_base::config::IndexingConfig;
+    use indexer_base::progress::{IndexingProgress, Severity};
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::io::Write;
+    use std::time::Duration;
+    use tempdir::TempDir;
+
+    fn msg(counter: u8) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some("ECU1".to_string()),
+                session_id: None,
+                timestamp: Some(42),
+                payload: Payload2 {
+                    payload_content: PayloadContent::NonVerbose(7, vec![1, 2, 3]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds: 100 + counter as u32,
+            microseconds: 0,
+        }))
+        .as_bytes()
+    }
+
+    /// waits for the next chunk, fails on errors, returns the warnings
+    fn next_chunk(rx: &cc::Receiver<ChunkResults>) -> (Chunk, Vec<String>) {
+        let mut warnings = vec![];
+        loop {
+            match rx.recv_timeout(Duration::from_secs(10)) {
+                Ok(Ok(IndexingProgress::GotItem { item })) => return (item, warnings),
+                Ok(Err(n)) if n.severity == Severity::WARNING => warnings.push(n.content),
+                Ok(Err(n)) => panic!("unexpected error: {}", n.content),
+                Ok(Ok(progress)) => panic!("expected a chunk but got {:?}", progress),
+                Err(e) => panic!("no chunk: {}", e),
+            }
+        }
+    }
+
+    fn append(path: &std::path::Path, content: &[u8]) {
+        let mut f = fs::OpenOptions::new().append(true).open(path).unwrap();
+        f.write_all(content).unwrap();
+    }
+
+    #[test]
+    fn test_follow_dlt_file() {
+        let tmp_dir = TempDir::new("follow").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("trace.dlt");
+        let out_path = tmp_dir.path().join("trace.out");
+        let second = msg(2);
+        let mut content = msg(1);
+        content.extend_from_slice(&second[..10]);
+        fs::write(&in_path, &content).unwrap();
+
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let (shutdown_tx, shutdown_rx) = cc::bounded(1);
+        let (in_file, out_file) = (in_path.clone(), out_path.clone());
+        let handle = std::thread::spawn(move || {
+            follow_dlt_file(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 100,
+                    in_file,
+                    out_path: &out_file,
+                    append: false,
+                },
+                None,
+                None,
+                Duration::from_millis(10),
+                tx,
+                Some(shutdown_rx),
+            )
+        });
+        // the partly written message is not reported as error
+        let (chunk, warnings) = next_chunk(&rx);
+        assert_eq!((0, 0), chunk.r);
+        assert!(warnings.is_empty());
+
+        let mut rest = second[10..].to_vec();
+        rest.extend_from_slice(&msg(3));
+        append(&in_path, &rest);
+        let (chunk, warnings) = next_chunk(&rx);
+        assert_eq!((1, 2), chunk.r);
+        assert!(warnings.is_empty());
+
+        // rotation: the old file is moved away and a new one is written
+        fs::rename(&in_path, tmp_dir.path().join("trace.dlt.1")).unwrap();
+        fs::write(&in_path, msg(4)).unwrap();
+        let (chunk, warnings) = next_chunk(&rx);
+        assert_eq!((3, 3), chunk.r);
+        assert_eq!(1, warnings.len());
+
+        shutdown_tx.send(()).unwrap();
+        handle.join().unwrap().expect("follow failed");
+        let out = fs::read_to_string(&out_path).unwrap();
+        assert_eq!(4, out.lines().count());
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_generator::*;
+    use crate::dlt_parse::{dlt_message, ParsedMessage};
+    use crate::fibex::{read_fibexes, FibexMetadata};
+    use pretty_assertions::assert_eq;
+    use std::rc::Rc;
+    use tempdir::TempDir;
+
+    fn config(non_verbose_ratio: f64, corruption_ratio: f64) -> DltGeneratorConfig {
+        DltGeneratorConfig {
+            target_size: 20 * 1024,
+            non_verbose_ratio,
+            corruption_ratio,
+            seed: 42,
+            ..Default::default()
+        }
+    }
+
+    fn parse_all(content: &[u8], fibex: Option<Rc<FibexMetadata>>) -> Vec<Message> {
+        let mut input = content;
+        let mut messages = vec![];
+        while let Ok((rest, parsed)) = dlt_message(input, None, 0, None, fibex.clone(), true) {
+            if let ParsedMessage::Item(msg) = parsed {
+                messages.push(msg);
+            }
+            input = rest;
+        }
+        messages
+    }
+
+    #[test]
+    fn test_generate_verbose() {
+        let mut generator = DltGenerator::new(config(0.0, 0.0)).unwrap();
+        let mut content: Vec<u8> = vec![];
+        let stats = generator.generate(&mut content).unwrap();
+        assert!(stats.bytes >= 20 * 1024);
+        assert_eq!(stats.bytes, content.len() as u64);
+        assert_eq!(0, stats.non_verbose);
+        let messages = parse_all(&content, None);
+        assert_eq!(stats.messages, messages.len());
+        for msg in &messages {
+            let ecu = msg.header.ecu_id.as_ref().expect("no ecu id");
+            assert!(ecu == "ECU1" || ecu == "ECU2");
+            assert!(
+                msg.extended_header
+                    .as_ref()
+                    .expect("no extended header")
+                    .verbose
+            );
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_content() {
+        let generate = |seed| {
+            let mut content: Vec<u8> = vec![];
+            let mut cfg = config(0.3, 0.1);
+            cfg.seed = seed;
+            DltGenerator::new(cfg)
+                .unwrap()
+                .generate(&mut content)
+                .unwrap();
+            content
+        };
+        assert_eq!(generate(1), generate(1));
+        assert_ne!(generate(1), generate(2));
+    }
+
+    #[test]
+    fn test_non_verbose_with_fibex() {
+        let tmp_dir = TempDir::new("generator").expect("could not create temp dir");
+        let dlt_path = tmp_dir.path().join("generated.dlt");
+        let fibex_path = tmp_dir.path().join("generated.xml");
+        let stats = generate_dlt_file(config(1.0, 0.0), &dlt_path, Some(&fibex_path)).unwrap();
+        assert_eq!(stats.messages, stats.non_verbose);
+
+        let fibex = read_fibexes(vec![fibex_path]).expect("could not read generated fibex");
+        let content = std::fs::read(&dlt_path).unwrap();
+        let messages = parse_all(&content, Some(Rc::new(fibex)));
+        assert_eq!(stats.messages, messages.len());
+        for msg in &messages {
+            let formatted = msg.to_string();
+            assert!(
+                formatted.contains("speed:")
+                    || formatted.contains("temperature:")
+                    || formatted.contains("state changed to"),
+                "not resolved: {}",
+                formatted
+            );
+        }
+    }
+
+    #[test]
+    fn test_level_weights() {
+        let mut cfg = config(0.5, 0.0);
+        cfg.level_weights = [0, 1, 0, 0, 0, 0];
+        let mut content: Vec<u8> = vec![];
+        DltGenerator::new(cfg)
+            .unwrap()
+            .generate(&mut content)
+            .unwrap();
+        for msg in parse_all(&content, None) {
+            assert_eq!(
+                MessageType::Log(LogLevel::Error),
+                msg.extended_header.unwrap().message_type
+            );
+        }
+        let mut no_levels = config(0.0, 0.0);
+        no_levels.level_weights = [0; 6];
+        assert!(DltGenerator::new(no_levels).is_err());
+    }
+
+    #[test]
+    fn test_corruption() {
+        let mut content: Vec<u8> = vec![];
+        let stats = DltGenerator::new(config(0.0, 0.2))
+            .unwrap()
+            .generate(&mut content)
+            .unwrap();
+        assert!(stats.corrupted > 0);
+        assert!(stats.corrupted < stats.messages);
+        let mut clean: Vec<u8> = vec![];
+        let clean_stats = DltGenerator::new(config(0.0, 0.0))
+            .unwrap()
+            .generate(&mut clean)
+            .unwrap();
+        assert_eq!(0, clean_stats.corrupted);
+        assert_ne!(clean, content);
+    }
+}
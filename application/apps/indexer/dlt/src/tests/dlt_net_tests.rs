@@ -8,6 +8,7 @@ mod tests {
     use crossbeam_channel as cc;
     use futures::stream::StreamExt;
     use indexer_base::chunks::ChunkResults;
+    use indexer_base::config::{MulticastInfo, UdpEndpoint};
 
     fn message_without_storage_header() -> Vec<u8> {
         #[rustfmt::skip]
@@ -80,4 +81,96 @@ mod tests {
             x => panic!("should have been 2 messages but was: {:?}", x),
         }
     }
+
+    #[test]
+    fn test_endpoint_label() {
+        let unicast = UdpEndpoint {
+            multicast_addr: None,
+            bind_addr: "127.0.0.1".to_string(),
+            port: "3490".to_string(),
+        };
+        assert_eq!("127.0.0.1:3490", endpoint_label(&unicast));
+        let multicast = UdpEndpoint {
+            multicast_addr: Some(MulticastInfo {
+                multiaddr: "ff02::1".to_string(),
+                interface: None,
+            }),
+            bind_addr: "::".to_string(),
+            port: "3491".to_string(),
+        };
+        assert_eq!("ff02::1:3491", endpoint_label(&multicast));
+    }
+
+    #[test]
+    fn test_source_is_last_column() {
+        let (_, msg) = crate::dlt_parse::dlt_message(
+            &message_without_storage_header(),
+            None,
+            0,
+            None,
+            None,
+            false,
+        )
+        .expect("could not parse message");
+        let msg = match msg {
+            crate::dlt_parse::ParsedMessage::Item(m) => m.add_storage_header(None),
+            _ => panic!("no message"),
+        };
+        let line = MessageWithSource {
+            msg: &msg,
+            source: "127.0.0.1:3490",
+        }
+        .to_string();
+        let columns: Vec<&str> = line.split(crate::dlt::DLT_COLUMN_SENTINAL).collect();
+        assert_eq!(
+            msg.to_string()
+                .split(crate::dlt::DLT_COLUMN_SENTINAL)
+                .count()
+                + 1,
+            columns.len()
+        );
+        assert_eq!(Some(&"127.0.0.1:3490"), columns.last());
+    }
+
+    #[async_std::test]
+    async fn test_receive_from_multiple_endpoints() {
+        let endpoint = |ip: &str| UdpEndpoint {
+            multicast_addr: None,
+            bind_addr: ip.to_string(),
+            port: "0".to_string(),
+        };
+        let socket_a = bind_udp_endpoint(&endpoint("127.0.0.1"))
+            .await
+            .expect("could not bind first endpoint");
+        let socket_b = bind_udp_endpoint(&endpoint("127.0.0.1"))
+            .await
+            .expect("could not bind second endpoint");
+        let addr_b = socket_b.local_addr().expect("could not get addr of socket");
+        assert!(bind_udp_endpoint(&endpoint("not an ip")).await.is_err());
+
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let mut merged = futures::stream::select_all(
+            vec![
+                UdpMessageProducer::new(socket_a, tx.clone(), None, None),
+                UdpMessageProducer::new(socket_b, tx, None, None),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(source, producer)| producer.map(move |res| (source, res))),
+        );
+        let sender = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("could not create sender");
+        sender
+            .send_to(&message_without_storage_header(), addr_b)
+            .await
+            .expect("could not send");
+        match merged.next().await {
+            Some((source, Ok(Some(msgs)))) => {
+                assert_eq!(1, source);
+                assert_eq!(1, msgs.len());
+            }
+            x => panic!("should have received a message on endpoint 1: {:?}", x),
+        }
+    }
 }
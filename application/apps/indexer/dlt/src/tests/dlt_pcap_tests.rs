@@ -0,0 +1,360 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_parse::{dlt_message, ParsedMessage};
+    use crate::dlt_pcap::*;
+    use crossbeam_channel as cc;
+    use etherparse::{InternetSlice, SlicedPacket, TransportSlice};
+    use indexer_base::chunks::ChunkResults;
+    use indexer_base::config::{IndexSection, IndexingConfig, SectionConfig};
+    use pcap_parser::traits::PcapReaderIterator;
+    use pcap_parser::*;
+    use pretty_assertions::assert_eq;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn msg(ecu: &str, counter: u8, seconds: u32, microseconds: u32) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some(ecu.to_string()),
+                session_id: None,
+                timestamp: Some(42),
+                // odd payload length to check the padding
+                payload: Payload2 {
+                    payload_content: PayloadContent::NonVerbose(7, vec![1, 2, 3]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds,
+            microseconds,
+        }))
+        .as_bytes()
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Packet {
+        timestamp_us: u64,
+        source: SocketAddrV4,
+        destination: SocketAddrV4,
+        counter: u8,
+        ecu_id: Option<String>,
+    }
+
+    fn export(
+        messages: &[Vec<u8>],
+        sections: Vec<IndexSection>,
+        config: &PcapExportConfig,
+        dir: &Path,
+    ) -> Vec<Packet> {
+        let in_path = dir.join("trace.dlt");
+        let out_path = dir.join("trace.pcapng");
+        std::fs::write(&in_path, messages.concat()).unwrap();
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let written = export_as_pcapng(
+            &in_path,
+            &out_path,
+            SectionConfig { sections },
+            config,
+            tx,
+            None,
+        )
+        .expect("export failed");
+
+        let mut reader = PcapNGReader::new(65536, std::fs::File::open(&out_path).unwrap())
+            .expect("not a pcapng file");
+        let mut packets = vec![];
+        loop {
+            match reader.next() {
+                Ok((offset, block)) => {
+                    if let PcapBlockOwned::NG(Block::EnhancedPacket(epb)) = block {
+                        // the data of the block is padded to 32 bits
+                        let sliced = SlicedPacket::from_ethernet(&epb.data[..epb.caplen as usize])
+                            .expect("no ethernet");
+                        let (source_ip, destination_ip) = match sliced.ip {
+                            Some(InternetSlice::Ipv4(ip)) => {
+                                (ip.source_addr(), ip.destination_addr())
+                            }
+                            _ => panic!("no ipv4 packet"),
+                        };
+                        let (source_port, destination_port) = match sliced.transport {
+                            Some(TransportSlice::Udp(udp)) => {
+                                (udp.source_port(), udp.destination_port())
+                            }
+                            _ => panic!("no udp packet"),
+                        };
+                        let (rest, parsed) =
+                            dlt_message(sliced.payload, None, 0, None, None, false).unwrap();
+                        assert!(rest.is_empty());
+                        let msg = match parsed {
+                            ParsedMessage::Item(msg) => msg,
+                            _ => panic!("no dlt message"),
+                        };
+                        packets.push(Packet {
+                            timestamp_us: (u64::from(epb.ts_high) << 32) | u64::from(epb.ts_low),
+                            source: SocketAddrV4::new(source_ip, source_port),
+                            destination: SocketAddrV4::new(destination_ip, destination_port),
+                            counter: msg.header.message_counter,
+                            ecu_id: msg.header.ecu_id,
+                        });
+                    }
+                    reader.consume(offset);
+                }
+                Err(PcapError::Eof) => break,
+                Err(PcapError::Incomplete) => reader.refill().unwrap(),
+                Err(e) => panic!("error reading pcapng: {:?}", e),
+            }
+        }
+        assert_eq!(written, packets.len());
+        packets
+    }
+
+    #[test]
+    fn test_export_as_pcapng() {
+        let tmp_dir = TempDir::new("pcapng").expect("could not create temp dir");
+        let messages = vec![
+            msg("ECU1", 0, 100, 5),
+            msg("ECU2", 1, 101, 999_999),
+            msg("ECU3", 2, 102, 0),
+        ];
+        let mut config = PcapExportConfig::default();
+        let (ecu, address) = parse_ecu_endpoint("ECU2=10.0.0.2:4000").unwrap();
+        config.ecu_sources.insert(ecu, address);
+        let packets = export(&messages, vec![], &config, tmp_dir.path());
+        let ecu2 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 4000);
+        assert_eq!(
+            vec![
+                (100_000_005, config.default_source, 0),
+                (101_999_999, ecu2, 1),
+                (102_000_000, config.default_source, 2),
+            ],
+            packets
+                .iter()
+                .map(|p| (p.timestamp_us, p.source, p.counter))
+                .collect::<Vec<(u64, SocketAddrV4, u8)>>()
+        );
+        for p in &packets {
+            assert_eq!(config.destination, p.destination);
+        }
+        assert_eq!(Some("ECU2".to_string()), packets[1].ecu_id);
+    }
+
+    #[test]
+    fn test_export_sections_as_pcapng() {
+        let tmp_dir = TempDir::new("pcapng").expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> = (0..5).map(|i| msg("ECU1", i, 100, 0)).collect();
+        let packets = export(
+            &messages,
+            vec![
+                IndexSection {
+                    first_line: 0,
+                    last_line: 0,
+                },
+                IndexSection {
+                    first_line: 3,
+                    last_line: 4,
+                },
+            ],
+            &PcapExportConfig::default(),
+            tmp_dir.path(),
+        );
+        assert_eq!(
+            vec![0, 3, 4],
+            packets.iter().map(|p| p.counter).collect::<Vec<u8>>()
+        );
+    }
+
+    #[test]
+    fn test_pcapng_round_trip() {
+        let tmp_dir = TempDir::new("pcapng").expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> = (0..3).map(|i| msg("ECU1", i, 100 + i as u32, 0)).collect();
+        export(
+            &messages,
+            vec![],
+            &PcapExportConfig::default(),
+            tmp_dir.path(),
+        );
+        let out_path = tmp_dir.path().join("trace.out");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let (_shutdown_tx, shutdown_rx) = async_std::sync::channel(1);
+        create_index_and_mapping_dlt_from_pcap(
+            IndexingConfig {
+                tag: "TAG",
+                chunk_size: 5,
+                in_file: tmp_dir.path().join("trace.pcapng"),
+                out_path: &out_path,
+                append: false,
+            },
+            None,
+            &tx,
+            shutdown_rx,
+            None,
+        )
+        .expect("could not read exported pcapng");
+        let content = std::fs::read_to_string(&out_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[2].starts_with("1970-01-01T00:01:42"));
+    }
+
+    #[test]
+    fn test_pcapng_blocks_without_packets_are_skipped() {
+        let tmp_dir = TempDir::new("pcapng").expect("could not create temp dir");
+        let source = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), DLT_UDP_PORT);
+        let destination = SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), DLT_UDP_PORT);
+        let pcapng = |counter: u8| {
+            let mut writer = PcapNgWriter::new(vec![]).unwrap();
+            writer
+                .write_udp_packet(
+                    1_000_000,
+                    &source,
+                    &destination,
+                    &msg("ECU1", counter, 1, 0)[STORAGE_HEADER_LENGTH..],
+                )
+                .unwrap();
+            writer.finish().unwrap()
+        };
+        // a second interface description block (after the 28 bytes of the
+        // section header) between the packets
+        let content = [pcapng(0), pcapng(1)[28..].to_vec()].concat();
+        let in_path = tmp_dir.path().join("trace.pcapng");
+        let out_path = tmp_dir.path().join("trace.out");
+        std::fs::write(&in_path, content).unwrap();
+        let (done_tx, done_rx) = cc::bounded(1);
+        let indexed_out_path = out_path.clone();
+        std::thread::spawn(move || {
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            let (_shutdown_tx, shutdown_rx) = async_std::sync::channel(1);
+            let res = create_index_and_mapping_dlt_from_pcap(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 5,
+                    in_file: in_path,
+                    out_path: &indexed_out_path,
+                    append: false,
+                },
+                None,
+                &tx,
+                shutdown_rx,
+                None,
+            );
+            let _ = done_tx.send(res.is_ok());
+        });
+        // a block without packet data must not leave the message stream pending
+        assert_eq!(
+            Ok(true),
+            done_rx.recv_timeout(std::time::Duration::from_secs(10))
+        );
+        let content = std::fs::read_to_string(&out_path).unwrap();
+        assert_eq!(2, content.lines().count());
+    }
+
+    #[test]
+    fn test_pcapng_keeps_microseconds() {
+        let tmp_dir = TempDir::new("pcapng").expect("could not create temp dir");
+        let messages = vec![msg("ECU1", 0, 100, 123_456), msg("ECU1", 1, 100, 123_457)];
+        export(
+            &messages,
+            vec![],
+            &PcapExportConfig::default(),
+            tmp_dir.path(),
+        );
+        let out_path = tmp_dir.path().join("trace.out");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let (_shutdown_tx, shutdown_rx) = async_std::sync::channel(1);
+        create_index_and_mapping_dlt_from_pcap(
+            IndexingConfig {
+                tag: "TAG",
+                chunk_size: 5,
+                in_file: tmp_dir.path().join("trace.pcapng"),
+                out_path: &out_path,
+                append: false,
+            },
+            None,
+            &tx,
+            shutdown_rx,
+            None,
+        )
+        .expect("could not read exported pcapng");
+        let content = std::fs::read_to_string(&out_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert!(lines[0].starts_with("1970-01-01T00:01:40.123456000Z"));
+        assert!(lines[1].starts_with("1970-01-01T00:01:40.123457000Z"));
+    }
+
+    #[test]
+    fn test_packet_time_resolution() {
+        let packet = |ts: u64| EnhancedPacketBlock {
+            block_type: 6,
+            block_len1: 32,
+            if_id: 0,
+            ts_high: (ts >> 32) as u32,
+            ts_low: ts as u32,
+            caplen: 0,
+            origlen: 0,
+            data: &[],
+            options: vec![],
+            block_len2: 32,
+        };
+        // microseconds if the interface is not known
+        assert_eq!(100_123_456, packet_time_us(&packet(100_123_456), &[]));
+        assert_eq!(100_123_456, packet_time_us(&packet(100_123_456), &[(6, 0)]));
+        // nanoseconds
+        assert_eq!(
+            100_123_456,
+            packet_time_us(&packet(100_123_456_789), &[(9, 0)])
+        );
+        // milliseconds with an offset of 10 seconds
+        assert_eq!(110_123_000, packet_time_us(&packet(100_123), &[(3, 10)]));
+    }
+
+    #[test]
+    fn test_parse_ecu_endpoint() {
+        assert_eq!(
+            (
+                "ECU1".to_string(),
+                SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 10), 3490)
+            ),
+            parse_ecu_endpoint("ECU1=192.168.1.10:3490").unwrap()
+        );
+        assert!(parse_ecu_endpoint("ECU1").is_err());
+        assert!(parse_ecu_endpoint("=192.168.1.10:3490").is_err());
+        assert!(parse_ecu_endpoint("ECU1=192.168.1.10").is_err());
+    }
+
+    #[test]
+    fn test_pcap_export_options() {
+        let options: PcapExportOptions = serde_json::from_str(
+            r#"{"endpoints": ["ECU1=10.0.0.1:3491"], "destination": "10.0.0.2:3492"}"#,
+        )
+        .unwrap();
+        let config = options.to_config().unwrap();
+        assert_eq!(
+            Some(&SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 3491)),
+            config.ecu_sources.get("ECU1")
+        );
+        assert_eq!(
+            SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 2), 3492),
+            config.destination
+        );
+        let defaults: PcapExportOptions = serde_json::from_str("{}").unwrap();
+        assert_eq!(
+            PcapExportConfig::default().destination,
+            defaults.to_config().unwrap().destination
+        );
+        let invalid: PcapExportOptions =
+            serde_json::from_str(r#"{"destination": "10.0.0.2"}"#).unwrap();
+        assert!(invalid.to_config().is_err());
+    }
+}
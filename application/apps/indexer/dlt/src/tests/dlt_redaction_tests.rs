@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_parse::{dlt_message, ParsedMessage};
+    use crate::dlt_redaction::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use indexer_base::config::SectionConfig;
+    use indexer_base::redaction::*;
+    use pretty_assertions::assert_eq;
+    use tempdir::TempDir;
+
+    fn arg(value: Value, kind: TypeInfoKind) -> Argument {
+        Argument {
+            type_info: TypeInfo {
+                kind,
+                coding: StringCoding::UTF8,
+                has_variable_info: false,
+                has_trace_info: false,
+            },
+            name: None,
+            unit: None,
+            fixed_point: None,
+            value,
+        }
+    }
+
+    fn message(app: &str, payload_content: PayloadContent) -> Message {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Little,
+                ecu_id: Some("ECU1".to_string()),
+                session_id: None,
+                timestamp: Some(5),
+                payload: Payload2 { payload_content },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: app.to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds: 100,
+            microseconds: 0,
+        }))
+    }
+
+    fn text_message(app: &str, text: &str) -> Message {
+        message(
+            app,
+            PayloadContent::Verbose(vec![arg(
+                Value::StringVal(text.to_string()),
+                TypeInfoKind::StringType,
+            )]),
+        )
+    }
+
+    fn parse_all(content: &[u8]) -> Vec<Message> {
+        let mut input = content;
+        let mut messages = vec![];
+        while !input.is_empty() {
+            let (rest, parsed) = dlt_message(input, None, 0, None, None, true).unwrap();
+            if let ParsedMessage::Item(msg) = parsed {
+                messages.push(msg);
+            }
+            input = rest;
+        }
+        messages
+    }
+
+    #[test]
+    fn test_redact_message() {
+        let mut redactor = Redactor::new(RedactionConfig::default()).unwrap();
+        let mut msg = text_message("APP", "connecting to 10.20.30.40 as user=admin");
+        assert_eq!(
+            MessageRedaction::Redacted,
+            redact_message(&mut msg, &mut redactor)
+        );
+        let bytes = msg.as_bytes();
+        let parsed = parse_all(&bytes);
+        assert_eq!(1, parsed.len());
+        assert_eq!(
+            PayloadContent::Verbose(vec![arg(
+                Value::StringVal("connecting to *** as user=***".to_string()),
+                TypeInfoKind::StringType
+            )]),
+            parsed[0].payload.payload_content
+        );
+        let mut unchanged = text_message("APP", "nothing to hide");
+        assert_eq!(
+            MessageRedaction::Unchanged,
+            redact_message(&mut unchanged, &mut redactor)
+        );
+        let mut non_verbose = message("APP", PayloadContent::NonVerbose(1, vec![1, 2, 3, 4]));
+        assert_eq!(
+            MessageRedaction::Dropped,
+            redact_message(&mut non_verbose, &mut redactor)
+        );
+        let summary = redactor.summary();
+        assert_eq!(
+            (3, 1, 1),
+            (summary.inspected, summary.redacted, summary.dropped)
+        );
+    }
+
+    #[test]
+    fn test_scoped_argument_masking() {
+        let config = RedactionConfig {
+            rules: vec![RedactionRule {
+                name: "position".to_string(),
+                pattern: None,
+                action: RedactionAction::Mask,
+                app_id: Some("NAV".to_string()),
+                context_id: None,
+                argument: Some(1),
+            }],
+            key: None,
+            mask: "<hidden>".to_string(),
+        };
+        let mut redactor = Redactor::new(config).unwrap();
+        let payload = || {
+            PayloadContent::Verbose(vec![
+                arg(
+                    Value::StringVal("lat".to_string()),
+                    TypeInfoKind::StringType,
+                ),
+                arg(
+                    Value::F64(48.137_154),
+                    TypeInfoKind::Float(FloatWidth::Width64),
+                ),
+            ])
+        };
+        let mut other_app = message("HMI", payload());
+        assert_eq!(
+            MessageRedaction::Unchanged,
+            redact_message(&mut other_app, &mut redactor)
+        );
+        let mut nav = message("NAV", payload());
+        assert_eq!(
+            MessageRedaction::Redacted,
+            redact_message(&mut nav, &mut redactor)
+        );
+        let parsed = parse_all(&nav.as_bytes());
+        assert_eq!(
+            PayloadContent::Verbose(vec![
+                arg(
+                    Value::StringVal("lat".to_string()),
+                    TypeInfoKind::StringType
+                ),
+                arg(
+                    Value::StringVal("<hidden>".to_string()),
+                    TypeInfoKind::StringType
+                ),
+            ]),
+            parsed[0].payload.payload_content
+        );
+    }
+
+    #[test]
+    fn test_export_as_dlt_file_redacted() {
+        let tmp_dir = TempDir::new("redaction").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("trace.dlt");
+        let out_path = tmp_dir.path().join("redacted.dlt");
+        let messages = [
+            text_message("APP", "VIN WVWZZZ1JZXW000001"),
+            text_message("APP", "plain"),
+            message("APP", PayloadContent::NonVerbose(1, vec![1, 2, 3, 4])),
+            text_message("APP", "last"),
+        ];
+        let bytes: Vec<Vec<u8>> = messages.iter().map(|m| m.as_bytes()).collect();
+        std::fs::write(&in_path, bytes.concat()).unwrap();
+        let mut redactor = Redactor::new(RedactionConfig::default()).unwrap();
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        export_as_dlt_file_redacted(
+            &in_path,
+            &out_path,
+            SectionConfig { sections: vec![] },
+            &mut redactor,
+            tx,
+        )
+        .expect("export failed");
+        let content = std::fs::read(&out_path).unwrap();
+        let exported = parse_all(&content);
+        // the non-verbose message is left out
+        assert_eq!(3, exported.len());
+        assert_eq!(
+            text_message("APP", "VIN ***").as_bytes(),
+            exported[0].as_bytes()
+        );
+        // unchanged messages are copied
+        assert!(content.ends_with(&[bytes[1].clone(), bytes[3].clone()].concat()));
+        assert_eq!(1, redactor.summary().dropped);
+        assert!(rx.try_iter().any(|r| r.is_err()));
+    }
+}
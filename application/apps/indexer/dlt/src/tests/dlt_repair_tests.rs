@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_generator::{DltGenerator, DltGeneratorConfig};
+    use crate::dlt_parse::{dlt_message, ParsedMessage};
+    use crate::dlt_repair::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+    use tempdir::TempDir;
+
+    fn msg(counter: u8) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some("ECU".to_string()),
+                session_id: None,
+                timestamp: Some(u32::from(counter)),
+                payload: Payload2 {
+                    payload_content: PayloadContent::NonVerbose(1, vec![counter; 8]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds: 1000,
+            microseconds: 0,
+        }))
+        .as_bytes()
+    }
+
+    fn repair(content: &[u8]) -> (RepairReport, Vec<u8>) {
+        let tmp_dir = TempDir::new("repair").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("damaged.dlt");
+        let out_path = tmp_dir.path().join("repaired.dlt");
+        std::fs::write(&in_path, content).unwrap();
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let report = repair_dlt_file(&in_path, &out_path, tx, None).expect("repair failed");
+        (report, std::fs::read(&out_path).unwrap())
+    }
+
+    #[test]
+    fn test_inspect_message() {
+        let m = msg(1);
+        assert_eq!(Inspection::Valid(m.len()), inspect_message(&m, true));
+        let mut garbage = vec![1, 2, 3, b'D', b'L'];
+        match inspect_message(&garbage, false) {
+            // keep what could be the start of the next storage header
+            Inspection::Damaged(2, DamageKind::Garbage, _) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+        garbage.extend(&m);
+        match inspect_message(&garbage, true) {
+            Inspection::Damaged(5, DamageKind::Garbage, _) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_repair_damaged_file() {
+        let (m0, m1, m2, m3, m4, m5) = (msg(0), msg(1), msg(2), msg(3), msg(4), msg(5));
+        let mut content = vec![];
+        content.extend(&m0);
+        let garbage_offset = content.len();
+        content.extend(&[0xde, 0xad, 0xbe, 0xef, 0x00]);
+        let truncated_offset = content.len();
+        content.extend(&m1[..10]);
+        content.extend(&m2);
+        let bogus_offset = content.len();
+        let mut bogus = m3.clone();
+        bogus[STORAGE_HEADER_LENGTH] = 0xff; // version 7
+        content.extend(&bogus);
+        let invalid_length_offset = content.len();
+        let mut invalid_length = m3.clone();
+        invalid_length[STORAGE_HEADER_LENGTH + 2] = 0;
+        invalid_length[STORAGE_HEADER_LENGTH + 3] = 2;
+        content.extend(&invalid_length);
+        content.extend(&m4);
+        let tail_offset = content.len();
+        content.extend(&m5[..m5.len() - 3]);
+
+        let (report, repaired) = repair(&content);
+        let expected: Vec<u8> = vec![m0, m2, m4].into_iter().flatten().collect();
+        assert_eq!(expected, repaired);
+        assert_eq!(3, report.valid_messages);
+        assert_eq!(content.len() as u64, report.source_size);
+        assert_eq!(
+            report.source_size,
+            report.written_bytes + report.damaged_bytes
+        );
+        let damaged: Vec<(usize, usize, DamageKind)> = report
+            .damaged
+            .iter()
+            .map(|d| (d.offset as usize, d.length as usize, d.kind))
+            .collect();
+        assert_eq!(
+            vec![
+                (garbage_offset, 5, DamageKind::Garbage),
+                (truncated_offset, 10, DamageKind::TruncatedMessage),
+                (bogus_offset, bogus.len(), DamageKind::BogusStorageHeader),
+                (
+                    invalid_length_offset,
+                    invalid_length.len(),
+                    DamageKind::InvalidPayloadLength
+                ),
+                (tail_offset, m5.len() - 3, DamageKind::TruncatedTail),
+            ],
+            damaged
+        );
+    }
+
+    #[test]
+    fn test_repaired_file_parses_cleanly() {
+        let mut content: Vec<u8> = vec![];
+        let stats = DltGenerator::new(DltGeneratorConfig {
+            target_size: 50 * 1024,
+            non_verbose_ratio: 0.3,
+            corruption_ratio: 0.1,
+            seed: 3,
+            ..Default::default()
+        })
+        .unwrap()
+        .generate(&mut content)
+        .unwrap();
+        let (report, repaired) = repair(&content);
+        assert!(!report.damaged.is_empty());
+        assert!(report.valid_messages >= stats.messages - stats.corrupted);
+        let mut input = &repaired[..];
+        let mut parsed = 0;
+        while !input.is_empty() {
+            match dlt_message(input, None, parsed, None, None, true) {
+                Ok((rest, ParsedMessage::Item(_))) => input = rest,
+                other => panic!("repaired file contains damaged message: {:?}", other),
+            }
+            parsed += 1;
+        }
+        assert_eq!(report.valid_messages, parsed);
+    }
+
+    #[test]
+    fn test_write_report() {
+        let tmp_dir = TempDir::new("repair").expect("could not create temp dir");
+        let report_path: PathBuf = tmp_dir.path().join("report.json");
+        let (report, _) = repair(&msg(0)[..20]);
+        write_repair_report(&report, &report_path).unwrap();
+        let read: RepairReport =
+            serde_json::from_str(&std::fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert_eq!(report, read);
+        assert_eq!(DamageKind::TruncatedTail, read.damaged[0].kind);
+    }
+}
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_replay::*;
+    use async_std::net::UdpSocket;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use pretty_assertions::assert_eq;
+    use std::io::{Read, Write};
+    use std::time::Duration;
+    use tempdir::TempDir;
+
+    fn ts(seconds: u32, microseconds: u32) -> DltTimeStamp {
+        DltTimeStamp {
+            seconds,
+            microseconds,
+        }
+    }
+
+    fn msg(counter: u8, storage_time: DltTimeStamp) -> Message {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some("ECU".to_string()),
+                session_id: None,
+                timestamp: Some(u32::from(counter)),
+                payload: Payload2 {
+                    payload_content: PayloadContent::Verbose(vec![]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(storage_time))
+    }
+
+    #[test]
+    fn test_replay_clock() {
+        let mut clock = ReplayClock::new(ReplayTiming::Original).unwrap();
+        assert_eq!(Duration::from_secs(0), clock.send_offset(Some(&ts(10, 0))));
+        assert_eq!(
+            Duration::from_millis(1500),
+            clock.send_offset(Some(&ts(11, 500_000)))
+        );
+        // no timestamp or going back in time: send right away
+        assert_eq!(Duration::from_millis(1500), clock.send_offset(None));
+        assert_eq!(
+            Duration::from_millis(1500),
+            clock.send_offset(Some(&ts(10, 100)))
+        );
+
+        let mut fast = ReplayClock::new(ReplayTiming::Speed(2.0)).unwrap();
+        fast.send_offset(Some(&ts(10, 0)));
+        assert_eq!(
+            Duration::from_millis(750),
+            fast.send_offset(Some(&ts(11, 500_000)))
+        );
+
+        let mut asap = ReplayClock::new(ReplayTiming::AsFastAsPossible).unwrap();
+        asap.send_offset(Some(&ts(10, 0)));
+        assert_eq!(Duration::from_secs(0), asap.send_offset(Some(&ts(100, 0))));
+    }
+
+    #[test]
+    fn test_invalid_replay_speed() {
+        for speed in &[0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(ReplayClock::new(ReplayTiming::Speed(*speed)).is_err());
+        }
+    }
+
+    #[async_std::test]
+    async fn test_replay_over_udp() {
+        let tmp_dir = TempDir::new("replay").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("replay.dlt");
+        let messages: Vec<Message> = (0..3u8)
+            .map(|i| msg(i, ts(1000, u32::from(i) * 10_000)))
+            .collect();
+        let mut in_file = std::fs::File::create(&in_path).unwrap();
+        for m in &messages {
+            in_file.write_all(&m.as_bytes()).unwrap();
+        }
+        drop(in_file);
+
+        let receiver = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("could not create receiver");
+        let target = receiver.local_addr().expect("no local addr");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let sent = replay_dlt_file(
+            in_path,
+            ReplayTarget::Udp(target),
+            ReplayTiming::Speed(10.0),
+            tx,
+            None,
+        )
+        .await
+        .expect("replay failed");
+        assert_eq!(3, sent);
+
+        let mut buf = [0u8; 1024];
+        for m in messages {
+            let len = receiver.recv(&mut buf).await.expect("nothing received");
+            let mut expected = m;
+            expected.storage_header = None;
+            assert_eq!(expected.as_bytes(), buf[..len].to_vec());
+        }
+    }
+
+    #[async_std::test]
+    async fn test_replay_over_tcp() {
+        let tmp_dir = TempDir::new("replay").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("replay.dlt");
+        let messages: Vec<Message> = (0..3u8).map(|i| msg(i, ts(1000, 0))).collect();
+        let mut in_file = std::fs::File::create(&in_path).unwrap();
+        for m in &messages {
+            in_file.write_all(&m.as_bytes()).unwrap();
+        }
+        drop(in_file);
+
+        // find a free port
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("no free port");
+        let client = std::thread::spawn(move || {
+            let mut received = vec![];
+            for _ in 0..50 {
+                if let Ok(mut stream) = std::net::TcpStream::connect(addr) {
+                    stream.read_to_end(&mut received).expect("could not read");
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            received
+        });
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let sent = replay_dlt_file(
+            in_path,
+            ReplayTarget::Tcp(addr),
+            ReplayTiming::AsFastAsPossible,
+            tx,
+            None,
+        )
+        .await
+        .expect("replay failed");
+        assert_eq!(3, sent);
+        let expected: Vec<u8> = messages
+            .into_iter()
+            .flat_map(|mut m| {
+                m.storage_header = None;
+                m.as_bytes()
+            })
+            .collect();
+        assert_eq!(expected, client.join().unwrap());
+    }
+}
@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_split::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn msg(ecu: &str, app: &str, uptime: u32, seconds: u32) -> Vec<u8> {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Big,
+                ecu_id: Some(ecu.to_string()),
+                session_id: None,
+                timestamp: Some(uptime),
+                payload: Payload2 {
+                    payload_content: PayloadContent::NonVerbose(1, vec![0; 8]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: app.to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds,
+            microseconds: 0,
+        }))
+        .as_bytes()
+    }
+
+    fn split(
+        messages: &[Vec<u8>],
+        criterion: SplitCriterion,
+        dir: &Path,
+    ) -> (SplitManifest, Vec<Vec<u8>>) {
+        let in_path = dir.join("trace.dlt");
+        std::fs::write(&in_path, messages.concat()).unwrap();
+        let out_dir = dir.join("out");
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let manifest =
+            split_dlt_file(&in_path, &out_dir, criterion, tx, None).expect("split failed");
+        assert_eq!(
+            manifest,
+            read_split_manifest(&split_manifest_path(&in_path, &out_dir)).unwrap()
+        );
+        let contents = manifest
+            .files
+            .iter()
+            .map(|f| std::fs::read(out_dir.join(&f.file_name)).unwrap())
+            .collect();
+        (manifest, contents)
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        let tmp_dir = TempDir::new("split").expect("could not create temp dir");
+        let messages: Vec<Vec<u8>> = (0..5).map(|i| msg("ECU1", "APP", i, 100)).collect();
+        let msg_len = messages[0].len() as u64;
+        let (manifest, contents) =
+            split(&messages, SplitCriterion::Size(2 * msg_len), tmp_dir.path());
+        let names: Vec<&str> = manifest
+            .files
+            .iter()
+            .map(|f| f.file_name.as_str())
+            .collect();
+        assert_eq!(
+            vec!["trace_0000.dlt", "trace_0001.dlt", "trace_0002.dlt"],
+            names
+        );
+        assert_eq!(messages.concat(), contents.concat());
+        let ranges: Vec<(usize, usize, usize)> = manifest
+            .files
+            .iter()
+            .map(|f| (f.first_message, f.last_message, f.messages))
+            .collect();
+        assert_eq!(vec![(0, 1, 2), (2, 3, 2), (4, 4, 1)], ranges);
+    }
+
+    #[test]
+    fn test_split_by_interval() {
+        let tmp_dir = TempDir::new("split").expect("could not create temp dir");
+        let messages = vec![
+            msg("ECU1", "APP", 1, 61),
+            msg("ECU1", "APP", 2, 119),
+            msg("ECU1", "APP", 3, 120),
+            msg("ECU1", "APP", 4, 300),
+        ];
+        let (manifest, contents) = split(&messages, SplitCriterion::Interval(60), tmp_dir.path());
+        assert_eq!(
+            vec![
+                messages[0..2].concat(),
+                messages[2].clone(),
+                messages[3].clone()
+            ],
+            contents
+        );
+        assert_eq!(Some(61_000_000), manifest.files[0].first_time_us);
+        assert_eq!(Some(119_000_000), manifest.files[0].last_time_us);
+    }
+
+    #[test]
+    fn test_split_by_ecu_and_app() {
+        let tmp_dir = TempDir::new("split").expect("could not create temp dir");
+        let messages = vec![
+            msg("ECU1", "APP1", 1, 100),
+            msg("ECU2", "APP1", 1, 100),
+            msg("ECU1", "AP/2", 2, 100),
+        ];
+        let (manifest, contents) = split(&messages, SplitCriterion::EcuId, tmp_dir.path());
+        let keys: Vec<(&str, &str)> = manifest
+            .files
+            .iter()
+            .map(|f| (f.key.as_str(), f.file_name.as_str()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("ECU1", "trace_ecu_ECU1.dlt"),
+                ("ECU2", "trace_ecu_ECU2.dlt")
+            ],
+            keys
+        );
+        assert_eq!(
+            vec![
+                [messages[0].clone(), messages[2].clone()].concat(),
+                messages[1].clone()
+            ],
+            contents
+        );
+
+        let tmp_dir = TempDir::new("split").expect("could not create temp dir");
+        let (manifest, _) = split(&messages, SplitCriterion::AppId, tmp_dir.path());
+        let names: Vec<&str> = manifest
+            .files
+            .iter()
+            .map(|f| f.file_name.as_str())
+            .collect();
+        assert_eq!(vec!["trace_app_APP1.dlt", "trace_app_AP_2.dlt"], names);
+    }
+
+    #[test]
+    fn test_split_into_more_files_than_can_be_open() {
+        let tmp_dir = TempDir::new("split").expect("could not create temp dir");
+        let apps: Vec<String> = (0..MAX_OPEN_SPLIT_FILES + 6)
+            .map(|i| format!("A{:03}", i))
+            .collect();
+        // every file is written to twice, so closed files have to be reopened
+        let messages: Vec<Vec<u8>> = (0..2u32)
+            .flat_map(|round| apps.iter().map(move |app| msg("ECU1", app, round, 100)))
+            .collect();
+        let (manifest, contents) = split(&messages, SplitCriterion::AppId, tmp_dir.path());
+        assert_eq!(apps.len(), manifest.files.len());
+        for (i, content) in contents.iter().enumerate() {
+            assert_eq!(2, manifest.files[i].messages);
+            assert_eq!(
+                &[messages[i].clone(), messages[apps.len() + i].clone()].concat(),
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_by_lifecycle() {
+        let tmp_dir = TempDir::new("split").expect("could not create temp dir");
+        let messages = vec![
+            msg("ECU1", "APP", 50_000, 100),
+            msg("ECU1", "APP", 60_000, 101),
+            // restart
+            msg("ECU1", "APP", 100, 102),
+            msg("ECU1", "APP", 200, 103),
+        ];
+        let (manifest, contents) = split(&messages, SplitCriterion::Lifecycle, tmp_dir.path());
+        let names: Vec<&str> = manifest
+            .files
+            .iter()
+            .map(|f| f.file_name.as_str())
+            .collect();
+        assert_eq!(
+            vec!["trace_lifecycle_0000.dlt", "trace_lifecycle_0001.dlt"],
+            names
+        );
+        assert_eq!(
+            vec![messages[0..2].concat(), messages[2..4].concat()],
+            contents
+        );
+    }
+}
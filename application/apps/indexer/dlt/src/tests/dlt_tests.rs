@@ -33,6 +33,31 @@ mod tests {
     // type struct .............................^||      ||||
     // trace info ...............................^|      ||||
     // fixed point ...............................^      ||||
+    #[test]
+    fn test_timestamp_resolution() {
+        assert_eq!(
+            DltTimeStamp {
+                seconds: 100,
+                microseconds: 123_000
+            },
+            DltTimeStamp::from_ms(100_123)
+        );
+        assert_eq!(
+            DltTimeStamp {
+                seconds: 100,
+                microseconds: 123_456
+            },
+            DltTimeStamp::from_us(100_123_456)
+        );
+        assert_eq!(
+            DltTimeStamp {
+                seconds: 100,
+                microseconds: 123_456
+            },
+            DltTimeStamp::from_ns(100_123_456_789)
+        );
+    }
+
     #[test]
     fn test_convert_header_to_bytes() {
         // 0x37 =  0b0011 0111
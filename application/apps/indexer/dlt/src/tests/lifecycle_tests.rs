@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_file::create_index_and_mapping_dlt;
+    use crate::lifecycle::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::ChunkResults;
+    use indexer_base::config::IndexingConfig;
+    use indexer_base::utils::restore_line;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn ts(seconds: u32, microseconds: u32) -> DltTimeStamp {
+        DltTimeStamp {
+            seconds,
+            microseconds,
+        }
+    }
+
+    fn msg(ecu: &str, uptime: u32, storage_time: DltTimeStamp) -> Message {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter: 0,
+                endianness: Endianness::Big,
+                ecu_id: Some(ecu.to_string()),
+                session_id: None,
+                timestamp: Some(uptime),
+                payload: Payload2 {
+                    payload_content: PayloadContent::Verbose(vec![]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(storage_time))
+    }
+
+    #[test]
+    fn test_detect_restart() {
+        let mut detector = LifecycleDetector::new();
+        let a = "A".to_string();
+        let b = "B".to_string();
+        detector.track(0, a.clone(), Some(100_000), Some(&ts(1010, 0)));
+        detector.track(1, b.clone(), Some(5_000), Some(&ts(1010, 100)));
+        // going back a little is not a restart
+        detector.track(2, a.clone(), Some(95_000), Some(&ts(1010, 200)));
+        detector.track(3, a.clone(), Some(120_000), Some(&ts(1012, 0)));
+        detector.track(4, a.clone(), Some(300), Some(&ts(1100, 30_000)));
+        detector.track(5, b.clone(), Some(6_000), Some(&ts(1100, 40_000)));
+        detector.track(6, a, None, None);
+        let lifecycles = detector.finish();
+        assert_eq!(3, lifecycles.len());
+
+        assert_eq!(0, lifecycles[0].id);
+        assert_eq!("A", lifecycles[0].ecu_id);
+        assert_eq!((0, 3), (lifecycles[0].first_line, lifecycles[0].last_line));
+        assert_eq!(3, lifecycles[0].message_count);
+        assert_eq!(100_000, lifecycles[0].start_uptime);
+        assert_eq!(120_000, lifecycles[0].end_uptime);
+        // 1010 s - 10 s uptime
+        assert_eq!(Some(1_000_000_000), lifecycles[0].start_time_us);
+
+        assert_eq!("B", lifecycles[1].ecu_id);
+        assert_eq!((1, 5), (lifecycles[1].first_line, lifecycles[1].last_line));
+
+        assert_eq!("A", lifecycles[2].ecu_id);
+        assert_eq!((4, 6), (lifecycles[2].first_line, lifecycles[2].last_line));
+        assert_eq!(2, lifecycles[2].message_count);
+        assert_eq!(Some(1_100_000_000), lifecycles[2].start_time_us);
+        assert_eq!(Some(ts(1100, 50_000)), lifecycles[2].corrected_time(500));
+    }
+
+    #[test]
+    fn test_offset_uses_smallest_delay() {
+        let mut detector = LifecycleDetector::new();
+        detector.track(0, "A".to_string(), Some(10_000), Some(&ts(101, 500_000)));
+        detector.track(1, "A".to_string(), Some(20_000), Some(&ts(102, 0)));
+        let lifecycles = detector.finish();
+        assert_eq!(Some(100_000_000), lifecycles[0].start_time_us);
+    }
+
+    #[test]
+    fn test_lifecycle_sections() {
+        let mut detector = LifecycleDetector::new();
+        for (line, (ecu, uptime)) in [("A", 50_000), ("B", 1), ("B", 2), ("A", 10), ("A", 20)]
+            .iter()
+            .enumerate()
+        {
+            detector.track(line, ecu.to_string(), Some(*uptime), None);
+        }
+        let lifecycles = detector.finish();
+        assert_eq!(3, lifecycles.len());
+        let sections = lifecycle_sections(&lifecycles, &[2]).sections;
+        assert_eq!(1, sections.len());
+        assert_eq!((3, 4), (sections[0].first_line, sections[0].last_line));
+        let sections = lifecycle_sections(&lifecycles, &[0, 1]).sections;
+        assert_eq!(1, sections.len());
+        assert_eq!((0, 2), (sections[0].first_line, sections[0].last_line));
+        assert!(lifecycle_sections(&lifecycles, &[7]).sections.is_empty());
+    }
+
+    #[test]
+    fn test_index_with_corrected_time() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("lifecycles.dlt");
+        let out_path = tmp_dir.path().join("lifecycles.dlt.out");
+        let messages = vec![
+            msg("A", 10_000, ts(1001, 0)),
+            msg("A", 20_000, ts(1002, 0)),
+            msg("A", 1_000, ts(2000, 200)),
+            msg("A", 2_000, ts(2000, 100_100)),
+        ];
+        let mut in_file = std::fs::File::create(&in_path).unwrap();
+        for m in &messages {
+            in_file.write_all(&m.as_bytes()).unwrap();
+        }
+        drop(in_file);
+
+        let lifecycles = scan_lifecycles(&in_path).expect("could not scan lifecycles");
+        assert_eq!(2, lifecycles.len());
+        assert_eq!((2, 3), (lifecycles[1].first_line, lifecycles[1].last_line));
+
+        let source_file_size = std::fs::metadata(&in_path).unwrap().len() as usize;
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        create_index_and_mapping_dlt(
+            IndexingConfig {
+                tag: "TAG",
+                chunk_size: 500,
+                in_file: in_path,
+                out_path: &out_path,
+                append: false,
+            },
+            source_file_size,
+            None,
+            &tx,
+            None,
+            None,
+            true,
+        )
+        .expect("indexing failed");
+        let content = std::fs::read_to_string(&out_path).unwrap();
+        let corrected: Vec<&str> = content
+            .lines()
+            .map(|l| restore_line(l).rsplit(DLT_COLUMN_SENTINAL).next().unwrap())
+            .collect();
+        let expected: Vec<String> = [ts(1001, 0), ts(1002, 0), ts(2000, 100), ts(2000, 100_100)]
+            .iter()
+            .map(|t| format!("{}", t))
+            .collect();
+        assert_eq!(expected, corrected);
+    }
+}
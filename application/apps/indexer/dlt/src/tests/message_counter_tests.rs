@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::LogLevel;
+    use crate::filtering::ProcessedDltFilterConfig;
+    use crate::message_counter::*;
+    use pretty_assertions::assert_eq;
+
+    fn key(ecu: &str, app: &str) -> CounterKey {
+        CounterKey {
+            ecu_id: Some(ecu.to_string()),
+            session_id: Some(1),
+            app_id: Some(app.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_continuous_counters_are_fine() {
+        let mut tracker = MessageCounterTracker::new();
+        for c in 0..10u8 {
+            assert_eq!(None, tracker.track(key("ECU1", "APP"), c));
+        }
+        assert!(tracker.lost_messages().is_empty());
+    }
+
+    #[test]
+    fn test_gap_duplicate_and_wraparound() {
+        let mut tracker = MessageCounterTracker::new();
+        assert_eq!(None, tracker.track(key("ECU1", "APP"), 3));
+        assert_eq!(
+            Some(CounterEvent::Gap {
+                expected: 4,
+                received: 7,
+                lost: 3
+            }),
+            tracker.track(key("ECU1", "APP"), 7)
+        );
+        assert_eq!(
+            Some(CounterEvent::Duplicate { counter: 7 }),
+            tracker.track(key("ECU1", "APP"), 7)
+        );
+        assert_eq!(None, tracker.track(key("ECU1", "APP"), 8));
+        assert_eq!(
+            Some(CounterEvent::Gap {
+                expected: 9,
+                received: 255,
+                lost: 246
+            }),
+            tracker.track(key("ECU1", "APP"), 255)
+        );
+        // a regular wraparound is only counted
+        assert_eq!(None, tracker.track(key("ECU1", "APP"), 0));
+        assert_eq!(
+            Some(CounterEvent::Duplicate { counter: 0 }),
+            tracker.track(key("ECU1", "APP"), 0)
+        );
+        let lost = tracker.lost_messages();
+        assert_eq!(1, lost.len());
+        assert_eq!(
+            (
+                "ECU1".to_string(),
+                LostMessagesInfo {
+                    lost: 3 + 246,
+                    gaps: 2,
+                    duplicates: 2,
+                    wraparounds: 1,
+                }
+            ),
+            lost[0]
+        );
+    }
+
+    #[test]
+    fn test_gap_across_wraparound() {
+        let mut tracker = MessageCounterTracker::new();
+        tracker.track(key("ECU1", "APP"), 250);
+        assert_eq!(
+            Some(CounterEvent::Gap {
+                expected: 251,
+                received: 2,
+                lost: 7
+            }),
+            tracker.track(key("ECU1", "APP"), 2)
+        );
+        assert_eq!(1, tracker.lost_messages()[0].1.wraparounds);
+    }
+
+    #[test]
+    fn test_senders_are_tracked_separately() {
+        let mut tracker = MessageCounterTracker::new();
+        assert_eq!(None, tracker.track(key("ECU1", "APP1"), 10));
+        assert_eq!(None, tracker.track(key("ECU1", "APP2"), 100));
+        assert_eq!(None, tracker.track(key("ECU2", "APP1"), 0));
+        assert_eq!(None, tracker.track(key("ECU1", "APP1"), 11));
+        assert_eq!(None, tracker.track(key("ECU1", "APP2"), 101));
+        assert_eq!(
+            Some(CounterEvent::Gap {
+                expected: 1,
+                received: 2,
+                lost: 1
+            }),
+            tracker.track(key("ECU2", "APP1"), 2)
+        );
+        let lost = tracker.lost_messages();
+        assert_eq!(1, lost.len());
+        assert_eq!("ECU2", lost[0].0);
+    }
+
+    #[test]
+    fn test_notification_has_line() {
+        let n = counter_event_notification(
+            &key("ECU1", "APP"),
+            &CounterEvent::Duplicate { counter: 5 },
+            42,
+        );
+        assert_eq!(Some(42), n.line);
+        assert_eq!(
+            "duplicated message counter 5 for ECU1/APP (session 1)",
+            n.content
+        );
+    }
+
+    #[test]
+    fn test_counter_sequence_intact_with_filters() {
+        assert!(counter_sequence_intact(None));
+        let app_filter = ProcessedDltFilterConfig {
+            min_log_level: Some(LogLevel::Verbose),
+            app_ids: Some(vec!["APP".to_string()].into_iter().collect()),
+            ecu_ids: None,
+            context_ids: None,
+        };
+        assert!(counter_sequence_intact(Some(&app_filter)));
+        let level_filter = ProcessedDltFilterConfig {
+            min_log_level: Some(LogLevel::Warn),
+            ..app_filter
+        };
+        assert!(!counter_sequence_intact(Some(&level_filter)));
+    }
+}
@@ -1,6 +1,18 @@
 #[macro_use]
 mod dlt_tests;
+mod dlt_export_tests;
 mod dlt_file_tests;
+mod dlt_follow_tests;
+mod dlt_generator_tests;
 mod dlt_net_tests;
 mod dlt_parse_tests;
+mod dlt_pcap_tests;
+mod dlt_redaction_tests;
+mod dlt_repair_tests;
+mod dlt_replay_tests;
+mod dlt_split_tests;
 mod fibex_tests;
+mod lifecycle_tests;
+mod message_counter_tests;
+mod reorder_tests;
+mod ring_buffer_tests;
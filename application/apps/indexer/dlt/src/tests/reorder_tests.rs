@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::reorder::ReorderBuffer;
+    use pretty_assertions::assert_eq;
+    use std::time::{Duration, Instant};
+
+    fn msg(ecu: &str, uptime: Option<u32>, counter: u8) -> Message {
+        app_msg(ecu, "APP", uptime, counter)
+    }
+
+    fn app_msg(ecu: &str, app: &str, uptime: Option<u32>, counter: u8) -> Message {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some(ecu.to_string()),
+                session_id: None,
+                timestamp: uptime,
+                payload: Payload2 {
+                    payload_content: PayloadContent::Verbose(vec![]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: app.to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+    }
+
+    fn ids(msgs: &[Message]) -> Vec<(String, u8)> {
+        msgs.iter()
+            .map(|m| {
+                (
+                    m.header.ecu_id.clone().unwrap_or_default(),
+                    m.header.message_counter,
+                )
+            })
+            .collect()
+    }
+
+    fn pairs(v: &[(&str, u8)]) -> Vec<(String, u8)> {
+        v.iter().map(|(e, c)| (e.to_string(), *c)).collect()
+    }
+
+    #[test]
+    fn test_nothing_released_within_window() {
+        let start = Instant::now();
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(100));
+        buffer.push(msg("A", Some(20), 2), start);
+        buffer.push(msg("A", Some(10), 1), start);
+        assert!(buffer
+            .pop_ready(start + Duration::from_millis(50))
+            .is_empty());
+        let released = buffer.pop_ready(start + Duration::from_millis(100));
+        assert_eq!(pairs(&[("A", 1), ("A", 2)]), ids(&released));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_late_message_is_released_with_earlier_one() {
+        let start = Instant::now();
+        let later = start + Duration::from_millis(80);
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(100));
+        buffer.push(msg("A", Some(20), 2), start);
+        buffer.push(msg("A", Some(30), 3), start);
+        // arrived late but belongs before the others
+        buffer.push(msg("A", Some(10), 1), later);
+        buffer.push(msg("A", Some(40), 4), later);
+        let released = buffer.pop_ready(start + Duration::from_millis(100));
+        assert_eq!(pairs(&[("A", 1), ("A", 2), ("A", 3)]), ids(&released));
+        let released = buffer.flush();
+        assert_eq!(pairs(&[("A", 4)]), ids(&released));
+    }
+
+    #[test]
+    fn test_ecus_keep_arrival_order() {
+        let start = Instant::now();
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(10));
+        buffer.push(msg("A", Some(5_000), 1), start);
+        buffer.push(msg("B", Some(10), 1), start);
+        buffer.push(msg("B", Some(5), 0), start);
+        buffer.push(msg("A", Some(6_000), 2), start);
+        let released = buffer.pop_ready(start + Duration::from_millis(10));
+        assert_eq!(
+            pairs(&[("A", 1), ("B", 0), ("B", 1), ("A", 2)]),
+            ids(&released)
+        );
+    }
+
+    #[test]
+    fn test_counter_decides_for_same_timestamp() {
+        let start = Instant::now();
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(10));
+        buffer.push(msg("A", Some(100), 254), start);
+        buffer.push(msg("A", Some(100), 0), start);
+        buffer.push(msg("A", Some(100), 255), start);
+        // no timestamp: stays behind the last message of its ECU
+        buffer.push(msg("A", None, 1), start);
+        let released = buffer.flush();
+        assert_eq!(
+            pairs(&[("A", 254), ("A", 255), ("A", 0), ("A", 1)]),
+            ids(&released)
+        );
+    }
+
+    #[test]
+    fn test_counters_are_compared_per_sender() {
+        let start = Instant::now();
+        let mut buffer = ReorderBuffer::new(Duration::from_millis(10));
+        // the counters of two apps of one ECU are independent
+        buffer.push(app_msg("A", "APP1", Some(100), 10), start);
+        buffer.push(app_msg("A", "APP2", Some(100), 138), start);
+        buffer.push(app_msg("A", "APP2", Some(100), 137), start);
+        buffer.push(app_msg("A", "APP1", Some(100), 11), start);
+        let released = buffer.flush();
+        assert_eq!(
+            vec![
+                ("APP1".to_string(), 10),
+                ("APP2".to_string(), 137),
+                ("APP2".to_string(), 138),
+                ("APP1".to_string(), 11)
+            ],
+            released
+                .iter()
+                .map(|m| (
+                    m.extended_header
+                        .as_ref()
+                        .map(|h| h.application_id.clone())
+                        .unwrap_or_default(),
+                    m.header.message_counter
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,315 @@
+#[cfg(test)]
+mod tests {
+    use crate::dlt::*;
+    use crate::dlt_file::export_segments_as_dlt;
+    use crate::ring_buffer::*;
+    use crossbeam_channel as cc;
+    use indexer_base::chunks::{Chunk, ChunkResults};
+    use indexer_base::config::{IndexSection, RingBufferConfig, SectionConfig};
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, Instant};
+    use tempdir::TempDir;
+
+    fn msg(counter: u8) -> Message {
+        Message::new(
+            MessageConfig {
+                version: 1,
+                counter,
+                endianness: Endianness::Big,
+                ecu_id: Some("ECU".to_string()),
+                session_id: None,
+                timestamp: Some(u32::from(counter)),
+                payload: Payload2 {
+                    payload_content: PayloadContent::Verbose(vec![]),
+                },
+                extended_header_info: Some(ExtendedHeaderConfig {
+                    message_type: MessageType::Log(LogLevel::Info),
+                    app_id: "APP".to_string(),
+                    context_id: "CTX".to_string(),
+                }),
+            },
+            None,
+            None,
+        )
+        .add_storage_header(Some(DltTimeStamp {
+            seconds: 1000,
+            microseconds: 0,
+        }))
+    }
+
+    fn config(max_segment_bytes: Option<u64>, archive_dir: Option<&Path>) -> RingBufferConfig {
+        RingBufferConfig {
+            max_segment_bytes,
+            max_segment_duration_s: None,
+            max_segments: 2,
+            archive_dir: archive_dir.map(|p| p.to_string_lossy().to_string()),
+        }
+    }
+
+    fn line(counter: u8) -> String {
+        format!("{}\n", msg(counter))
+    }
+
+    fn append(path: &Path, content: &str) {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(content.as_bytes())
+            .unwrap();
+    }
+
+    /// writes the messages and their lines like a live session would,
+    /// returns the ring and all notifications
+    fn capture(
+        mut ring: SegmentRing,
+        mut file: std::fs::File,
+        index_file: &Path,
+        counters: impl Iterator<Item = u8>,
+    ) -> (SegmentRing, Vec<String>) {
+        let mut notifications = vec![];
+        for counter in counters {
+            if let Some((new_file, dropped)) = ring.rotate_if_needed(Instant::now()).unwrap() {
+                file = new_file;
+                notifications.extend(dropped.into_iter().map(|n| n.content));
+            }
+            let bytes = msg(counter).as_bytes();
+            file.write_all(&bytes).unwrap();
+            append(index_file, &line(counter));
+            ring.add_message(bytes.len(), line(counter).len());
+        }
+        ring.finish().unwrap();
+        (ring, notifications)
+    }
+
+    #[test]
+    fn test_segment_path() {
+        assert_eq!(
+            PathBuf::from("/tmp/capture_000003.dlt"),
+            segment_path(Path::new("/tmp/capture.dlt"), 3)
+        );
+        assert_eq!(
+            PathBuf::from("/tmp/capture_000012"),
+            segment_path(Path::new("/tmp/capture"), 12)
+        );
+    }
+
+    #[test]
+    fn test_local_sections() {
+        let segment = SegmentInfo {
+            index: 1,
+            first_line: 10,
+            line_count: 10,
+            dlt_bytes: 0,
+            text_bytes: 0,
+            dlt_file: PathBuf::from("a.dlt"),
+        };
+        let sections = SectionConfig {
+            sections: vec![
+                IndexSection {
+                    first_line: 0,
+                    last_line: 12,
+                },
+                IndexSection {
+                    first_line: 15,
+                    last_line: 30,
+                },
+            ],
+        };
+        let local = segment.local_sections(&sections).expect("should overlap");
+        let local: Vec<(usize, usize)> = local
+            .sections
+            .iter()
+            .map(|s| (s.first_line, s.last_line))
+            .collect();
+        assert_eq!(vec![(0, 2), (5, 9)], local);
+        let outside = SectionConfig {
+            sections: vec![IndexSection {
+                first_line: 20,
+                last_line: 30,
+            }],
+        };
+        assert!(segment.local_sections(&outside).is_none());
+    }
+
+    #[test]
+    fn test_rotate_and_drop() {
+        let tmp_dir = TempDir::new("ring").expect("could not create temp dir");
+        let dlt_base = tmp_dir.path().join("session.dlt");
+        let index_file = tmp_dir.path().join("session.txt");
+        let line_len = line(0).len() as u64;
+        // 3 lines per segment, first line 100 continues an existing session
+        append(&index_file, "existing\n");
+        let (ring, file) = SegmentRing::start(
+            config(Some(3 * line_len), None),
+            dlt_base.clone(),
+            index_file.clone(),
+            100,
+            Instant::now(),
+        )
+        .unwrap();
+        let (ring, notifications) = capture(ring, file, &index_file, 0..10);
+        assert_eq!(
+            vec![
+                "dropped dlt segment 0 (lines 100 - 102)".to_string(),
+                "removed 3 lines from the start of the session, line numbers are offset by 3"
+                    .to_string(),
+                "dropped dlt segment 1 (lines 103 - 105)".to_string(),
+                "removed 3 lines from the start of the session, line numbers are offset by 6"
+                    .to_string(),
+            ],
+            notifications
+        );
+        let manifest = read_segment_manifest(&manifest_path(&dlt_base)).unwrap();
+        assert_eq!(ring.manifest(), &manifest);
+        assert_eq!(2, manifest.dropped_segments);
+        assert_eq!(6, manifest.dropped_lines);
+        let retained: Vec<(usize, usize, usize)> = manifest
+            .segments
+            .iter()
+            .map(|s| (s.index, s.first_line, s.line_count))
+            .collect();
+        assert_eq!(vec![(2, 106, 3), (3, 109, 1)], retained);
+        assert!(!segment_path(&dlt_base, 0).exists());
+        assert!(segment_path(&dlt_base, 2).exists());
+        let expected_lines: String = std::iter::once("existing\n".to_string())
+            .chain((6..10).map(line))
+            .collect();
+        assert_eq!(
+            expected_lines,
+            std::fs::read_to_string(&index_file).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_archive_and_export() {
+        let tmp_dir = TempDir::new("ring").expect("could not create temp dir");
+        let archive = tmp_dir.path().join("archive");
+        let dlt_base = tmp_dir.path().join("session.dlt");
+        let index_file = tmp_dir.path().join("session.txt");
+        let line_len = line(0).len() as u64;
+        let (ring, file) = SegmentRing::start(
+            config(Some(2 * line_len), Some(&archive)),
+            dlt_base.clone(),
+            index_file.clone(),
+            0,
+            Instant::now(),
+        )
+        .unwrap();
+        let (_, notifications) = capture(ring, file, &index_file, 0..5);
+        assert_eq!(
+            vec![
+                "archived dlt segment 0 (lines 0 - 1)".to_string(),
+                "removed 2 lines from the start of the session, line numbers are offset by 2"
+                    .to_string(),
+            ],
+            notifications
+        );
+        assert!(archive.join("session_000000.dlt").exists());
+        assert_eq!(
+            format!("{}{}", line(0), line(1)),
+            std::fs::read_to_string(archive.join("session_000000.txt")).unwrap()
+        );
+
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let target = tmp_dir.path().join("export.dlt");
+        export_segments_as_dlt(
+            &manifest_path(&dlt_base),
+            target.clone(),
+            SectionConfig {
+                sections: vec![IndexSection {
+                    first_line: 0,
+                    last_line: 3,
+                }],
+            },
+            tx,
+        )
+        .expect("export failed");
+        let exported = std::fs::read(&target).unwrap();
+        let expected: Vec<u8> = (2..4).flat_map(|c| msg(c).as_bytes()).collect();
+        assert_eq!(expected, exported);
+    }
+
+    #[test]
+    fn test_rotate_by_duration() {
+        let tmp_dir = TempDir::new("ring").expect("could not create temp dir");
+        let mut cfg = config(None, None);
+        cfg.max_segment_duration_s = Some(60);
+        let start = Instant::now();
+        let (mut ring, _file) = SegmentRing::start(
+            cfg,
+            tmp_dir.path().join("session.dlt"),
+            tmp_dir.path().join("session.txt"),
+            0,
+            start,
+        )
+        .unwrap();
+        ring.add_message(10, 0);
+        assert!(ring
+            .rotate_if_needed(start + Duration::from_secs(59))
+            .unwrap()
+            .is_none());
+        assert!(ring
+            .rotate_if_needed(start + Duration::from_secs(60))
+            .unwrap()
+            .is_some());
+        assert_eq!(2, ring.manifest().segments.len());
+    }
+
+    #[test]
+    fn test_index_file_stays_within_limit() {
+        let tmp_dir = TempDir::new("ring").expect("could not create temp dir");
+        let index_file = tmp_dir.path().join("session.txt");
+        let max_segment_bytes = 1000;
+        let (mut ring, mut file) = SegmentRing::start(
+            config(Some(max_segment_bytes), None),
+            tmp_dir.path().join("session.dlt"),
+            index_file.clone(),
+            0,
+            Instant::now(),
+        )
+        .unwrap();
+        let longest_line = (0..=255).map(|c| line(c).len() as u64).max().unwrap();
+        // 2 segments, each one can exceed the limit by a line
+        let limit = 2 * (max_segment_bytes + longest_line);
+        let mut written_bytes = 0;
+        for i in 0..5000usize {
+            let counter = (i % 256) as u8;
+            if let Some((new_file, _)) = ring.rotate_if_needed(Instant::now()).unwrap() {
+                file = new_file;
+            }
+            let bytes = msg(counter).as_bytes();
+            file.write_all(&bytes).unwrap();
+            append(&index_file, &line(counter));
+            ring.add_message(bytes.len(), line(counter).len());
+            written_bytes += line(counter).len();
+            assert!(std::fs::metadata(&index_file).unwrap().len() <= limit);
+        }
+        let manifest = ring.manifest();
+        assert!(manifest.dropped_segments > 0);
+        let content = std::fs::read_to_string(&index_file).unwrap();
+        assert_eq!(
+            written_bytes as u64 - manifest.dropped_text_bytes,
+            content.len() as u64
+        );
+        // the remaining lines are the ones of the retained segments
+        let first_line = manifest.segments[0].first_line;
+        assert_eq!(manifest.dropped_lines, first_line);
+        assert_eq!(
+            Some(line((first_line % 256) as u8).trim_end()),
+            content.lines().next()
+        );
+        assert_eq!(5000 - first_line, content.lines().count());
+        // and the chunk of the last line points to the end of the file
+        let last_line_len = line((4999 % 256) as u8).len();
+        let chunk = manifest.index_chunk(Chunk {
+            r: (4999, 4999),
+            b: (written_bytes - last_line_len, written_bytes),
+        });
+        assert_eq!((4999 - first_line, 4999 - first_line), chunk.r);
+        assert_eq!((content.len() - last_line_len, content.len()), chunk.b);
+    }
+}
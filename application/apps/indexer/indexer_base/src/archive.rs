@@ -0,0 +1,367 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::compression::{decompress, open_input, Compression};
+use failure::{err_msg, Error};
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// separates the path of an archive from the path of an entry inside of it,
+/// e.g. `logs.tar.gz!/ecu1/trace.dlt`
+pub const ARCHIVE_ENTRY_SEPARATOR: &str = "!/";
+/// number of bytes that are looked at to detect the type of an entry
+const SNIFF_LENGTH: usize = 4096;
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const TAR_MAGIC: &[u8] = b"ustar";
+const TAR_MAGIC_OFFSET: usize = 257;
+const DLT_STORAGE_MAGIC: &[u8] = b"DLT\x01";
+const PCAP_MAGIC_BE: &[u8] = &[0xa1, 0xb2, 0xc3, 0xd4];
+const PCAP_MAGIC_LE: &[u8] = &[0xd4, 0xc3, 0xb2, 0xa1];
+const PCAPNG_MAGIC: &[u8] = &[0x0a, 0x0d, 0x0d, 0x0a];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    /// a tar file, can itself be compressed (e.g. `.tar.gz`)
+    Tar,
+    Zip,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileType {
+    /// DLT with storage headers
+    Dlt,
+    Pcap,
+    PcapNg,
+    Text,
+    /// an archive in an archive, cannot be opened directly
+    Archive,
+    Binary,
+}
+
+impl FileType {
+    /// detects the type from the first (decompressed) bytes of a file
+    pub fn from_content(start: &[u8]) -> FileType {
+        if start.starts_with(DLT_STORAGE_MAGIC) {
+            FileType::Dlt
+        } else if start.starts_with(PCAP_MAGIC_BE) || start.starts_with(PCAP_MAGIC_LE) {
+            FileType::Pcap
+        } else if start.starts_with(PCAPNG_MAGIC) {
+            FileType::PcapNg
+        } else if start.starts_with(ZIP_MAGIC) || is_tar(start) {
+            FileType::Archive
+        } else if is_text(start) {
+            FileType::Text
+        } else {
+            FileType::Binary
+        }
+    }
+}
+
+fn is_tar(start: &[u8]) -> bool {
+    start.len() >= TAR_MAGIC_OFFSET + TAR_MAGIC.len()
+        && &start[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()] == TAR_MAGIC
+}
+
+/// no NUL bytes and only a few control characters (non UTF-8 encodings
+/// count as text as well)
+fn is_text(start: &[u8]) -> bool {
+    if start.contains(&0) {
+        return false;
+    }
+    let control = start
+        .iter()
+        .filter(|b| **b < 0x20 && !b"\t\r\n\x0c\x1b".contains(b))
+        .count();
+    control * 20 <= start.len()
+}
+
+/// a file inside of an archive
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// path inside of the archive
+    pub name: String,
+    /// size after the archive is unpacked
+    pub size: u64,
+    /// size inside of the archive (differs for compressed zip entries)
+    pub stored_size: u64,
+    /// compression of the entry itself (e.g. `trace.dlt.gz` in a tar)
+    pub compression: Compression,
+    pub file_type: FileType,
+}
+
+impl ArchiveEntry {
+    /// the path that can be used to open the entry as source
+    pub fn source_path(&self, archive: &Path) -> PathBuf {
+        entry_path(archive, &self.name)
+    }
+}
+
+pub fn entry_path(archive: &Path, entry: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}{}{}",
+        archive.to_string_lossy(),
+        ARCHIVE_ENTRY_SEPARATOR,
+        entry
+    ))
+}
+
+/// splits `archive!/entry` into the archive path and the entry name,
+/// `None` for normal files (also if the file name contains the separator)
+pub fn split_entry_path(path: &Path) -> Option<(PathBuf, String)> {
+    if path.exists() {
+        return None;
+    }
+    let path_str = path.to_str()?;
+    let mut search_start = 0;
+    while let Some(index) = path_str[search_start..].find(ARCHIVE_ENTRY_SEPARATOR) {
+        let split = search_start + index;
+        let archive = Path::new(&path_str[..split]);
+        if archive.is_file() {
+            let entry = &path_str[split + ARCHIVE_ENTRY_SEPARATOR.len()..];
+            return Some((archive.to_path_buf(), entry.to_string()));
+        }
+        search_start = split + ARCHIVE_ENTRY_SEPARATOR.len();
+    }
+    None
+}
+
+pub fn detect_archive(path: &Path) -> Result<Option<ArchiveFormat>, Error> {
+    let mut start = Vec::with_capacity(TAR_MAGIC_OFFSET + TAR_MAGIC.len());
+    fs::File::open(path)?
+        .take(ZIP_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    if start.starts_with(ZIP_MAGIC) {
+        return Ok(Some(ArchiveFormat::Zip));
+    }
+    start.clear();
+    open_input(path)?
+        .take((TAR_MAGIC_OFFSET + TAR_MAGIC.len()) as u64)
+        .read_to_end(&mut start)?;
+    if is_tar(&start) {
+        Ok(Some(ArchiveFormat::Tar))
+    } else {
+        Ok(None)
+    }
+}
+
+fn archive_format(path: &Path) -> Result<ArchiveFormat, Error> {
+    detect_archive(path)?.ok_or_else(|| err_msg(format!("{:?} is no tar or zip archive", path)))
+}
+
+/// reads the start of an entry to find out its compression and type
+fn sniff<R: Read>(reader: R) -> Result<(Compression, FileType), Error> {
+    let mut start = Vec::with_capacity(SNIFF_LENGTH);
+    reader.take(SNIFF_LENGTH as u64).read_to_end(&mut start)?;
+    let compression = Compression::from_magic(&start);
+    if compression == Compression::None {
+        return Ok((compression, FileType::from_content(&start)));
+    }
+    // only a part of the compressed data is available, decompress as much as possible
+    let mut decoder = decompress(compression, Box::new(io::Cursor::new(start)))?;
+    let mut decompressed = vec![0u8; SNIFF_LENGTH];
+    let mut len = 0;
+    while len < decompressed.len() {
+        match decoder.read(&mut decompressed[len..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => len += n,
+        }
+    }
+    Ok((compression, FileType::from_content(&decompressed[..len])))
+}
+
+/// lists all files in a tar (also compressed) or zip archive
+pub fn list_archive(path: &Path) -> Result<Vec<ArchiveEntry>, Error> {
+    let mut entries = vec![];
+    match archive_format(path)? {
+        ArchiveFormat::Tar => {
+            let mut archive = tar::Archive::new(open_input(path)?);
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path()?.to_string_lossy().to_string();
+                let size = entry.size();
+                let (compression, file_type) = sniff(&mut entry)?;
+                entries.push(ArchiveEntry {
+                    name,
+                    size,
+                    stored_size: size,
+                    compression,
+                    file_type,
+                });
+            }
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+            for i in 0..archive.len() {
+                let entry = archive.by_index(i)?;
+                if !entry.is_file() {
+                    continue;
+                }
+                let name = entry.name().to_string();
+                let size = entry.size();
+                let stored_size = entry.compressed_size();
+                let (compression, file_type) = sniff(entry)?;
+                entries.push(ArchiveEntry {
+                    name,
+                    size,
+                    stored_size,
+                    compression,
+                    file_type,
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// where the data of a tar entry starts in the (unpacked) tar stream
+#[derive(Debug, Clone, Copy)]
+struct TarEntryLocation {
+    position: u64,
+    size: u64,
+}
+
+/// the files of a tar, valid as long as the archive is not modified
+struct TarIndex {
+    modified: Option<SystemTime>,
+    length: u64,
+    entries: FxHashMap<String, TarEntryLocation>,
+}
+
+lazy_static! {
+    /// tars have no directory, so they are only scanned once
+    static ref TAR_INDICES: Mutex<FxHashMap<PathBuf, Arc<TarIndex>>> =
+        Mutex::new(FxHashMap::default());
+}
+
+fn tar_index(path: &Path) -> Result<Arc<TarIndex>, Error> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified().ok();
+    let length = metadata.len();
+    if let Ok(indices) = TAR_INDICES.lock() {
+        if let Some(index) = indices.get(path) {
+            if index.modified == modified && index.length == length {
+                return Ok(Arc::clone(index));
+            }
+        }
+    }
+    let mut entries = FxHashMap::default();
+    let mut archive = tar::Archive::new(open_input(path)?);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() {
+            // like when unpacking the first entry of a name is used
+            entries
+                .entry(entry.path()?.to_string_lossy().to_string())
+                .or_insert(TarEntryLocation {
+                    position: entry.raw_file_position(),
+                    size: entry.size(),
+                });
+        }
+    }
+    let index = Arc::new(TarIndex {
+        modified,
+        length,
+        entries,
+    });
+    if let Ok(mut indices) = TAR_INDICES.lock() {
+        indices.insert(path.to_path_buf(), Arc::clone(&index));
+    }
+    Ok(index)
+}
+
+fn entry_not_found(archive_path: &Path, name: &str) -> Error {
+    err_msg(format!("no entry {} in {:?}", name, archive_path))
+}
+
+fn tar_entry(archive_path: &Path, name: &str) -> Result<TarEntryLocation, Error> {
+    tar_index(archive_path)?
+        .entries
+        .get(name)
+        .cloned()
+        .ok_or_else(|| entry_not_found(archive_path, name))
+}
+
+fn zip_entry<'a, R: Read + Seek>(
+    archive: &'a mut zip::ZipArchive<R>,
+    archive_path: &Path,
+    name: &str,
+) -> Result<zip::read::ZipFile<'a>, Error> {
+    archive.by_name(name).map_err(|e| match e {
+        zip::result::ZipError::FileNotFound => entry_not_found(archive_path, name),
+        e => Error::from(e),
+    })
+}
+
+/// the number of stored bytes of an archive entry (without unpacking it)
+pub(crate) fn entry_stored_size(archive_path: &Path, name: &str) -> Result<u64, Error> {
+    match archive_format(archive_path)? {
+        ArchiveFormat::Tar => Ok(tar_entry(archive_path, name)?.size),
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(fs::File::open(archive_path)?)?;
+            let entry = zip_entry(&mut archive, archive_path, name)?;
+            Ok(entry.compressed_size())
+        }
+    }
+}
+
+/// the stored bytes of an archive entry, `Compression::Deflate` if the zip
+/// entry still has to be inflated
+pub(crate) fn open_entry(
+    archive_path: &Path,
+    name: &str,
+) -> Result<(Box<dyn Read + Send>, Compression, u64), Error> {
+    match archive_format(archive_path)? {
+        ArchiveFormat::Tar => {
+            let location = tar_entry(archive_path, name)?;
+            let mut input = open_input(archive_path)?;
+            let reader: Box<dyn Read + Send> = if input.is_compressed() {
+                // a compressed tar cannot seek, everything before the entry is skipped
+                io::copy(&mut (&mut input).take(location.position), &mut io::sink())?;
+                Box::new(input.take(location.size))
+            } else {
+                let mut file = fs::File::open(archive_path)?;
+                file.seek(SeekFrom::Start(location.position))?;
+                Box::new(file.take(location.size))
+            };
+            Ok((reader, Compression::None, location.size))
+        }
+        ArchiveFormat::Zip => {
+            let mut file = fs::File::open(archive_path)?;
+            let (data_start, stored_size, compression) = {
+                let mut archive = zip::ZipArchive::new(&mut file)?;
+                let entry = zip_entry(&mut archive, archive_path, name)?;
+                let compression = match entry.compression() {
+                    zip::CompressionMethod::Stored => Compression::None,
+                    zip::CompressionMethod::Deflated => Compression::Deflate,
+                    method => {
+                        return Err(err_msg(format!(
+                            "zip compression {:?} of {} is not supported",
+                            method, name
+                        )))
+                    }
+                };
+                (entry.data_start(), entry.compressed_size(), compression)
+            };
+            file.seek(SeekFrom::Start(data_start))?;
+            Ok((Box::new(file.take(stored_size)), compression, stored_size))
+        }
+    }
+}
@@ -68,6 +68,24 @@ impl ChunkFactory {
         }
         None
     }
+    /// a chunk for the lines added since the last chunk, used to report lines
+    /// before the chunk is full (e.g. when a followed file has no new content)
+    pub fn create_pending_chunk(&mut self, line_nr: usize) -> Option<Chunk> {
+        if self.lines_in_chunk == 0 {
+            return None;
+        }
+        self.last_line_current_chunk = line_nr;
+        let chunk = Chunk {
+            r: (
+                self.last_line_current_chunk - self.lines_in_chunk,
+                self.last_line_current_chunk - 1,
+            ),
+            b: (self.start_of_chunk_byte_index, self.current_byte_index),
+        };
+        self.start_of_chunk_byte_index = self.current_byte_index + 1;
+        self.lines_in_chunk = 0;
+        Some(chunk)
+    }
     pub fn create_last_chunk(&mut self, line_nr: usize, only_chunk: bool) -> Option<Chunk> {
         // only add junk if we produced any output lines
         if line_nr > 0 && self.start_of_chunk_byte_index != self.current_byte_index {
@@ -0,0 +1,263 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::archive;
+use failure::{err_msg, Error};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// id and deflate method
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b, 0x08];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+    Bzip2,
+    /// raw deflate stream of a zip entry (has no magic)
+    Deflate,
+}
+
+impl Compression {
+    /// detects the compression from the first bytes of a file
+    pub fn from_magic(start: &[u8]) -> Compression {
+        if start.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if start.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if start.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else if start.starts_with(BZIP2_MAGIC)
+            && start.len() > BZIP2_MAGIC.len()
+            && (b'1'..=b'9').contains(&start[BZIP2_MAGIC.len()])
+        {
+            // "BZh" followed by the block size
+            Compression::Bzip2
+        } else {
+            Compression::None
+        }
+    }
+
+    /// the file extension that is usually used for this compression
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+            Compression::Xz => Some("xz"),
+            Compression::Bzip2 => Some("bz2"),
+            Compression::Deflate => None,
+        }
+    }
+}
+
+pub fn detect_compression(path: &Path) -> Result<Compression, Error> {
+    if archive::split_entry_path(path).is_some() {
+        return Ok(open_input(path)?.compression());
+    }
+    let mut start = Vec::with_capacity(XZ_MAGIC.len());
+    fs::File::open(path)?
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    Ok(Compression::from_magic(&start))
+}
+
+/// shared count of the bytes that were read from a file (before decompression)
+#[derive(Debug, Clone, Default)]
+pub struct ConsumedBytes(Arc<AtomicUsize>);
+
+impl ConsumedBytes {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+    fn add(&self, bytes: usize) {
+        self.0.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+struct CountingReader<R: Read> {
+    inner: R,
+    consumed: ConsumedBytes,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.consumed.add(len);
+        Ok(len)
+    }
+}
+
+/// a file that is transparently decompressed while it is read
+pub struct InputReader {
+    reader: Box<dyn Read + Send>,
+    compression: Compression,
+    consumed: ConsumedBytes,
+}
+
+impl InputReader {
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compression != Compression::None
+    }
+
+    /// the bytes read from the (compressed) file so far
+    pub fn consumed(&self) -> ConsumedBytes {
+        self.consumed.clone()
+    }
+
+    /// like `consumed` but only for compressed files, where the decompressed
+    /// bytes cannot be used for progress reporting
+    pub fn compressed_input(&self) -> Option<ConsumedBytes> {
+        if self.is_compressed() {
+            Some(self.consumed())
+        } else {
+            None
+        }
+    }
+}
+
+impl Read for InputReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+/// wraps `reader` in the decoder for `compression`
+pub(crate) fn decompress(
+    compression: Compression,
+    reader: Box<dyn Read + Send>,
+) -> Result<Box<dyn Read + Send>, Error> {
+    Ok(match compression {
+        Compression::None => reader,
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(
+            zstd::stream::read::Decoder::new(reader)
+                .map_err(|e| err_msg(format!("could not read zstd compressed data: {}", e)))?,
+        ),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Compression::Deflate => Box::new(flate2::read::DeflateDecoder::new(reader)),
+    })
+}
+
+/// the peeked bytes followed by the rest of the stream, unlike `Read::chain`
+/// a read does not stop at the end of the prefix (some readers expect the
+/// first read to fill their buffer)
+struct PrefixedReader {
+    prefix: io::Cursor<Vec<u8>>,
+    inner: Box<dyn Read + Send>,
+    /// an error of the stream after the prefix, returned by the next read
+    pending_error: Option<io::Error>,
+}
+
+impl Read for PrefixedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(e) = self.pending_error.take() {
+            return Err(e);
+        }
+        let len = self.prefix.read(buf)?;
+        if len == buf.len() {
+            return Ok(len);
+        }
+        match self.inner.read(&mut buf[len..]) {
+            Ok(n) => Ok(len + n),
+            Err(e) if len > 0 => {
+                self.pending_error = Some(e);
+                Ok(len)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// reads the magic bytes of `reader` and puts them back in front of the rest
+pub(crate) fn peek_compression(
+    mut reader: Box<dyn Read + Send>,
+) -> Result<(Compression, Box<dyn Read + Send>), Error> {
+    let mut start = Vec::with_capacity(XZ_MAGIC.len());
+    (&mut reader)
+        .take(XZ_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    let compression = Compression::from_magic(&start);
+    let prefixed = PrefixedReader {
+        prefix: io::Cursor::new(start),
+        inner: reader,
+        pending_error: None,
+    };
+    Ok((compression, Box::new(prefixed)))
+}
+
+/// opens a file that might be compressed with gzip, zstd, xz or bzip2,
+/// the compression is detected from the content and not from the file name.
+/// `archive.tar.gz!/path/in/archive` opens an entry of a tar or zip archive
+/// (see `archive::ARCHIVE_ENTRY_SEPARATOR`) without extracting it
+pub fn open_input(path: &Path) -> Result<InputReader, Error> {
+    let consumed = ConsumedBytes::default();
+    let (raw, container_compression): (Box<dyn Read + Send>, Compression) =
+        match archive::split_entry_path(path) {
+            Some((archive_path, entry)) => {
+                let (reader, compression, _) = archive::open_entry(&archive_path, &entry)?;
+                (reader, compression)
+            }
+            None => (Box::new(fs::File::open(path)?), Compression::None),
+        };
+    let counting = CountingReader {
+        inner: raw,
+        consumed: consumed.clone(),
+    };
+    let unpacked = decompress(container_compression, Box::new(counting))?;
+    let (compression, stream) = peek_compression(unpacked)?;
+    let reader = decompress(compression, stream)
+        .map_err(|e| err_msg(format!("could not open {:?}: {}", path, e)))?;
+    trace!("open_input {:?} ({:?})", path, compression);
+    Ok(InputReader {
+        reader,
+        compression: if compression == Compression::None {
+            container_compression
+        } else {
+            compression
+        },
+        consumed,
+    })
+}
+
+/// true for existing files and for entries of existing archives
+pub fn input_exists(path: &Path) -> bool {
+    match archive::split_entry_path(path) {
+        Some((archive_path, entry)) => {
+            archive_path.exists() && archive::entry_stored_size(&archive_path, &entry).is_ok()
+        }
+        None => path.exists(),
+    }
+}
+
+/// size of a file or of the stored bytes of an archive entry, this is what
+/// `ConsumedBytes` counts up to
+pub fn input_size(path: &Path) -> Result<u64, Error> {
+    match archive::split_entry_path(path) {
+        Some((archive_path, entry)) => archive::entry_stored_size(&archive_path, &entry),
+        None => Ok(fs::metadata(path)?.len()),
+    }
+}
@@ -39,21 +39,49 @@ pub struct IndexingConfig<'a> {
 pub struct FibexConfig {
     pub fibex_file_paths: Vec<String>,
 }
-/// network socket config
+/// one UDP endpoint we receive dlt messages on
 /// if udp packets are sent via multicast, then the `multicast_addr` has to
 /// be specified
 #[derive(Serialize, Deserialize, Debug)]
-pub struct SocketConfig {
+pub struct UdpEndpoint {
     pub multicast_addr: Option<MulticastInfo>,
     pub bind_addr: String,
     pub port: String,
 }
+/// network socket config
+/// all `endpoints` are received in the same session
+/// if `reorder_window_ms` is set, messages are held back for that long
+/// to sort messages that arrived out of order
+/// with a `ring_buffer` the raw dlt messages are split into segments and only
+/// the latest segments are kept (the text output is not split)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SocketConfig {
+    pub endpoints: Vec<UdpEndpoint>,
+    pub reorder_window_ms: Option<u64>,
+    pub ring_buffer: Option<RingBufferConfig>,
+}
+/// limits the disk space of a long running live capture (raw dlt messages and
+/// text output)
+/// a segment is finished when its raw dlt content or its text output reached
+/// `max_segment_bytes` or after `max_segment_duration_s` seconds (whatever
+/// comes first)
+/// at most `max_segments` are kept, older ones are deleted or moved
+/// to `archive_dir` and their lines are removed from the session file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RingBufferConfig {
+    pub max_segment_bytes: Option<u64>,
+    pub max_segment_duration_s: Option<u64>,
+    pub max_segments: usize,
+    pub archive_dir: Option<String>,
+}
 /// Multicast config information.
 /// `multiaddr` address must be a valid multicast address
 /// `interface` is the address of the local interface with which the
 /// system should join the
 /// multicast group. If it's equal to `INADDR_ANY` then an appropriate
 /// interface is chosen by the system.
+/// For IPv6 groups `interface` is the index of the interface (`0` lets the
+/// system choose).
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MulticastInfo {
     pub multiaddr: String,
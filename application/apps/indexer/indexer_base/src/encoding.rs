@@ -0,0 +1,174 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::compression::{open_input, InputReader};
+pub use encoding_rs::{Encoding, UTF_8};
+use encoding_rs::{SHIFT_JIS, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use encoding_rs_io::{DecodeReaderBytes, DecodeReaderBytesBuilder};
+use failure::{err_msg, Error};
+use std::io::Read;
+use std::path::Path;
+
+/// number of bytes that are looked at to detect the encoding
+const SAMPLE_LENGTH: usize = 64 * 1024;
+/// fewer invalid sequences than this are taken as broken UTF-8 text
+const MIN_INVALID_SEQUENCES: usize = 8;
+
+/// text decoded to UTF-8, invalid sequences are replaced with U+FFFD
+pub type TextReader<R> = DecodeReaderBytes<R, Vec<u8>>;
+
+/// the encoding for a label like `utf-8`, `utf-16le`, `shift_jis` or `latin1`
+/// (see https://encoding.spec.whatwg.org/#names-and-labels)
+pub fn encoding_for_label(label: &str) -> Result<&'static Encoding, Error> {
+    Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| err_msg(format!("unknown encoding: {}", label)))
+}
+
+/// the configured encoding or the one that is detected from the start of the file
+pub fn resolve_encoding(label: Option<&str>, path: &Path) -> Result<&'static Encoding, Error> {
+    match label {
+        Some(label) => encoding_for_label(label),
+        None => detect_encoding(path),
+    }
+}
+
+pub fn detect_encoding(path: &Path) -> Result<&'static Encoding, Error> {
+    let mut sample = Vec::with_capacity(SAMPLE_LENGTH);
+    open_input(path)?
+        .take(SAMPLE_LENGTH as u64)
+        .read_to_end(&mut sample)?;
+    let encoding = detect_encoding_in_sample(&sample);
+    debug!("detected encoding {} for {:?}", encoding.name(), path);
+    Ok(encoding)
+}
+
+/// guesses the encoding: a BOM, UTF-16 without BOM (every second byte is 0
+/// for mostly ASCII text), UTF-8 unless most of the non-ASCII characters are
+/// invalid (a few broken characters are replaced when decoding), Shift_JIS
+/// with japanese characters, everything else is taken as Latin-1 (windows-1252)
+pub fn detect_encoding_in_sample(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    if let Some(encoding) = detect_utf16(sample) {
+        return encoding;
+    }
+    if !mostly_invalid_utf8(sample) {
+        return UTF_8;
+    }
+    if is_shift_jis(sample) {
+        SHIFT_JIS
+    } else {
+        WINDOWS_1252
+    }
+}
+
+fn mostly_invalid_utf8(sample: &[u8]) -> bool {
+    let non_ascii_chars = |s: &str| s.chars().filter(|c| !c.is_ascii()).count();
+    let mut valid_chars = 0;
+    let mut invalid_sequences = 0;
+    let mut rest = sample;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(s) => {
+                valid_chars += non_ascii_chars(s);
+                break;
+            }
+            Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                valid_chars += std::str::from_utf8(valid).map_or(0, non_ascii_chars);
+                match e.error_len() {
+                    Some(len) => {
+                        invalid_sequences += 1;
+                        rest = &invalid[len..];
+                    }
+                    // the sample can end within a character
+                    None => break,
+                }
+            }
+        }
+    }
+    invalid_sequences >= MIN_INVALID_SEQUENCES && invalid_sequences > valid_chars
+}
+
+fn detect_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let zeros_at = |offset: usize| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .take(pairs)
+            .filter(|b| **b == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    let mostly = |n: usize| n * 10 >= pairs * 4;
+    let hardly = |n: usize| n * 20 <= pairs;
+    if mostly(odd) && hardly(even) {
+        Some(UTF_16LE)
+    } else if mostly(even) && hardly(odd) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Latin-1 text often decodes as Shift_JIS as well, so all decoded
+/// characters have to be ASCII or japanese
+fn is_shift_jis(sample: &[u8]) -> bool {
+    // the last line could end within a character
+    let end = sample
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(sample.len(), |p| p + 1);
+    match SHIFT_JIS.decode_without_bom_handling_and_without_replacement(&sample[..end]) {
+        Some(decoded) => decoded.chars().all(|c| c.is_ascii() || is_japanese(c)),
+        None => false,
+    }
+}
+
+fn is_japanese(c: char) -> bool {
+    match c as u32 {
+        // punctuation, hiragana and katakana
+        0x3000..=0x30ff => true,
+        // CJK ideographs
+        0x4e00..=0x9fff => true,
+        // fullwidth ASCII
+        0xff01..=0xff5e => true,
+        _ => false,
+    }
+}
+
+/// decodes `reader` to UTF-8, a BOM takes precedence over `encoding`
+pub fn text_reader<R: Read>(reader: R, encoding: &'static Encoding) -> TextReader<R> {
+    DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .bom_override(true)
+        .strip_bom(true)
+        .build(reader)
+}
+
+/// opens a (compressed) text file decoded with the given encoding, or the
+/// detected one if there is none
+pub fn open_text_input(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<TextReader<InputReader>, Error> {
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => detect_encoding(path)?,
+    };
+    Ok(text_reader(open_input(path)?, encoding))
+}
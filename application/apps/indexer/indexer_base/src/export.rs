@@ -1,22 +1,69 @@
 use crate::chunks::ChunkResults;
 use crate::config::SectionConfig;
+use crate::encoding::open_text_input;
 use crate::progress::{IndexingProgress, Notification, Severity};
-use crate::utils::restore_line;
+use crate::redaction::Redactor;
+use crate::utils::{decode_newlines, restore_line};
 use crossbeam_channel as cc;
 use failure::{err_msg, Error};
 use std::fs;
-use std::io::{BufRead, BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, Write};
 
 use std::path::PathBuf;
 
 /// will save sections of a file that is based on lines (newlines)
-/// and remove session file data if required (if `was_session_file` is true)
+/// and remove session file data if required (if `was_session_file` is true).
+/// `grouped_records` restores the lines of records in a session file that was
+/// indexed with record grouping.
 pub fn export_file_line_based(
     file_path: PathBuf,
     destination_path: PathBuf,
     sections: SectionConfig,
     was_session_file: bool,
+    grouped_records: bool,
     update_channel: cc::Sender<ChunkResults>,
+) -> Result<(), Error> {
+    export_lines(
+        file_path,
+        destination_path,
+        sections,
+        was_session_file,
+        grouped_records,
+        update_channel,
+        None,
+    )
+}
+
+/// like `export_file_line_based` but every line is passed through the
+/// `redactor` (which keeps the summary of what was replaced)
+pub fn export_file_line_based_redacted(
+    file_path: PathBuf,
+    destination_path: PathBuf,
+    sections: SectionConfig,
+    was_session_file: bool,
+    grouped_records: bool,
+    update_channel: cc::Sender<ChunkResults>,
+    redactor: &mut Redactor,
+) -> Result<(), Error> {
+    export_lines(
+        file_path,
+        destination_path,
+        sections,
+        was_session_file,
+        grouped_records,
+        update_channel,
+        Some(redactor),
+    )
+}
+
+fn export_lines(
+    file_path: PathBuf,
+    destination_path: PathBuf,
+    sections: SectionConfig,
+    was_session_file: bool,
+    grouped_records: bool,
+    update_channel: cc::Sender<ChunkResults>,
+    mut redactor: Option<&mut Redactor>,
 ) -> Result<(), Error> {
     trace!(
         "export_file_line_based {:?} to file: {:?}, exporting {:?} ({})",
@@ -29,21 +76,30 @@ pub fn export_file_line_based(
             "no cleanup required"
         }
     );
+    // only grouped records have encoded newlines, the same character can be
+    // part of other lines (e.g. of dlt messages)
+    let decode_records = was_session_file && grouped_records;
     if file_path.exists() {
         trace!("found file to export: {:?}", &file_path);
-        let f = fs::File::open(&file_path)?;
-        let reader = &mut std::io::BufReader::new(f);
+        // session files are UTF-8, original files are decoded
+        let reader: Box<dyn BufRead> = if was_session_file {
+            Box::new(BufReader::new(fs::File::open(&file_path)?))
+        } else {
+            Box::new(BufReader::new(open_text_input(&file_path, None)?))
+        };
         let out_file = std::fs::File::create(destination_path)?;
         let lines_iter = &mut reader.lines();
         let mut out_writer = BufWriter::new(out_file);
         // check if we have to export the whole file
         if sections.sections.is_empty() {
             for elem in lines_iter {
-                if was_session_file {
-                    out_writer.write_fmt(format_args!("{}\n", restore_line(&elem?)))?;
-                } else {
-                    out_writer.write_fmt(format_args!("{}\n", elem?))?;
-                }
+                write_line(
+                    &mut out_writer,
+                    &elem?,
+                    was_session_file,
+                    decode_records,
+                    &mut redactor,
+                )?;
             }
         } else {
             let mut index = 0usize;
@@ -53,11 +109,13 @@ pub fn export_file_line_based(
                 let section_size = section.last_line - section.first_line + 1;
                 let elem_iter = lines_iter.skip(forward).take(section_size);
                 for elem in elem_iter {
-                    if was_session_file {
-                        out_writer.write_fmt(format_args!("{}\n", restore_line(&elem?)))?;
-                    } else {
-                        out_writer.write_fmt(format_args!("{}\n", elem?))?;
-                    }
+                    write_line(
+                        &mut out_writer,
+                        &elem?,
+                        was_session_file,
+                        decode_records,
+                        &mut redactor,
+                    )?;
                 }
                 index += forward;
                 index += section_size;
@@ -76,3 +134,24 @@ pub fn export_file_line_based(
         Err(err_msg(reason))
     }
 }
+
+fn write_line<W: Write>(
+    out_writer: &mut W,
+    line: &str,
+    was_session_file: bool,
+    decode_records: bool,
+    redactor: &mut Option<&mut Redactor>,
+) -> Result<(), Error> {
+    let restored = if decode_records {
+        decode_newlines(restore_line(line))
+    } else if was_session_file {
+        restore_line(line).into()
+    } else {
+        line.into()
+    };
+    match redactor {
+        Some(r) => out_writer.write_fmt(format_args!("{}\n", r.redact_line(&restored)))?,
+        None => out_writer.write_fmt(format_args!("{}\n", restored))?,
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::archive;
+use crate::compression::{detect_compression, Compression};
+use crossbeam_channel as cc;
+use failure::{err_msg, Error};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// how often a followed file is checked for new content
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 250;
+
+/// what happened to a followed file since it was opened or last checked
+#[derive(Debug, PartialEq)]
+pub enum SourceChange {
+    Unchanged,
+    /// the file got shorter, it is read again from the start
+    Truncated,
+    /// a new file was created under the same path (log rotation), `rest` is
+    /// what was still appended to the old file before the new one is read
+    Rotated {
+        rest: Vec<u8>,
+    },
+}
+
+/// a file that is still being written, reads return 0 at the current end
+/// but can deliver more bytes later
+pub struct FollowedFile {
+    path: PathBuf,
+    file: fs::File,
+    file_id: Option<u64>,
+    position: u64,
+}
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.ino() ^ meta.dev().rotate_left(32))
+}
+
+/// without inodes the creation time tells if the file was replaced
+#[cfg(not(unix))]
+fn file_id(meta: &fs::Metadata) -> Option<u64> {
+    meta.created()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as u64)
+}
+
+impl FollowedFile {
+    /// only plain files can be followed (no compressed files or archive entries)
+    pub fn open(path: &Path) -> Result<FollowedFile, Error> {
+        if archive::split_entry_path(path).is_some() {
+            return Err(err_msg(format!("cannot follow archive entry {:?}", path)));
+        }
+        if detect_compression(path)? != Compression::None {
+            return Err(err_msg(format!("cannot follow compressed file {:?}", path)));
+        }
+        let file = fs::File::open(path)?;
+        let file_id = file_id(&file.metadata()?);
+        Ok(FollowedFile {
+            path: path.to_path_buf(),
+            file,
+            file_id,
+            position: 0,
+        })
+    }
+
+    /// bytes read from the current file (starts at 0 after truncation or rotation)
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// has to be called when a read returned 0, reopens the file if it was
+    /// truncated or rotated
+    pub fn check_source(&mut self) -> Result<SourceChange, Error> {
+        let meta = match fs::metadata(&self.path) {
+            Ok(meta) => meta,
+            // rotated away and the new file is not created yet
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(SourceChange::Unchanged)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let current_id = file_id(&meta);
+        if current_id != self.file_id {
+            let mut rest = vec![];
+            self.file.read_to_end(&mut rest)?;
+            self.file = fs::File::open(&self.path)?;
+            self.file_id = file_id(&self.file.metadata()?);
+            self.position = 0;
+            debug!(
+                "{:?} was rotated ({} bytes left in old file)",
+                self.path,
+                rest.len()
+            );
+            return Ok(SourceChange::Rotated { rest });
+        }
+        if meta.len() < self.position {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            debug!("{:?} was truncated", self.path);
+            return Ok(SourceChange::Truncated);
+        }
+        Ok(SourceChange::Unchanged)
+    }
+}
+
+impl Read for FollowedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.file.read(buf)?;
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+/// waits for `poll_interval`, returns true if a stop was requested meanwhile
+pub fn wait_for_more(
+    poll_interval: Duration,
+    shutdown_receiver: &Option<cc::Receiver<()>>,
+) -> bool {
+    match shutdown_receiver.as_ref() {
+        Some(rx) => match rx.recv_timeout(poll_interval) {
+            Ok(_) | Err(cc::RecvTimeoutError::Disconnected) => {
+                info!("shutdown received while following");
+                true
+            }
+            Err(cc::RecvTimeoutError::Timeout) => false,
+        },
+        None => {
+            std::thread::sleep(poll_interval);
+            false
+        }
+    }
+}
@@ -9,14 +9,21 @@
 // Dissemination of this information or reproduction of this material
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
+pub mod archive;
 pub mod chunks;
+pub mod compression;
 pub mod config;
+pub mod encoding;
 pub mod error_reporter;
 pub mod export;
+pub mod follow;
 pub mod progress;
+pub mod redaction;
 pub mod timedline;
 pub mod utils;
 
+#[macro_use]
+extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
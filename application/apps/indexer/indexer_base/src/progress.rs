@@ -1,6 +1,7 @@
-use serde::Serialize;
 use crate::chunks::*;
+use crate::compression::ConsumedBytes;
 use crossbeam_channel as cc;
+use serde::Serialize;
 
 #[derive(Serialize, Debug, PartialEq)]
 pub enum Severity {
@@ -36,6 +37,7 @@ pub struct ProgressReporter {
     processed_bytes: usize,
     progress_percentage: usize,
     total: usize,
+    read_bytes: Option<ConsumedBytes>,
 }
 impl ProgressReporter {
     pub fn new(total: usize, update_channel: cc::Sender<ChunkResults>) -> ProgressReporter {
@@ -44,10 +46,20 @@ impl ProgressReporter {
             processed_bytes: 0,
             progress_percentage: 0,
             total,
+            read_bytes: None,
         }
     }
+    /// take the progress from the bytes read from the file instead of the bytes
+    /// passed to `make_progress` (needed for compressed files, where those are
+    /// decompressed bytes)
+    pub fn track_read_bytes(&mut self, read_bytes: Option<ConsumedBytes>) {
+        self.read_bytes = read_bytes;
+    }
     pub fn make_progress(&mut self, consumed: usize) {
-        self.processed_bytes += consumed;
+        match &self.read_bytes {
+            Some(read_bytes) => self.processed_bytes = read_bytes.get(),
+            None => self.processed_bytes += consumed,
+        }
         let new_progress_percentage: usize =
             (self.processed_bytes as f64 / self.total as f64 * 100.0).round() as usize;
         if new_progress_percentage != self.progress_percentage {
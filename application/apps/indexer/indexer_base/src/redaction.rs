@@ -0,0 +1,344 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use failure::{err_msg, Error};
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const DEFAULT_MASK: &str = "***";
+/// name of the capture group that limits the redaction to a part of the match
+pub const VALUE_GROUP: &str = "value";
+/// distinct values with a pseudonym that are counted per rule, more are not
+/// counted to keep the memory bounded
+pub const MAX_COUNTED_PSEUDONYMS: usize = 100_000;
+
+/// what happens to a match
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionAction {
+    /// replace with the mask
+    Mask,
+    /// replace with a pseudonym that is the same for the same input and key
+    Pseudonymize,
+}
+
+fn default_action() -> RedactionAction {
+    RedactionAction::Mask
+}
+
+/// A rule matches either a regex (only the `value` group if present) or,
+/// without a pattern, a whole DLT argument.
+/// `app_id`, `context_id` and `argument` restrict a rule to DLT messages
+/// (such rules are not used for text lines).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RedactionRule {
+    pub name: String,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default = "default_action")]
+    pub action: RedactionAction,
+    #[serde(default)]
+    pub app_id: Option<String>,
+    #[serde(default)]
+    pub context_id: Option<String>,
+    /// index of the argument in a verbose DLT message
+    #[serde(default)]
+    pub argument: Option<usize>,
+}
+
+impl RedactionRule {
+    pub fn new(name: &str, pattern: &str, action: RedactionAction) -> Self {
+        RedactionRule {
+            name: name.to_string(),
+            pattern: Some(pattern.to_string()),
+            action,
+            app_id: None,
+            context_id: None,
+            argument: None,
+        }
+    }
+    fn is_dlt_only(&self) -> bool {
+        self.app_id.is_some() || self.context_id.is_some() || self.argument.is_some()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RedactionConfig {
+    pub rules: Vec<RedactionRule>,
+    /// secret for the pseudonyms, needed if a rule pseudonymizes
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default = "default_mask")]
+    pub mask: String,
+}
+
+fn default_mask() -> String {
+    DEFAULT_MASK.to_string()
+}
+
+impl Default for RedactionConfig {
+    /// rules for VINs, GPS coordinates, IPv4 addresses and user names
+    fn default() -> Self {
+        RedactionConfig {
+            rules: vec![
+                // VIN alphabet (without I, O and Q), the manufacturer code
+                // (first 3 characters) contains a letter
+                RedactionRule::new(
+                    "vin",
+                    r"\b(?:[A-HJ-NPR-Z][A-HJ-NPR-Z0-9]{2}|\d[A-HJ-NPR-Z][A-HJ-NPR-Z0-9]|\d{2}[A-HJ-NPR-Z])[A-HJ-NPR-Z0-9]{14}\b",
+                    RedactionAction::Mask,
+                ),
+                RedactionRule::new(
+                    "gps",
+                    r"-?\d{1,3}\.\d{4,}\s*,\s*-?\d{1,3}\.\d{4,}",
+                    RedactionAction::Mask,
+                ),
+                RedactionRule::new(
+                    "ip",
+                    r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+                    RedactionAction::Mask,
+                ),
+                RedactionRule::new(
+                    "user",
+                    r#"(?i)\b(?:user(?:name)?|login)\s*[=:]\s*(?P<value>[^\s,;)\]"']+)"#,
+                    RedactionAction::Mask,
+                ),
+            ],
+            key: None,
+            mask: default_mask(),
+        }
+    }
+}
+
+pub fn read_redaction_config(path: &Path) -> Result<RedactionConfig, Error> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content).map_err(|e| {
+        err_msg(format!(
+            "could not parse redaction config {:?}: {}",
+            path, e
+        ))
+    })
+}
+
+/// what was redacted during an export
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RedactionSummary {
+    /// lines or messages that were looked at
+    pub inspected: usize,
+    /// lines or messages with at least one replacement
+    pub redacted: usize,
+    /// messages that were left out because they cannot be redacted
+    /// (non-verbose and control DLT messages)
+    pub dropped: usize,
+    /// number of replacements per rule
+    pub replacements: BTreeMap<String, usize>,
+    /// number of distinct values that got a pseudonym per rule (at most
+    /// `MAX_COUNTED_PSEUDONYMS`)
+    pub pseudonyms: BTreeMap<String, usize>,
+}
+
+pub fn write_redaction_summary(summary: &RedactionSummary, path: &Path) -> Result<(), Error> {
+    let out = fs::File::create(path)?;
+    serde_json::to_writer_pretty(out, summary)?;
+    Ok(())
+}
+
+/// a rule without a scope applies to all messages
+fn in_scope(scope: &Option<String>, id: Option<&str>) -> bool {
+    match scope {
+        Some(expected) => Some(expected.as_str()) == id,
+        None => true,
+    }
+}
+
+struct CompiledRule {
+    rule: RedactionRule,
+    regex: Option<Regex>,
+}
+
+/// applies the rules of a `RedactionConfig` and keeps track of what was redacted
+pub struct Redactor {
+    rules: Vec<CompiledRule>,
+    key: Vec<u8>,
+    mask: String,
+    summary: RedactionSummary,
+    /// pseudonym codes handed out per rule (to count distinct values)
+    seen: BTreeMap<String, rustc_hash::FxHashSet<u64>>,
+}
+
+impl Redactor {
+    pub fn new(config: RedactionConfig) -> Result<Self, Error> {
+        let needs_key = config
+            .rules
+            .iter()
+            .any(|r| r.action == RedactionAction::Pseudonymize);
+        let key = match config.key {
+            Some(key) if !key.is_empty() => key.into_bytes(),
+            _ if needs_key => return Err(err_msg("pseudonymisation needs a key")),
+            _ => vec![],
+        };
+        let mut rules = vec![];
+        for rule in config.rules {
+            let regex = match &rule.pattern {
+                Some(p) => Some(
+                    Regex::new(p)
+                        .map_err(|e| err_msg(format!("invalid pattern in {}: {}", rule.name, e)))?,
+                ),
+                None if !rule.is_dlt_only() => {
+                    return Err(err_msg(format!(
+                        "rule {} needs a pattern or a dlt scope",
+                        rule.name
+                    )))
+                }
+                None => None,
+            };
+            rules.push(CompiledRule { rule, regex });
+        }
+        Ok(Redactor {
+            rules,
+            key,
+            mask: config.mask,
+            summary: RedactionSummary::default(),
+            seen: BTreeMap::new(),
+        })
+    }
+
+    pub fn summary(&self) -> &RedactionSummary {
+        &self.summary
+    }
+
+    /// redacts one line of a text file
+    pub fn redact_line<'a>(&mut self, line: &'a str) -> Cow<'a, str> {
+        self.summary.inspected += 1;
+        let mut result = Cow::Borrowed(line);
+        for i in 0..self.rules.len() {
+            if self.rules[i].rule.is_dlt_only() {
+                continue;
+            }
+            if let Some(replaced) = self.apply(i, &result) {
+                result = Cow::Owned(replaced);
+            }
+        }
+        if let Cow::Owned(_) = result {
+            self.summary.redacted += 1;
+        }
+        result
+    }
+
+    /// has to be called once for every DLT message before its arguments are
+    /// redacted with `redact_argument`
+    pub fn start_message(&mut self) {
+        self.summary.inspected += 1;
+    }
+
+    /// has to be called after all arguments of a message are redacted
+    pub fn finish_message(&mut self, was_redacted: bool) {
+        if was_redacted {
+            self.summary.redacted += 1;
+        }
+    }
+
+    /// counts a message that is left out because it cannot be redacted
+    pub fn drop_message(&mut self) {
+        self.summary.inspected += 1;
+        self.summary.dropped += 1;
+    }
+
+    pub fn mask(&self) -> &str {
+        &self.mask
+    }
+
+    /// redacts the (formatted) value of an argument of a DLT message,
+    /// `None` if nothing has to be replaced
+    pub fn redact_argument(
+        &mut self,
+        app_id: Option<&str>,
+        context_id: Option<&str>,
+        index: usize,
+        value: &str,
+    ) -> Option<String> {
+        let mut result: Option<String> = None;
+        for i in 0..self.rules.len() {
+            let rule = &self.rules[i].rule;
+            let applies = in_scope(&rule.app_id, app_id)
+                && in_scope(&rule.context_id, context_id)
+                && rule.argument.unwrap_or(index) == index;
+            if !applies {
+                continue;
+            }
+            let current = result.as_ref().map_or(value, |r| r.as_str());
+            if let Some(replaced) = self.apply(i, current) {
+                result = Some(replaced);
+            }
+        }
+        result
+    }
+
+    fn apply(&mut self, rule_index: usize, input: &str) -> Option<String> {
+        let (name, action) = {
+            let rule = &self.rules[rule_index].rule;
+            (rule.name.clone(), rule.action)
+        };
+        let ranges: Vec<(usize, usize)> = match &self.rules[rule_index].regex {
+            None => vec![(0, input.len())],
+            Some(regex) => regex
+                .captures_iter(input)
+                .filter_map(|c| c.name(VALUE_GROUP).or_else(|| c.get(0)))
+                .filter(|m| !m.as_str().is_empty())
+                .map(|m| (m.start(), m.end()))
+                .collect(),
+        };
+        if ranges.is_empty() {
+            return None;
+        }
+        let mut output = String::with_capacity(input.len());
+        let mut last = 0;
+        for (start, end) in &ranges {
+            output.push_str(&input[last..*start]);
+            let replacement = match action {
+                RedactionAction::Mask => self.mask.clone(),
+                RedactionAction::Pseudonymize => self.pseudonym(&name, &input[*start..*end]),
+            };
+            output.push_str(&replacement);
+            last = *end;
+        }
+        output.push_str(&input[last..]);
+        *self.summary.replacements.entry(name).or_insert(0) += ranges.len();
+        Some(output)
+    }
+
+    /// `<rule>_<first 8 bytes of HMAC-SHA256 as hex>`
+    fn pseudonym(&mut self, rule_name: &str, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_varkey(&self.key).expect("hmac accepts any key length");
+        mac.input(rule_name.as_bytes());
+        mac.input(&[0]);
+        mac.input(value.as_bytes());
+        let code = mac.result().code();
+        let hex: String = code[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        let seen = self.seen.entry(rule_name.to_string()).or_default();
+        if seen.len() < MAX_COUNTED_PSEUDONYMS {
+            let mut id = [0u8; 8];
+            id.copy_from_slice(&code[..8]);
+            if seen.insert(u64::from_le_bytes(id)) {
+                self.summary
+                    .pseudonyms
+                    .insert(rule_name.to_string(), seen.len());
+            }
+        }
+        format!("{}_{}", rule_name, hex)
+    }
+}
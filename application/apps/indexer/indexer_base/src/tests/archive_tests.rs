@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use crate::archive::*;
+    use crate::compression::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::path::Path;
+    use tempdir::TempDir;
+
+    fn text_content() -> Vec<u8> {
+        (0..500)
+            .map(|i| format!("2020-03-01 10:00:{:02} line {} of the log\n", i % 60, i))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    fn dlt_content() -> Vec<u8> {
+        let mut content = b"DLT\x01".to_vec();
+        content.extend_from_slice(&[0u8; 60]);
+        content
+    }
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn read_input(path: &Path) -> (Vec<u8>, usize) {
+        let mut input = open_input(path).expect("could not open entry");
+        let consumed = input.consumed();
+        let mut content = vec![];
+        input.read_to_end(&mut content).unwrap();
+        (content, consumed.get())
+    }
+
+    #[test]
+    fn test_tar_archive_entries() {
+        let tmp_dir = TempDir::new("archive").expect("could not create temp dir");
+        let archive_path = tmp_dir.path().join("bundle.tar.gz");
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            fs::File::create(&archive_path).unwrap(),
+            flate2::Compression::default(),
+        ));
+        let add = |builder: &mut tar::Builder<_>, name: &str, content: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content).unwrap();
+        };
+        add(&mut builder, "logs/system.log", &text_content());
+        add(&mut builder, "logs/trace.dlt", &dlt_content());
+        add(&mut builder, "logs/old.log.gz", &gzip(&text_content()));
+        builder.into_inner().unwrap().finish().unwrap();
+
+        assert_eq!(
+            Some(ArchiveFormat::Tar),
+            detect_archive(&archive_path).unwrap()
+        );
+        let entries = list_archive(&archive_path).expect("could not list archive");
+        let listed: Vec<(&str, FileType, Compression)> = entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.file_type, e.compression))
+            .collect();
+        assert_eq!(
+            vec![
+                ("logs/system.log", FileType::Text, Compression::None),
+                ("logs/trace.dlt", FileType::Dlt, Compression::None),
+                ("logs/old.log.gz", FileType::Text, Compression::Gzip),
+            ],
+            listed
+        );
+
+        let text_path = entries[0].source_path(&archive_path);
+        assert_eq!(text_content().len() as u64, input_size(&text_path).unwrap());
+        assert_eq!(
+            (text_content(), text_content().len()),
+            read_input(&text_path)
+        );
+        let (dlt, _) = read_input(&entries[1].source_path(&archive_path));
+        assert_eq!(dlt_content(), dlt);
+        let gz_path = entries[2].source_path(&archive_path);
+        let (unpacked, consumed) = read_input(&gz_path);
+        assert_eq!(text_content(), unpacked);
+        assert_eq!(entries[2].stored_size as usize, consumed);
+        assert_eq!(Compression::Gzip, detect_compression(&gz_path).unwrap());
+
+        assert!(open_input(&entry_path(&archive_path, "logs/missing.log")).is_err());
+        assert!(input_exists(&text_path));
+        assert!(!input_exists(&entry_path(
+            &archive_path,
+            "logs/missing.log"
+        )));
+        assert!(!input_exists(&entry_path(
+            &tmp_dir.path().join("missing.tar.gz"),
+            "logs/system.log"
+        )));
+    }
+
+    #[test]
+    fn test_plain_tar_entries() {
+        let tmp_dir = TempDir::new("archive").expect("could not create temp dir");
+        let archive_path = tmp_dir.path().join("bundle.tar");
+        let write_tar = |entries: &[(&str, &[u8])]| {
+            let mut builder = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+            for (name, content) in entries {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder.append_data(&mut header, name, *content).unwrap();
+            }
+            builder.finish().unwrap();
+        };
+        write_tar(&[
+            ("a.log", b"first a\n"),
+            ("b.log", b"only b\n"),
+            ("a.log", b"second a\n"),
+        ]);
+        let b_path = entry_path(&archive_path, "b.log");
+        assert_eq!(7, input_size(&b_path).unwrap());
+        assert_eq!((b"only b\n".to_vec(), 7), read_input(&b_path));
+        // the first entry of a name is used
+        let (a, _) = read_input(&entry_path(&archive_path, "a.log"));
+        assert_eq!(b"first a\n".to_vec(), a);
+
+        // a modified archive is scanned again
+        write_tar(&[("c.log", b"c\n"), ("b.log", b"changed b\n")]);
+        assert_eq!(10, input_size(&b_path).unwrap());
+        assert_eq!((b"changed b\n".to_vec(), 10), read_input(&b_path));
+        assert!(open_input(&entry_path(&archive_path, "a.log")).is_err());
+    }
+
+    #[test]
+    fn test_zip_archive_entries() {
+        let tmp_dir = TempDir::new("archive").expect("could not create temp dir");
+        let archive_path = tmp_dir.path().join("bundle.zip");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&archive_path).unwrap());
+        let stored =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        let deflated =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        writer.add_directory("ecu1/", stored).unwrap();
+        writer.start_file("ecu1/trace.dlt", stored).unwrap();
+        writer.write_all(&dlt_content()).unwrap();
+        writer.start_file("ecu1/system.log", deflated).unwrap();
+        writer.write_all(&text_content()).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            Some(ArchiveFormat::Zip),
+            detect_archive(&archive_path).unwrap()
+        );
+        let entries = list_archive(&archive_path).expect("could not list archive");
+        assert_eq!(2, entries.len());
+        assert_eq!(FileType::Dlt, entries[0].file_type);
+        assert_eq!(FileType::Text, entries[1].file_type);
+        assert_eq!(text_content().len() as u64, entries[1].size);
+        assert!(entries[1].stored_size < entries[1].size);
+
+        let (dlt, consumed) = read_input(&entries[0].source_path(&archive_path));
+        assert_eq!(dlt_content(), dlt);
+        assert_eq!(dlt_content().len(), consumed);
+        let text_path = entries[1].source_path(&archive_path);
+        let input = open_input(&text_path).unwrap();
+        assert!(input.is_compressed());
+        let (text, consumed) = read_input(&text_path);
+        assert_eq!(text_content(), text);
+        assert_eq!(entries[1].stored_size as usize, consumed);
+        assert_eq!(entries[1].stored_size, input_size(&text_path).unwrap());
+
+        assert_eq!(None, split_entry_path(&archive_path));
+        assert!(open_input(&entry_path(&archive_path, "ecu2/trace.dlt")).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use crate::compression::*;
+    use pretty_assertions::assert_eq;
+    use std::io::{Read, Write};
+    use tempdir::TempDir;
+
+    fn compress(content: &[u8], compression: Compression) -> Vec<u8> {
+        match compression {
+            Compression::None => content.to_vec(),
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Zstd => zstd::stream::encode_all(content, 3).unwrap(),
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(content).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    const ALL: [Compression; 5] = [
+        Compression::None,
+        Compression::Gzip,
+        Compression::Zstd,
+        Compression::Xz,
+        Compression::Bzip2,
+    ];
+
+    #[test]
+    fn test_detect_compression() {
+        for compression in ALL.iter() {
+            let compressed = compress(b"some log line\n", *compression);
+            assert_eq!(*compression, Compression::from_magic(&compressed));
+        }
+        assert_eq!(
+            Compression::None,
+            Compression::from_magic(b"BZh is no bzip2")
+        );
+        assert_eq!(Compression::None, Compression::from_magic(b""));
+    }
+
+    #[test]
+    fn test_open_compressed_input() {
+        let tmp_dir = TempDir::new("compression").expect("could not create temp dir");
+        let content: Vec<u8> = (0..2000)
+            .map(|i| format!("line {} of the log\n", i))
+            .collect::<String>()
+            .into_bytes();
+        for compression in ALL.iter() {
+            let path = tmp_dir.path().join(format!("{:?}.log", compression));
+            let compressed = compress(&content, *compression);
+            std::fs::write(&path, &compressed).unwrap();
+            let mut input = open_input(&path).expect("could not open input");
+            assert_eq!(*compression, input.compression());
+            let read_bytes = input.consumed();
+            let mut decompressed = vec![];
+            input.read_to_end(&mut decompressed).unwrap();
+            assert_eq!(content, decompressed);
+            assert_eq!(compressed.len(), read_bytes.get());
+            assert_eq!(
+                *compression != Compression::None,
+                input.compressed_input().is_some()
+            );
+        }
+    }
+
+    /// gives its content, fails once and then ends
+    struct FailingReader {
+        content: std::io::Cursor<Vec<u8>>,
+        failed: bool,
+    }
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.content.read(buf)?;
+            if n == 0 && !self.failed {
+                self.failed = true;
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "broken input",
+                ));
+            }
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_error_after_peeked_bytes_is_kept() {
+        // only the peeked bytes, the error happens when the prefix is read
+        let failing = FailingReader {
+            content: std::io::Cursor::new(b"line 1".to_vec()),
+            failed: false,
+        };
+        let (_, mut reader) = peek_compression(Box::new(failing)).unwrap();
+        let mut buf = [0u8; 64];
+        assert_eq!(6, reader.read(&mut buf).unwrap());
+        assert_eq!(b"line 1", &buf[..6]);
+        let e = reader.read(&mut buf).expect_err("the error should be kept");
+        assert_eq!(std::io::ErrorKind::InvalidData, e.kind());
+        assert_eq!(0, reader.read(&mut buf).unwrap());
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use crate::encoding::*;
+    use encoding_rs::{SHIFT_JIS, UTF_16BE, UTF_16LE, WINDOWS_1252};
+    use pretty_assertions::assert_eq;
+    use std::io::Read;
+    use tempdir::TempDir;
+
+    const TEXT: &str =
+        "2020-03-14 10:00:00.000 INFO started\n2020-03-14 10:00:01.000 WARN no connection\n";
+
+    fn utf16(text: &str, little_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|c| {
+                if little_endian {
+                    c.to_le_bytes().to_vec()
+                } else {
+                    c.to_be_bytes().to_vec()
+                }
+            })
+            .collect()
+    }
+
+    fn decode(bytes: &[u8], encoding: &'static Encoding) -> String {
+        let mut decoded = String::new();
+        text_reader(bytes, encoding)
+            .read_to_string(&mut decoded)
+            .unwrap();
+        decoded
+    }
+
+    #[test]
+    fn test_encoding_for_label() {
+        assert_eq!(UTF_8, encoding_for_label("utf8").unwrap());
+        assert_eq!(SHIFT_JIS, encoding_for_label("Shift_JIS").unwrap());
+        assert_eq!(UTF_16LE, encoding_for_label("utf-16le").unwrap());
+        assert_eq!(WINDOWS_1252, encoding_for_label("latin1").unwrap());
+        assert!(encoding_for_label("no-such-encoding").is_err());
+    }
+
+    #[test]
+    fn test_detect_encoding_in_sample() {
+        assert_eq!(UTF_8, detect_encoding_in_sample(TEXT.as_bytes()));
+        assert_eq!(UTF_8, detect_encoding_in_sample("Größe: 3 µs\n".as_bytes()));
+        // the sample ends within a character
+        assert_eq!(UTF_8, detect_encoding_in_sample(&"Größe".as_bytes()[..3]));
+        assert_eq!(UTF_16LE, detect_encoding_in_sample(&utf16(TEXT, true)));
+        assert_eq!(UTF_16BE, detect_encoding_in_sample(&utf16(TEXT, false)));
+        let (sjis, _, _) = SHIFT_JIS.encode("2020-03-14 10:00:00.000 接続がありません\n");
+        assert_eq!(SHIFT_JIS, detect_encoding_in_sample(&sjis));
+        let latin1_text = "2020-03-14 10:00:00.000 Größe überschritten, Länge ungültig\n".repeat(2);
+        let (latin1, _, _) = WINDOWS_1252.encode(&latin1_text);
+        assert_eq!(WINDOWS_1252, detect_encoding_in_sample(&latin1));
+        // a few invalid bytes don't change the encoding
+        assert_eq!(UTF_8, detect_encoding_in_sample(b"A\xC0\x80B\nC\n"));
+        let mut broken = "Größe: 3 µs\n".repeat(10).into_bytes();
+        broken.extend(latin1.iter());
+        assert_eq!(UTF_8, detect_encoding_in_sample(&broken));
+        // a BOM wins
+        let mut with_bom = vec![0xfe, 0xff];
+        with_bom.extend(utf16("a", false));
+        assert_eq!(UTF_16BE, detect_encoding_in_sample(&with_bom));
+    }
+
+    #[test]
+    fn test_text_reader() {
+        assert_eq!(TEXT, decode(&utf16(TEXT, true), UTF_16LE));
+        let (sjis, _, _) = SHIFT_JIS.encode("接続がありません");
+        assert_eq!("接続がありません", decode(&sjis, SHIFT_JIS));
+        // invalid sequences are replaced
+        assert_eq!("a\u{FFFD}b", decode(b"a\xffb", UTF_8));
+        // the BOM is stripped and overrides the encoding
+        let mut with_bom = vec![0xff, 0xfe];
+        with_bom.extend(utf16("abc", true));
+        assert_eq!("abc", decode(&with_bom, UTF_8));
+    }
+
+    #[test]
+    fn test_open_text_input() {
+        let tmp_dir = TempDir::new("encoding").expect("could not create temp dir");
+        let path = tmp_dir.path().join("utf16.log");
+        std::fs::write(&path, utf16(TEXT, true)).unwrap();
+        assert_eq!(UTF_16LE, detect_encoding(&path).unwrap());
+        let mut decoded = String::new();
+        open_text_input(&path, None)
+            .unwrap()
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(TEXT, decoded);
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use crate::chunks::ChunkResults;
+    use crate::config::SectionConfig;
+    use crate::export::export_file_line_based;
+    use crate::utils::{NEWLINE_SENTINAL, PLUGIN_ID_SENTINAL};
+    use crossbeam_channel as cc;
+    use pretty_assertions::assert_eq;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_export_session_restores_only_grouped_records() {
+        let tmp_dir = TempDir::new("export").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("session.txt");
+        let out_path = tmp_dir.path().join("out.txt");
+        std::fs::write(
+            &in_path,
+            format!(
+                "first{n}second{p}TAG{p}0\nplain{p}TAG{p}1\n",
+                n = NEWLINE_SENTINAL,
+                p = PLUGIN_ID_SENTINAL
+            ),
+        )
+        .unwrap();
+        let export = |grouped_records: bool| {
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+            export_file_line_based(
+                in_path.clone(),
+                out_path.clone(),
+                SectionConfig { sections: vec![] },
+                true,
+                grouped_records,
+                tx,
+            )
+            .expect("export failed");
+            std::fs::read_to_string(&out_path).unwrap()
+        };
+        assert_eq!("first\nsecond\nplain\n", export(true));
+        // e.g. dlt messages use the same character for newlines in arguments
+        assert_eq!(
+            format!("first{}second\nplain\n", NEWLINE_SENTINAL),
+            export(false)
+        );
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use crate::chunks::ChunkFactory;
+    use crate::follow::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::io::{Read, Write};
+    use tempdir::TempDir;
+
+    fn read_available(followed: &mut FollowedFile) -> String {
+        let mut content = String::new();
+        followed.read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn test_followed_file_changes() {
+        let tmp_dir = TempDir::new("follow").expect("could not create temp dir");
+        let path = tmp_dir.path().join("app.log");
+        fs::write(&path, "first\n").unwrap();
+        let mut followed = FollowedFile::open(&path).expect("could not follow");
+        assert_eq!("first\n", read_available(&mut followed));
+        assert_eq!(SourceChange::Unchanged, followed.check_source().unwrap());
+
+        let mut writer = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        writer.write_all(b"second\n").unwrap();
+        assert_eq!("second\n", read_available(&mut followed));
+        assert_eq!(13, followed.position());
+
+        fs::write(&path, "x\n").unwrap();
+        assert_eq!(SourceChange::Truncated, followed.check_source().unwrap());
+        assert_eq!("x\n", read_available(&mut followed));
+
+        // rotation while the old file still gets some bytes
+        fs::rename(&path, tmp_dir.path().join("app.log.1")).unwrap();
+        writer.write_all(b"late\n").unwrap();
+        fs::write(&path, "rotated\n").unwrap();
+        assert_eq!(
+            SourceChange::Rotated {
+                rest: b"late\n".to_vec()
+            },
+            followed.check_source().unwrap()
+        );
+        assert_eq!("rotated\n", read_available(&mut followed));
+    }
+
+    #[test]
+    fn test_follow_compressed_file_fails() {
+        let tmp_dir = TempDir::new("follow").expect("could not create temp dir");
+        let path = tmp_dir.path().join("app.log.gz");
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(b"line\n").unwrap();
+        fs::write(&path, encoder.finish().unwrap()).unwrap();
+        assert!(FollowedFile::open(&path).is_err());
+    }
+
+    #[test]
+    fn test_pending_chunks() {
+        let mut factory = ChunkFactory::new(3, 0);
+        assert!(factory.create_pending_chunk(0).is_none());
+        assert!(factory.add_bytes(1, 10).is_none());
+        let chunk = factory.create_pending_chunk(1).expect("no pending chunk");
+        assert_eq!(((0, 0), (0, 10)), (chunk.r, chunk.b));
+        assert!(factory.create_pending_chunk(1).is_none());
+        assert!(factory.add_bytes(2, 10).is_none());
+        assert!(factory.add_bytes(3, 10).is_none());
+        let chunk = factory.add_bytes(4, 10).expect("no full chunk");
+        assert_eq!(((1, 3), (11, 40)), (chunk.r, chunk.b));
+    }
+}
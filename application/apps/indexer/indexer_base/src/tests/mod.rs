@@ -1,2 +1,8 @@
+mod archive_tests;
+mod compression_tests;
+mod encoding_tests;
+mod export_tests;
+mod follow_tests;
+mod redaction_tests;
 #[macro_use]
 mod utils_tests;
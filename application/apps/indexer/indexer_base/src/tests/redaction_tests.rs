@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod tests {
+    use crate::chunks::ChunkResults;
+    use crate::config::{IndexSection, SectionConfig};
+    use crate::export::export_file_line_based_redacted;
+    use crate::redaction::*;
+    use crossbeam_channel as cc;
+    use pretty_assertions::assert_eq;
+    use tempdir::TempDir;
+
+    fn pseudonymizing(key: Option<&str>) -> RedactionConfig {
+        RedactionConfig {
+            rules: vec![RedactionRule::new(
+                "ip",
+                r"\d+\.\d+\.\d+\.\d+",
+                RedactionAction::Pseudonymize,
+            )],
+            key: key.map(String::from),
+            mask: DEFAULT_MASK.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let mut redactor = Redactor::new(RedactionConfig::default()).unwrap();
+        assert_eq!(
+            "vehicle *** connected",
+            redactor.redact_line("vehicle WVWZZZ1JZXW000001 connected")
+        );
+        assert_eq!(
+            "position: *** (fix)",
+            redactor.redact_line("position: 48.137154, 11.576124 (fix)")
+        );
+        assert_eq!(
+            "connect to *** failed",
+            redactor.redact_line("connect to 192.168.10.254 failed")
+        );
+        assert_eq!(
+            "login: *** (user=***)",
+            redactor.redact_line("login: jdoe (user=john.doe)")
+        );
+        assert_eq!("version 1.2.3", redactor.redact_line("version 1.2.3"));
+        // no VINs: only digits, an O that is not in the VIN alphabet
+        assert_eq!(
+            "id 12345678901234567",
+            redactor.redact_line("id 12345678901234567")
+        );
+        assert_eq!(
+            "WVWZZZ1JZXWO00001",
+            redactor.redact_line("WVWZZZ1JZXWO00001")
+        );
+        let summary = redactor.summary();
+        assert_eq!(7, summary.inspected);
+        assert_eq!(4, summary.redacted);
+        assert_eq!(Some(&2), summary.replacements.get("user"));
+        assert_eq!(Some(&1), summary.replacements.get("vin"));
+    }
+
+    #[test]
+    fn test_pseudonyms() {
+        let mut redactor = Redactor::new(pseudonymizing(Some("secret"))).unwrap();
+        let first = redactor
+            .redact_line("from 10.0.0.1 to 10.0.0.2")
+            .to_string();
+        let second = redactor.redact_line("again 10.0.0.1").to_string();
+        let parts: Vec<&str> = first.split(' ').collect();
+        assert!(parts[1].starts_with("ip_"));
+        assert_eq!(19, parts[1].len());
+        assert_ne!(parts[1], parts[3]);
+        assert_eq!(format!("again {}", parts[1]), second);
+        assert_eq!(Some(&2), redactor.summary().pseudonyms.get("ip"));
+        assert_eq!(Some(&3), redactor.summary().replacements.get("ip"));
+
+        let mut other_key = Redactor::new(pseudonymizing(Some("other"))).unwrap();
+        assert_ne!(second, other_key.redact_line("again 10.0.0.1"));
+        assert!(Redactor::new(pseudonymizing(None)).is_err());
+    }
+
+    #[test]
+    fn test_counted_pseudonyms_are_limited() {
+        let mut config = pseudonymizing(Some("secret"));
+        config.rules[0].pattern = Some(r"\d+".to_string());
+        let mut redactor = Redactor::new(config).unwrap();
+        let line: Vec<String> = (0..MAX_COUNTED_PSEUDONYMS + 10)
+            .map(|i| i.to_string())
+            .collect();
+        redactor.redact_line(&line.join(" "));
+        assert_eq!(
+            Some(&MAX_COUNTED_PSEUDONYMS),
+            redactor.summary().pseudonyms.get("ip")
+        );
+        assert_eq!(
+            Some(&(MAX_COUNTED_PSEUDONYMS + 10)),
+            redactor.summary().replacements.get("ip")
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let mut config = RedactionConfig::default();
+        config
+            .rules
+            .push(RedactionRule::new("broken", "(", RedactionAction::Mask));
+        assert!(Redactor::new(config).is_err());
+        let mut config = RedactionConfig::default();
+        config.rules[0].pattern = None;
+        assert!(Redactor::new(config).is_err());
+        let json = r#"{"rules": [{"name": "pos", "app_id": "NAV", "argument": 1}]}"#;
+        let config: RedactionConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(DEFAULT_MASK, config.mask);
+        assert_eq!(RedactionAction::Mask, config.rules[0].action);
+        assert!(Redactor::new(config).is_ok());
+    }
+
+    #[test]
+    fn test_export_lines_redacted() {
+        let tmp_dir = TempDir::new("redaction").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("in.txt");
+        let out_path = tmp_dir.path().join("out.txt");
+        std::fs::write(&in_path, "a 1.2.3.4\nb\nc 5.6.7.8\nd 9.9.9.9\n").unwrap();
+        let mut redactor = Redactor::new(RedactionConfig::default()).unwrap();
+        let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        export_file_line_based_redacted(
+            in_path,
+            out_path.clone(),
+            SectionConfig {
+                sections: vec![IndexSection {
+                    first_line: 1,
+                    last_line: 2,
+                }],
+            },
+            false,
+            false,
+            tx,
+            &mut redactor,
+        )
+        .expect("export failed");
+        assert_eq!("b\nc ***\n", std::fs::read_to_string(&out_path).unwrap());
+        assert_eq!(2, redactor.summary().inspected);
+        assert_eq!(1, redactor.summary().redacted);
+    }
+}
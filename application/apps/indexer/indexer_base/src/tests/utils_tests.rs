@@ -43,5 +43,93 @@ mod tests {
         ]
         .concat();
         check(content3.to_vec(), 3);
+        // with a timestamp after the row number
+        let content4 = &[
+            &[b'A', D1, b't', b'a', b'g', D1, D2, 0x30, D2][..],
+            b"1564481282555",
+            &[D2, NL],
+            &[b'B', D1, b't', b'a', b'g', D1, D2, 0x31, D2][..],
+            b"1564481282555.123456",
+            &[D2, NL],
+        ]
+        .concat();
+        check(content4.to_vec(), 2);
+    }
+
+    #[test]
+    fn test_session_timestamps() {
+        assert_eq!(
+            "1564481282555",
+            format_session_timestamp(1_564_481_282_555_000_000)
+        );
+        assert_eq!(
+            "1564481282555.123456",
+            format_session_timestamp(1_564_481_282_555_123_456)
+        );
+        assert_eq!("-1.500000", format_session_timestamp(-1_500_000));
+        assert_eq!(
+            Some(1_564_481_282_555_000_000),
+            parse_session_timestamp("1564481282555")
+        );
+        assert_eq!(
+            Some(1_564_481_282_555_123_456),
+            parse_session_timestamp("1564481282555.123456")
+        );
+        assert_eq!(
+            Some(1_564_481_282_555_100_000),
+            parse_session_timestamp("1564481282555.1")
+        );
+        assert_eq!(Some(-1_500_000), parse_session_timestamp("-1.500000"));
+        assert_eq!(None, parse_session_timestamp("1564481282555."));
+        assert_eq!(None, parse_session_timestamp("abc"));
+
+        let mut out: Vec<u8> = vec![];
+        let written = write_tagged_line(
+            "tag",
+            &mut out,
+            "content",
+            7,
+            true,
+            Some(1_564_481_282_555_123_456),
+        )
+        .unwrap();
+        assert_eq!(out.len(), written);
+        let line = String::from_utf8(out).unwrap();
+        assert_eq!(
+            Some(1_564_481_282_555_123_456),
+            session_line_timestamp(&line)
+        );
+        // written before timestamps had a sub-millisecond part
+        let old_line = format!(
+            "content{}tag{}{}7{}1564481282555{}\n",
+            PLUGIN_ID_SENTINAL,
+            PLUGIN_ID_SENTINAL,
+            ROW_NUMBER_SENTINAL,
+            ROW_NUMBER_SENTINAL,
+            ROW_NUMBER_SENTINAL
+        );
+        assert_eq!(
+            Some(1_564_481_282_555_000_000),
+            session_line_timestamp(&old_line)
+        );
+        let mut out: Vec<u8> = vec![];
+        write_tagged_line("tag", &mut out, "content", 7, true, None).unwrap();
+        assert_eq!(
+            None,
+            session_line_timestamp(&String::from_utf8(out).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_encode_newlines() {
+        assert_eq!("single line", encode_newlines("single line"));
+        let record = "error\n\tat a.b(A.java:1)\r\n\tat c.d(C.java:2)";
+        let encoded = encode_newlines(record);
+        assert!(!encoded.contains('\n') && !encoded.contains('\r'));
+        assert_eq!(3, encoded.split(NEWLINE_SENTINAL).count());
+        assert_eq!(
+            "error\n\tat a.b(A.java:1)\n\tat c.d(C.java:2)",
+            decode_newlines(&encoded)
+        );
     }
 }
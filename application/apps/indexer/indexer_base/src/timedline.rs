@@ -13,6 +13,7 @@ use std::cmp::Ordering;
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct TimedLine {
+    /// nanoseconds since the epoch
     pub timestamp: i64,
     pub content: String,
     pub tag: String,
@@ -11,6 +11,7 @@
 // from E.S.R.Labs.
 use crossbeam_channel as cc;
 use failure::{err_msg, Error};
+use std::borrow::Cow;
 use std::char;
 use std::fmt::Display;
 use std::fs;
@@ -21,9 +22,16 @@ use std::str;
 pub const ROW_NUMBER_SENTINAL: char = '\u{0002}';
 pub const PLUGIN_ID_SENTINAL: char = '\u{0003}';
 pub const SENTINAL_LENGTH: usize = 1;
+/// replaces the newlines inside of a record that spans multiple lines
+/// (the same character is used for newlines in dlt payloads)
+pub const NEWLINE_SENTINAL: char = '\u{0006}';
+/// separates the columns of a line, also of dlt messages
+pub const COLUMN_SENTINAL: char = '\u{0004}';
 // 1449941111000
 pub const POSIX_TIMESTAMP_LENGTH: usize = 13;
-const PEEK_END_SIZE: usize = 12;
+pub const NANOS_PER_MILLI: i64 = 1_000_000;
+// enough for the row number and a timestamp with fraction
+const PEEK_END_SIZE: usize = 64;
 
 #[inline]
 pub fn is_newline(c: char) -> bool {
@@ -43,6 +51,25 @@ pub fn restore_line(line: &str) -> &str {
     }
 }
 
+/// encodes the newlines of a multi-line record so it can be stored as one line
+pub fn encode_newlines(record: &str) -> Cow<'_, str> {
+    if record.contains('\n') {
+        let sentinal = NEWLINE_SENTINAL.to_string();
+        Cow::Owned(record.replace("\r\n", &sentinal).replace('\n', &sentinal))
+    } else {
+        Cow::Borrowed(record)
+    }
+}
+
+/// restores the newlines of a record that was encoded with `encode_newlines`
+pub fn decode_newlines(line: &str) -> Cow<'_, str> {
+    if line.contains(NEWLINE_SENTINAL) {
+        Cow::Owned(line.replace(NEWLINE_SENTINAL, "\n"))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
 #[inline]
 pub fn create_tagged_line_d<T: Display>(
     tag: &str,
@@ -70,6 +97,55 @@ pub fn create_tagged_line_d<T: Display>(
 }
 
 #[inline]
+/// the timestamp (nanoseconds) of a session file line, in milliseconds with
+/// the sub-millisecond part as decimal fraction (1564481282555.123456)
+pub fn format_session_timestamp(timestamp_ns: i64) -> String {
+    let sign = if timestamp_ns < 0 { "-" } else { "" };
+    let abs = timestamp_ns.unsigned_abs();
+    let (ms, sub_ms) = (abs / NANOS_PER_MILLI as u64, abs % NANOS_PER_MILLI as u64);
+    if sub_ms == 0 {
+        format!("{}{}", sign, ms)
+    } else {
+        format!("{}{}.{:06}", sign, ms, sub_ms)
+    }
+}
+
+/// reads a timestamp written by `format_session_timestamp`, session files
+/// written before contain whole milliseconds
+pub fn parse_session_timestamp(s: &str) -> Option<i64> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let mut parts = s.splitn(2, '.');
+    let ms: i64 = parts.next()?.parse().ok()?;
+    let sub_ms: i64 = match parts.next() {
+        Some(fraction) if !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit()) => {
+            format!("{:0<6.6}", fraction).parse().ok()?
+        }
+        Some(_) => return None,
+        None => 0,
+    };
+    let ns = ms.checked_mul(NANOS_PER_MILLI)?.checked_add(sub_ms)?;
+    Some(if negative { -ns } else { ns })
+}
+
+/// the timestamp (nanoseconds) of a line in a session file, `None` if
+/// the line was written without one
+pub fn session_line_timestamp(line: &str) -> Option<i64> {
+    let mut columns = line
+        .trim_end_matches(is_newline)
+        .rsplit(PLUGIN_ID_SENTINAL)
+        .next()?
+        .split(ROW_NUMBER_SENTINAL);
+    // ␂row␂timestamp␂
+    match (columns.next(), columns.next(), columns.next()) {
+        (Some(""), Some(_row), Some(timestamp)) => parse_session_timestamp(timestamp),
+        _ => None,
+    }
+}
+
+/// `timestamp` is in nanoseconds, see `format_session_timestamp`
 pub fn write_tagged_line(
     tag: &str,
     out_buffer: &mut dyn io::Write,
@@ -80,11 +156,12 @@ pub fn write_tagged_line(
 ) -> io::Result<usize> {
     match timestamp {
         Some(ts) => {
+            let ts = format_session_timestamp(ts);
             let line_len_with_timestamp_no_nl = trimmed_line.len()
                 + 5 * SENTINAL_LENGTH
                 + tag.len()
                 + number_string_len(line_nr)
-                + number_string_len(ts as usize);
+                + ts.len();
 
             if with_newline {
                 writeln!(
@@ -196,15 +273,16 @@ pub fn next_line_nr(path: &std::path::Path) -> Result<usize, Error> {
     let size_of_slice = seek_offset.abs() as usize;
     let mut buf: Vec<u8> = vec![0; size_of_slice];
     reader.read_exact(&mut buf)?;
-    // |tag|#row#\n
-    for i in 0..size_of_slice - 1 {
+    // |tag|#row#\n or |tag|#row#timestamp#\n
+    for i in (0..size_of_slice - 1).rev() {
         if buf[i] == (PLUGIN_ID_SENTINAL as u8) && buf[i + 1] == ROW_NUMBER_SENTINAL as u8 {
             // row nr starts at i + 2
             let row_slice = &buf[i + 2..];
             let row_string = std::str::from_utf8(row_slice)?;
             let row_nr: usize = row_string
-                .trim_end_matches(is_newline)
-                .trim_end_matches(ROW_NUMBER_SENTINAL)
+                .split(ROW_NUMBER_SENTINAL)
+                .next()
+                .unwrap_or_default()
                 .parse()?;
             return Ok(row_nr + 1);
         }
@@ -22,16 +22,30 @@ extern crate lazy_static;
 use async_std::task;
 use crossbeam_channel as cc;
 use crossbeam_channel::unbounded;
-use dlt::dlt_file::export_as_dlt_file;
+use dlt::dlt_export::{
+    export_dlt_file_structured, export_filtered_dlt_file, export_filtered_segments,
+    parse_csv_columns, ExportFormat,
+};
+use dlt::dlt_file::{export_as_dlt_file, export_segments_as_dlt};
 use dlt::dlt_parse::StatisticsResults;
 use dlt::dlt_pcap::convert_to_dlt_file;
+use dlt::dlt_pcap::{export_as_pcapng, parse_ecu_endpoint, PcapExportConfig};
+use dlt::dlt_redaction::export_as_dlt_file_redacted;
+use dlt::dlt_replay::{ReplayTarget, ReplayTiming};
 use dlt::fibex::FibexMetadata;
 use failure::{err_msg, Error};
+use indexer_base::archive::{list_archive, FileType};
 use indexer_base::chunks::{serialize_chunks, Chunk, ChunkResults};
+use indexer_base::compression::input_size;
 use indexer_base::config::*;
+use indexer_base::encoding::encoding_for_label;
 use indexer_base::error_reporter::*;
-use indexer_base::export::export_file_line_based;
+use indexer_base::export::{export_file_line_based, export_file_line_based_redacted};
+use indexer_base::follow::DEFAULT_POLL_INTERVAL_MS;
 use indexer_base::progress::IndexingResults;
+use indexer_base::redaction::{
+    read_redaction_config, write_redaction_summary, RedactionConfig, Redactor,
+};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::rc::Rc;
 
@@ -47,15 +61,18 @@ extern crate clap;
 extern crate log;
 use clap::{App, Arg, SubCommand};
 use indexer_base::progress::{IndexingProgress, Notification, Severity};
+use processor::grouping::RecordStart;
+use processor::json_log::{read_json_log_config, JsonLogConfig};
 use processor::parse::{
     detect_timestamp_in_string, line_matching_format_expression, match_format_string_in_file,
     posix_timestamp_as_string, read_format_string_options, timespan_in_files, DiscoverItem,
     FormatTestOptions, TimestampFormatResult,
 };
+use processor::stats::{get_text_file_info, read_text_stats_config, TextStatisticsResults};
 use std::fs;
 use std::io::Read;
 use std::path;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use std::thread;
 
@@ -125,6 +142,38 @@ fn main() {
                         .short("w")
                         .help("add timestamp info if available"),
                 )
+                .arg(
+                    Arg::with_name("group_records")
+                        .long("group-records")
+                        .help("index lines without timestamp together with the line before")
+                        .conflicts_with("follow"),
+                )
+                .arg(
+                    Arg::with_name("record_start")
+                        .long("record-start")
+                        .value_name("REGEX")
+                        .help("lines matching REGEX start a new record (implies --group-records)")
+                        .conflicts_with("follow"),
+                )
+                .arg(
+                    Arg::with_name("encoding")
+                        .long("encoding")
+                        .value_name("LABEL")
+                        .help("encoding of the file (e.g. utf-16le, shift_jis, latin1), detected if not present")
+                        .conflicts_with("follow"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .value_name("CONFIG")
+                        .help("index a file with one JSON object per line with these keys and filters ({} for the defaults)")
+                        .conflicts_with_all(&["follow", "group_records", "record_start"]),
+                )
+                .arg(
+                    Arg::with_name("follow")
+                        .long("follow")
+                        .help("keep indexing what is appended to the file (until Ctrl-C)"),
+                )
                 .arg(
                     Arg::with_name("append")
                         .short("a")
@@ -236,12 +285,75 @@ fn main() {
                         .required(false)
                         .default_value(""),
                 )
+                .arg(
+                    Arg::with_name("lifecycles")
+                        .short("l")
+                        .long("lifecycles")
+                        .value_name("LIFECYCLES")
+                        .help("export ECU lifecycles of a dlt file (ids as in dlt-stats), e.g. \"0,2\"")
+                        .conflicts_with("sections"),
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .value_name("FILTER_CONFIG")
+                        .help("export only the dlt messages selected by this filter (json with header filters, payload_regexes, from_ms, to_ms)")
+                        .conflicts_with_all(&["lifecycles", "redact", "redaction_rules"]),
+                )
                 .arg(
                     Arg::with_name("is_session_file")
                         .short("x")
                         .long("sessionfile")
                         .help("eliminiate session file quirks"),
                 )
+                .arg(
+                    Arg::with_name("grouped_records")
+                        .long("grouped-records")
+                        .help("the session file was indexed with --group-records or --record-start, restore the lines of its records")
+                        .requires("is_session_file"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("export format for dlt files")
+                        .possible_values(&["dlt", "json", "csv", "pcapng"])
+                        .default_value("dlt"),
+                )
+                .arg(
+                    Arg::with_name("columns")
+                        .long("columns")
+                        .value_name("COLUMNS")
+                        .help("columns of the csv export (time,ecu,app,context,level,payload)")
+                        .default_value("time,ecu,app,context,level,payload"),
+                )
+                .arg(
+                    Arg::with_name("endpoint")
+                        .long("endpoint")
+                        .value_name("ECU=IP:PORT")
+                        .help("pcapng: udp source of the messages of an ECU, e.g. \"ECU1=192.168.0.10:3490\"")
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("redact")
+                        .short("r")
+                        .long("redact")
+                        .help("redact VINs, GPS coordinates, IP addresses and user names"),
+                )
+                .arg(
+                    Arg::with_name("redaction_rules")
+                        .long("redaction-rules")
+                        .value_name("RULES")
+                        .help("redact with the rules of this json file (implies --redact)"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .value_name("IP:PORT")
+                        .help("pcapng: udp destination of all messages")
+                        .default_value("239.255.42.99:3490"),
+                )
                 .arg(
                     Arg::with_name("target")
                         .short("t")
@@ -279,6 +391,37 @@ fn main() {
                         .short("f")
                         .help("file where the timeformat should be detected")
                         .long("file"),
+                )
+                .arg(
+                    Arg::with_name("encoding")
+                        .long("encoding")
+                        .value_name("LABEL")
+                        .requires("input-file")
+                        .help("encoding of the file, detected if not present"),
+                )
+                .arg(
+                    Arg::with_name("regex")
+                        .long("regex")
+                        .value_name("REGEX")
+                        .requires("input-file")
+                        .help("regex with named groups for the timestamp (year, month, day, hour, minute, second, fraction, tz or epoch)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("archive")
+                .about("list the files in a tar or zip archive")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the archive (tar, tar.gz, tar.xz, ..., zip)")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("print the entries as json"),
                 ),
         )
         .subcommand(
@@ -328,6 +471,17 @@ fn main() {
                         .value_name("FILTER_CONFIG")
                         .help("json file that defines dlt filter settings"),
                 )
+                .arg(
+                    Arg::with_name("corrected_time")
+                        .long("corrected-time")
+                        .help("add a column with the absolute time calculated from the ECU uptime")
+                        .conflicts_with("follow"),
+                )
+                .arg(
+                    Arg::with_name("follow")
+                        .long("follow")
+                        .help("keep indexing what is appended to the file (until Ctrl-C)"),
+                )
                 .arg(
                     Arg::with_name("stdout")
                         .short("s")
@@ -391,7 +545,11 @@ fn main() {
                     Arg::with_name("ip")
                         .short("i")
                         .long("ip")
-                        .help("the ip address + port")
+                        .help(
+                            "the ip address + port (ADDR or ADDR:PORT, default port is 8888), \
+                             can be given multiple times to listen on several endpoints",
+                        )
+                        .multiple(true)
                         .required(true)
                         .index(1),
                 )
@@ -426,6 +584,38 @@ fn main() {
                         .value_name("FILTER_CONFIG")
                         .help("json file that defines dlt filter settings"),
                 )
+                .arg(
+                    Arg::with_name("reorder_window")
+                        .short("r")
+                        .long("reorder")
+                        .value_name("MS")
+                        .help("hold messages back for MS milliseconds to fix their order"),
+                )
+                .arg(
+                    Arg::with_name("segment_size")
+                        .long("segment-size")
+                        .value_name("BYTES")
+                        .help("ring buffer: start a new segment after BYTES of dlt content or text"),
+                )
+                .arg(
+                    Arg::with_name("segment_duration")
+                        .long("segment-duration")
+                        .value_name("SECONDS")
+                        .help("ring buffer: start a new segment after SECONDS"),
+                )
+                .arg(
+                    Arg::with_name("segments")
+                        .long("segments")
+                        .value_name("COUNT")
+                        .help("ring buffer: how many segments are kept")
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::with_name("archive")
+                        .long("archive")
+                        .value_name("DIR")
+                        .help("ring buffer: move old segments to DIR instead of deleting them"),
+                )
                 .arg(
                     Arg::with_name("stdout")
                         .short("s")
@@ -433,6 +623,117 @@ fn main() {
                         .help("put out chunk information on stdout"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("dlt-replay")
+                .about("send the messages of a dlt file like an ECU")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the DLT file to replay")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("udp")
+                        .short("u")
+                        .long("udp")
+                        .value_name("ADDR:PORT")
+                        .help("send via UDP to this (unicast or multicast) address")
+                        .required_unless("tcp")
+                        .conflicts_with("tcp"),
+                )
+                .arg(
+                    Arg::with_name("tcp")
+                        .short("t")
+                        .long("tcp")
+                        .value_name("ADDR:PORT")
+                        .help("wait for a TCP client on this address (like a dlt-daemon)"),
+                )
+                .arg(
+                    Arg::with_name("speed")
+                        .short("s")
+                        .long("speed")
+                        .value_name("FACTOR")
+                        .help("replay faster (> 1) or slower (< 1) then recorded")
+                        .default_value("1.0"),
+                )
+                .arg(
+                    Arg::with_name("fast")
+                        .short("f")
+                        .long("fast")
+                        .help("send as fast as possible, ignoring the recorded timing")
+                        .conflicts_with("speed"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dlt-repair")
+                .about("write the valid messages of a damaged dlt file to a new file")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the damaged DLT file")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("OUT")
+                        .help("the repaired file, \"<input>.repaired.dlt\" if not present"),
+                )
+                .arg(
+                    Arg::with_name("report")
+                        .short("r")
+                        .long("report")
+                        .value_name("REPORT")
+                        .help("json report of the damages, \"<OUT>.report.json\" if not present"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dlt-split")
+                .about("split a dlt file into several smaller dlt files")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the DLT file to split")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("by")
+                        .short("b")
+                        .long("by")
+                        .value_name("CRITERION")
+                        .help("how messages are distributed to the files")
+                        .possible_values(&["size", "interval", "ecu", "app", "lifecycle"])
+                        .default_value("size"),
+                )
+                .arg(
+                    Arg::with_name("max_size")
+                        .long("max-size")
+                        .value_name("BYTES")
+                        .help("split by size: maximum size of a file")
+                        .default_value("1073741824"),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .value_name("SECONDS")
+                        .help("split by interval: time span of a file (storage header time)")
+                        .default_value("3600"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("DIR")
+                        .help("directory for the files, same as the input if not present"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("dlt-stats")
                 .about("dlt statistics")
@@ -451,6 +752,138 @@ fn main() {
                         .help("put out chunk information on stdout"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("text-stats")
+                .about("level and source statistics for a text log")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("the text file to inspect")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("config")
+                        .short("c")
+                        .long("config")
+                        .value_name("CONFIG")
+                        .help("patterns, timestamp format and encoding as json"),
+                )
+                .arg(
+                    Arg::with_name("level_pattern")
+                        .long("level-pattern")
+                        .value_name("REGEX")
+                        .help("regex for the severity (group `level`), built-in patterns if not present"),
+                )
+                .arg(
+                    Arg::with_name("source_pattern")
+                        .long("source-pattern")
+                        .value_name("REGEX")
+                        .help("regex for the logger or tag (group `source`)"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("format of the timestamps, detected if not present"),
+                )
+                .arg(
+                    Arg::with_name("encoding")
+                        .long("encoding")
+                        .value_name("LABEL")
+                        .help("encoding of the file, detected if not present"),
+                )
+                .arg(
+                    Arg::with_name("top")
+                        .long("top")
+                        .value_name("N")
+                        .help("number of sources with the most lines that are reported"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("generate a synthetic dlt file or text log for load tests")
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("the file to write")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("text")
+                        .long("text")
+                        .help("generate a text log instead of a dlt file"),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .value_name("BYTES")
+                        .help("stop after BYTES were written")
+                        .default_value("10485760"),
+                )
+                .arg(
+                    Arg::with_name("ecus")
+                        .long("ecus")
+                        .value_name("IDS")
+                        .help("comma separated ECU ids")
+                        .default_value("ECU1,ECU2"),
+                )
+                .arg(
+                    Arg::with_name("apps")
+                        .long("apps")
+                        .value_name("IDS")
+                        .help("comma separated application ids (components for text logs)")
+                        .default_value("APP1,APP2,DIAG,NAV"),
+                )
+                .arg(
+                    Arg::with_name("contexts")
+                        .long("contexts")
+                        .value_name("IDS")
+                        .help("comma separated context ids")
+                        .default_value("CTX1,CTX2,MAIN"),
+                )
+                .arg(
+                    Arg::with_name("levels")
+                        .long("levels")
+                        .value_name("WEIGHTS")
+                        .help(
+                            "relative weights of fatal,error,warn,info,debug,verbose \
+                             (error,warn,info,debug,trace for text logs)",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("non_verbose")
+                        .long("non-verbose")
+                        .value_name("RATIO")
+                        .help("fraction of non-verbose messages (0.0 - 1.0)")
+                        .default_value("0.0"),
+                )
+                .arg(
+                    Arg::with_name("fibex")
+                        .short("x")
+                        .long("fibex")
+                        .value_name("FILE")
+                        .help("write the FIBEX for the non-verbose messages to FILE"),
+                )
+                .arg(
+                    Arg::with_name("corrupt")
+                        .long("corrupt")
+                        .value_name("RATIO")
+                        .help("fraction of damaged messages or lines (0.0 - 1.0)")
+                        .default_value("0.0"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .value_name("SEED")
+                        .help("the same seed always generates the same content")
+                        .default_value("0"),
+                ),
+        )
         .get_matches();
 
     // Vary the output based on how many times the user used the "verbose" flag
@@ -471,10 +904,22 @@ fn main() {
         handle_dlt_pcap_subcommand(matches)
     } else if let Some(matches) = matches.subcommand_matches("dlt-udp") {
         handle_dlt_udp_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("dlt-replay") {
+        handle_dlt_replay_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("dlt-repair") {
+        handle_dlt_repair_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("dlt-split") {
+        handle_dlt_split_subcommand(matches)
     } else if let Some(matches) = matches.subcommand_matches("dlt-stats") {
         handle_dlt_stats_subcommand(matches, start, use_stderr_for_status_updates)
+    } else if let Some(matches) = matches.subcommand_matches("text-stats") {
+        handle_text_stats_subcommand(matches, start, use_stderr_for_status_updates)
     } else if let Some(matches) = matches.subcommand_matches("discover") {
         handle_discover_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("generate") {
+        handle_generate_subcommand(matches)
+    } else if let Some(matches) = matches.subcommand_matches("archive") {
+        handle_archive_subcommand(matches)
     }
 
     fn handle_index_subcommand(
@@ -486,7 +931,7 @@ fn main() {
             let file = matches.value_of("input").expect("input must be present");
             let file_path = path::PathBuf::from(file);
             let tag = matches.value_of("tag").expect("tag must be present");
-            let total = fs::metadata(file).expect("file size error").len();
+            let total = input_size(&file_path).expect("file size error");
             let progress_bar = initialize_progress_bar(total);
             let tag_string = tag.to_string();
             let fallback_out = file.to_string() + ".out";
@@ -499,8 +944,8 @@ fn main() {
                 path::PathBuf::from(file.to_string() + ".map.json");
             let chunk_size = value_t_or_exit!(matches.value_of("chunk_size"), usize);
 
-            let source_file_size = match fs::metadata(file) {
-                Ok(file_meta) => file_meta.len() as usize,
+            let source_file_size = match input_size(&file_path) {
+                Ok(size) => size as usize,
                 Err(_) => {
                     report_error("could not find out size of source file");
                     std::process::exit(2);
@@ -508,10 +953,59 @@ fn main() {
             };
             let append: bool = matches.is_present("append");
             let timestamps: bool = matches.is_present("timestamp");
+            let record_start = match matches.value_of("record_start") {
+                Some(pattern) => Some(RecordStart::Pattern(pattern.to_string())),
+                None if matches.is_present("group_records") => Some(RecordStart::Timestamp),
+                None => None,
+            };
+            let encoding = match matches.value_of("encoding").map(encoding_for_label) {
+                Some(Ok(encoding)) => Some(encoding),
+                Some(Err(e)) => {
+                    report_error(format!("{}", e));
+                    std::process::exit(2);
+                }
+                None => None,
+            };
+            let json: Option<JsonLogConfig> = match matches.value_of("json") {
+                Some(config) => match fs::File::open(config)
+                    .map_err(Error::from)
+                    .and_then(|mut f| read_json_log_config(&mut f))
+                {
+                    Ok(json) => Some(json),
+                    Err(e) => {
+                        report_error(format!("could not read JSON config {}: {}", config, e));
+                        std::process::exit(2);
+                    }
+                },
+                None => None,
+            };
             let (tx, rx): (
                 cc::Sender<IndexingResults<Chunk>>,
                 cc::Receiver<ChunkResults>,
             ) = unbounded();
+            if matches.is_present("follow") {
+                progress_bar.finish_and_clear();
+                thread::spawn(move || {
+                    if let Err(why) = processor::processor::follow_file(
+                        IndexingConfig {
+                            tag: tag_string.as_str(),
+                            chunk_size,
+                            in_file: file_path,
+                            out_path: &out_path,
+                            append,
+                        },
+                        timestamps,
+                        Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+                        tx,
+                        None,
+                    ) {
+                        report_error(format!("couldn't follow: {}", why));
+                        std::process::exit(2)
+                    }
+                });
+                print_followed_chunks(rx);
+                return;
+            }
 
             let _h = thread::spawn(move || {
                 match processor::processor::create_index_and_mapping(
@@ -524,6 +1018,9 @@ fn main() {
                     },
                     source_file_size,
                     timestamps,
+                    record_start,
+                    encoding,
+                    json,
                     tx,
                     None,
                 ) {
@@ -636,6 +1133,7 @@ fn main() {
             };
             match match_format_string_in_file(
                 options.format.as_str(),
+                options.regex.as_deref(),
                 options.file.as_str(),
                 options.lines_to_test,
             ) {
@@ -674,6 +1172,32 @@ fn main() {
             last_line: elems[1].parse()?,
         })
     }
+    /// multicast addresses are joined on all interfaces, for other
+    /// addresses we bind to that address directly
+    fn to_udp_endpoint(input: &str) -> Result<UdpEndpoint, Error> {
+        let addr: std::net::SocketAddr = match input.parse() {
+            Ok(addr) => addr,
+            Err(_) => std::net::SocketAddr::new(input.parse()?, 8888),
+        };
+        let ip = addr.ip();
+        if ip.is_multicast() {
+            let any = if ip.is_ipv4() { "0.0.0.0" } else { "::" };
+            Ok(UdpEndpoint {
+                multicast_addr: Some(MulticastInfo {
+                    multiaddr: ip.to_string(),
+                    interface: None,
+                }),
+                bind_addr: any.to_string(),
+                port: addr.port().to_string(),
+            })
+        } else {
+            Ok(UdpEndpoint {
+                multicast_addr: None,
+                bind_addr: ip.to_string(),
+                port: addr.port().to_string(),
+            })
+        }
+    }
     fn handle_export_subcommand(matches: &clap::ArgMatches, _start: std::time::Instant) {
         debug!("handle_export_subcommand");
 
@@ -681,68 +1205,269 @@ fn main() {
             let fallback_out = file_name.to_string() + ".out";
             let out_path = path::PathBuf::from(
                 matches
-                    .value_of("output")
+                    .value_of("target")
                     .unwrap_or_else(|| fallback_out.as_str()),
             );
             let file_path = path::PathBuf::from(file_name);
             let was_session_file: bool = matches.is_present("is_session_file");
+            let grouped_records: bool = matches.is_present("grouped_records");
             let sections_string = value_t_or_exit!(matches.value_of("sections"), String);
-            let sections: Vec<IndexSection> = sections_string
-                .split('|')
-                .map(|s| to_pair(s).expect("could not parse section pair"))
-                .collect();
+            let sections = || -> Vec<IndexSection> {
+                sections_string
+                    .split('|')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| to_pair(s).expect("could not parse section pair"))
+                    .collect()
+            };
 
-            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
-            let ending = &file_path.extension().expect("could not get extension");
-            if ending.to_str() == Some("dlt") {
-                trace!("was dlt file");
-                export_as_dlt_file(file_path, out_path, SectionConfig { sections }, tx)
-                    .expect("export did not work");
-            } else {
-                trace!("was regular file");
-                export_file_line_based(
-                    file_path,
-                    out_path,
-                    SectionConfig { sections },
-                    was_session_file,
-                    tx,
-                )
-                .expect("export did not work");
+            let redaction_config = match matches.value_of("redaction_rules") {
+                Some(rules_path) => match read_redaction_config(path::Path::new(rules_path)) {
+                    Ok(config) => Some(config),
+                    Err(e) => {
+                        report_error(format!("{}", e));
+                        std::process::exit(2)
+                    }
+                },
+                None if matches.is_present("redact") => Some(RedactionConfig::default()),
+                None => None,
+            };
+            let mut redactor = match redaction_config.map(Redactor::new) {
+                Some(Ok(redactor)) => Some(redactor),
+                Some(Err(e)) => {
+                    report_error(format!("invalid redaction rules: {}", e));
+                    std::process::exit(2)
+                }
+                None => None,
             };
 
-            println!("done with handle_export_subcommand");
-            std::process::exit(0)
-        }
-    }
-    fn handle_dlt_subcommand(matches: &clap::ArgMatches, start: std::time::Instant) {
-        debug!("handle_dlt_subcommand");
-        if let (Some(file_name), Some(tag)) = (matches.value_of("input"), matches.value_of("tag")) {
-            let filter_conf: Option<dlt::filtering::DltFilterConfig> = match matches
-                .value_of("filter_config")
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            let ending = &file_path.extension().expect("could not get extension");
+            if redactor.is_some()
+                && (file_name.ends_with(dlt::ring_buffer::SEGMENT_MANIFEST_SUFFIX)
+                    || matches.value_of("format") == Some("pcapng"))
             {
-                Some(filter_config_file_name) => {
-                    let config_path = path::PathBuf::from(filter_config_file_name);
-                    let mut cnf_file = match fs::File::open(&config_path) {
-                        Ok(file) => file,
-                        Err(_) => {
-                            report_error(format!("could not open filter config {:?}", config_path));
+                report_error("redaction is not supported for segments and pcapng".to_string());
+                std::process::exit(2)
+            }
+            if let Some(filter_path) = matches.value_of("filter") {
+                trace!("filtered dlt export");
+                if matches.value_of("format") != Some("dlt") || !sections().is_empty() {
+                    report_error(
+                        "a filter can only be used for dlt exports without sections".to_string(),
+                    );
+                    std::process::exit(2)
+                }
+                let filter =
+                    match dlt::filtering::read_export_filter_options(path::Path::new(filter_path))
+                        .and_then(dlt::filtering::process_export_filter_config)
+                    {
+                        Ok(filter) => filter,
+                        Err(e) => {
+                            report_error(format!("invalid filter: {}", e));
                             std::process::exit(2)
                         }
                     };
-                    dlt::filtering::read_filter_options(&mut cnf_file).ok()
-                }
-                None => None,
-            };
-            let append: bool = matches.is_present("append");
-            let source_file_size = match fs::metadata(file_name) {
-                Ok(file_meta) => file_meta.len() as usize,
-                Err(_) => {
-                    report_error("could not find out size of source file");
-                    std::process::exit(2);
+                let exported = if file_name.ends_with(dlt::ring_buffer::SEGMENT_MANIFEST_SUFFIX) {
+                    export_filtered_segments(&file_path, &out_path, &filter, None, tx, None)
+                } else {
+                    export_filtered_dlt_file(&file_path, &out_path, &filter, None, tx, None)
+                };
+                match exported {
+                    Ok(written) => println!("exported {} messages", written),
+                    Err(e) => {
+                        report_error(format!("export did not work: {}", e));
+                        std::process::exit(2)
+                    }
                 }
-            };
-            let fallback_out = file_name.to_string() + ".out";
-            let out_path = path::PathBuf::from(
+            } else if file_name.ends_with(dlt::ring_buffer::SEGMENT_MANIFEST_SUFFIX) {
+                trace!("was segment manifest");
+                export_segments_as_dlt(
+                    &file_path,
+                    out_path.clone(),
+                    SectionConfig {
+                        sections: sections(),
+                    },
+                    tx,
+                )
+                .expect("export did not work");
+            } else if ending.to_str() == Some("dlt") {
+                trace!("was dlt file");
+                let section_config = match matches.value_of("lifecycles") {
+                    Some(ids_string) => {
+                        let ids: Vec<usize> = ids_string
+                            .split(',')
+                            .map(|s| s.trim().parse().expect("could not parse lifecycle id"))
+                            .collect();
+                        let lifecycles = match dlt::lifecycle::scan_lifecycles(&file_path) {
+                            Ok(lifecycles) => lifecycles,
+                            Err(e) => {
+                                report_error(format!("could not detect lifecycles: {}", e));
+                                std::process::exit(2)
+                            }
+                        };
+                        dlt::lifecycle::lifecycle_sections(&lifecycles, &ids)
+                    }
+                    None => SectionConfig {
+                        sections: sections(),
+                    },
+                };
+                let format = match matches.value_of("format") {
+                    Some("json") => Some(ExportFormat::JsonLines),
+                    Some("csv") => {
+                        let columns_string = value_t_or_exit!(matches.value_of("columns"), String);
+                        match parse_csv_columns(&columns_string) {
+                            Ok(columns) => Some(ExportFormat::Csv(columns)),
+                            Err(e) => {
+                                report_error(format!("invalid columns: {}", e));
+                                std::process::exit(2)
+                            }
+                        }
+                    }
+                    _ => None,
+                };
+                match format {
+                    Some(format) => {
+                        match export_dlt_file_structured(
+                            &file_path,
+                            &out_path,
+                            section_config,
+                            format,
+                            None,
+                            redactor.as_mut(),
+                            tx,
+                            None,
+                        ) {
+                            Ok(written) => println!("exported {} messages", written),
+                            Err(e) => {
+                                report_error(format!("export did not work: {}", e));
+                                std::process::exit(2)
+                            }
+                        }
+                    }
+                    None if matches.value_of("format") == Some("pcapng") => {
+                        let mut config = PcapExportConfig::default();
+                        if let Some(endpoints) = matches.values_of("endpoint") {
+                            for endpoint in endpoints {
+                                match parse_ecu_endpoint(endpoint) {
+                                    Ok((ecu, address)) => {
+                                        config.ecu_sources.insert(ecu, address);
+                                    }
+                                    Err(e) => {
+                                        report_error(format!("{}", e));
+                                        std::process::exit(2)
+                                    }
+                                }
+                            }
+                        }
+                        config.destination = value_t_or_exit!(
+                            matches.value_of("destination"),
+                            std::net::SocketAddrV4
+                        );
+                        match export_as_pcapng(
+                            &file_path,
+                            &out_path,
+                            section_config,
+                            &config,
+                            tx,
+                            None,
+                        ) {
+                            Ok(written) => println!("exported {} messages", written),
+                            Err(e) => {
+                                report_error(format!("export did not work: {}", e));
+                                std::process::exit(2)
+                            }
+                        }
+                    }
+                    None => match redactor.as_mut() {
+                        Some(redactor) => export_as_dlt_file_redacted(
+                            &file_path,
+                            &out_path,
+                            section_config,
+                            redactor,
+                            tx,
+                        )
+                        .expect("export did not work"),
+                        None => export_as_dlt_file(file_path, out_path.clone(), section_config, tx)
+                            .expect("export did not work"),
+                    },
+                }
+            } else {
+                trace!("was regular file");
+                let section_config = SectionConfig {
+                    sections: sections(),
+                };
+                match redactor.as_mut() {
+                    Some(redactor) => export_file_line_based_redacted(
+                        file_path,
+                        out_path.clone(),
+                        section_config,
+                        was_session_file,
+                        grouped_records,
+                        tx,
+                        redactor,
+                    ),
+                    None => export_file_line_based(
+                        file_path,
+                        out_path.clone(),
+                        section_config,
+                        was_session_file,
+                        grouped_records,
+                        tx,
+                    ),
+                }
+                .expect("export did not work");
+            };
+            if let Some(redactor) = redactor {
+                let summary = redactor.summary();
+                println!(
+                    "redacted {} of {} lines/messages ({} left out, they cannot be redacted)",
+                    summary.redacted, summary.inspected, summary.dropped
+                );
+                for (rule, count) in &summary.replacements {
+                    println!("  {}: {} replacements", rule, count);
+                }
+                let summary_path =
+                    path::PathBuf::from(format!("{}.redaction.json", out_path.display()));
+                if let Err(e) = write_redaction_summary(summary, &summary_path) {
+                    report_error(format!("could not write redaction summary: {}", e));
+                    std::process::exit(2)
+                }
+                println!("redaction summary written to {:?}", summary_path);
+            }
+
+            println!("done with handle_export_subcommand");
+            std::process::exit(0)
+        }
+    }
+    fn handle_dlt_subcommand(matches: &clap::ArgMatches, start: std::time::Instant) {
+        debug!("handle_dlt_subcommand");
+        if let (Some(file_name), Some(tag)) = (matches.value_of("input"), matches.value_of("tag")) {
+            let filter_conf: Option<dlt::filtering::DltFilterConfig> = match matches
+                .value_of("filter_config")
+            {
+                Some(filter_config_file_name) => {
+                    let config_path = path::PathBuf::from(filter_config_file_name);
+                    let mut cnf_file = match fs::File::open(&config_path) {
+                        Ok(file) => file,
+                        Err(_) => {
+                            report_error(format!("could not open filter config {:?}", config_path));
+                            std::process::exit(2)
+                        }
+                    };
+                    dlt::filtering::read_filter_options(&mut cnf_file).ok()
+                }
+                None => None,
+            };
+            let append: bool = matches.is_present("append");
+            let source_file_size = match input_size(path::Path::new(file_name)) {
+                Ok(size) => size as usize,
+                Err(_) => {
+                    report_error("could not find out size of source file");
+                    std::process::exit(2);
+                }
+            };
+            let fallback_out = file_name.to_string() + ".out";
+            let out_path = path::PathBuf::from(
                 matches
                     .value_of("output")
                     .unwrap_or_else(|| fallback_out.as_str()),
@@ -754,12 +1479,36 @@ fn main() {
             let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
             let chunk_size = value_t_or_exit!(matches.value_of("chunk_size"), usize);
             let tag_string = tag.to_string();
+            let with_corrected_time: bool = matches.is_present("corrected_time");
 
             // let filter_config: Option<dlt::filtering::ProcessedDltFilterConfig> =
             //     filter_conf.map(dlt::filtering::process_filter_config);
             // let dlt_file_future = parse_dlt_file(file_path, filter_config, None);
             // let res = task::block_on(dlt_file_future);
 
+            if matches.is_present("follow") {
+                thread::spawn(move || {
+                    if let Err(why) = dlt::dlt_follow::follow_dlt_file(
+                        IndexingConfig {
+                            tag: tag_string.as_str(),
+                            chunk_size,
+                            in_file: file_path,
+                            out_path: &out_path,
+                            append,
+                        },
+                        filter_conf,
+                        load_test_fibex(),
+                        Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+                        tx,
+                        None,
+                    ) {
+                        report_error(format!("couldn't follow: {}", why));
+                        std::process::exit(2)
+                    }
+                });
+                print_followed_chunks(rx);
+                return;
+            }
             let progress_bar = initialize_progress_bar(source_file_size as u64);
             thread::spawn(move || {
                 if let Err(why) = dlt::dlt_file::create_index_and_mapping_dlt(
@@ -779,6 +1528,7 @@ fn main() {
                     //     components: None,
                     // },
                     load_test_fibex(),
+                    with_corrected_time,
                 ) {
                     report_error(format!("couldn't process: {}", why));
                     std::process::exit(2)
@@ -865,7 +1615,7 @@ fn main() {
             let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
             let chunk_size = value_t_or_exit!(matches.value_of("chunk_size"), usize);
             let tag_string = tag.to_string();
-            let total = fs::metadata(&file_path).expect("file size error").len();
+            let total = input_size(&file_path).expect("file size error");
             let progress_bar = initialize_progress_bar(total);
             let in_one_go: bool = matches.is_present("direct");
             if in_one_go {
@@ -937,8 +1687,8 @@ fn main() {
     };
     fn handle_dlt_udp_subcommand(matches: &clap::ArgMatches) {
         debug!("handle_dlt_udp_subcommand");
-        if let (Some(ip_address), Some(tag), Some(output)) = (
-            matches.value_of("ip"),
+        if let (Some(ip_addresses), Some(tag), Some(output)) = (
+            matches.values_of("ip"),
             matches.value_of("tag"),
             matches.value_of("output"),
         ) {
@@ -965,19 +1715,59 @@ fn main() {
             let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
             let shutdown_channel = async_std::sync::channel(1);
             let tag_string = tag.to_string();
-            let multicast_conf = MulticastInfo {
-                multiaddr: ip_address.to_string(),
-                interface: None,
+            let reorder_window_ms: Option<u64> = if matches.is_present("reorder_window") {
+                Some(value_t_or_exit!(matches.value_of("reorder_window"), u64))
+            } else {
+                None
+            };
+            let mut endpoints: Vec<UdpEndpoint> = vec![];
+            for ip_address in ip_addresses {
+                match to_udp_endpoint(ip_address) {
+                    Ok(endpoint) => endpoints.push(endpoint),
+                    Err(e) => {
+                        report_error(format!("invalid endpoint {}: {}", ip_address, e));
+                        std::process::exit(2)
+                    }
+                }
+            }
+            let max_segment_bytes: Option<u64> = if matches.is_present("segment_size") {
+                Some(value_t_or_exit!(matches.value_of("segment_size"), u64))
+            } else {
+                None
             };
+            let max_segment_duration_s: Option<u64> = if matches.is_present("segment_duration") {
+                Some(value_t_or_exit!(matches.value_of("segment_duration"), u64))
+            } else {
+                None
+            };
+            let ring_buffer = if max_segment_bytes.is_some() || max_segment_duration_s.is_some() {
+                Some(RingBufferConfig {
+                    max_segment_bytes,
+                    max_segment_duration_s,
+                    max_segments: value_t_or_exit!(matches.value_of("segments"), usize),
+                    archive_dir: matches.value_of("archive").map(|s| s.to_string()),
+                })
+            } else {
+                None
+            };
+            let with_ring_buffer = ring_buffer.is_some();
             let socket_conf = SocketConfig {
-                multicast_addr: Some(multicast_conf),
-                bind_addr: "0.0.0.0".to_string(),
-                port: "8888".to_string(),
+                endpoints,
+                reorder_window_ms,
+                ring_buffer,
             };
 
             use chrono::Local;
             let now = Local::now();
             let session_id = format!("dlt_session_id_{}.dlt", now.format("%Y%b%d_%H-%M-%S"));
+            if with_ring_buffer {
+                if let Ok(session_file) = dlt::dlt_file::session_file_path(&session_id) {
+                    println!(
+                        "segments are listed in {:?}",
+                        dlt::ring_buffer::manifest_path(&session_file)
+                    );
+                }
+            }
             thread::spawn(move || {
                 let dlt_socket_future = dlt::dlt_net::create_index_and_mapping_dlt_from_socket(
                     session_id,
@@ -1035,6 +1825,309 @@ fn main() {
         }
     }
 
+    fn handle_dlt_replay_subcommand(matches: &clap::ArgMatches) {
+        debug!("handle_dlt_replay_subcommand");
+        if let Some(file_name) = matches.value_of("input") {
+            let target = match (matches.value_of("udp"), matches.value_of("tcp")) {
+                (Some(addr), _) => addr.parse().map(ReplayTarget::Udp),
+                (None, Some(addr)) => addr.parse().map(ReplayTarget::Tcp),
+                (None, None) => unreachable!("clap requires one of udp or tcp"),
+            };
+            let target = match target {
+                Ok(target) => target,
+                Err(e) => {
+                    report_error(format!("invalid address: {}", e));
+                    std::process::exit(2)
+                }
+            };
+            let timing = if matches.is_present("fast") {
+                ReplayTiming::AsFastAsPossible
+            } else {
+                let speed = value_t_or_exit!(matches.value_of("speed"), f64);
+                if !speed.is_finite() || speed <= 0.0 {
+                    report_error(format!("invalid speed {} (has to be positive)", speed));
+                    std::process::exit(2)
+                }
+                if (speed - 1.0).abs() < f64::EPSILON {
+                    ReplayTiming::Original
+                } else {
+                    ReplayTiming::Speed(speed)
+                }
+            };
+            let file_path = path::PathBuf::from(file_name);
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            let replay = thread::spawn(move || {
+                task::block_on(dlt::dlt_replay::replay_dlt_file(
+                    file_path, target, timing, tx, None,
+                ))
+            });
+            loop {
+                match rx.recv() {
+                    Ok(Ok(IndexingProgress::Progress { ticks })) => {
+                        let progress_fraction = ticks.0 as f64 / ticks.1 as f64;
+                        trace!("progress... ({:.0} %)", progress_fraction * 100.0);
+                    }
+                    Ok(Err(Notification {
+                        severity,
+                        content,
+                        line,
+                    })) => {
+                        if severity == Severity::WARNING {
+                            report_warning_ln(content, line);
+                        } else {
+                            report_error_ln(content, line);
+                        }
+                    }
+                    Ok(Ok(_)) | Err(_) => break,
+                }
+            }
+            match replay.join() {
+                Ok(Ok(sent)) => println!("sent {} messages", sent),
+                Ok(Err(reason)) => {
+                    report_error(format!("couldn't replay: {}", reason));
+                    std::process::exit(2)
+                }
+                Err(_) => {
+                    report_error("replay thread panicked");
+                    std::process::exit(2)
+                }
+            }
+            println!("done with handle_dlt_replay_subcommand");
+            std::process::exit(0)
+        }
+    }
+
+    fn handle_dlt_repair_subcommand(matches: &clap::ArgMatches) {
+        debug!("handle_dlt_repair_subcommand");
+        if let Some(file_name) = matches.value_of("input") {
+            let in_path = path::PathBuf::from(file_name);
+            let out_path = path::PathBuf::from(
+                matches
+                    .value_of("output")
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("{}.repaired.dlt", file_name)),
+            );
+            let report_path = match matches.value_of("report") {
+                Some(report) => path::PathBuf::from(report),
+                None => path::PathBuf::from(format!("{}.report.json", out_path.to_string_lossy())),
+            };
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            let repair_out_path = out_path.clone();
+            let repair = thread::spawn(move || {
+                dlt::dlt_repair::repair_dlt_file(&in_path, &repair_out_path, tx, None)
+            });
+            loop {
+                match rx.recv() {
+                    Ok(Ok(IndexingProgress::Progress { ticks })) => {
+                        let progress_fraction = ticks.0 as f64 / ticks.1 as f64;
+                        trace!("progress... ({:.0} %)", progress_fraction * 100.0);
+                    }
+                    Ok(Err(Notification {
+                        severity,
+                        content,
+                        line,
+                    })) => {
+                        if severity == Severity::WARNING {
+                            report_warning_ln(content, line);
+                        } else {
+                            report_error_ln(content, line);
+                        }
+                    }
+                    Ok(Ok(_)) | Err(_) => break,
+                }
+            }
+            let report = match repair.join() {
+                Ok(Ok(report)) => report,
+                Ok(Err(reason)) => {
+                    report_error(format!("couldn't repair: {}", reason));
+                    std::process::exit(2)
+                }
+                Err(_) => {
+                    report_error("repair thread panicked");
+                    std::process::exit(2)
+                }
+            };
+            if let Err(e) = dlt::dlt_repair::write_repair_report(&report, &report_path) {
+                report_error(format!("couldn't write report: {}", e));
+                std::process::exit(2)
+            }
+            println!(
+                "kept {} messages, dropped {} damaged ranges ({} bytes)",
+                report.valid_messages,
+                report.damaged.len(),
+                report.damaged_bytes
+            );
+            println!("repaired file: {:?}", out_path);
+            println!("report: {:?}", report_path);
+            std::process::exit(0)
+        }
+    }
+
+    fn handle_dlt_split_subcommand(matches: &clap::ArgMatches) {
+        use dlt::dlt_split::SplitCriterion;
+        debug!("handle_dlt_split_subcommand");
+        if let Some(file_name) = matches.value_of("input") {
+            let in_path = path::PathBuf::from(file_name);
+            let out_dir = match matches.value_of("output") {
+                Some(dir) => path::PathBuf::from(dir),
+                None => in_path
+                    .parent()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|| path::PathBuf::from(".")),
+            };
+            let criterion = match matches.value_of("by") {
+                Some("interval") => {
+                    SplitCriterion::Interval(value_t_or_exit!(matches.value_of("interval"), u64))
+                }
+                Some("ecu") => SplitCriterion::EcuId,
+                Some("app") => SplitCriterion::AppId,
+                Some("lifecycle") => SplitCriterion::Lifecycle,
+                _ => SplitCriterion::Size(value_t_or_exit!(matches.value_of("max_size"), u64)),
+            };
+            let manifest_path = dlt::dlt_split::split_manifest_path(&in_path, &out_dir);
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            let split = thread::spawn(move || {
+                dlt::dlt_split::split_dlt_file(&in_path, &out_dir, criterion, tx, None)
+            });
+            loop {
+                match rx.recv() {
+                    Ok(Ok(IndexingProgress::Progress { ticks })) => {
+                        let progress_fraction = ticks.0 as f64 / ticks.1 as f64;
+                        trace!("progress... ({:.0} %)", progress_fraction * 100.0);
+                    }
+                    Ok(Err(Notification {
+                        severity,
+                        content,
+                        line,
+                    })) => {
+                        if severity == Severity::WARNING {
+                            report_warning_ln(content, line);
+                        } else {
+                            report_error_ln(content, line);
+                        }
+                    }
+                    Ok(Ok(_)) | Err(_) => break,
+                }
+            }
+            match split.join() {
+                Ok(Ok(manifest)) => {
+                    for file in &manifest.files {
+                        println!(
+                            "{}: {} messages ({} bytes)",
+                            file.file_name, file.messages, file.bytes
+                        );
+                    }
+                    println!("manifest: {:?}", manifest_path);
+                }
+                Ok(Err(reason)) => {
+                    report_error(format!("couldn't split: {}", reason));
+                    std::process::exit(2)
+                }
+                Err(_) => {
+                    report_error("split thread panicked");
+                    std::process::exit(2)
+                }
+            }
+            std::process::exit(0)
+        }
+    }
+
+    fn handle_generate_subcommand(matches: &clap::ArgMatches) {
+        debug!("handle_generate_subcommand");
+        fn id_list(matches: &clap::ArgMatches, name: &str) -> Vec<String> {
+            matches
+                .value_of(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect()
+        }
+        fn level_weights(matches: &clap::ArgMatches) -> Option<Vec<u32>> {
+            let weights = matches.value_of("levels")?;
+            match weights.split(',').map(|w| w.trim().parse()).collect() {
+                Ok(weights) => Some(weights),
+                Err(e) => {
+                    report_error(format!("invalid level weights: {}", e));
+                    std::process::exit(2)
+                }
+            }
+        }
+        fn ratio(matches: &clap::ArgMatches, name: &str) -> f64 {
+            let ratio = value_t_or_exit!(matches.value_of(name), f64);
+            if !(0.0..=1.0).contains(&ratio) {
+                report_error(format!("{} has to be between 0.0 and 1.0", name));
+                std::process::exit(2)
+            }
+            ratio
+        }
+        if let Some(file_name) = matches.value_of("output") {
+            let out_path = path::PathBuf::from(file_name);
+            let target_size = value_t_or_exit!(matches.value_of("size"), u64);
+            let seed = value_t_or_exit!(matches.value_of("seed"), u64);
+            let corruption_ratio = ratio(matches, "corrupt");
+            let weights = level_weights(matches);
+            let result = if matches.is_present("text") {
+                let mut config = processor::generator::TextLogGeneratorConfig {
+                    target_size,
+                    corruption_ratio,
+                    seed,
+                    ..Default::default()
+                };
+                if matches.occurrences_of("apps") > 0 {
+                    config.components = id_list(matches, "apps");
+                }
+                if let Some(weights) = weights {
+                    if weights.len() != config.level_weights.len() {
+                        report_error("need 5 level weights for text logs");
+                        std::process::exit(2)
+                    }
+                    config.level_weights.copy_from_slice(&weights);
+                }
+                processor::generator::generate_text_log_file(&config, &out_path)
+                    .map(|stats| format!("{} lines ({} broken)", stats.lines, stats.corrupted))
+            } else {
+                let mut config = dlt::dlt_generator::DltGeneratorConfig {
+                    target_size,
+                    ecus: id_list(matches, "ecus"),
+                    apps: id_list(matches, "apps"),
+                    contexts: id_list(matches, "contexts"),
+                    non_verbose_ratio: ratio(matches, "non_verbose"),
+                    corruption_ratio,
+                    seed,
+                    ..Default::default()
+                };
+                if let Some(weights) = weights {
+                    if weights.len() != config.level_weights.len() {
+                        report_error("need 6 level weights for dlt files");
+                        std::process::exit(2)
+                    }
+                    config.level_weights.copy_from_slice(&weights);
+                }
+                let fibex_path = matches.value_of("fibex").map(path::PathBuf::from);
+                if config.non_verbose_ratio > 0.0 && fibex_path.is_none() {
+                    report_warning("non-verbose messages are generated without a FIBEX file");
+                }
+                dlt::dlt_generator::generate_dlt_file(config, &out_path, fibex_path.as_deref()).map(
+                    |stats| {
+                        format!(
+                            "{} messages ({} non-verbose, {} corrupted)",
+                            stats.messages, stats.non_verbose, stats.corrupted
+                        )
+                    },
+                )
+            };
+            match result {
+                Ok(summary) => println!("generated {:?}: {}", out_path, summary),
+                Err(e) => {
+                    report_error(format!("couldn't generate {:?}: {}", out_path, e));
+                    std::process::exit(2)
+                }
+            }
+            std::process::exit(0)
+        }
+    }
+
     fn handle_discover_subcommand(matches: &clap::ArgMatches) {
         if let Some(test_string) = matches.value_of("input-string") {
             match detect_timestamp_in_string(test_string, None) {
@@ -1053,6 +2146,8 @@ fn main() {
             ) = unbounded();
             let items: Vec<DiscoverItem> = vec![DiscoverItem {
                 path: file_name_string,
+                encoding: matches.value_of("encoding").map(String::from),
+                regex: matches.value_of("regex").map(String::from),
             }];
 
             let progress_bar = initialize_progress_bar(100);
@@ -1187,6 +2282,44 @@ fn main() {
         }
     }
 
+    fn handle_archive_subcommand(matches: &clap::ArgMatches) {
+        let file_name = matches.value_of("input").expect("input must be present");
+        let archive_path = path::PathBuf::from(file_name);
+        let entries = match list_archive(&archive_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report_error(format!("could not list {:?}: {}", archive_path, e));
+                std::process::exit(2)
+            }
+        };
+        if matches.is_present("json") {
+            match serde_json::to_string(&entries) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    report_error(format!("serializing entries failed: {}", e));
+                    std::process::exit(2)
+                }
+            }
+            return;
+        }
+        for entry in entries {
+            let file_type = match entry.file_type {
+                FileType::Dlt => "dlt",
+                FileType::Pcap => "pcap",
+                FileType::PcapNg => "pcapng",
+                FileType::Text => "text",
+                FileType::Archive => "archive",
+                FileType::Binary => "binary",
+            };
+            println!(
+                "{:<8}{:>12}  {}",
+                file_type,
+                entry.size,
+                entry.source_path(&archive_path).display()
+            );
+        }
+    }
+
     fn handle_dlt_stats_subcommand(
         matches: &clap::ArgMatches,
         start: std::time::Instant,
@@ -1194,15 +2327,8 @@ fn main() {
     ) {
         let file_name = matches.value_of("input").expect("input must be present");
         let file_path = path::PathBuf::from(file_name);
-        let f = match fs::File::open(&file_path) {
-            Ok(file) => file,
-            Err(_) => {
-                report_error(format!("could not open {:?}", file_path));
-                std::process::exit(2)
-            }
-        };
-        let source_file_size = match f.metadata() {
-            Ok(file_meta) => file_meta.len() as usize,
+        let source_file_size = match input_size(&file_path) {
+            Ok(size) => size as usize,
             Err(_) => {
                 report_error("could not find out size of source file");
                 std::process::exit(2);
@@ -1278,6 +2404,114 @@ fn main() {
             }
         }
     }
+
+    fn handle_text_stats_subcommand(
+        matches: &clap::ArgMatches,
+        start: std::time::Instant,
+        status_updates: bool,
+    ) {
+        let file_name = matches.value_of("input").expect("input must be present");
+        let file_path = path::PathBuf::from(file_name);
+        let source_file_size = match input_size(&file_path) {
+            Ok(size) => size as usize,
+            Err(_) => {
+                report_error("could not find out size of source file");
+                std::process::exit(2);
+            }
+        };
+        let mut config = match matches.value_of("config") {
+            Some(config) => match fs::File::open(config)
+                .map_err(Error::from)
+                .and_then(|mut f| read_text_stats_config(&mut f))
+            {
+                Ok(config) => config,
+                Err(e) => {
+                    report_error(format!("could not read config {}: {}", config, e));
+                    std::process::exit(2);
+                }
+            },
+            None => Default::default(),
+        };
+        // the arguments override the config file
+        if let Some(pattern) = matches.value_of("level_pattern") {
+            config.level_pattern = Some(pattern.to_string());
+        }
+        if let Some(pattern) = matches.value_of("source_pattern") {
+            config.source_pattern = Some(pattern.to_string());
+        }
+        if let Some(format) = matches.value_of("format") {
+            config.format = Some(format.to_string());
+        }
+        if let Some(label) = matches.value_of("encoding") {
+            config.encoding = Some(label.to_string());
+        }
+        if let Some(top) = matches.value_of("top") {
+            config.top_sources = match top.parse() {
+                Ok(n) => n,
+                Err(_) => {
+                    report_error(format!("invalid number of sources: {}", top));
+                    std::process::exit(2);
+                }
+            };
+        }
+        let progress_bar = initialize_progress_bar(source_file_size as u64);
+        let (tx, rx): (
+            cc::Sender<TextStatisticsResults>,
+            cc::Receiver<TextStatisticsResults>,
+        ) = unbounded();
+
+        thread::spawn(move || {
+            if let Err(why) = get_text_file_info(&file_path, &config, &tx, None) {
+                report_error(format!("couldn't collect statistics: {}", why));
+                std::process::exit(2)
+            }
+        });
+        loop {
+            match rx.recv() {
+                Ok(Ok(IndexingProgress::GotItem { item: res })) => {
+                    match serde_json::to_string(&res) {
+                        Ok(stats) => println!("{}", stats),
+                        Err(e) => {
+                            report_error(format!("serializing result {:?} failed: {}", res, e));
+                            std::process::exit(2)
+                        }
+                    }
+                    if status_updates {
+                        duration_report(
+                            start,
+                            format!("collecting statistics for {} lines", res.lines),
+                        );
+                    }
+                }
+                Ok(Ok(IndexingProgress::Progress { ticks: t })) => {
+                    progress_bar.set_position(t.0 as u64);
+                }
+                Ok(Ok(IndexingProgress::Finished)) => {
+                    progress_bar.finish_and_clear();
+                    break;
+                }
+                Ok(Err(Notification {
+                    severity,
+                    content,
+                    line,
+                })) => {
+                    if severity == Severity::WARNING {
+                        report_warning_ln(content, line);
+                    } else {
+                        report_error_ln(content, line);
+                    }
+                }
+                Ok(Ok(IndexingProgress::Stopped)) => {
+                    report_warning("IndexingProgress::Stopped");
+                    break;
+                }
+                Err(_) => {
+                    report_error("couldn't process");
+                    std::process::exit(2)
+                }
+            }
+        }
+    }
 }
 
 fn duration_report(start: std::time::Instant, report: String) {
@@ -1312,6 +2546,35 @@ fn load_test_fibex() -> Option<FibexMetadata> {
         }),
     )
 }
+/// prints the chunks of a followed file as they come in
+fn print_followed_chunks(rx: cc::Receiver<ChunkResults>) {
+    loop {
+        match rx.recv() {
+            Ok(Ok(IndexingProgress::GotItem { item: chunk })) => {
+                println!(
+                    "lines {} - {} (bytes {} - {})",
+                    chunk.r.0, chunk.r.1, chunk.b.0, chunk.b.1
+                )
+            }
+            Ok(Err(Notification {
+                severity,
+                content,
+                line,
+            })) => {
+                if severity == Severity::WARNING {
+                    report_warning_ln(content, line);
+                } else {
+                    report_error_ln(content, line);
+                }
+            }
+            Ok(Ok(IndexingProgress::Stopped)) | Ok(Ok(IndexingProgress::Finished)) | Err(_) => {
+                break
+            }
+            Ok(Ok(IndexingProgress::Progress { .. })) => (),
+        }
+    }
+}
+
 fn initialize_progress_bar(len: u64) -> ProgressBar {
     let progress_bar = ProgressBar::new(len);
     progress_bar.set_style(ProgressStyle::default_bar()
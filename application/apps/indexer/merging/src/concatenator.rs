@@ -13,6 +13,8 @@ use crossbeam_channel as cc;
 use failure::err_msg;
 use indexer_base::chunks::ChunkFactory;
 use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::{input_size, open_input, InputReader};
+use indexer_base::encoding::{resolve_encoding, text_reader, TextReader, UTF_8};
 use indexer_base::progress::IndexingProgress;
 use indexer_base::utils;
 use serde::{Deserialize, Serialize};
@@ -25,6 +27,9 @@ use std::path::PathBuf;
 pub struct ConcatItemOptions {
     path: String,
     tag: String,
+    /// label of the encoding (e.g. `latin1`), detected if not present
+    #[serde(default)]
+    encoding: Option<String>,
 }
 
 pub fn read_concat_options(f: &mut fs::File) -> Result<Vec<ConcatItemOptions>, failure::Error> {
@@ -39,6 +44,8 @@ pub fn read_concat_options(f: &mut fs::File) -> Result<Vec<ConcatItemOptions>, f
 pub struct ConcatenatorInput {
     path: String,
     tag: String,
+    #[serde(default)]
+    encoding: Option<String>,
 }
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ConcatenatorResult {
@@ -67,6 +74,7 @@ pub fn concat_files_use_config_file(
                 .to_string_lossy()
                 .into(),
             tag: o.tag,
+            encoding: o.encoding,
         })
         .collect();
     concat_files(
@@ -104,8 +112,8 @@ pub fn concat_files(
 
     let combined_source_file_size = concat_inputs.iter().try_fold(0, |acc, i| {
         let f = &PathBuf::from(i.path.clone());
-        match fs::metadata(f) {
-            Ok(metadata) => Ok(acc + metadata.len()),
+        match input_size(f) {
+            Ok(size) => Ok(acc + size),
             Err(e) => Err(err_msg(format!(
                 "error getting size of file {:?} ({})",
                 f, e
@@ -118,8 +126,18 @@ pub fn concat_files(
             update_channel.send(Ok(IndexingProgress::Stopped))?;
             return Ok(());
         }
-        let f: fs::File = fs::File::open(input.path)?;
-        let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+        let path = PathBuf::from(input.path);
+        let encoding = resolve_encoding(input.encoding.as_deref(), &path)?;
+        let f = open_input(&path)?;
+        // compressed or transcoded files: progress in bytes read from the file
+        let read_bytes = if encoding == UTF_8 {
+            f.compressed_input()
+        } else {
+            Some(f.consumed())
+        };
+        let file_start = processed_bytes;
+        let mut reader: BufReader<TextReader<InputReader>> =
+            BufReader::new(text_reader(f, encoding));
         let mut buf = vec![];
         while let Ok(len) = reader.read_until(b'\n', &mut buf) {
             if len == 0 {
@@ -127,7 +145,10 @@ pub fn concat_files(
                 break;
             };
             let original_line_length = len;
-            processed_bytes += original_line_length;
+            processed_bytes = match &read_bytes {
+                Some(read) => file_start + read.get(),
+                None => processed_bytes + original_line_length,
+            };
             let s = unsafe { std::str::from_utf8_unchecked(&buf) };
             let trimmed_line = s.trim_matches(utils::is_newline);
 
@@ -13,14 +13,18 @@ use crossbeam_channel as cc;
 use failure::err_msg;
 use indexer_base::chunks::ChunkFactory;
 use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::{input_size, open_input, ConsumedBytes, InputReader};
+use indexer_base::encoding::{open_text_input, resolve_encoding, text_reader, TextReader, UTF_8};
 use indexer_base::error_reporter::*;
-use indexer_base::progress::IndexingProgress;
+use indexer_base::progress::{IndexingProgress, Notification, Severity};
 use indexer_base::timedline::*;
 use indexer_base::utils;
-use processor::parse::{line_to_timed_line, lookup_regex_for_format_str};
+use processor::grouping::{RecordGrouping, RecordReader, RecordStart};
+use processor::inference::TimestampInference;
+use processor::json_log::{JsonLine, JsonLog, JsonLogConfig, JSON_FORMAT};
+use processor::parse::{extra_columns, line_to_timed_line, timestamp_regex};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::BinaryHeap;
 use std::fs;
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::iter::{Iterator, Peekable};
@@ -32,7 +36,22 @@ pub struct MergeItemOptions {
     pub offset: Option<i64>,
     pub year: Option<i32>,
     pub tag: String,
+    #[serde(default)]
     pub format: String,
+    /// regex with named groups that is used instead of the format, named groups
+    /// that are not part of the timestamp become columns of the merged lines
+    #[serde(default)]
+    pub regex: Option<String>,
+    /// keys and filters of a file with one JSON object per line
+    /// (the default ones are used for the format `JSON`)
+    #[serde(default)]
+    pub json: Option<JsonLogConfig>,
+    /// group multi-line records (e.g. stack traces) into one merged line
+    #[serde(default)]
+    pub records: Option<RecordStart>,
+    /// label of the encoding (e.g. `shift_jis`), detected if not present
+    #[serde(default)]
+    pub encoding: Option<String>,
 }
 
 pub fn read_merge_options(f: &mut fs::File) -> Result<Vec<MergeItemOptions>, failure::Error> {
@@ -48,40 +67,98 @@ pub struct MergerInput {
     pub path: PathBuf,
     pub offset: Option<i64>,
     pub year: Option<i32>,
+    #[serde(default)]
     pub format: String,
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub json: Option<JsonLogConfig>,
     pub tag: String,
+    #[serde(default)]
+    pub records: Option<RecordStart>,
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+/// how the timestamps of the lines of an input are found
+pub enum LineFormat {
+    /// the regex of a format string or a custom regex
+    Regex(Regex),
+    /// one JSON object per line
+    Json(JsonLog),
 }
 pub struct TimedLineIter<'a, T: Read> {
-    reader: BufReader<T>,
+    reader: RecordReader<BufReader<T>>,
     tag: &'a str,
-    regex: Regex,
+    format: LineFormat,
+    /// extra named groups of a custom regex
+    columns: Vec<String>,
     year: Option<i32>,
     time_offset: Option<i64>,
     last_timestamp: i64,
     pub current_line_nr: usize,
     reporter: Reporter,
+    /// adjusts timestamps of lines without a year (year rollover, clock jumps)
+    inference: TimestampInference,
+    /// line in the input file, used for notifications
+    input_line_nr: usize,
+    /// adjustments are sent as notifications, or reported if there is no channel
+    update_channel: Option<cc::Sender<ChunkResults>>,
 }
 impl<'a, T> TimedLineIter<'a, T>
 where
     T: Read,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         read_from: T,
         tag: &'a str,
-        regex: Regex,
+        format: LineFormat,
         year: Option<i32>,
         time_offset: Option<i64>,
         current_line_nr: usize,
+        grouping: Option<RecordGrouping>,
+        update_channel: Option<cc::Sender<ChunkResults>>,
     ) -> TimedLineIter<'a, T> {
+        let columns = match &format {
+            LineFormat::Regex(regex) => extra_columns(regex),
+            LineFormat::Json(_) => vec![],
+        };
         TimedLineIter {
-            reader: BufReader::new(read_from),
+            reader: RecordReader::new(BufReader::new(read_from), grouping),
             tag,
-            regex,
+            format,
+            columns,
             year,
             time_offset,
             last_timestamp: 0,
             current_line_nr,
             reporter: Default::default(),
+            inference: Default::default(),
+            input_line_nr: 0,
+            update_channel,
+        }
+    }
+    fn notify(&self, notification: Notification) {
+        let content = format!("{}: {}", self.tag, notification.content);
+        match &self.update_channel {
+            Some(channel) => {
+                let _ = channel.send(Err(Notification {
+                    content,
+                    ..notification
+                }));
+            }
+            None => report_warning_ln(content, notification.line),
+        }
+    }
+    /// lines without a timestamp get the one of the line before
+    fn untimed_line(&self, content: String, original_length: usize) -> TimedLine {
+        TimedLine {
+            content,
+            tag: self.tag.to_string(),
+            timestamp: self.last_timestamp,
+            original_length,
+            year_was_missing: false,
+            line_nr: self.current_line_nr,
         }
     }
 }
@@ -91,42 +168,90 @@ where
 {
     type Item = TimedLine;
     fn next(&mut self) -> Option<TimedLine> {
-        let mut buf = vec![];
-        match self.reader.read_until(b'\n', &mut buf) {
-            Ok(len) => {
-                if len == 0 {
-                    self.reporter.flush();
-                    return None;
+        loop {
+            let mut buf = vec![];
+            let len = match self.reader.read_record(&mut buf) {
+                Ok(len) => len,
+                Err(_) => return None,
+            };
+            if len == 0 {
+                self.reporter.flush();
+                return None;
+            }
+            let s = String::from_utf8_lossy(&buf);
+            let trimmed_line = utils::encode_newlines(s.trim_matches(utils::is_newline));
+            let timed_line = match &self.format {
+                LineFormat::Regex(regex) => {
+                    let timed_line = match line_to_timed_line(
+                        &trimmed_line,
+                        len,
+                        self.tag,
+                        regex,
+                        self.year,
+                        self.time_offset,
+                        self.current_line_nr,
+                        &mut self.reporter,
+                    ) {
+                        Ok(mut timed_line) => {
+                            if timed_line.year_was_missing {
+                                let (timestamp, notification) = self
+                                    .inference
+                                    .adjust(timed_line.timestamp, self.input_line_nr);
+                                timed_line.timestamp = timestamp;
+                                if let Some(notification) = notification {
+                                    self.notify(notification);
+                                }
+                                // only a year that had to be inferred is reported as missing
+                                timed_line.year_was_missing = self.year.is_none();
+                            }
+                            timed_line
+                        }
+                        Err(_) => self.untimed_line(trimmed_line.to_string(), len),
+                    };
+                    if self.columns.is_empty() {
+                        Some(timed_line)
+                    } else {
+                        Some(TimedLine {
+                            content: line_with_columns(&timed_line.content, regex, &self.columns),
+                            ..timed_line
+                        })
+                    }
                 }
-                let original_line_length = len;
-                let s = unsafe { std::str::from_utf8_unchecked(&buf) };
-                let trimmed_line = s.trim_matches(utils::is_newline);
-                let timed_line = line_to_timed_line(
-                    trimmed_line,
-                    original_line_length,
-                    self.tag,
-                    &self.regex,
-                    self.year,
-                    self.time_offset,
-                    self.current_line_nr,
-                    &mut self.reporter,
-                )
-                .unwrap_or_else(|_| TimedLine {
-                    content: trimmed_line.to_string(),
-                    tag: self.tag.to_string(),
-                    timestamp: self.last_timestamp,
-                    original_length: original_line_length,
-                    year_was_missing: false,
-                    line_nr: self.current_line_nr,
-                });
+                LineFormat::Json(json) => match json.parse_line(&trimmed_line) {
+                    JsonLine::Entry { timestamp, content } => Some(TimedLine {
+                        // like for text lines the offset (ms) is applied
+                        timestamp: timestamp
+                            .map(|ts| ts - self.time_offset.unwrap_or(0) * utils::NANOS_PER_MILLI)
+                            .unwrap_or(self.last_timestamp),
+                        ..self.untimed_line(content, len)
+                    }),
+                    JsonLine::FilteredOut => None,
+                    JsonLine::Text => Some(self.untimed_line(trimmed_line.to_string(), len)),
+                },
+            };
+            self.input_line_nr += buf.iter().filter(|b| **b == b'\n').count().max(1);
+            if let Some(timed_line) = timed_line {
                 self.last_timestamp = timed_line.timestamp;
                 self.current_line_nr += 1;
-                Some(timed_line)
+                return Some(timed_line);
             }
-            Err(_) => None,
         }
     }
 }
+/// the values of the extra named groups as columns in front of the line
+/// (empty if the line does not match)
+fn line_with_columns(line: &str, regex: &Regex, columns: &[String]) -> String {
+    let captures = regex.captures(line);
+    let mut content = String::new();
+    for column in columns {
+        if let Some(value) = captures.as_ref().and_then(|caps| caps.name(column)) {
+            content.push_str(value.as_str());
+        }
+        content.push(utils::COLUMN_SENTINAL);
+    }
+    content.push_str(line);
+    content
+}
 #[allow(clippy::too_many_arguments)]
 pub fn merge_files_use_config_file(
     config_path: &PathBuf,
@@ -148,7 +273,11 @@ pub fn merge_files_use_config_file(
             offset: o.offset,
             year: o.year,
             format: o.format,
+            regex: o.regex,
+            json: o.json,
             tag: o.tag,
+            records: o.records,
+            encoding: o.encoding,
         })
         .collect();
     merge_files_iter(
@@ -167,7 +296,7 @@ pub fn merge_and_sort_files(
     append: bool,
     chunk_size: usize, // used for mapping line numbers to byte positions
 ) -> Result<(), failure::Error> {
-    let mut heap: BinaryHeap<TimedLine> = BinaryHeap::new();
+    let mut lines: Vec<TimedLine> = Vec::new();
     let mut line_nr = 0;
     let out_file: std::fs::File = if append {
         std::fs::OpenOptions::new().append(true).open(out_path)?
@@ -185,17 +314,21 @@ pub fn merge_and_sort_files(
     for input in merger_inputs {
         // let kind: RegexKind = detect_timestamp_regex(&input.path)?;
         // let r: &Regex = &REGEX_REGISTRY[&kind];
-        let r = lookup_regex_for_format_str(&input.format)?;
-        let f: fs::File = fs::File::open(input.path)?;
-        let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+        let r = timestamp_regex(&input.format, input.regex.as_deref())?;
+        let columns = extra_columns(&r);
+        let encoding = resolve_encoding(input.encoding.as_deref(), &input.path)?;
+        let f = open_text_input(&input.path, Some(encoding))?;
+        let mut reader: BufReader<TextReader<InputReader>> = BufReader::new(f);
         let mut buf = vec![];
         let mut last_timestamp: i64 = 0;
+        let mut inference: TimestampInference = Default::default();
+        let mut input_line_nr = 0;
         while let Ok(len) = reader.read_until(b'\n', &mut buf) {
             if len == 0 {
                 // no more content
                 break;
             };
-            let s = unsafe { std::str::from_utf8_unchecked(&buf) };
+            let s = String::from_utf8_lossy(&buf);
             let trimmed_line = s.trim_matches(utils::is_newline);
             let alt_tag = input.tag.clone();
             let timed_line = line_to_timed_line(
@@ -208,6 +341,22 @@ pub fn merge_and_sort_files(
                 line_nr,
                 &mut reporter,
             )
+            .map(|mut timed_line| {
+                if timed_line.year_was_missing {
+                    let (timestamp, notification) =
+                        inference.adjust(timed_line.timestamp, input_line_nr);
+                    timed_line.timestamp = timestamp;
+                    if let Some(notification) = notification {
+                        report_warning_ln(
+                            format!("{}: {}", input.tag, notification.content),
+                            notification.line,
+                        );
+                    }
+                    // only a year that had to be inferred is reported as missing
+                    timed_line.year_was_missing = input.year.is_none();
+                }
+                timed_line
+            })
             .unwrap_or_else(|_| {
                 lines_where_we_reuse_previous_date += 1;
                 TimedLine {
@@ -223,13 +372,21 @@ pub fn merge_and_sort_files(
                 lines_with_year_missing += 1
             }
             last_timestamp = timed_line.timestamp;
-            heap.push(timed_line);
+            if columns.is_empty() {
+                lines.push(timed_line);
+            } else {
+                lines.push(TimedLine {
+                    content: line_with_columns(&timed_line.content, &r, &columns),
+                    ..timed_line
+                });
+            }
+            input_line_nr += 1;
             buf = vec![];
         }
     }
     if lines_with_year_missing > 0 {
         report_warning(format!(
-            "year was missing for {} lines",
+            "year was missing for {} lines and had to be inferred",
             lines_with_year_missing
         ));
     }
@@ -239,8 +396,9 @@ pub fn merge_and_sort_files(
             lines_where_we_reuse_previous_date
         ));
     }
-    let sorted = heap.into_sorted_vec();
-    for t in sorted {
+    // stable, lines with the same timestamp keep the order of the inputs
+    lines.sort_by_key(|t| t.timestamp);
+    for t in lines {
         let additional_bytes = utils::write_tagged_line(
             &t.tag[..],
             &mut buf_writer,
@@ -294,38 +452,92 @@ pub fn merge_files_iter(
     let mut stopped = false;
 
     let mut progress_percentage = 0usize;
+    // for compressed or transcoded inputs and JSON lines (that can be filtered
+    // out) the progress is based on the bytes read from the files
+    let mut read_bytes: Vec<ConsumedBytes> = vec![];
+    let mut track_read_bytes = false;
     // create a peekable iterator for all file inputs
-    let mut readers: Vec<Peekable<TimedLineIter<fs::File>>> = merger_inputs
-        .iter()
-        .map(|input| {
-            fs::File::open(&input.path)
-                .map_err(failure::Error::from)
-                .and_then(|f| {
-                    let r: Regex = lookup_regex_for_format_str(&input.format)?;
-                    Ok(TimedLineIter::new(
-                        f,
-                        input.tag.as_str(),
-                        r,
-                        input.year,
-                        input.offset,
-                        line_nr,
-                    )
-                    .peekable())
-                })
-        })
-        .filter_map(Result::ok) // TODO better error handling
-        .collect();
+    let readers: Result<Vec<Peekable<TimedLineIter<TextReader<InputReader>>>>, failure::Error> =
+        merger_inputs
+            .iter()
+            .map(|input| {
+                open_input(&input.path)
+                    .and_then(|f| {
+                        let encoding = resolve_encoding(input.encoding.as_deref(), &input.path)?;
+                        track_read_bytes =
+                            track_read_bytes || f.is_compressed() || encoding != UTF_8;
+                        read_bytes.push(f.consumed());
+                        let json = match &input.json {
+                            Some(json) => Some(json.clone()),
+                            None if input.format == JSON_FORMAT => Some(JsonLogConfig::default()),
+                            None => None,
+                        };
+                        track_read_bytes = track_read_bytes || json.is_some();
+                        let (format, grouping) = match json {
+                            // every JSON object is a record of its own
+                            Some(json) => (LineFormat::Json(JsonLog::new(json)?), None),
+                            None => {
+                                let r: Regex =
+                                    timestamp_regex(&input.format, input.regex.as_deref())?;
+                                let grouping = match &input.records {
+                                    Some(RecordStart::Timestamp) if input.regex.is_some() => {
+                                        Some(RecordGrouping::with_regex(r.clone()))
+                                    }
+                                    Some(records) => Some(RecordGrouping::new(
+                                        records,
+                                        Some(&input.format),
+                                        &input.path,
+                                        Some(encoding),
+                                    )?),
+                                    None => None,
+                                };
+                                (LineFormat::Regex(r), grouping)
+                            }
+                        };
+                        Ok(TimedLineIter::new(
+                            text_reader(f, encoding),
+                            input.tag.as_str(),
+                            format,
+                            input.year,
+                            input.offset,
+                            line_nr,
+                            grouping,
+                            Some(update_channel.clone()),
+                        )
+                        .peekable())
+                    })
+                    .map_err(|e| err_msg(format!("could not merge {:?} ({})", input.path, e)))
+            })
+            .collect();
+    let mut readers = match readers {
+        Ok(readers) => readers,
+        Err(e) => {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: e.to_string(),
+                line: None,
+            }));
+            return Err(e);
+        }
+    };
     // MergerInput
     let combined_source_file_size = merger_inputs.iter().try_fold(0, |acc, i| {
         let f = &i.path.clone();
-        match fs::metadata(f) {
-            Ok(metadata) => Ok(acc + metadata.len()),
+        match input_size(f) {
+            Ok(size) => Ok(acc + size),
             Err(e) => Err(err_msg(format!(
                 "error getting size of file {:?} ({})",
                 f, e
             ))),
         }
     })?;
+    let progress_bytes = |processed_bytes: usize| -> usize {
+        if track_read_bytes {
+            read_bytes.iter().map(ConsumedBytes::get).sum()
+        } else {
+            processed_bytes
+        }
+    };
 
     let mut buf_writer = BufWriter::with_capacity(100 * 1024 * 1024, out_file);
     loop {
@@ -333,7 +545,8 @@ pub fn merge_files_iter(
             info!("we where stopped while merging");
             break;
         }
-        // keep track of the min timestamp together with the index of the file it belongs to
+        // keep track of the min timestamp together with the index of the file it belongs to,
+        // for equal timestamps (nanoseconds) the earlier input comes first
         let mut minimum: Option<(i64, usize)> = None;
         for (i, iter) in readers.iter_mut().enumerate() {
             if let Some(line) = iter.peek() {
@@ -347,14 +560,14 @@ pub fn merge_files_iter(
                         minimum = Some((line.timestamp, i));
                     }
                 }
-                if line.year_was_missing {
-                    lines_with_year_missing += 1
-                }
             }
         }
         if let Some((_, min_index)) = minimum {
             // we found a line with a minimal timestamp
             if let Some(line) = readers[min_index].next() {
+                if line.year_was_missing {
+                    lines_with_year_missing += 1
+                }
                 // important: keep track of how many bytes we processed
                 processed_bytes += line.original_length;
                 let trimmed_len = line.content.len();
@@ -378,13 +591,14 @@ pub fn merge_files_iter(
                         update_channel.send(Ok(IndexingProgress::GotItem { item: chunk }))?;
                     }
 
+                    let progress_bytes = progress_bytes(processed_bytes);
                     let new_progress_percentage: usize =
-                        (processed_bytes as f64 / combined_source_file_size as f64 * 100.0).round()
+                        (progress_bytes as f64 / combined_source_file_size as f64 * 100.0).round()
                             as usize;
                     if new_progress_percentage != progress_percentage {
                         progress_percentage = new_progress_percentage;
                         update_channel.send(Ok(IndexingProgress::Progress {
-                            ticks: (processed_bytes, combined_source_file_size as usize),
+                            ticks: (progress_bytes, combined_source_file_size as usize),
                         }))?;
                     }
                 }
@@ -399,9 +613,18 @@ pub fn merge_files_iter(
         debug!("sending IndexingProgress::Stopped");
         update_channel.send(Ok(IndexingProgress::Stopped))?;
     } else {
+        // the last lines could have been filtered out
+        let progress_bytes = progress_bytes(processed_bytes);
+        let new_progress_percentage: usize =
+            (progress_bytes as f64 / combined_source_file_size as f64 * 100.0).round() as usize;
+        if new_progress_percentage != progress_percentage {
+            update_channel.send(Ok(IndexingProgress::Progress {
+                ticks: (progress_bytes, combined_source_file_size as usize),
+            }))?;
+        }
         if lines_with_year_missing > 0 {
             report_warning(format!(
-                "year was missing for {} lines",
+                "year was missing for {} lines and had to be inferred",
                 lines_with_year_missing
             ));
         }
@@ -7,7 +7,7 @@ mod tests {
     use std::path::PathBuf;
     use tempdir::TempDir;
     use indexer_base::chunks::ChunkResults;
-    use indexer_base::progress::{IndexingProgress, Notification};
+    use indexer_base::progress::{IndexingProgress, Notification, Severity};
     use crossbeam_channel as cc;
     extern crate log;
 
@@ -107,5 +107,106 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_configured_year_is_not_missing() {
+        let content = "Dec 31 23:59:59 last\nJan  1 00:00:01 first\n";
+        let years_missing = |year: Option<i32>| -> Vec<bool> {
+            let regex = processor::parse::timestamp_regex("MMM DD hh:mm:ss", None).unwrap();
+            TimedLineIter::new(
+                content.as_bytes(),
+                "TAG",
+                LineFormat::Regex(regex),
+                year,
+                Some(0),
+                0,
+                None,
+                None,
+            )
+            .map(|line| line.year_was_missing)
+            .collect()
+        };
+        assert_eq!(vec![false, false], years_missing(Some(2019)));
+        assert_eq!(vec![true, true], years_missing(None));
+    }
+
+    #[test]
+    fn test_merge_fails_for_invalid_input() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let out_file_path = tmp_dir.path().join("merged.out");
+        let samples = PathBuf::from("..").join("merging/test_samples/custom_regex");
+        let input = |name: &str, regex: &str| MergerInput {
+            path: samples.join(name),
+            offset: Some(0),
+            year: None,
+            format: "YYYY-MM-DD hh:mm:ss.s".to_string(),
+            regex: Some(regex.to_string()),
+            json: None,
+            tag: name.to_string(),
+            records: None,
+            encoding: None,
+        };
+        let inputs = vec![
+            input(
+                "a.log",
+                r"\[(?P<day>\d{2})\.(?P<month>\d{2})\.(?P<year>\d{4}) (?P<hour>\d{2}):(?P<minute>\d{2}):(?P<second>\d{2})",
+            ),
+            input("b.log", "(?P<year>"),
+        ];
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        let result = merge_files_iter(false, inputs, &out_file_path, 5, tx, None);
+        assert!(result.is_err());
+        match rx.try_recv() {
+            Ok(Err(Notification {
+                severity: Severity::ERROR,
+                content,
+                ..
+            })) => assert!(content.contains("b.log"), "{}", content),
+            _ => panic!("expected an error notification"),
+        }
+    }
+
+    #[test]
+    fn test_merge_progress_with_filtered_json_lines() {
+        let tmp_dir = TempDir::new("test_dir").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("app.log");
+        let out_file_path = tmp_dir.path().join("merged.out");
+        // the last line is filtered out
+        fs::write(
+            &in_path,
+            r#"{"time":"2020-03-14T10:00:00.000Z","level":"info","msg":"started"}
+{"time":"2020-03-14T10:00:01.000Z","level":"debug","msg":"connecting"}
+"#,
+        )
+        .unwrap();
+        let input = MergerInput {
+            path: in_path,
+            offset: Some(0),
+            year: None,
+            format: processor::json_log::JSON_FORMAT.to_string(),
+            regex: None,
+            json: Some(processor::json_log::JsonLogConfig {
+                min_level: Some("info".to_string()),
+                ..Default::default()
+            }),
+            tag: "TAG".to_string(),
+            records: None,
+            encoding: None,
+        };
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = cc::unbounded();
+        merge_files_iter(false, vec![input], &out_file_path, 5, tx, None)
+            .expect("merging should succeed");
+        let last_ticks = rx
+            .try_iter()
+            .filter_map(|progress| match progress {
+                Ok(IndexingProgress::Progress { ticks }) => Some(ticks),
+                _ => None,
+            })
+            .last();
+        match last_ticks {
+            Some((processed, total)) => assert_eq!(total, processed),
+            None => panic!("no progress was reported"),
+        }
+    }
+
     // TODO test files with lines without timestamp
 }
@@ -79,5 +79,32 @@ fn parse_benchmark(c: &mut Criterion) {
     });
 }
 
+fn generated_log_benchmark(c: &mut Criterion) {
+    let mut content: Vec<u8> = vec![];
+    processor::generator::generate_text_log(
+        &processor::generator::TextLogGeneratorConfig {
+            target_size: 1024 * 1024,
+            corruption_ratio: 0.05,
+            ..Default::default()
+        },
+        &mut content,
+    )
+    .expect("could not generate log");
+    let content = String::from_utf8(content).expect("generated log is not utf8");
+    c.bench_function("detect_timestamp_in_string 1 MB generated log", move |b| {
+        b.iter(|| {
+            content
+                .lines()
+                .filter(|line| detect_timestamp_in_string(line, Some(0)).is_ok())
+                .count()
+        })
+    });
+}
+
 criterion_group!(benches, parse_benchmark);
-criterion_main!(benches);
+criterion_group! {
+    name = generated;
+    config = Criterion::default().sample_size(10);
+    targets = generated_log_benchmark
+}
+criterion_main!(benches, generated);
@@ -0,0 +1,325 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::json_log::{is_json_lines, JsonLine, JsonLog, JsonLogConfig, JSON_FORMAT};
+use crate::parse::{lookup_regex_for_format_str, timestamp_formats_in_string};
+use crate::stats::{builtin_level_field, LOGCAT_FIELD, LOGCAT_THREADTIME_FIELD};
+use failure::Error;
+use indexer_base::archive::FileType;
+use indexer_base::compression::{detect_compression, open_input, Compression};
+use indexer_base::encoding::{detect_encoding_in_sample, open_text_input, Encoding};
+use regex::Regex;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// number of (decompressed) bytes that are looked at to detect binary formats
+const SNIFF_LENGTH: usize = 64 * 1024;
+/// number of lines of a text file that are inspected
+const LINES_TO_INSPECT: usize = 1000;
+/// share of the lines that have to match to classify a text file as syslog or logcat
+const KIND_THRESHOLD: f32 = 0.5;
+/// share of the sniffed bytes that have to be DLT messages for a DLT stream
+const DLT_STREAM_THRESHOLD: f32 = 0.9;
+/// a single header with a length that covers the sample is easily found in
+/// random bytes, a stream has to start with a few messages
+const MIN_DLT_STREAM_MESSAGES: usize = 2;
+/// number of candidates that are reported
+const MAX_CANDIDATES: usize = 3;
+const DLT_SERIAL_HEADER: &[u8] = b"DLS\x01";
+/// name of the level field of JSON lines
+pub const JSON_LEVEL_FIELD: &str = "level";
+/// start of the lines logcat writes when it switches to another buffer
+const LOGCAT_BUFFER_MARKER: &str = "--------- ";
+/// record grouping for lines without a timestamp (see `RecordStart::Timestamp`)
+pub const TIMESTAMP_GROUPING: &str = "timestamp";
+
+lazy_static! {
+    /// BSD syslog (`Jul 30 10:08:02 host prog[42]: `) with an optional priority,
+    /// or RFC 5424 (`<34>1 2019-07-30T10:08:02Z host app - - `)
+    static ref SYSLOG_REGEX: Regex = Regex::new(
+        r"^(?:(?:<\d{1,3}>\d?\s?)?(?:[A-Z][a-z]{2}\s+\d{1,2}\s+\d{2}:\d{2}:\d{2}|\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\S*)\s+\S+\s+[^\s:\[]+(?:\[\d+\])?:\s|<\d{1,3}>1\s+\S+\s+\S+\s+\S+\s+\S+\s+\S+\s)"
+    )
+    .unwrap();
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    /// DLT with storage headers
+    Dlt,
+    /// DLT messages without storage headers (as received over the network or serial line)
+    DltStream,
+    Pcap,
+    PcapNg,
+    JsonLines,
+    Syslog,
+    Logcat,
+    Text,
+    /// an archive in an archive, cannot be opened directly
+    Archive,
+    Binary,
+}
+
+impl FileKind {
+    /// files that are indexed line by line
+    pub fn is_text(self) -> bool {
+        self == FileKind::JsonLines
+            || self == FileKind::Syslog
+            || self == FileKind::Logcat
+            || self == FileKind::Text
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub value: String,
+    /// share of the inspected lines that match (0 to 1)
+    pub confidence: f32,
+}
+
+/// what kind of file it is and, for text files, suggestions for the timestamp
+/// format, the level field and the record grouping (best candidates first)
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FormatDiscovery {
+    pub kind: FileKind,
+    pub confidence: f32,
+    pub compression: Compression,
+    pub timestamp_formats: Vec<Candidate>,
+    /// names of the built-in level patterns (see `stats`)
+    pub level_fields: Vec<Candidate>,
+    pub record_grouping: Option<Candidate>,
+}
+
+impl FormatDiscovery {
+    fn binary(kind: FileKind, confidence: f32, compression: Compression) -> FormatDiscovery {
+        FormatDiscovery {
+            kind,
+            confidence,
+            compression,
+            timestamp_formats: vec![],
+            level_fields: vec![],
+            record_grouping: None,
+        }
+    }
+}
+
+/// rounded to 2 digits
+fn share(count: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (count as f32 / total as f32 * 100.0).round() / 100.0
+}
+
+/// the values with the most matches first (the first seen value for the same count)
+fn candidates(counts: Vec<(&str, usize)>, total: usize) -> Vec<Candidate> {
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().filter(|(_, n)| *n > 0).collect();
+    counts.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    counts
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .map(|(value, n)| Candidate {
+            value: value.to_string(),
+            confidence: share(n, total),
+        })
+        .collect()
+}
+
+fn count(counts: &mut Vec<(&'static str, usize)>, value: &'static str) {
+    match counts.iter_mut().find(|(v, _)| *v == value) {
+        Some((_, n)) => *n += 1,
+        None => counts.push((value, 1)),
+    }
+}
+
+/// share of the bytes that can be read as consecutive DLT messages
+/// (standard header with an optional serial header), only messages that
+/// end inside the sample are counted
+pub fn dlt_stream_share(start: &[u8]) -> f32 {
+    let mut offset = 0usize;
+    let mut messages = 0usize;
+    loop {
+        let mut header = offset;
+        if start[header..].starts_with(DLT_SERIAL_HEADER) {
+            header += DLT_SERIAL_HEADER.len();
+        }
+        if header + 4 > start.len() {
+            break;
+        }
+        let header_type = start[header];
+        let version = (header_type >> 5) & 0b111;
+        // ECU id, session id and timestamp are optional
+        let min_length = 4 + [0x04u8, 0x08, 0x10]
+            .iter()
+            .filter(|flag| header_type & **flag != 0)
+            .count()
+            * 4;
+        let length = u16::from_be_bytes([start[header + 2], start[header + 3]]) as usize;
+        if version != 1 || length < min_length || header + length > start.len() {
+            break;
+        }
+        messages += 1;
+        offset = header + length;
+        if offset == start.len() {
+            break;
+        }
+    }
+    if messages < MIN_DLT_STREAM_MESSAGES {
+        return 0.0;
+    }
+    share(offset, start.len())
+}
+
+/// classifies a text file from its lines
+pub fn discover_text_format<'a, I: Iterator<Item = &'a str>>(lines: I) -> FormatDiscovery {
+    let lines: Vec<&str> = lines.filter(|l| !l.trim().is_empty()).collect();
+    let total = lines.len();
+    if is_json_lines(lines.iter().cloned()) {
+        return discover_json_format(&lines);
+    }
+    let mut formats: Vec<(&'static str, usize)> = vec![];
+    let mut level_fields: Vec<(&'static str, usize)> = vec![];
+    let mut syslog_lines = 0usize;
+    let mut logcat_lines = 0usize;
+    for line in &lines {
+        for format in timestamp_formats_in_string(line) {
+            count(&mut formats, format);
+        }
+        if let Some(field) = builtin_level_field(line) {
+            count(&mut level_fields, field);
+            if field == LOGCAT_FIELD || field == LOGCAT_THREADTIME_FIELD {
+                logcat_lines += 1;
+            }
+        }
+        if SYSLOG_REGEX.is_match(line) {
+            syslog_lines += 1;
+        }
+    }
+    let timestamp_formats = candidates(formats, total);
+    let record_grouping = timestamp_formats
+        .first()
+        .and_then(|best| record_grouping(&lines, best));
+    let (kind, confidence) =
+        if logcat_lines > syslog_lines && share(logcat_lines, total) >= KIND_THRESHOLD {
+            (FileKind::Logcat, share(logcat_lines, total))
+        } else if share(syslog_lines, total) >= KIND_THRESHOLD {
+            (FileKind::Syslog, share(syslog_lines, total))
+        } else {
+            let matched = std::cmp::max(syslog_lines, logcat_lines);
+            (FileKind::Text, share(total - matched, total))
+        };
+    FormatDiscovery {
+        kind,
+        confidence,
+        compression: Compression::None,
+        timestamp_formats,
+        level_fields: candidates(level_fields, total),
+        record_grouping,
+    }
+}
+
+/// grouping by timestamp if there are lines without one after the first
+/// timestamp, as sure as the format matches (logcat buffer markers like
+/// `--------- beginning of crash` are no continuation lines)
+fn record_grouping(lines: &[&str], best: &Candidate) -> Option<Candidate> {
+    let regex = lookup_regex_for_format_str(&best.value).ok()?;
+    let mut seen_timestamp = false;
+    let mut continuation_lines = 0usize;
+    for line in lines {
+        if regex.is_match(line) {
+            seen_timestamp = true;
+        } else if seen_timestamp && !line.starts_with(LOGCAT_BUFFER_MARKER) {
+            continuation_lines += 1;
+        }
+    }
+    if continuation_lines == 0 {
+        return None;
+    }
+    Some(Candidate {
+        value: TIMESTAMP_GROUPING.to_string(),
+        confidence: best.confidence,
+    })
+}
+
+fn discover_json_format(lines: &[&str]) -> FormatDiscovery {
+    let total = lines.len();
+    let mut objects = 0usize;
+    let mut with_timestamp = 0usize;
+    let mut with_level = 0usize;
+    if let Ok(json) = JsonLog::new(JsonLogConfig::default()) {
+        for line in lines {
+            if let JsonLine::Entry { timestamp, content } = json.parse_line(line) {
+                objects += 1;
+                with_timestamp += timestamp.is_some() as usize;
+                // the level is the second column
+                let level = content.split(indexer_base::utils::COLUMN_SENTINAL).nth(1);
+                with_level += level.map_or(0, |l| !l.is_empty() as usize);
+            }
+        }
+    }
+    FormatDiscovery {
+        kind: FileKind::JsonLines,
+        confidence: share(objects, total),
+        compression: Compression::None,
+        timestamp_formats: candidates(vec![(JSON_FORMAT, with_timestamp)], total),
+        level_fields: candidates(vec![(JSON_LEVEL_FIELD, with_level)], total),
+        record_grouping: None,
+    }
+}
+
+/// what kind of file it is (the content of compressed files and archive entries
+/// is inspected), without `encoding` it is detected for text files
+pub fn discover_format(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<FormatDiscovery, Error> {
+    let compression = detect_compression(path)?;
+    let mut start = Vec::with_capacity(SNIFF_LENGTH);
+    open_input(path)?
+        .take(SNIFF_LENGTH as u64)
+        .read_to_end(&mut start)?;
+    let kind = match FileType::from_content(&start) {
+        FileType::Dlt => FileKind::Dlt,
+        FileType::Pcap => FileKind::Pcap,
+        FileType::PcapNg => FileKind::PcapNg,
+        FileType::Archive => FileKind::Archive,
+        FileType::Text => FileKind::Text,
+        FileType::Binary => {
+            let dlt_share = dlt_stream_share(&start);
+            let sample_encoding = encoding.unwrap_or_else(|| detect_encoding_in_sample(&start));
+            if dlt_share >= DLT_STREAM_THRESHOLD {
+                return Ok(FormatDiscovery::binary(
+                    FileKind::DltStream,
+                    dlt_share,
+                    compression,
+                ));
+            } else if sample_encoding.is_ascii_compatible() {
+                return Ok(FormatDiscovery::binary(FileKind::Binary, 1.0, compression));
+            }
+            // UTF-16
+            FileKind::Text
+        }
+    };
+    if !kind.is_text() {
+        return Ok(FormatDiscovery::binary(kind, 1.0, compression));
+    }
+    let reader = BufReader::new(open_text_input(path, encoding)?);
+    let lines: Vec<String> = reader
+        .lines()
+        .take(LINES_TO_INSPECT)
+        .filter_map(Result::ok)
+        .collect();
+    Ok(FormatDiscovery {
+        compression,
+        ..discover_text_format(lines.iter().map(String::as_str))
+    })
+}
@@ -0,0 +1,140 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use chrono::NaiveDateTime;
+use failure::{err_msg, Error};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub const GENERATED_LEVELS: [&str; 5] = ["ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+
+/// format of the timestamps in generated lines (can be detected by
+/// `parse::detect_timeformat_in_string`)
+pub const GENERATED_TIMESTAMP_FORMAT: &str = "YYYY-MM-DD hh:mm:ss.s";
+
+/// describes what kind of text log should be generated
+#[derive(Debug, Clone)]
+pub struct TextLogGeneratorConfig {
+    /// stop after this many bytes have been written
+    pub target_size: u64,
+    /// names of the components that write log lines
+    pub components: Vec<String>,
+    /// relative frequency of error, warn, info, debug and trace lines
+    pub level_weights: [u32; 5],
+    /// fraction (0.0 - 1.0) of lines without a timestamp (like stack traces
+    /// or broken lines)
+    pub corruption_ratio: f64,
+    /// time of the first line (ms since epoch)
+    pub start_time_ms: i64,
+    /// the same seed always produces the same file
+    pub seed: u64,
+}
+
+impl Default for TextLogGeneratorConfig {
+    fn default() -> Self {
+        TextLogGeneratorConfig {
+            target_size: 1024 * 1024,
+            components: vec![
+                "network".to_string(),
+                "storage".to_string(),
+                "ui".to_string(),
+            ],
+            level_weights: [5, 10, 60, 20, 5],
+            corruption_ratio: 0.0,
+            start_time_ms: 1_580_000_000_000,
+            seed: 0,
+        }
+    }
+}
+
+/// what was actually written
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TextLogStats {
+    pub lines: usize,
+    pub corrupted: usize,
+    pub bytes: u64,
+}
+
+const MESSAGES: [&str; 8] = [
+    "connection established",
+    "received request",
+    "cache miss for key",
+    "retrying operation",
+    "timeout waiting for response",
+    "state machine transition",
+    "buffer usage",
+    "configuration reloaded",
+];
+
+const BROKEN_LINES: [&str; 3] = [
+    "    at com.example.Worker.run(Worker.java:42)",
+    "Caused by: java.io.IOException: broken pipe",
+    "\u{fffd}\u{fffd}@@#!",
+];
+
+pub fn generate_text_log<W: Write>(
+    config: &TextLogGeneratorConfig,
+    out: &mut W,
+) -> Result<TextLogStats, Error> {
+    if config.components.is_empty() {
+        return Err(err_msg("need at least one component"));
+    }
+    let total_weight: u32 = config.level_weights.iter().sum();
+    if total_weight == 0 {
+        return Err(err_msg("at least one log level needs a weight"));
+    }
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut stats = TextLogStats::default();
+    let mut time_ms = config.start_time_ms;
+    while stats.bytes < config.target_size {
+        let line = if rng.gen_bool(config.corruption_ratio) {
+            stats.corrupted += 1;
+            BROKEN_LINES[rng.gen_range(0, BROKEN_LINES.len())].to_string()
+        } else {
+            time_ms += rng.gen_range(0, 50);
+            let timestamp =
+                NaiveDateTime::from_timestamp(time_ms / 1000, (time_ms % 1000) as u32 * 1_000_000);
+            let mut n = rng.gen_range(0, total_weight);
+            let mut level = GENERATED_LEVELS[0];
+            for (i, weight) in config.level_weights.iter().enumerate() {
+                if n < *weight {
+                    level = GENERATED_LEVELS[i];
+                    break;
+                }
+                n -= weight;
+            }
+            format!(
+                "{} {:5} [{}] {} {}",
+                timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+                level,
+                config.components[rng.gen_range(0, config.components.len())],
+                MESSAGES[rng.gen_range(0, MESSAGES.len())],
+                rng.gen_range(0, 100_000),
+            )
+        };
+        writeln!(out, "{}", line)?;
+        stats.bytes += line.len() as u64 + 1;
+        stats.lines += 1;
+    }
+    out.flush()?;
+    Ok(stats)
+}
+
+pub fn generate_text_log_file(
+    config: &TextLogGeneratorConfig,
+    out_path: &Path,
+) -> Result<TextLogStats, Error> {
+    let mut out = BufWriter::new(fs::File::create(out_path)?);
+    generate_text_log(config, &mut out)
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::parse::{detect_timestamp_format_in_file, lookup_regex_for_format_str};
+use failure::{err_msg, Error};
+use indexer_base::encoding::Encoding;
+use indexer_base::utils;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// which lines start a new record, all following lines up to the next start
+/// belong to the record (e.g. the lines of a stack trace)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordStart {
+    /// lines with a timestamp (of the detected or configured format)
+    Timestamp,
+    /// lines that match the regex
+    Pattern(String),
+}
+
+pub struct RecordGrouping {
+    start: Regex,
+}
+
+impl RecordGrouping {
+    /// `format` is needed for `RecordStart::Timestamp`, it is detected from
+    /// the file (read with `encoding`) if not present
+    pub fn new(
+        record_start: &RecordStart,
+        format: Option<&str>,
+        path: &Path,
+        encoding: Option<&'static Encoding>,
+    ) -> Result<RecordGrouping, Error> {
+        match record_start {
+            RecordStart::Timestamp => match format {
+                Some(format) => RecordGrouping::with_format(format),
+                None => {
+                    RecordGrouping::with_format(&detect_timestamp_format_in_file(path, encoding)?)
+                }
+            },
+            RecordStart::Pattern(pattern) => RecordGrouping::with_pattern(pattern),
+        }
+    }
+
+    pub fn with_format(format: &str) -> Result<RecordGrouping, Error> {
+        Ok(RecordGrouping {
+            start: lookup_regex_for_format_str(format)?,
+        })
+    }
+
+    /// records start with a timestamp of the (custom) timestamp regex
+    pub fn with_regex(start: Regex) -> RecordGrouping {
+        RecordGrouping { start }
+    }
+
+    pub fn with_pattern(pattern: &str) -> Result<RecordGrouping, Error> {
+        let start = Regex::new(pattern)
+            .map_err(|e| err_msg(format!("invalid record pattern {}: {}", pattern, e)))?;
+        Ok(RecordGrouping { start })
+    }
+
+    pub fn is_record_start(&self, line: &[u8]) -> bool {
+        let s = String::from_utf8_lossy(line);
+        self.start.is_match(s.trim_matches(utils::is_newline))
+    }
+}
+
+/// reads records instead of lines, without grouping every line is a record
+pub struct RecordReader<R: BufRead> {
+    reader: R,
+    grouping: Option<RecordGrouping>,
+    /// the start of the next record (read to find the end of the current one)
+    next_line: Vec<u8>,
+}
+
+impl<R: BufRead> RecordReader<R> {
+    pub fn new(reader: R, grouping: Option<RecordGrouping>) -> RecordReader<R> {
+        RecordReader {
+            reader,
+            grouping,
+            next_line: vec![],
+        }
+    }
+
+    /// appends the next record (with its newlines) to `buf`, returns the
+    /// number of bytes that were read or 0 at the end
+    pub fn read_record(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let mut len = if self.next_line.is_empty() {
+            self.reader.read_until(b'\n', buf)?
+        } else {
+            let len = self.next_line.len();
+            buf.append(&mut self.next_line);
+            len
+        };
+        let grouping = match &self.grouping {
+            Some(grouping) => grouping,
+            None => return Ok(len),
+        };
+        if len == 0 {
+            return Ok(0);
+        }
+        loop {
+            let line_len = self.reader.read_until(b'\n', &mut self.next_line)?;
+            if line_len == 0 {
+                break;
+            }
+            if grouping.is_record_start(&self.next_line) {
+                break;
+            }
+            len += line_len;
+            buf.append(&mut self.next_line);
+        }
+        Ok(len)
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::parse::{checked_timestamp_nanos, posix_timestamp_as_string};
+use chrono::{Datelike, Duration, NaiveDateTime};
+use indexer_base::progress::{Notification, Severity};
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+const NANOS_PER_MINUTE: i64 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_DAY: i64 = 24 * 60 * NANOS_PER_MINUTE;
+/// going back more than this is taken as a new year (december to january)
+const ROLLOVER_GAP: i64 = 180 * NANOS_PER_DAY;
+/// going back more than this is taken as the clock being set back
+const BACKWARD_JUMP_THRESHOLD: i64 = NANOS_PER_MINUTE;
+/// going forward more than this is reported as a jump of the clock
+const FORWARD_JUMP_THRESHOLD: i64 = NANOS_PER_DAY;
+
+/// Infers the timestamps of a log whose format has no year.
+/// The year is increased when the dates roll over from december to january.
+/// When the clock is set back (e.g. by an NTP correction) the following
+/// timestamps are shifted so that the lines stay in order, a jump forward
+/// by about the same amount removes the shift again.
+#[derive(Default)]
+pub struct TimestampInference {
+    years_added: i32,
+    /// shift (ns) that compensates a clock that was set back
+    shift: i64,
+    /// last timestamp as read (with the added years but without the shift)
+    last_timestamp: Option<i64>,
+}
+
+impl TimestampInference {
+    /// the adjusted timestamp (ns) and a notification if something was adjusted,
+    /// `line_nr` is only used for the notification
+    pub fn adjust(&mut self, timestamp: i64, line_nr: usize) -> (i64, Option<Notification>) {
+        let mut current = add_years(timestamp, self.years_added);
+        let mut notification = None;
+        if let Some(last) = self.last_timestamp {
+            if current - last < -ROLLOVER_GAP {
+                self.years_added += 1;
+                current = add_years(timestamp, self.years_added);
+                notification = Some(format!(
+                    "year rollover detected, timestamp taken as {}",
+                    posix_timestamp_as_string(current)
+                ));
+            } else if current - last > ROLLOVER_GAP && self.years_added > 0 {
+                // clock was set back over the year rollover
+                self.years_added -= 1;
+                current = add_years(timestamp, self.years_added);
+            }
+            let delta = current - last;
+            if delta < -BACKWARD_JUMP_THRESHOLD {
+                self.shift -= delta;
+                notification = Some(format!(
+                    "clock jumped back by {}, following timestamps are shifted by {}",
+                    duration_as_string(-delta),
+                    duration_as_string(self.shift)
+                ));
+            } else if self.shift > 0
+                && delta > BACKWARD_JUMP_THRESHOLD
+                && delta >= self.shift - BACKWARD_JUMP_THRESHOLD
+            {
+                notification = Some(format!(
+                    "clock jumped forward by {}, timestamps are no longer shifted",
+                    duration_as_string(delta)
+                ));
+                self.shift = 0;
+            } else if delta > FORWARD_JUMP_THRESHOLD {
+                notification = Some(format!(
+                    "clock jumped forward by {}",
+                    duration_as_string(delta)
+                ));
+            }
+        }
+        self.last_timestamp = Some(current);
+        (
+            current + self.shift,
+            notification.map(|content| Notification {
+                severity: Severity::WARNING,
+                content,
+                line: Some(line_nr),
+            }),
+        )
+    }
+}
+
+/// the timestamp is kept if the moved date cannot be represented (after 2262)
+fn add_years(timestamp: i64, years: i32) -> i64 {
+    if years == 0 {
+        return timestamp;
+    }
+    let date_time = NaiveDateTime::from_timestamp(
+        timestamp.div_euclid(NANOS_PER_SECOND),
+        timestamp.rem_euclid(NANOS_PER_SECOND) as u32,
+    );
+    let moved = match date_time.with_year(date_time.year() + years) {
+        Some(moved) => moved,
+        // february 29th
+        None => date_time + Duration::days(365 * i64::from(years)),
+    };
+    checked_timestamp_nanos(&moved).unwrap_or(timestamp)
+}
+
+fn duration_as_string(nanos: i64) -> String {
+    let seconds = nanos / NANOS_PER_SECOND;
+    if seconds >= 3600 {
+        format!(
+            "{}h {}m {}s",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else if seconds >= 60 {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}ms", nanos / 1_000_000)
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::parse::detect_timestamp_in_string;
+use failure::{err_msg, Error};
+use indexer_base::encoding::{open_text_input, Encoding};
+use indexer_base::utils::{encode_newlines, COLUMN_SENTINAL};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// used instead of a format string for files with one JSON object per line
+pub const JSON_FORMAT: &str = "JSON";
+/// number of lines that are looked at to detect JSON lines
+const LINES_TO_INSPECT: usize = 100;
+
+/// which fields of a JSON log line are the timestamp, level and message,
+/// keys of nested objects are joined with a dot (e.g. `log.level`)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct JsonLogConfig {
+    /// the first key that is present is used
+    pub timestamp_keys: Vec<String>,
+    pub level_keys: Vec<String>,
+    pub message_keys: Vec<String>,
+    /// fields that get a column of their own (in this order), all
+    /// other fields are appended as `key=value` columns
+    pub columns: Vec<String>,
+    /// only lines where all filters match are used
+    pub filters: Vec<JsonFieldFilter>,
+    /// only lines with this level and more severe ones (fatal, error, warn,
+    /// info, debug, trace), lines without a known level are always used
+    pub min_level: Option<String>,
+}
+
+impl Default for JsonLogConfig {
+    fn default() -> JsonLogConfig {
+        let keys = |keys: &[&str]| keys.iter().map(|k| (*k).to_string()).collect();
+        JsonLogConfig {
+            timestamp_keys: keys(&["timestamp", "@timestamp", "time", "ts", "date"]),
+            level_keys: keys(&["level", "severity", "lvl", "log.level"]),
+            message_keys: keys(&["message", "msg", "@message"]),
+            columns: vec![],
+            filters: vec![],
+            min_level: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonFieldFilter {
+    pub key: String,
+    /// regex that has to match the value
+    pub pattern: String,
+}
+
+pub fn read_json_log_config(f: &mut fs::File) -> Result<JsonLogConfig, Error> {
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)?;
+    let v: JsonLogConfig = serde_json::from_str(&contents[..])?;
+    Ok(v)
+}
+
+pub enum JsonLine {
+    /// the timestamp (ns) if there is one and the fields as columns
+    Entry {
+        timestamp: Option<i64>,
+        content: String,
+    },
+    FilteredOut,
+    /// not a JSON object, the line is used as it is
+    Text,
+}
+
+pub struct JsonLog {
+    config: JsonLogConfig,
+    filters: Vec<(String, Regex)>,
+    min_level: Option<u8>,
+}
+
+impl JsonLog {
+    pub fn new(config: JsonLogConfig) -> Result<JsonLog, Error> {
+        let filters = config
+            .filters
+            .iter()
+            .map(|f| {
+                Regex::new(&f.pattern)
+                    .map(|r| (f.key.clone(), r))
+                    .map_err(|e| err_msg(format!("invalid filter {}: {}", f.pattern, e)))
+            })
+            .collect::<Result<Vec<(String, Regex)>, Error>>()?;
+        let min_level = match &config.min_level {
+            Some(level) => Some(
+                level_rank(&Value::String(level.clone()))
+                    .ok_or_else(|| err_msg(format!("unknown level {}", level)))?,
+            ),
+            None => None,
+        };
+        Ok(JsonLog {
+            config,
+            filters,
+            min_level,
+        })
+    }
+
+    /// the columns are the timestamp, level and message, the configured
+    /// columns and the remaining fields as `key=value`
+    pub fn parse_line(&self, line: &str) -> JsonLine {
+        let object = match parse_object(line) {
+            Some(object) => object,
+            None => return JsonLine::Text,
+        };
+        let mut fields: Vec<(String, &Value)> = vec![];
+        flatten("", &object, &mut fields);
+
+        let find = |keys: &[String]| {
+            keys.iter()
+                .find_map(|k| fields.iter().position(|(key, _)| key == k))
+        };
+        let timestamp_index = find(&self.config.timestamp_keys);
+        let level_index = find(&self.config.level_keys);
+        let message_index = find(&self.config.message_keys);
+
+        if let (Some(min_level), Some(i)) = (self.min_level, level_index) {
+            match level_rank(fields[i].1) {
+                Some(rank) if rank > min_level => return JsonLine::FilteredOut,
+                _ => (),
+            }
+        }
+        for (key, regex) in &self.filters {
+            let selected = fields
+                .iter()
+                .any(|(k, value)| k == key && regex.is_match(&value_as_text(value)));
+            if !selected {
+                return JsonLine::FilteredOut;
+            }
+        }
+
+        let timestamp = timestamp_index.and_then(|i| value_as_timestamp(fields[i].1));
+        let column =
+            |index: Option<usize>| index.map_or_else(String::new, |i| value_as_text(fields[i].1));
+        let mut columns = vec![
+            column(timestamp_index),
+            column(level_index),
+            column(message_index),
+        ];
+        for key in &self.config.columns {
+            columns.push(column(fields.iter().position(|(k, _)| k == key)));
+        }
+        for (i, (key, value)) in fields.iter().enumerate() {
+            let used = Some(i) == timestamp_index
+                || Some(i) == level_index
+                || Some(i) == message_index
+                || self.config.columns.contains(key);
+            if !used {
+                columns.push(format!("{}={}", key, value_as_text(value)));
+            }
+        }
+        // string values can contain newlines
+        let content = columns.join(&COLUMN_SENTINAL.to_string());
+        JsonLine::Entry {
+            timestamp,
+            content: encode_newlines(&content).into_owned(),
+        }
+    }
+}
+
+fn parse_object(line: &str) -> Option<Value> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with('{') {
+        return None;
+    }
+    match serde_json::from_str(trimmed) {
+        Ok(value @ Value::Object(_)) => Some(value),
+        _ => None,
+    }
+}
+
+/// the leaves of nested objects with their keys joined by a dot
+fn flatten<'a>(prefix: &str, value: &'a Value, fields: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                let key = if prefix.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, v, fields);
+            }
+        }
+        _ => fields.push((prefix.to_string(), value)),
+    }
+}
+
+/// strings without quotes, arrays as JSON
+fn value_as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// strings in one of the known formats (taken as UTC without a timezone),
+/// numbers as seconds, milliseconds, microseconds or nanoseconds since 1970
+fn value_as_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(s) => detect_timestamp_in_string(s, None)
+            .or_else(|_| detect_timestamp_in_string(s, Some(0)))
+            .ok()
+            .map(|(timestamp, _, _)| timestamp),
+        Value::Number(n) => number_as_nanos(n),
+        _ => None,
+    }
+}
+
+/// the unit of the number is guessed from its magnitude
+fn number_as_nanos(number: &Number) -> Option<i64> {
+    let text = number.to_string();
+    let mut parts = text.splitn(2, '.');
+    let whole: i64 = parts.next()?.parse().ok()?;
+    let (factor, digits) = match whole.abs() {
+        a if a < 100_000_000_000 => (1_000_000_000, 9),
+        a if a < 100_000_000_000_000 => (1_000_000, 6),
+        a if a < 100_000_000_000_000_000 => (1_000, 3),
+        _ => (1, 0),
+    };
+    let fraction: i64 = match parts.next() {
+        Some(fraction) if digits > 0 => format!("{:0<width$.width$}", fraction, width = digits)
+            .parse()
+            .ok()?,
+        _ => 0,
+    };
+    whole.checked_mul(factor)?.checked_add(fraction)
+}
+
+/// 1 (fatal) to 6 (trace) like the dlt log levels, numbers as used by bunyan and pino
+fn level_rank(value: &Value) -> Option<u8> {
+    match value {
+        Value::String(s) => match s.to_lowercase().as_str() {
+            "fatal" | "critical" | "crit" | "emerg" | "emergency" | "alert" | "panic" => Some(1),
+            "error" | "err" => Some(2),
+            "warn" | "warning" => Some(3),
+            "info" | "information" | "notice" => Some(4),
+            "debug" => Some(5),
+            "trace" | "verbose" => Some(6),
+            _ => None,
+        },
+        Value::Number(n) => match n.as_u64()? {
+            60 => Some(1),
+            50 => Some(2),
+            40 => Some(3),
+            30 => Some(4),
+            20 => Some(5),
+            10 => Some(6),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// most of the first lines are JSON objects
+pub fn is_json_lines<'a, I: Iterator<Item = &'a str>>(lines: I) -> bool {
+    let (objects, inspected) = lines
+        .filter(|l| !l.trim().is_empty())
+        .take(LINES_TO_INSPECT)
+        .fold((0usize, 0usize), |(objects, inspected), line| {
+            let is_object = parse_object(line).is_some();
+            (objects + is_object as usize, inspected + 1)
+        });
+    inspected > 0 && objects * 10 >= inspected * 8
+}
+
+/// without `encoding` it is detected from the file
+pub fn detect_json_lines(path: &Path, encoding: Option<&'static Encoding>) -> Result<bool, Error> {
+    let reader = BufReader::new(open_text_input(path, encoding)?);
+    let lines: Vec<String> = reader
+        .lines()
+        .take(2 * LINES_TO_INSPECT)
+        .filter_map(Result::ok)
+        .collect();
+    Ok(is_json_lines(lines.iter().map(String::as_str)))
+}
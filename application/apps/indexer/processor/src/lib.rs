@@ -18,8 +18,14 @@ extern crate log;
 
 extern crate crossbeam_channel as cc;
 
+pub mod discovery;
+pub mod generator;
+pub mod grouping;
+pub mod inference;
+pub mod json_log;
 pub mod parse;
 pub mod processor;
+pub mod stats;
 
 #[cfg(test)]
 mod tests;
@@ -9,12 +9,20 @@
 // Dissemination of this information or reproduction of this material
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
+use crate::discovery::{discover_format, FileKind, FormatDiscovery};
+use crate::json_log::{detect_json_lines, JsonLine, JsonLog, JsonLogConfig, JSON_FORMAT};
 use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Utc};
 use crossbeam_channel as cc;
+use indexer_base::archive::split_entry_path;
+use indexer_base::compression::{detect_compression, Compression};
+use indexer_base::encoding::{
+    encoding_for_label, open_text_input, resolve_encoding, Encoding, UTF_8,
+};
 use indexer_base::error_reporter::*;
 use indexer_base::progress::Severity;
 use indexer_base::progress::*;
 use indexer_base::timedline::TimedLine;
+use indexer_base::utils::NANOS_PER_MILLI;
 
 use nom::bytes::complete::tag;
 use nom::bytes::complete::take;
@@ -23,7 +31,7 @@ use nom::combinator::{map, map_res, opt};
 use nom::multi::{fold_many0, many1};
 use nom::IResult;
 
-use regex::Regex;
+use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
 
 use std::borrow::Cow;
@@ -52,6 +60,110 @@ macro_rules! add_twice {
     };
 }
 
+/// add a format string that cannot have a timezone
+macro_rules! add_once {
+    ($var:expr, $a:expr, $m:expr) => {
+        $a.push($var);
+        $m.insert(
+            $var,
+            date_format_str_to_regex($var).expect("static regex failed"),
+        );
+    };
+}
+
+/// month names (short and full form) in english, german, french and spanish
+const MONTH_NAMES: [[&str; 12]; 8] = [
+    [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+    [
+        "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+    ],
+    [
+        "janv", "févr", "mars", "avr", "mai", "juin", "juil", "août", "sept", "oct", "nov", "déc",
+    ],
+    [
+        "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic",
+    ],
+    [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    [
+        "Januar",
+        "Februar",
+        "März",
+        "April",
+        "Mai",
+        "Juni",
+        "Juli",
+        "August",
+        "September",
+        "Oktober",
+        "November",
+        "Dezember",
+    ],
+    [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+];
+/// the first rows of `MONTH_NAMES` contain the short forms
+const SHORT_MONTH_NAME_ROWS: usize = 4;
+
+/// case insensitive alternation of the names, longer names first so that
+/// "Sept" is not matched as "Sep"
+fn month_names_regex<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    let mut names: Vec<&str> = names.collect();
+    names.sort_by(|a, b| b.chars().count().cmp(&a.chars().count()).then(a.cmp(b)));
+    names.dedup();
+    format!("(?i:{})", names.join("|"))
+}
+
+lazy_static! {
+    static ref MONTH_SHORT_NAMES_REGEX: String = {
+        let short_names = MONTH_NAMES[..SHORT_MONTH_NAME_ROWS].iter().flat_map(|row| row.iter().copied());
+        // abbreviations are sometimes written with a dot
+        format!(r"{}\.?", month_names_regex(short_names))
+    };
+    static ref MONTH_FULL_NAMES_REGEX: String =
+        month_names_regex(MONTH_NAMES[SHORT_MONTH_NAME_ROWS..].iter().flat_map(|row| row.iter().copied()));
+}
+
 lazy_static! {
     static ref FORMAT_REGEX_MAPPINGS: (Vec<&'static str>, BTreeMap<&'static str, Regex>) = {
         let mut arr = Vec::new();
@@ -74,6 +186,16 @@ lazy_static! {
         add_twice!("DD-MMM-YYYY hh:mm:ss", &mut arr, &mut v);
         add_twice!("DD/MMM/YYYYThh:mm:ss", &mut arr, &mut v);
         add_twice!("DD MMM YYYY hh:mm:ss", &mut arr, &mut v);
+        add_twice!("DD MMMM YYYY hh:mm:ss", &mut arr, &mut v);
+        add_twice!("DD. MMMM YYYY hh:mm:ss", &mut arr, &mut v);
+        add_twice!("DD. MMM YYYY hh:mm:ss", &mut arr, &mut v);
+        // RFC3164 syslog
+        add_twice!("MMM DD hh:mm:ss", &mut arr, &mut v);
+        add_once!("[UPTIME]", &mut arr, &mut v);
+        add_once!("EPOCH_NS", &mut arr, &mut v);
+        add_once!("EPOCH_US", &mut arr, &mut v);
+        add_once!("EPOCH_MS", &mut arr, &mut v);
+        add_once!("EPOCH", &mut arr, &mut v);
         (arr, v)
     };
     static ref AVAILABLE_FORMATS: Vec<&'static str> = FORMAT_REGEX_MAPPINGS.0.clone();
@@ -81,16 +203,19 @@ lazy_static! {
         FORMAT_REGEX_MAPPINGS.1.clone();
 }
 
-pub fn posix_timestamp_as_string(timestamp_ms: i64) -> String {
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// e.g. "2019-07-30 10:08:02.555123 UTC" for a timestamp in nanoseconds
+pub fn posix_timestamp_as_string(timestamp_ns: i64) -> String {
     match NaiveDateTime::from_timestamp_opt(
-        (timestamp_ms as f64 / 1000.0) as i64,
-        (timestamp_ms as f64 % 1000.0) as u32 * 1000,
+        timestamp_ns.div_euclid(NANOS_PER_SECOND),
+        timestamp_ns.rem_euclid(NANOS_PER_SECOND) as u32,
     ) {
         Some(naive_datetime_max) => {
             let t: DateTime<Utc> = DateTime::from_utc(naive_datetime_max, Utc);
             format!("{}", t)
         }
-        None => format!("could not parse: {}", timestamp_ms),
+        None => format!("could not parse: {}", timestamp_ns),
     }
 }
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
@@ -98,15 +223,23 @@ pub enum FormatPiece {
     Day,
     Month,
     MonthName,
+    MonthFullName,
     Year,
     YearShort,
     Hour,
     Minute,
     Second,
     Fraction,
+    /// fraction of a second with a fixed number of digits
+    FixedFraction(usize),
     TimeZone,
     AmPm,
     AbsoluteMilliseconds,
+    EpochSeconds,
+    EpochMilliseconds,
+    EpochMicroseconds,
+    EpochNanoseconds,
+    Uptime,
     SeperatorChar(char),
     Seperator(String),
 }
@@ -116,15 +249,22 @@ impl std::fmt::Display for FormatPiece {
             FormatPiece::Day => write!(f, "Day"),
             FormatPiece::Month => write!(f, "Month"),
             FormatPiece::MonthName => write!(f, "MonthName"),
+            FormatPiece::MonthFullName => write!(f, "MonthFullName"),
             FormatPiece::Year => write!(f, "Year"),
             FormatPiece::YearShort => write!(f, "YearShort"),
             FormatPiece::Hour => write!(f, "Hour"),
             FormatPiece::Minute => write!(f, "Minute"),
             FormatPiece::Second => write!(f, "Second"),
             FormatPiece::Fraction => write!(f, "Fraction"),
+            FormatPiece::FixedFraction(_) => write!(f, "FixedFraction"),
             FormatPiece::AmPm => write!(f, "AmPm"),
             FormatPiece::TimeZone => write!(f, "TimeZone"),
             FormatPiece::AbsoluteMilliseconds => write!(f, "AbsoluteMilliseconds"),
+            FormatPiece::EpochSeconds => write!(f, "EpochSeconds"),
+            FormatPiece::EpochMilliseconds => write!(f, "EpochMilliseconds"),
+            FormatPiece::EpochMicroseconds => write!(f, "EpochMicroseconds"),
+            FormatPiece::EpochNanoseconds => write!(f, "EpochNanoseconds"),
+            FormatPiece::Uptime => write!(f, "Uptime"),
             FormatPiece::SeperatorChar(_) => write!(f, "SeperatorChar"),
             FormatPiece::Seperator(_) => write!(f, "Seperator"),
         }
@@ -135,16 +275,26 @@ impl std::fmt::Display for FormatPiece {
 /// e.g. "DD-MM" => [Day,Seperator("-"),Month]
 ///      YYYY = four-digit year
 ///      yy   = two-digit year
-///      MMM  = short month form (Jan, Feb, Mar, ..., Dec)
+///      MMMM = full month name (January, ..., December, Januar, janvier, enero, ...)
+///      MMM  = short month form (Jan, Feb, Mar, ..., Dec, Mär, janv., ene, ...)
 ///      MM   = two-digit month (01=January, etc.)
-///      DD   = two-digit day of month (01 through 31)
+///      DD   = two-digit day of month (01 through 31), or space-padded (" 4")
 ///      hh   = two digits of hour (00 through 23) (am/pm NOT allowed)
 ///      mm   = two digits of minute (00 through 59)
 ///      ss   = two digits of second (00 through 59)
 ///      s    = one or more digits representing a decimal fraction of a second
+///      SSS  = three digits of milliseconds (also SSSSSS and SSSSSSSSS)
 ///      TZD  = time zone designator (Z or +hh:mm or -hh:mm)
 ///      a    = support for 12-hour clock format (AM or PM)
+///      sss  = absolute milliseconds
+///      EPOCH    = seconds since 1970 (10 digits), optionally with a fraction
+///      EPOCH_MS = milliseconds since 1970 (13 digits)
+///      EPOCH_US = microseconds since 1970 (16 digits)
+///      EPOCH_NS = nanoseconds since 1970 (19 digits)
+///                 (at the start of the line or of a field like `ts=` or `[`)
+///      UPTIME   = seconds since boot with a fraction, as in `[   12.345678]`
 static DAY_FORMAT_TAG: &str = "DD";
+static MONTH_FORMAT_FULL_NAME_TAG: &str = "MMMM";
 static MONTH_FORMAT_SHORT_NAME_TAG: &str = "MMM";
 static MONTH_FORMAT_TAG: &str = "MM";
 static YEAR_FORMAT_TAG: &str = "YYYY";
@@ -153,12 +303,22 @@ static HOURS_FORMAT_TAG: &str = "hh";
 static MINUTES_FORMAT_TAG: &str = "mm";
 static SECONDS_FORMAT_TAG: &str = "ss";
 static TIMEZONE_FORMAT_TAG: &str = "TZD";
+static EPOCH_SECONDS_FORMAT_TAG: &str = "EPOCH";
+static EPOCH_MILLIS_FORMAT_TAG: &str = "EPOCH_MS";
+static EPOCH_MICROS_FORMAT_TAG: &str = "EPOCH_US";
+static EPOCH_NANOS_FORMAT_TAG: &str = "EPOCH_NS";
+static UPTIME_FORMAT_TAG: &str = "UPTIME";
 const FRACTION_FORMAT_CHAR: char = 's';
 const AM_PM_TAG: char = 'a';
 
 fn days(input: &str) -> IResult<&str, FormatPiece> {
     map(tag(DAY_FORMAT_TAG), |_| FormatPiece::Day)(input)
 }
+fn month_full(input: &str) -> IResult<&str, FormatPiece> {
+    map(tag(MONTH_FORMAT_FULL_NAME_TAG), |_| {
+        FormatPiece::MonthFullName
+    })(input)
+}
 fn month_short(input: &str) -> IResult<&str, FormatPiece> {
     map(tag(MONTH_FORMAT_SHORT_NAME_TAG), |_| FormatPiece::MonthName)(input)
 }
@@ -185,6 +345,12 @@ fn fraction(input: &str) -> IResult<&str, FormatPiece> {
         FormatPiece::Fraction
     })(input)
 }
+fn fixed_fraction(input: &str) -> IResult<&str, FormatPiece> {
+    map(
+        nom::branch::alt((tag("SSSSSSSSS"), tag("SSSSSS"), tag("SSS"))),
+        |s: &str| FormatPiece::FixedFraction(s.len()),
+    )(input)
+}
 fn am_pm(input: &str) -> IResult<&str, FormatPiece> {
     map(nom::character::complete::char(AM_PM_TAG), |_| {
         FormatPiece::AmPm
@@ -196,6 +362,23 @@ fn timezone(input: &str) -> IResult<&str, FormatPiece> {
 fn absolute_millis(input: &str) -> IResult<&str, FormatPiece> {
     map(tag("sss"), |_| FormatPiece::AbsoluteMilliseconds)(input)
 }
+fn epoch(input: &str) -> IResult<&str, FormatPiece> {
+    nom::branch::alt((
+        map(tag(EPOCH_MILLIS_FORMAT_TAG), |_| {
+            FormatPiece::EpochMilliseconds
+        }),
+        map(tag(EPOCH_MICROS_FORMAT_TAG), |_| {
+            FormatPiece::EpochMicroseconds
+        }),
+        map(tag(EPOCH_NANOS_FORMAT_TAG), |_| {
+            FormatPiece::EpochNanoseconds
+        }),
+        map(tag(EPOCH_SECONDS_FORMAT_TAG), |_| FormatPiece::EpochSeconds),
+    ))(input)
+}
+fn uptime(input: &str) -> IResult<&str, FormatPiece> {
+    map(tag(UPTIME_FORMAT_TAG), |_| FormatPiece::Uptime)(input)
+}
 fn many_spaces(input: &str) -> IResult<&str, char> {
     map(many1(nom::character::complete::char(' ')), |_| ' ')(input)
 }
@@ -208,6 +391,7 @@ fn seperator(input: &str) -> IResult<&str, FormatPiece> {
 pub(crate) fn any_date_format(input: &str) -> IResult<&str, FormatPiece> {
     nom::branch::alt((
         days,
+        month_full,
         month_short,
         month,
         year,
@@ -217,8 +401,11 @@ pub(crate) fn any_date_format(input: &str) -> IResult<&str, FormatPiece> {
         absolute_millis,
         seconds,
         fraction,
+        fixed_fraction,
         am_pm,
         timezone,
+        epoch,
+        uptime,
         seperator,
     ))(input)
 }
@@ -274,6 +461,53 @@ pub fn lookup_regex_for_format_str(date_format: &str) -> Result<Regex, failure::
         None => date_format_str_to_regex(date_format),
     }
 }
+/// the regex for a user defined `custom_regex` if there is one, otherwise for the format string
+pub fn timestamp_regex(format: &str, custom_regex: Option<&str>) -> Result<Regex, failure::Error> {
+    match custom_regex {
+        Some(pattern) => custom_regex_to_regex(pattern),
+        None => lookup_regex_for_format_str(format),
+    }
+}
+/// a regex with named groups for the parts of the timestamp (year, month, day, hour,
+/// minute, second, fraction, tz or epoch), the other named groups are extra columns.
+/// Either epoch or day, month, hour, minute and second have to be present.
+pub fn custom_regex_to_regex(pattern: &str) -> Result<Regex, failure::Error> {
+    let renamed =
+        NAMED_GROUP_REGEX.replace_all(pattern, |caps: &Captures| {
+            match timestamp_group_for(&caps[1]) {
+                Some(group) => format!("(?P<{}>", group),
+                None => caps[0].to_string(),
+            }
+        });
+    let regex = Regex::new(&renamed)
+        .map_err(|e| failure::err_msg(format!("invalid regex {}: {}", pattern, e)))?;
+    let has_group = |group: &str| regex.capture_names().any(|name| name == Some(group));
+    let has_date = [
+        DAY_GROUP,
+        MONTH_GROUP,
+        HOUR_GROUP,
+        MINUTE_GROUP,
+        SECONDS_GROUP,
+    ]
+    .iter()
+    .all(|group| has_group(group));
+    if !has_date && !has_group(EPOCH_SECONDS_GROUP) {
+        return Err(failure::err_msg(format!(
+            "regex {} needs the named groups epoch or day, month, hour, minute and second",
+            pattern
+        )));
+    }
+    Ok(regex)
+}
+/// named groups of the regex that are not part of the timestamp
+pub fn extra_columns(regex: &Regex) -> Vec<String> {
+    regex
+        .capture_names()
+        .flatten()
+        .filter(|name| !is_timestamp_group(name))
+        .map(String::from)
+        .collect()
+}
 fn date_format_str_to_regex(date_format: &str) -> Result<Regex, failure::Error> {
     if date_format.is_empty() {
         return Err(failure::err_msg("cannot construct regex from empty string"));
@@ -286,7 +520,16 @@ fn date_format_str_to_regex(date_format: &str) -> Result<Regex, failure::Error>
                     "could not create regex, problems with format pieces",
                 ));
             }
-            let s = r.1.iter().fold(String::from(r""), |mut acc, x| {
+            // an epoch at the start of the format has to start the line or a
+            // delimited field, otherwise every long number would be a timestamp
+            let start = match r.1.first() {
+                Some(FormatPiece::EpochSeconds)
+                | Some(FormatPiece::EpochMilliseconds)
+                | Some(FormatPiece::EpochMicroseconds)
+                | Some(FormatPiece::EpochNanoseconds) => EPOCH_FIELD_START,
+                _ => "",
+            };
+            let s = r.1.iter().fold(String::from(start), |mut acc, x| {
                 let part = format_piece_as_regex_string(x);
                 acc.push_str(part.as_str());
                 acc
@@ -313,6 +556,56 @@ static FRACTION_GROUP: &str = "millis";
 static AM_PM_GROUP: &str = "am_pm";
 static TIMEZONE_GROUP: &str = "timezone";
 static ABSOLUTE_MS_GROUP: &str = "absolute";
+static MONTH_FULL_NAME_GROUP: &str = "MMMM";
+static EPOCH_SECONDS_GROUP: &str = "epoch_s";
+/// the start of the line or a delimiter like `[`, `=` or `,`
+static EPOCH_FIELD_START: &str = r#"(?:^\s*|[\[(=:,;|"\t]\s*)"#;
+static EPOCH_MILLIS_GROUP: &str = "epoch_ms";
+static EPOCH_MICROS_GROUP: &str = "epoch_us";
+static EPOCH_NANOS_GROUP: &str = "epoch_ns";
+static UPTIME_GROUP: &str = "uptime";
+
+lazy_static! {
+    static ref NAMED_GROUP_REGEX: Regex = Regex::new(r"\(\?P<(\w+)>").unwrap();
+}
+/// group names that can be used in a custom regex
+fn timestamp_group_for(name: &str) -> Option<&'static str> {
+    match name {
+        "year" => Some(YEAR_GROUP),
+        "month" => Some(MONTH_GROUP),
+        "day" => Some(DAY_GROUP),
+        "hour" => Some(HOUR_GROUP),
+        "minute" => Some(MINUTE_GROUP),
+        "second" => Some(SECONDS_GROUP),
+        "fraction" => Some(FRACTION_GROUP),
+        "tz" => Some(TIMEZONE_GROUP),
+        "epoch" => Some(EPOCH_SECONDS_GROUP),
+        _ => None,
+    }
+}
+fn is_timestamp_group(name: &str) -> bool {
+    [
+        DAY_GROUP,
+        MONTH_GROUP,
+        MONTH_SHORT_NAME_GROUP,
+        YEAR_GROUP,
+        YEAR_SHORT_GROUP,
+        HOUR_GROUP,
+        MINUTE_GROUP,
+        SECONDS_GROUP,
+        FRACTION_GROUP,
+        AM_PM_GROUP,
+        TIMEZONE_GROUP,
+        ABSOLUTE_MS_GROUP,
+        MONTH_FULL_NAME_GROUP,
+        EPOCH_SECONDS_GROUP,
+        EPOCH_MILLIS_GROUP,
+        EPOCH_MICROS_GROUP,
+        EPOCH_NANOS_GROUP,
+        UPTIME_GROUP,
+    ]
+    .contains(&name)
+}
 
 fn named_group(regex: &str, capture_id: &str) -> String {
     format!(r"(?P<{}>{})", capture_id, regex)
@@ -320,21 +613,32 @@ fn named_group(regex: &str, capture_id: &str) -> String {
 
 fn format_piece_as_regex_string(p: &FormatPiece) -> String {
     match p {
-        FormatPiece::Day => named_group(r"([0-2]\d|3[01])", DAY_GROUP),
+        // syslog pads single digit days with a space ("Mar  4")
+        FormatPiece::Day => named_group(r"([0-2]\d|3[01]| [1-9])", DAY_GROUP),
         FormatPiece::Month => named_group(r"(0?\d|1[0-2])", MONTH_GROUP),
-        FormatPiece::MonthName => named_group(
-            r"(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)",
-            MONTH_SHORT_NAME_GROUP,
-        ),
+        FormatPiece::MonthName => named_group(&MONTH_SHORT_NAMES_REGEX, MONTH_SHORT_NAME_GROUP),
+        FormatPiece::MonthFullName => named_group(&MONTH_FULL_NAMES_REGEX, MONTH_FULL_NAME_GROUP),
         FormatPiece::Year => named_group(r"[0-2]\d{3}", YEAR_GROUP),
         FormatPiece::YearShort => named_group(r"\d{2}", YEAR_SHORT_GROUP),
         FormatPiece::Hour => named_group(r"(0?\d|1\d|2[0-3])", HOUR_GROUP),
         FormatPiece::Minute => named_group(r"[0-5]\d", MINUTE_GROUP),
         FormatPiece::Second => named_group(r"[0-5]\d", SECONDS_GROUP),
         FormatPiece::Fraction => named_group(r"\d+", FRACTION_GROUP),
+        FormatPiece::FixedFraction(digits) => {
+            named_group(&format!(r"\d{{{}}}", digits), FRACTION_GROUP)
+        }
         FormatPiece::AmPm => named_group(r"(AM|PM)", AM_PM_GROUP),
-        FormatPiece::TimeZone => named_group(r"[\+\-](0\d|1[0-4]):?(00|30|45)", TIMEZONE_GROUP),
+        FormatPiece::TimeZone => {
+            named_group(r"([\+\-](0\d|1[0-4]):?(00|30|45)|Z\b)", TIMEZONE_GROUP)
+        }
         FormatPiece::AbsoluteMilliseconds => named_group(r"\d+", ABSOLUTE_MS_GROUP),
+        // epoch values are restricted to the years 2001 to 2033 to not take
+        // any other number for a timestamp
+        FormatPiece::EpochSeconds => named_group(r"\b1\d{9}(\.\d+)?\b", EPOCH_SECONDS_GROUP),
+        FormatPiece::EpochMilliseconds => named_group(r"\b1\d{12}\b", EPOCH_MILLIS_GROUP),
+        FormatPiece::EpochMicroseconds => named_group(r"\b1\d{15}\b", EPOCH_MICROS_GROUP),
+        FormatPiece::EpochNanoseconds => named_group(r"\b1\d{18}\b", EPOCH_NANOS_GROUP),
+        FormatPiece::Uptime => format!(r"\s*{}", named_group(r"\d+\.\d+", UPTIME_GROUP)),
         FormatPiece::SeperatorChar(c) => {
             let mut s = String::from("");
             s.push(*c);
@@ -354,7 +658,11 @@ pub struct FormatStringMatches {
 pub struct FormatTestOptions {
     pub file: String,
     pub lines_to_test: i64,
+    #[serde(default)]
     pub format: String,
+    /// regex with named groups that is used instead of the format
+    #[serde(default)]
+    pub regex: Option<String>,
 }
 
 pub fn read_format_string_options(f: &mut fs::File) -> Result<FormatTestOptions, failure::Error> {
@@ -368,45 +676,37 @@ pub fn read_format_string_options(f: &mut fs::File) -> Result<FormatTestOptions,
 ///
 /// # Arguments
 ///
+/// * `encoding` - the encoding of the lines that are read
 /// * `regex` - the regex for the timestamp
 /// * `limit` - if set, do not inspect more then this many lines
-/// * `start` - offset in bytes where to start the file inspection
 ///
 /// # Returns
 ///
 /// * `min_timestamp` - return value for the min value
 /// * `max_timestamp` - return value for the max value
 ///
-fn scan_lines(
-    f: &std::fs::File,
+fn scan_lines<R: BufRead>(
+    reader: &mut R,
+    encoding: &'static Encoding,
     regex: &Regex,
     min_timestamp: &mut i64,
     max_timestamp: &mut i64,
     limit: Option<usize>,
-    start: Option<u64>,
-) -> Result<usize, (failure::Error, usize)> {
+) -> usize {
     let mut buf = vec![];
-
-    let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
     let mut scanned_cnt = 0usize;
-
-    if let Some(n) = start {
-        if let Err(e) = reader.seek(std::io::SeekFrom::Start(n)) {
-            return Err((failure::err_msg(format!("{}", e)), scanned_cnt));
-        }
-    }
     let mut inspected_lines = 0usize;
     while let Ok(len) = reader.read_until(b'\n', &mut buf) {
         if len == 0 {
             break; // file is done
         }
-        let line = String::from_utf8_lossy(&buf);
+        let (line, _) = encoding.decode_without_bom_handling(&buf);
         scanned_cnt += 1;
         let trimmed = line.trim();
         if !trimmed.is_empty() {
             inspected_lines += 1;
             // if regex.is_match(trimmed) {
-            match extract_posix_timestamp(trimmed, &regex, None, Some(0)) {
+            match extract_posix_timestamp(trimmed, regex, None, Some(0)) {
                 // TODO work on fast parsers to replace regex parsing
                 // match parse_full_timestamp(trimmed, &regex) {
                 Ok((timestamp, _)) => {
@@ -423,8 +723,164 @@ fn scan_lines(
             }
         }
     }
-    Ok(scanned_cnt)
+    scanned_cnt
+}
+
+/// scans the start and the end of a file, compressed files, archive entries
+/// and encodings that are not ASCII compatible (UTF-16) are scanned completely
+fn scan_timespan(
+    file_path: &Path,
+    encoding: &'static Encoding,
+    regex: &Regex,
+    update_channel: &cc::Sender<IndexingResults<TimestampFormatResult>>,
+) -> Result<(i64, i64), failure::Error> {
+    let mut min_timestamp = std::i64::MAX;
+    let mut max_timestamp = 0i64;
+    let can_seek = encoding.is_ascii_compatible()
+        && split_entry_path(file_path).is_none()
+        && detect_compression(file_path)? == Compression::None;
+    if !can_seek {
+        let mut reader = BufReader::new(open_text_input(file_path, Some(encoding))?);
+        scan_lines(
+            &mut reader,
+            UTF_8,
+            regex,
+            &mut min_timestamp,
+            &mut max_timestamp,
+            None,
+        );
+        return Ok((min_timestamp, max_timestamp));
+    }
+    let f: fs::File = fs::File::open(file_path)?;
+    let file_size = f.metadata()?.len();
+    let min_buf_size = std::cmp::min(file_size, 256 * 1024); // 256k
+    let lines_to_scan = 1000usize;
+    let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+    let _line_cnt = scan_lines(
+        &mut reader,
+        encoding,
+        regex,
+        &mut min_timestamp,
+        &mut max_timestamp,
+        Some(lines_to_scan),
+    );
+    // also read from end
+    let start = file_size - min_buf_size;
+    match reader.seek(std::io::SeekFrom::Start(start)) {
+        Ok(_) => {
+            let _last_scanned_lines = scan_lines(
+                &mut reader,
+                encoding,
+                regex,
+                &mut min_timestamp,
+                &mut max_timestamp,
+                None,
+            );
+        }
+        Err(e) => {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::WARNING,
+                content: format!("{}", e),
+                line: None,
+            }));
+        }
+    }
+    Ok((min_timestamp, max_timestamp))
+}
+
+/// JSON lines are scanned completely
+fn scan_json_timespan(
+    file_path: &Path,
+    encoding: &'static Encoding,
+) -> Result<(i64, i64), failure::Error> {
+    let json = JsonLog::new(JsonLogConfig::default())?;
+    let reader = BufReader::new(open_text_input(file_path, Some(encoding))?);
+    let mut min_timestamp = i64::MAX;
+    let mut max_timestamp = 0i64;
+    for line in reader.lines() {
+        if let JsonLine::Entry {
+            timestamp: Some(timestamp),
+            ..
+        } = json.parse_line(&line?)
+        {
+            min_timestamp = std::cmp::min(min_timestamp, timestamp);
+            max_timestamp = std::cmp::max(max_timestamp, timestamp);
+        }
+    }
+    Ok((min_timestamp, max_timestamp))
 }
+
+/// the encoding, the format (none for a custom regex) and the regex for the
+/// timestamps (none for JSON lines), JSON lines are detected if the kind
+/// of the file is not known
+fn timestamp_source(
+    item: &DiscoverItem,
+    file_path: &Path,
+    kind: Option<FileKind>,
+) -> Result<(&'static Encoding, Option<String>, Option<Regex>), failure::Error> {
+    let encoding = resolve_encoding(item.encoding.as_deref(), file_path)?;
+    if let Some(pattern) = &item.regex {
+        return Ok((encoding, None, Some(custom_regex_to_regex(pattern)?)));
+    }
+    let json_lines = match kind {
+        Some(kind) => kind == FileKind::JsonLines,
+        None => detect_json_lines(file_path, Some(encoding))?,
+    };
+    if json_lines {
+        return Ok((encoding, Some(JSON_FORMAT.to_string()), None));
+    }
+    let format_expr = detect_timestamp_format_in_file(file_path, Some(encoding))?;
+    let regex = lookup_regex_for_format_str(&format_expr)?;
+    Ok((encoding, Some(format_expr), Some(regex)))
+}
+
+/// scans the timespan of a text file and reports it with the discovered format
+fn timespan_in_text_file(
+    item: &DiscoverItem,
+    file_path: &Path,
+    discovery: Option<FormatDiscovery>,
+    update_channel: &cc::Sender<IndexingResults<TimestampFormatResult>>,
+) -> Result<(), failure::Error> {
+    match timestamp_source(item, file_path, discovery.as_ref().map(|d| d.kind)) {
+        Ok((encoding, format_expr, regex)) => {
+            let (min_timestamp, max_timestamp) = match &regex {
+                Some(regex) => scan_timespan(file_path, encoding, regex, update_channel)?,
+                None => scan_json_timespan(file_path, encoding)?,
+            };
+            let _ = update_channel.send(Ok(IndexingProgress::GotItem {
+                item: TimestampFormatResult {
+                    path: item.path.to_string(),
+                    format: format_expr,
+                    regex: item.regex.clone(),
+                    min_time: Some(posix_timestamp_as_string(min_timestamp)),
+                    max_time: Some(posix_timestamp_as_string(max_timestamp)),
+                    encoding: Some(encoding.name().to_string()),
+                    discovery,
+                },
+            }));
+        }
+        Err(e) => {
+            let _ = update_channel.send(Ok(IndexingProgress::GotItem {
+                item: TimestampFormatResult {
+                    path: item.path.to_string(),
+                    format: None,
+                    regex: item.regex.clone(),
+                    min_time: None,
+                    max_time: None,
+                    encoding: None,
+                    discovery,
+                },
+            }));
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::WARNING,
+                content: format!("executed with error: {}", e),
+                line: None,
+            }));
+        }
+    }
+    Ok(())
+}
+
 /// find out the lower and upper timestamp of a file
 pub fn timespan_in_files(
     items: Vec<DiscoverItem>,
@@ -434,77 +890,39 @@ pub fn timespan_in_files(
     let mut progress_percentage = 0usize;
     for (processed_items, item) in items.into_iter().enumerate() {
         let file_path = path::PathBuf::from(&item.path);
-        match detect_timestamp_format_in_file(&file_path) {
-            Ok(format_expr) => {
-                let regex = lookup_regex_for_format_str(&format_expr)?;
-                let f: fs::File = fs::File::open(file_path)?;
-                let mut min_timestamp = std::i64::MAX;
-                let mut max_timestamp = 0i64;
-                let file_size = f.metadata()?.len();
-                let min_buf_size = std::cmp::min(file_size, 256 * 1024); // 256k
-                let lines_to_scan = 1000usize;
-                let _line_cnt = match scan_lines(
-                    &f,
-                    &regex,
-                    &mut min_timestamp,
-                    &mut max_timestamp,
-                    Some(lines_to_scan),
-                    None,
-                ) {
-                    Ok(n) => n,
-                    Err((e, n)) => {
-                        let _ = update_channel.send(Err(Notification {
-                            severity: Severity::WARNING,
-                            content: format!("{}", e),
-                            line: None,
-                        }));
-                        n
-                    }
-                };
-                // also read from end
-                let start = file_size - min_buf_size;
-                let _last_scanned_lines = match scan_lines(
-                    &f,
-                    &regex,
-                    &mut min_timestamp,
-                    &mut max_timestamp,
-                    None,
-                    Some(start),
-                ) {
-                    Ok(n) => n,
-                    Err((e, n)) => {
-                        let _ = update_channel.send(Err(Notification {
-                            severity: Severity::WARNING,
-                            content: format!("{}", e),
-                            line: None,
-                        }));
-                        n
-                    }
-                };
-                let _ = update_channel.send(Ok(IndexingProgress::GotItem {
-                    item: TimestampFormatResult {
-                        path: item.path.to_string(),
-                        format: Some(format_expr),
-                        min_time: Some(posix_timestamp_as_string(min_timestamp)),
-                        max_time: Some(posix_timestamp_as_string(max_timestamp)),
-                    },
+        let discovery = match item
+            .encoding
+            .as_deref()
+            .map(encoding_for_label)
+            .transpose()
+            .and_then(|encoding| discover_format(&file_path, encoding))
+        {
+            Ok(discovery) => Some(discovery),
+            Err(e) => {
+                let _ = update_channel.send(Err(Notification {
+                    severity: Severity::WARNING,
+                    content: format!("could not discover format of {}: {}", item.path, e),
+                    line: None,
                 }));
+                None
             }
-            Err(e) => {
+        };
+        match discovery {
+            // there are no timestamps to scan in binary files
+            Some(discovery) if !discovery.kind.is_text() => {
                 let _ = update_channel.send(Ok(IndexingProgress::GotItem {
                     item: TimestampFormatResult {
                         path: item.path.to_string(),
                         format: None,
+                        regex: item.regex.clone(),
                         min_time: None,
                         max_time: None,
+                        encoding: None,
+                        discovery: Some(discovery),
                     },
                 }));
-                let _ = update_channel.send(Err(Notification {
-                    severity: Severity::WARNING,
-                    content: format!("executed with error: {}", e),
-                    line: None,
-                }));
             }
+            discovery => timespan_in_text_file(&item, &file_path, discovery, update_channel)?,
         }
         let new_progress_percentage: usize =
             (processed_items as f64 / item_count as f64 * 10.0).round() as usize;
@@ -518,16 +936,16 @@ pub fn timespan_in_files(
     let _ = update_channel.send(Ok(IndexingProgress::Finished));
     Ok(())
 }
-/// find out how often a format string matches a timestamp in a file
+/// find out how often a format string (or a custom regex) matches a timestamp in a file
 pub fn match_format_string_in_file(
     format_expr: &str,
+    custom_regex: Option<&str>,
     file_name: &str,
     max_lines: i64,
 ) -> Result<FormatStringMatches, failure::Error> {
-    let regex = lookup_regex_for_format_str(format_expr)?;
+    let regex = timestamp_regex(format_expr, custom_regex)?;
     let path = PathBuf::from(file_name);
-    let f: fs::File = fs::File::open(path)?;
-    let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+    let mut reader = BufReader::new(open_text_input(&path, None)?);
     let mut buf = vec![];
     let mut inspected_lines = 0usize;
     let mut matched_lines = 0usize;
@@ -566,24 +984,87 @@ pub fn line_matching_format_expression(
     let res = regex.is_match(line);
     Ok(res)
 }
-fn parse_from_month(mmm: &str) -> Result<u32, failure::Error> {
-    match mmm {
-        "Jan" => Ok(1),
-        "Feb" => Ok(2),
-        "Mar" => Ok(3),
-        "Apr" => Ok(4),
-        "May" => Ok(5),
-        "Jun" => Ok(6),
-        "Jul" => Ok(7),
-        "Aug" => Ok(8),
-        "Sep" => Ok(9),
-        "Oct" => Ok(10),
-        "Nov" => Ok(11),
-        "Dec" => Ok(12),
-        _ => Err(failure::err_msg(format!("could not parse month {:?}", mmm))),
+/// the month (1-12) for a short or full month name in one of the supported languages
+fn parse_from_month(name: &str) -> Result<u32, failure::Error> {
+    let lowercase = name.trim_end_matches('.').to_lowercase();
+    MONTH_NAMES
+        .iter()
+        .find_map(|row| row.iter().position(|m| m.to_lowercase() == lowercase))
+        .map(|index| index as u32 + 1)
+        .ok_or_else(|| failure::err_msg(format!("could not parse month {:?}", name)))
+}
+/// without a year in the timestamp we assume the current one, unless the date would
+/// be in the future (syslog written in december and read in january)
+pub(crate) fn infer_year(month: u32, day: u32, today: NaiveDate) -> i32 {
+    let year = today.year();
+    match NaiveDate::from_ymd_opt(year, month, day) {
+        Some(date) if date > today.succ() => year - 1,
+        _ => year,
     }
 }
-// return the timestamp and wether the year was missing
+/// nanoseconds of a fraction of a second, "5" is 500ms and "123456" is 123456us,
+/// digits beyond nanoseconds are dropped
+fn fraction_to_nanos(fraction: &str) -> Result<u32, failure::Error> {
+    Ok(format!("{:0<9.9}", fraction).parse()?)
+}
+/// epoch and uptime timestamps are complete on their own
+fn extract_timestamp_from_number(
+    caps: &regex::Captures,
+    time_offset: Option<i64>,
+) -> Option<Result<i64, failure::Error>> {
+    let parse_with = |group: &str, to_nanos: fn(i64) -> i64| {
+        caps.name(group).map(|m| {
+            m.as_str()
+                .parse::<i64>()
+                .map(to_nanos)
+                .map_err(failure::Error::from)
+        })
+    };
+    // epoch values are UTC, an offset does not apply
+    if let Some(epoch) = caps.name(EPOCH_SECONDS_GROUP) {
+        return Some(seconds_with_fraction_to_nanos(epoch.as_str()));
+    }
+    parse_with(EPOCH_MILLIS_GROUP, |ms| ms * NANOS_PER_MILLI)
+        .or_else(|| parse_with(EPOCH_MICROS_GROUP, |us| us * 1000))
+        .or_else(|| parse_with(EPOCH_NANOS_GROUP, |ns| ns))
+        .or_else(|| {
+            caps.name(UPTIME_GROUP).map(|uptime| {
+                seconds_with_fraction_to_nanos(uptime.as_str())
+                    .map(|ns| ns - time_offset.unwrap_or(0) * NANOS_PER_MILLI)
+            })
+        })
+}
+/// nanoseconds since 1970 of `date_time`, `None` if they don't fit into an i64
+/// (dates before 1677 or after 2262)
+pub fn checked_timestamp_nanos(date_time: &NaiveDateTime) -> Option<i64> {
+    date_time
+        .timestamp()
+        .checked_mul(1_000_000_000)
+        .and_then(|ns| ns.checked_add(i64::from(date_time.timestamp_subsec_nanos())))
+}
+/// the timestamp (ns) of a local date and time that is `offset` (ms) ahead of UTC
+fn utc_timestamp(date_time: &NaiveDateTime, offset: i64) -> Result<i64, failure::Error> {
+    checked_timestamp_nanos(date_time)
+        .and_then(|ns| ns.checked_sub(offset.checked_mul(NANOS_PER_MILLI)?))
+        .ok_or_else(|| {
+            failure::err_msg(format!(
+                "date {} is out of range (only 1677 to 2262 can be represented)",
+                date_time
+            ))
+        })
+}
+fn seconds_with_fraction_to_nanos(input: &str) -> Result<i64, failure::Error> {
+    let mut parts = input.splitn(2, '.');
+    let seconds: i64 = parts.next().unwrap_or_default().parse()?;
+    let nanos = match parts.next() {
+        Some(fraction) => fraction_to_nanos(fraction)?,
+        None => 0,
+    };
+    Ok(seconds * NANOS_PER_SECOND + i64::from(nanos))
+}
+/// returns the timestamp in nanoseconds and wether the year was missing
+/// (not part of the format, the supplied or an inferred year is used),
+/// `time_offset` is in milliseconds
 pub fn extract_posix_timestamp(
     line: &str,
     regex: &Regex,
@@ -593,24 +1074,35 @@ pub fn extract_posix_timestamp(
     let caps = regex
         .captures(line)
         .ok_or_else(|| failure::err_msg("no captures in regex"))?;
+    if let Some(timestamp) = extract_timestamp_from_number(&caps, time_offset) {
+        return timestamp.map(|t| (t, false));
+    }
     /* only one matched group in addition to the full match */
     if caps.len() == 1 + 1 {
         if let Some(abs_ms_capt) = caps.name(ABSOLUTE_MS_GROUP) {
             let absolute_ms: i64 = abs_ms_capt.as_str().parse()?;
-            return Ok((absolute_ms - time_offset.unwrap_or(0), false));
+            return Ok((
+                (absolute_ms - time_offset.unwrap_or(0)) * NANOS_PER_MILLI,
+                false,
+            ));
         }
     }
     let day_capt = caps
         .name(DAY_GROUP)
         .ok_or_else(|| failure::err_msg("no group for days found in regex"))?;
-    let day: u32 = day_capt.as_str().parse()?;
+    let day: u32 = day_capt.as_str().trim_start().parse()?;
     let month = match caps.name(MONTH_GROUP) {
+        // a custom regex can also match month names with the month group
         Some(month_capt) => month_capt
             .as_str()
             .parse()
+            .or_else(|_| parse_from_month(month_capt.as_str()))
             .map_err(|e| failure::err_msg(format!("could not parse month: {}", e))),
-        None => match caps.name(MONTH_SHORT_NAME_GROUP) {
-            Some(month_short_name) => parse_from_month(month_short_name.as_str()),
+        None => match caps
+            .name(MONTH_SHORT_NAME_GROUP)
+            .or_else(|| caps.name(MONTH_FULL_NAME_GROUP))
+        {
+            Some(month_name) => parse_from_month(month_name.as_str()),
             None => Err(failure::err_msg("no group for month found in regex")),
         },
     }?;
@@ -639,16 +1131,11 @@ pub fn extract_posix_timestamp(
         min_capt.as_str().parse()?,
         sec_capt.as_str().parse()?,
         match caps.name(FRACTION_GROUP) {
-            Some(m) => {
-                let s = m.as_str();
-                let cut = &s[..std::cmp::min(3, s.len())];
-                cut.parse()?
-            }
+            Some(m) => fraction_to_nanos(m.as_str())?,
             None => 0,
         },
     );
-    let millis = second_fractions;
-    // let millis = (second_fractions as f64 / 1000.0) as u32;
+    let nanos = second_fractions;
 
     let timezone_n = caps.name(TIMEZONE_GROUP);
     if time_offset.is_none() && timezone_n.is_none() {
@@ -664,6 +1151,7 @@ pub fn extract_posix_timestamp(
 
     // for the year first try YYYY, then yy, then fallback on the supplied year
     // if even this is not here, use the current year
+    let year_was_missing = caps.name(YEAR_GROUP).is_none() && caps.name(YEAR_SHORT_GROUP).is_none();
     let the_year: Option<i32> = match caps.name(YEAR_GROUP) {
         Some(year_str) => year_str.as_str().parse().ok(),
         None => match caps.name(YEAR_SHORT_GROUP) {
@@ -672,16 +1160,16 @@ pub fn extract_posix_timestamp(
                 .parse()
                 .map(|ys: i32| ys + 2000i32)
                 .ok(),
-            None => year.or_else(|| Some(Utc::now().year())),
+            None => year.or_else(|| Some(infer_year(month, day, Utc::today().naive_utc()))),
         },
     };
 
     match (the_year, offset_result) {
         (Some(y), Ok(offset)) => {
             let date_time: Option<NaiveDateTime> = NaiveDate::from_ymd_opt(y, month, day)
-                .and_then(|d| d.and_hms_milli_opt(hour, minutes, seconds, millis));
+                .and_then(|d| d.and_hms_nano_opt(hour, minutes, seconds, nanos));
             match date_time {
-                Some(dt) => Ok((dt.timestamp_millis() - offset, false)),
+                Some(dt) => utc_timestamp(&dt, offset).map(|ts| (ts, year_was_missing)),
                 None => Err(failure::err_msg(
                     "error while parsing year/month/day/hour/minute/seconds",
                 )),
@@ -690,9 +1178,9 @@ pub fn extract_posix_timestamp(
         (None, Ok(offset)) => {
             let y = Utc::now().year();
             let date_time: Option<NaiveDateTime> = NaiveDate::from_ymd_opt(y, month, day)
-                .and_then(|d| d.and_hms_milli_opt(hour, minutes, seconds, millis));
+                .and_then(|d| d.and_hms_nano_opt(hour, minutes, seconds, nanos));
             match date_time {
-                Some(dt) => Ok((dt.timestamp_millis() - offset, true)),
+                Some(dt) => utc_timestamp(&dt, offset).map(|ts| (ts, true)),
                 None => Err(failure::err_msg(
                     "error while parsing year/month/day/hour/minute/seconds",
                 )),
@@ -739,17 +1227,32 @@ pub fn line_to_timed_line(
 #[derive(Deserialize, Debug)]
 pub struct DiscoverItem {
     pub path: String,
+    /// label of the encoding, detected if not present
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// regex with named groups for the timestamp, the format is detected if not present
+    #[serde(default)]
+    pub regex: Option<String>,
 }
 #[derive(Serialize, Debug)]
 pub struct TimestampFormatResult {
     pub path: String,
     pub format: Option<String>,
+    /// the custom regex that was used instead of a format
+    pub regex: Option<String>,
     pub min_time: Option<String>,
     pub max_time: Option<String>,
+    /// the encoding that was used to read the file
+    pub encoding: Option<String>,
+    /// what kind of file it is with suggestions for the format
+    pub discovery: Option<FormatDiscovery>,
 }
-pub fn detect_timestamp_format_in_file(path: &Path) -> Result<String, failure::Error> {
-    let f: fs::File = fs::File::open(path)?;
-    let mut reader: BufReader<&std::fs::File> = BufReader::new(&f);
+/// without `encoding` it is detected from the file
+pub fn detect_timestamp_format_in_file(
+    path: &Path,
+    encoding: Option<&'static Encoding>,
+) -> Result<String, failure::Error> {
+    let mut reader = BufReader::new(open_text_input(path, encoding)?);
 
     let mut buf = vec![];
     let mut inspected_lines = 0;
@@ -804,7 +1307,7 @@ pub fn detect_timestamp_format_in_file(path: &Path) -> Result<String, failure::E
 }
 /// Trys to detect a valid timestamp in a string
 /// Returns the a tuple of
-/// * the timestamp as posix timestamp
+/// * the timestamp as posix timestamp in nanoseconds
 /// * if the year was missing
 ///   (we assume the current year (local time) if true)
 /// * the format string that was used
@@ -830,6 +1333,15 @@ pub fn detect_timestamp_in_string(
     }
     Err(failure::err_msg("try to detect timestamp but no match"))
 }
+/// all known formats that match a timestamp in a string
+pub fn timestamp_formats_in_string(input: &str) -> Vec<&'static str> {
+    let trimmed = input.trim();
+    AVAILABLE_FORMATS
+        .iter()
+        .filter(|format| FORMAT_REGEX_MAPPING[*format].is_match(trimmed))
+        .cloned()
+        .collect()
+}
 /// Trys to detect a valid time-format in a string
 /// Returns the found format if any
 ///
@@ -863,10 +1375,14 @@ pub fn detect_timeformat_in_string(
 }
 
 /// should parse timezone string, valid formats are
+/// Z (UTC)
 /// +hh:mm, +hhmm, or +hh
 /// -hh:mm, -hhmm, or -hh
 /// results in the offset in milliseconds
 fn timezone_parser(input: &str) -> IResult<&str, i64> {
+    if let Some(rest) = input.strip_prefix('Z') {
+        return Ok((rest, 0));
+    }
     let timezone_sign = map(nom::branch::alt((char('+'), char('-'))), |c| c == '+');
     fn timezone_count(input: &str) -> IResult<&str, i64> {
         let (rest, r) = nom::bytes::complete::take(2usize)(input)?;
@@ -942,7 +1458,7 @@ pub fn parse_full_timestamp(input: &str, regex: &Regex) -> Result<(i64, bool), f
                         NaiveDate::from_ymd_opt(year as i32, month, day)
                             .and_then(|d| d.and_hms_milli_opt(hour, minutes, seconds, 0));
                     match date_time {
-                        Some(dt) => Ok((dt.timestamp_millis() - offset, false)),
+                        Some(dt) => utc_timestamp(&dt, offset).map(|ts| (ts, false)),
                         None => Err(failure::err_msg(
                             "error while parsing year/month/day/hour/minute/seconds",
                         )),
@@ -10,27 +10,37 @@
 // is strictly forbidden unless prior written permission is obtained
 // from E.S.R.Labs.
 
+use crate::grouping::{RecordGrouping, RecordReader, RecordStart};
+use crate::json_log::{JsonLine, JsonLog, JsonLogConfig};
 use crate::parse;
 use crossbeam_channel as cc;
-use encoding_rs_io::*;
 use failure::{err_msg, Error};
 use indexer_base::chunks::ChunkFactory;
 use indexer_base::chunks::ChunkResults;
+use indexer_base::compression::{open_input, ConsumedBytes};
 use indexer_base::config::IndexingConfig;
+use indexer_base::encoding::{detect_encoding, text_reader, Encoding, UTF_8};
+use indexer_base::follow::{wait_for_more, FollowedFile, SourceChange};
 use indexer_base::progress::*;
 use indexer_base::utils;
 use indexer_base::utils::restore_line;
 use parse::detect_timestamp_in_string;
-use std::cell::RefCell;
 use std::fs;
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
+/// with `record_start` the lines of multi-line records (e.g. stack traces)
+/// are indexed as one line, without `encoding` it is detected from the file.
+/// With `json` lines of JSON objects are indexed as columns.
+#[allow(clippy::too_many_arguments)]
 pub fn create_index_and_mapping(
     config: IndexingConfig,
     source_file_size: usize,
     parse_timestamps: bool,
+    record_start: Option<RecordStart>,
+    encoding: Option<&'static Encoding>,
+    json: Option<JsonLogConfig>,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) -> Result<(), Error> {
@@ -52,7 +62,7 @@ pub fn create_index_and_mapping(
     let (out_file, current_out_file_size) =
         utils::get_out_file_and_size(config.append, &config.out_path)?;
 
-    let in_file = match fs::File::open(&config.in_file) {
+    let in_file = match open_input(&config.in_file) {
         Ok(file) => file,
         Err(e) => {
             warn!("could not open {:?}", config.in_file);
@@ -64,15 +74,43 @@ pub fn create_index_and_mapping(
             return Err(err_msg(format!("could not open file ({})", e)));
         }
     };
-    let mut decode_builder = DecodeReaderBytesBuilder::new();
-    decode_builder
-        .utf8_passthru(true)
-        .strip_bom(true)
-        .bom_override(true)
-        .bom_sniffing(true);
-    let decode_buffer_inst = RefCell::new(vec![0; 8 * (1 << 10)]);
-    let mut decode_buffer = decode_buffer_inst.borrow_mut();
-    let read_from = decode_builder.build_with_buffer(in_file, &mut *decode_buffer)?;
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => detect_encoding(&config.in_file)?,
+    };
+    let grouping = match record_start
+        .map(|start| RecordGrouping::new(&start, None, &config.in_file, Some(encoding)))
+        .transpose()
+    {
+        Ok(grouping) => grouping,
+        Err(e) => {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: format!("could not group records ({})", e),
+                line: None,
+            }));
+            return Err(e);
+        }
+    };
+    let json = match json.map(JsonLog::new).transpose() {
+        Ok(json) => json,
+        Err(e) => {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: format!("invalid JSON log configuration ({})", e),
+                line: None,
+            }));
+            return Err(e);
+        }
+    };
+    // the decoded bytes cannot be used for progress reporting if the file is
+    // compressed or transcoded
+    let read_bytes = if encoding == UTF_8 {
+        in_file.compressed_input()
+    } else {
+        Some(in_file.consumed())
+    };
+    let read_from = text_reader(in_file, encoding);
     index_file(
         read_from,
         config.tag,
@@ -80,8 +118,11 @@ pub fn create_index_and_mapping(
         current_out_file_size,
         config.chunk_size,
         source_file_size,
+        read_bytes,
         initial_line_nr,
         parse_timestamps,
+        grouping,
+        json,
         update_channel,
         shutdown_receiver,
     )
@@ -95,8 +136,11 @@ pub fn index_file<T: Read>(
     current_out_file_size: usize,
     chunk_size: usize,
     source_file_size: usize,
+    read_bytes: Option<ConsumedBytes>,
     initial_line_nr: usize,
     timestamps: bool,
+    grouping: Option<RecordGrouping>,
+    json: Option<JsonLog>,
     update_channel: cc::Sender<ChunkResults>,
     shutdown_receiver: Option<cc::Receiver<()>>,
 ) -> Result<(), Error> {
@@ -106,7 +150,7 @@ pub fn index_file<T: Read>(
     let mut last_byte_index = 0usize;
     let mut chunk_factory = ChunkFactory::new(chunk_size, current_out_file_size);
 
-    let mut reader = BufReader::new(read_from);
+    let mut reader = RecordReader::new(BufReader::new(read_from), grouping);
     let mut line_nr = initial_line_nr;
     let mut buf_writer = BufWriter::with_capacity(10 * 1024 * 1024, &out_file);
 
@@ -114,7 +158,8 @@ pub fn index_file<T: Read>(
     let mut stopped = false;
 
     let mut progress_reporter = ProgressReporter::new(source_file_size, update_channel.clone());
-    while let Ok(len) = reader.read_until(b'\n', &mut buf) {
+    progress_reporter.track_read_bytes(read_bytes);
+    while let Ok(len) = reader.read_record(&mut buf) {
         if stopped {
             info!("we where stopped in indexer",);
             break;
@@ -127,16 +172,39 @@ pub fn index_file<T: Read>(
             // no more content
             break;
         };
-        let ts = if timestamps {
-            match detect_timestamp_in_string(trimmed_line, None) {
-                Ok((time, _, _)) => Some(time),
-                Err(_) => Some(0),
+        let json_line = match &json {
+            Some(json) => json.parse_line(trimmed_line),
+            None => JsonLine::Text,
+        };
+        let (ts, record) = match json_line {
+            JsonLine::Entry { timestamp, content } => {
+                let ts = if timestamps {
+                    Some(timestamp.unwrap_or(0))
+                } else {
+                    None
+                };
+                (ts, content.into())
+            }
+            JsonLine::FilteredOut => {
+                progress_reporter.make_progress(len);
+                buf = vec![];
+                continue;
+            }
+            JsonLine::Text => {
+                let ts = if timestamps {
+                    match detect_timestamp_in_string(trimmed_line, None) {
+                        Ok((time, _, _)) => Some(time),
+                        Err(_) => Some(0),
+                    }
+                } else {
+                    None
+                };
+                // lines of a grouped record are stored as one line
+                (ts, utils::encode_newlines(trimmed_line))
             }
-        } else {
-            None
         };
         let additional_bytes: usize =
-            utils::write_tagged_line(tag, &mut buf_writer, trimmed_line, line_nr, had_newline, ts)?;
+            utils::write_tagged_line(tag, &mut buf_writer, &record, line_nr, had_newline, ts)?;
         line_nr += 1;
 
         match chunk_factory.add_bytes(line_nr, additional_bytes) {
@@ -185,9 +253,141 @@ pub fn index_file<T: Read>(
     }
 }
 
+/// writes the lines of a followed file and reports the chunks
+struct FollowedLines<'a> {
+    tag: &'a str,
+    parse_timestamps: bool,
+    writer: BufWriter<fs::File>,
+    chunk_factory: ChunkFactory,
+    line_nr: usize,
+    update_channel: cc::Sender<ChunkResults>,
+}
+
+impl<'a> FollowedLines<'a> {
+    fn write_line(&mut self, line: &[u8]) -> Result<(), Error> {
+        let s = String::from_utf8_lossy(line);
+        let trimmed_line = s.trim_matches(utils::is_newline);
+        let ts = if self.parse_timestamps {
+            match detect_timestamp_in_string(trimmed_line, None) {
+                Ok((time, _, _)) => Some(time),
+                Err(_) => Some(0),
+            }
+        } else {
+            None
+        };
+        let additional_bytes = utils::write_tagged_line(
+            self.tag,
+            &mut self.writer,
+            trimmed_line,
+            self.line_nr,
+            true,
+            ts,
+        )?;
+        self.line_nr += 1;
+        if let Some(chunk) = self.chunk_factory.add_bytes(self.line_nr, additional_bytes) {
+            self.writer.flush()?;
+            self.update_channel
+                .send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+        }
+        Ok(())
+    }
+
+    /// reports the lines that are not yet part of a chunk
+    fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        if let Some(chunk) = self.chunk_factory.create_pending_chunk(self.line_nr) {
+            self.update_channel
+                .send(Ok(IndexingProgress::GotItem { item: chunk }))?;
+        }
+        Ok(())
+    }
+}
+
+/// like `create_index_and_mapping` but the file is followed after its end was
+/// reached: appended lines are indexed as they come in and a chunk is reported
+/// whenever the file has no new content. Truncated or rotated files are read
+/// again from the start. Runs until a stop is requested.
+pub fn follow_file(
+    config: IndexingConfig,
+    parse_timestamps: bool,
+    poll_interval: Duration,
+    update_channel: cc::Sender<ChunkResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<(), Error> {
+    let line_nr = if config.append {
+        utils::next_line_nr(config.out_path)?
+    } else {
+        0
+    };
+    let (out_file, current_out_file_size) =
+        utils::get_out_file_and_size(config.append, config.out_path)?;
+    let followed = match FollowedFile::open(&config.in_file) {
+        Ok(followed) => followed,
+        Err(e) => {
+            let _ = update_channel.send(Err(Notification {
+                severity: Severity::ERROR,
+                content: format!("could not follow file ({})", e),
+                line: None,
+            }));
+            return Err(e);
+        }
+    };
+    let mut reader = BufReader::new(followed);
+    let mut lines = FollowedLines {
+        tag: config.tag,
+        parse_timestamps,
+        writer: BufWriter::new(out_file),
+        chunk_factory: ChunkFactory::new(config.chunk_size, current_out_file_size),
+        line_nr,
+        update_channel: update_channel.clone(),
+    };
+    // an incomplete line stays in `buf` until the rest was written
+    let mut buf = vec![];
+    loop {
+        reader.read_until(b'\n', &mut buf)?;
+        if buf.ends_with(b"\n") {
+            lines.write_line(&buf)?;
+            buf.clear();
+            continue;
+        }
+        // reached the current end of the file
+        lines.flush()?;
+        match reader.get_mut().check_source()? {
+            SourceChange::Unchanged => {
+                if wait_for_more(poll_interval, &shutdown_receiver) {
+                    break;
+                }
+                continue;
+            }
+            SourceChange::Truncated => (),
+            SourceChange::Rotated { rest } => buf.extend_from_slice(&rest),
+        }
+        // the old content might not end with a newline
+        for line in buf.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+            lines.write_line(line)?;
+        }
+        buf.clear();
+        lines.flush()?;
+        let _ = update_channel.send(Err(Notification {
+            severity: Severity::WARNING,
+            content: format!(
+                "{:?} was truncated or replaced, reading it from the start",
+                config.in_file
+            ),
+            line: Some(lines.line_nr),
+        }));
+    }
+    lines.flush()?;
+    update_channel.send(Ok(IndexingProgress::Stopped))?;
+    Ok(())
+}
+
+/// `grouped_records` restores the lines of records if the file was indexed
+/// with record grouping
 pub fn restore_original_from_indexed_file(
     indexed_file: &PathBuf,
     out: &PathBuf,
+    grouped_records: bool,
 ) -> Result<(), Error> {
     let f = fs::File::open(&indexed_file)?;
     let reader = &mut std::io::BufReader::new(f);
@@ -198,7 +398,12 @@ pub fn restore_original_from_indexed_file(
     let lines_iter = &mut reader.lines();
     for line_res in lines_iter {
         let line = line_res?;
-        out_writer.write_fmt(format_args!("{}\n", restore_line(&line)))?;
+        let restored = restore_line(&line);
+        if grouped_records {
+            out_writer.write_fmt(format_args!("{}\n", utils::decode_newlines(restored)))?;
+        } else {
+            out_writer.write_fmt(format_args!("{}\n", restored))?;
+        }
     }
     Ok(())
 }
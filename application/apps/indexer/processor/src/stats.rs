@@ -0,0 +1,408 @@
+// Copyright (c) 2020 E.S.R.Labs. All rights reserved.
+//
+// NOTICE:  All information contained herein is, and remains
+// the property of E.S.R.Labs and its suppliers, if any.
+// The intellectual and technical concepts contained herein are
+// proprietary to E.S.R.Labs and its suppliers and may be covered
+// by German and Foreign Patents, patents in process, and are protected
+// by trade secret or copyright law.
+// Dissemination of this information or reproduction of this material
+// is strictly forbidden unless prior written permission is obtained
+// from E.S.R.Labs.
+use crate::parse::{
+    custom_regex_to_regex, detect_timestamp_format_in_file, extract_posix_timestamp,
+    lookup_regex_for_format_str, posix_timestamp_as_string,
+};
+use crossbeam_channel as cc;
+use failure::{err_msg, Error};
+use indexer_base::compression::{input_size, open_input, ConsumedBytes};
+use indexer_base::encoding::{resolve_encoding, text_reader, Encoding, UTF_8};
+use indexer_base::progress::{IndexingProgress, Notification, Severity};
+use indexer_base::utils;
+use regex::Regex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// names of the built-in level patterns
+pub const SYSLOG_PRIORITY_FIELD: &str = "syslog_priority";
+pub const LOGCAT_THREADTIME_FIELD: &str = "logcat_threadtime";
+pub const LOGCAT_FIELD: &str = "logcat";
+/// lines after which progress is reported and a stop request is checked
+const STOP_CHECK_LINE_THRESHOLD: usize = 250_000;
+
+lazy_static! {
+    /// syslog priority (RFC 3164/5424), the severity is the lower 3 bits
+    static ref SYSLOG_PRI_REGEX: Regex = Regex::new(r"^<(?P<pri>\d{1,3})>").unwrap();
+    /// the built-in patterns with their names, the first one that matches is used
+    static ref LEVEL_REGEXES: Vec<(&'static str, Regex)> = vec![
+        // logcat threadtime: 07-30 10:08:02.555  1234  1240 W ActivityManager: ...
+        (
+            LOGCAT_THREADTIME_FIELD,
+            Regex::new(
+                r"^\d{2}-\d{2}\s+\d{2}:\d{2}:\d{2}\.\d+\s+\d+\s+\d+\s+(?P<level>[VDIWEFA])\s+(?P<source>[^:]*?)\s*:\s"
+            )
+            .unwrap(),
+        ),
+        // logcat brief, process and time: W/ActivityManager( 1234): ...
+        (
+            LOGCAT_FIELD,
+            Regex::new(r"(?:^|\s)(?P<level>[VDIWEFA])/(?P<source>[^(\s]+)\s*\(\s*\d+\)\s*:")
+                .unwrap(),
+        ),
+        // severity words, optionally followed by a logger: ERROR [main] com.example.Main - ...
+        (
+            "severity_word",
+            Regex::new(
+                r"\b(?P<level>FATAL|CRITICAL|CRIT|SEVERE|ERROR|ERR|WARNING|WARN|INFO|NOTICE|DEBUG|TRACE|VERBOSE)\b(?:\s+(?:\[[^\]]*\]\s+)?(?P<source>[A-Za-z_][\w.$/-]*)(?:\s*:|\s+-)\s)?"
+            )
+            .unwrap(),
+        ),
+        // logfmt: level=error
+        ("logfmt", Regex::new(r#"(?i)\blevel[=:]\s*"?(?P<level>[a-z]+)"#).unwrap()),
+    ];
+    /// syslog program name: myhost sshd[1234]: ...
+    static ref SYSLOG_SOURCE_REGEX: Regex =
+        Regex::new(r"\s(?P<source>[A-Za-z][\w.\-/]*)\[\d+\]:").unwrap();
+}
+
+/// how the levels, sources and timestamps of a text file are found
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TextStatsConfig {
+    /// regex whose `level` group (or first group or match) is the severity,
+    /// the built-in patterns are used if not present
+    pub level_pattern: Option<String>,
+    /// regex whose `source` group (or first group) is the logger or tag
+    pub source_pattern: Option<String>,
+    /// format of the timestamps, detected if neither a format nor a regex is present
+    pub format: Option<String>,
+    /// regex with named groups for the timestamps
+    pub regex: Option<String>,
+    /// label of the encoding, detected if not present
+    pub encoding: Option<String>,
+    /// how many of the sources with the most lines are reported
+    pub top_sources: usize,
+}
+
+impl Default for TextStatsConfig {
+    fn default() -> TextStatsConfig {
+        TextStatsConfig {
+            level_pattern: None,
+            source_pattern: None,
+            format: None,
+            regex: None,
+            encoding: None,
+            top_sources: 20,
+        }
+    }
+}
+
+pub fn read_text_stats_config(f: &mut fs::File) -> Result<TextStatsConfig, Error> {
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)?;
+    let v: TextStatsConfig = serde_json::from_str(&contents[..])?;
+    Ok(v)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextLevel {
+    Fatal,
+    Error,
+    Warning,
+    Info,
+    Debug,
+    Verbose,
+}
+
+/// the names, single letters (logcat) and numbers (bunyan, pino) of the levels
+pub fn level_from_token(token: &str) -> Option<TextLevel> {
+    match token.trim().to_lowercase().as_str() {
+        "fatal" | "f" | "a" | "assert" | "critical" | "crit" | "severe" | "emerg" | "alert"
+        | "panic" | "60" => Some(TextLevel::Fatal),
+        "error" | "err" | "e" | "50" => Some(TextLevel::Error),
+        "warning" | "warn" | "w" | "40" => Some(TextLevel::Warning),
+        "info" | "information" | "notice" | "i" | "30" => Some(TextLevel::Info),
+        "debug" | "d" | "20" => Some(TextLevel::Debug),
+        "verbose" | "trace" | "v" | "10" => Some(TextLevel::Verbose),
+        _ => None,
+    }
+}
+
+fn level_from_syslog_pri(pri: &str) -> Option<TextLevel> {
+    match pri.parse::<u8>().ok()? & 7 {
+        0..=2 => Some(TextLevel::Fatal),
+        3 => Some(TextLevel::Error),
+        4 => Some(TextLevel::Warning),
+        5 | 6 => Some(TextLevel::Info),
+        _ => Some(TextLevel::Debug),
+    }
+}
+
+/// the named group, the first group or the whole match
+fn group_text<'t>(regex: &Regex, line: &'t str, name: &str) -> Option<&'t str> {
+    let caps = regex.captures(line)?;
+    caps.name(name)
+        .or_else(|| caps.get(1))
+        .or_else(|| caps.get(0))
+        .map(|m| m.as_str())
+}
+
+/// finds the level and the source (logger or tag) of a line
+pub struct LevelDetector {
+    level_regex: Option<Regex>,
+    source_regex: Option<Regex>,
+}
+
+impl LevelDetector {
+    pub fn new(config: &TextStatsConfig) -> Result<LevelDetector, Error> {
+        let compile = |pattern: &Option<String>| -> Result<Option<Regex>, Error> {
+            match pattern {
+                Some(p) => Regex::new(p)
+                    .map(Some)
+                    .map_err(|e| err_msg(format!("invalid pattern {}: {}", p, e))),
+                None => Ok(None),
+            }
+        };
+        Ok(LevelDetector {
+            level_regex: compile(&config.level_pattern)?,
+            source_regex: compile(&config.source_pattern)?,
+        })
+    }
+
+    pub fn detect(&self, line: &str) -> (Option<TextLevel>, Option<String>) {
+        let mut level = None;
+        let mut source = None;
+        match &self.level_regex {
+            Some(regex) => level = group_text(regex, line, "level").and_then(level_from_token),
+            None => {
+                if let Some((_, builtin_level, builtin_source)) = builtin_level(line) {
+                    level = Some(builtin_level);
+                    source = builtin_source;
+                }
+            }
+        }
+        match &self.source_regex {
+            Some(regex) => {
+                source = regex.captures(line).and_then(|caps| {
+                    caps.name("source")
+                        .or_else(|| caps.get(1))
+                        .map(|m| m.as_str().to_string())
+                })
+            }
+            None if source.is_none() => {
+                source = SYSLOG_SOURCE_REGEX
+                    .captures(line)
+                    .map(|caps| caps["source"].to_string())
+            }
+            None => (),
+        }
+        (level, source.filter(|s| !s.is_empty()))
+    }
+}
+
+/// the name of the built-in pattern, the level and the source of a line
+fn builtin_level(line: &str) -> Option<(&'static str, TextLevel, Option<String>)> {
+    if let Some(caps) = SYSLOG_PRI_REGEX.captures(line) {
+        if let Some(level) = level_from_syslog_pri(&caps["pri"]) {
+            return Some((SYSLOG_PRIORITY_FIELD, level, None));
+        }
+    }
+    LEVEL_REGEXES.iter().find_map(|(name, regex)| {
+        let caps = regex.captures(line)?;
+        let level = level_from_token(caps.name("level")?.as_str())?;
+        let source = caps.name("source").map(|m| m.as_str().to_string());
+        Some((*name, level, source))
+    })
+}
+
+/// the name of the built-in pattern that finds the level of a line
+pub fn builtin_level_field(line: &str) -> Option<&'static str> {
+    builtin_level(line).map(|(name, _, _)| name)
+}
+
+/// number of lines per level, like the level distribution of DLT files
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct LevelDistribution {
+    pub no_level: usize,
+    pub log_fatal: usize,
+    pub log_error: usize,
+    pub log_warning: usize,
+    pub log_info: usize,
+    pub log_debug: usize,
+    pub log_verbose: usize,
+}
+
+impl LevelDistribution {
+    pub fn add(&mut self, level: Option<TextLevel>) {
+        match level {
+            None => self.no_level += 1,
+            Some(TextLevel::Fatal) => self.log_fatal += 1,
+            Some(TextLevel::Error) => self.log_error += 1,
+            Some(TextLevel::Warning) => self.log_warning += 1,
+            Some(TextLevel::Info) => self.log_info += 1,
+            Some(TextLevel::Debug) => self.log_debug += 1,
+            Some(TextLevel::Verbose) => self.log_verbose += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.no_level
+            + self.log_fatal
+            + self.log_error
+            + self.log_warning
+            + self.log_info
+            + self.log_debug
+            + self.log_verbose
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct TextStatisticInfo {
+    pub lines: usize,
+    pub empty_lines: usize,
+    pub lines_with_timestamp: usize,
+    /// the timestamp format that was used
+    pub format: Option<String>,
+    pub min_time: Option<String>,
+    pub max_time: Option<String>,
+    pub encoding: String,
+    /// levels of all lines that are not empty
+    pub levels: LevelDistribution,
+    /// number of different sources
+    pub source_count: usize,
+    /// the sources with the most lines (most lines first)
+    pub top_sources: Vec<(String, LevelDistribution)>,
+}
+
+pub type TextStatisticsResults =
+    std::result::Result<IndexingProgress<TextStatisticInfo>, Notification>;
+
+/// the format (if any) and regex used for the timestamps
+fn timestamp_regex_for(
+    config: &TextStatsConfig,
+    in_file: &Path,
+    encoding: &'static Encoding,
+    update_channel: &cc::Sender<TextStatisticsResults>,
+) -> Result<(Option<String>, Option<Regex>), Error> {
+    if let Some(pattern) = &config.regex {
+        return Ok((None, Some(custom_regex_to_regex(pattern)?)));
+    }
+    let format = match &config.format {
+        Some(format) => format.clone(),
+        None => match detect_timestamp_format_in_file(in_file, Some(encoding)) {
+            Ok(format) => format,
+            Err(e) => {
+                update_channel.send(Err(Notification {
+                    severity: Severity::WARNING,
+                    content: format!("no timestamps used: {}", e),
+                    line: None,
+                }))?;
+                return Ok((None, None));
+            }
+        },
+    };
+    let regex = lookup_regex_for_format_str(&format)?;
+    Ok((Some(format), Some(regex)))
+}
+
+/// collects the levels, sources, line counts and the time span of a text file
+pub fn get_text_file_info(
+    in_file: &Path,
+    config: &TextStatsConfig,
+    update_channel: &cc::Sender<TextStatisticsResults>,
+    shutdown_receiver: Option<cc::Receiver<()>>,
+) -> Result<(), Error> {
+    let detector = LevelDetector::new(config)?;
+    let encoding = resolve_encoding(config.encoding.as_deref(), in_file)?;
+    let (format, timestamp_regex) = timestamp_regex_for(config, in_file, encoding, update_channel)?;
+    let source_file_size = input_size(in_file)? as usize;
+    let input = open_input(in_file)?;
+    // the decoded bytes cannot be used for progress reporting if the file is
+    // compressed or transcoded
+    let read_bytes = if encoding == UTF_8 {
+        input.compressed_input()
+    } else {
+        Some(input.consumed())
+    };
+    let mut reader = BufReader::new(text_reader(input, encoding));
+
+    let mut lines = 0usize;
+    let mut empty_lines = 0usize;
+    let mut lines_with_timestamp = 0usize;
+    let mut min_timestamp: Option<i64> = None;
+    let mut max_timestamp: Option<i64> = None;
+    let mut levels = LevelDistribution::default();
+    let mut sources: FxHashMap<String, LevelDistribution> = FxHashMap::default();
+    let mut processed_bytes = 0usize;
+    let mut lines_since_check = 0usize;
+    let mut buf = vec![];
+    loop {
+        buf.clear();
+        let len = reader.read_until(b'\n', &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        processed_bytes += len;
+        lines += 1;
+        let line = String::from_utf8_lossy(&buf);
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            empty_lines += 1;
+        } else {
+            if let Some(regex) = &timestamp_regex {
+                if let Ok((timestamp, _)) = extract_posix_timestamp(line, regex, None, Some(0)) {
+                    lines_with_timestamp += 1;
+                    min_timestamp = Some(min_timestamp.map_or(timestamp, |t| t.min(timestamp)));
+                    max_timestamp = Some(max_timestamp.map_or(timestamp, |t| t.max(timestamp)));
+                }
+            }
+            let (level, source) = detector.detect(line);
+            levels.add(level);
+            if let Some(source) = source {
+                sources.entry(source).or_default().add(level);
+            }
+        }
+        lines_since_check += 1;
+        if lines_since_check == STOP_CHECK_LINE_THRESHOLD {
+            lines_since_check = 0;
+            if utils::check_if_stop_was_requested(&shutdown_receiver, "text stats producer") {
+                // the statistic of a part of the file would be misleading
+                update_channel.send(Ok(IndexingProgress::Stopped))?;
+                return Ok(());
+            }
+            let progress_bytes = read_bytes
+                .as_ref()
+                .map_or(processed_bytes, ConsumedBytes::get);
+            update_channel.send(Ok(IndexingProgress::Progress {
+                ticks: (
+                    std::cmp::min(progress_bytes, source_file_size),
+                    source_file_size,
+                ),
+            }))?;
+        }
+    }
+    let source_count = sources.len();
+    let mut top_sources: Vec<(String, LevelDistribution)> = sources.into_iter().collect();
+    top_sources.sort_by(|(a_name, a), (b_name, b)| {
+        b.total().cmp(&a.total()).then_with(|| a_name.cmp(b_name))
+    });
+    top_sources.truncate(config.top_sources);
+    let res = TextStatisticInfo {
+        lines,
+        empty_lines,
+        lines_with_timestamp,
+        format,
+        min_time: min_timestamp.map(posix_timestamp_as_string),
+        max_time: max_timestamp.map(posix_timestamp_as_string),
+        encoding: encoding.name().to_string(),
+        levels,
+        source_count,
+        top_sources,
+    };
+    update_channel.send(Ok(IndexingProgress::GotItem { item: res }))?;
+    update_channel.send(Ok(IndexingProgress::Finished))?;
+    Ok(())
+}
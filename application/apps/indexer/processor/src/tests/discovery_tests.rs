@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use crate::discovery::*;
+    use crate::json_log::JSON_FORMAT;
+    use flate2::write::GzEncoder;
+    use indexer_base::compression::Compression;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn values(candidates: &[Candidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.value.as_str()).collect()
+    }
+
+    /// DLT messages with ECU id and timestamp, 20 bytes each
+    fn dlt_messages(count: usize, serial_header: bool) -> Vec<u8> {
+        let mut bytes = vec![];
+        for i in 0..count {
+            if serial_header {
+                bytes.extend_from_slice(b"DLS\x01");
+            }
+            bytes.extend_from_slice(&[0x35, i as u8, 0x00, 0x14]);
+            bytes.extend_from_slice(b"ECU1");
+            bytes.extend_from_slice(&[0, 0, 0, 1]);
+            bytes.extend_from_slice(&[0x41; 8]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_discover_syslog() {
+        let content = "\
+Jul 30 10:08:02 myhost sshd[42]: auth failed
+<14>Jul 30 10:08:03 myhost cron[7]: job done
+Jul 30 10:08:04 myhost kernel: usb 1-1: new device
+<34>Jul 30 10:08:05 myhost app: started";
+        let discovery = discover_text_format(content.lines());
+        assert_eq!(FileKind::Syslog, discovery.kind);
+        assert_eq!(1.0, discovery.confidence);
+        assert_eq!(
+            Some("MMM DD hh:mm:ss"),
+            discovery
+                .timestamp_formats
+                .first()
+                .map(|c| c.value.as_str())
+        );
+        assert_eq!(1.0, discovery.timestamp_formats[0].confidence);
+        assert_eq!(None, discovery.record_grouping);
+        let rfc5424 = "<34>1 2019-07-30T10:08:05Z myhost app 12 - - started";
+        assert_eq!(
+            FileKind::Syslog,
+            discover_text_format(std::iter::once(rfc5424)).kind
+        );
+    }
+
+    #[test]
+    fn test_discover_logcat() {
+        let content = "\
+07-30 10:08:02.555  1234  1240 W ActivityManager: slow operation
+07-30 10:08:02.600  1234  1240 I ActivityManager: done
+--------- beginning of crash
+07-30 10:08:02.700  1234  1241 E AndroidRuntime: FATAL EXCEPTION";
+        let discovery = discover_text_format(content.lines());
+        assert_eq!(FileKind::Logcat, discovery.kind);
+        assert_eq!(0.75, discovery.confidence);
+        assert_eq!(
+            vec!["MM-DD hh:mm:ss.s"],
+            values(&discovery.timestamp_formats)
+        );
+        assert_eq!(vec!["logcat_threadtime"], values(&discovery.level_fields));
+        // the crash marker is not a continuation of the previous message
+        assert_eq!(None, discovery.record_grouping);
+    }
+
+    #[test]
+    fn test_discover_text_with_continuation_lines() {
+        let content = "\
+2019-07-30 10:08:02.100 INFO  Main - started
+2019-07-30 10:08:02.300 ERROR Db - connection lost
+java.io.IOException: reset
+    at com.example.Db.connect(Db.java:12)
+2019-07-30 10:08:03.000 WARN  Db - retrying";
+        let discovery = discover_text_format(content.lines());
+        assert_eq!(FileKind::Text, discovery.kind);
+        assert_eq!(1.0, discovery.confidence);
+        assert_eq!(
+            Some(&Candidate {
+                value: "YYYY-MM-DD hh:mm:ss.s".to_string(),
+                confidence: 0.6,
+            }),
+            discovery.timestamp_formats.first()
+        );
+        assert_eq!(
+            Some(&Candidate {
+                value: "severity_word".to_string(),
+                confidence: 0.6,
+            }),
+            discovery.level_fields.first()
+        );
+        assert_eq!(
+            Some(Candidate {
+                value: TIMESTAMP_GROUPING.to_string(),
+                confidence: 0.6,
+            }),
+            discovery.record_grouping
+        );
+    }
+
+    #[test]
+    fn test_discover_json_lines() {
+        let content = r#"{"timestamp":"2019-07-30T10:08:02.000Z","level":"info","msg":"started"}
+{"timestamp":"2019-07-30T10:08:03.000Z","msg":"no level"}
+{"msg":"neither"}"#;
+        let discovery = discover_text_format(content.lines());
+        assert_eq!(FileKind::JsonLines, discovery.kind);
+        assert_eq!(1.0, discovery.confidence);
+        assert_eq!(
+            vec![Candidate {
+                value: JSON_FORMAT.to_string(),
+                confidence: 0.67,
+            }],
+            discovery.timestamp_formats
+        );
+        assert_eq!(
+            vec![Candidate {
+                value: JSON_LEVEL_FIELD.to_string(),
+                confidence: 0.33,
+            }],
+            discovery.level_fields
+        );
+    }
+
+    #[test]
+    fn test_dlt_stream_share() {
+        assert_eq!(1.0, dlt_stream_share(&dlt_messages(10, false)));
+        assert_eq!(1.0, dlt_stream_share(&dlt_messages(10, true)));
+        let mut garbage = dlt_messages(2, false);
+        garbage.extend_from_slice(&[0xffu8; 60]);
+        assert_eq!(0.4, dlt_stream_share(&garbage));
+        assert_eq!(0.0, dlt_stream_share(b"\x00\x01\x02\x03\x04\x05"));
+        assert_eq!(0.0, dlt_stream_share(&[]));
+        // a message that does not end in the sample is not counted
+        let mut truncated = dlt_messages(4, false);
+        let mut longer = dlt_messages(1, false);
+        longer[3] = 40;
+        truncated.extend_from_slice(&longer);
+        assert_eq!(0.8, dlt_stream_share(&truncated));
+    }
+
+    #[test]
+    fn test_binary_with_one_header_is_no_dlt_stream() {
+        let tmp_dir = TempDir::new("discovery").expect("could not create temp dir");
+        // looks like a version 1 header with a length that covers the 64 KiB
+        // sample, followed by random bytes
+        let mut content = vec![0x3d, 0x00, 0xff, 0xf0];
+        let mut random: u32 = 42;
+        while content.len() < 64 * 1024 {
+            random = random.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            content.push((random >> 16) as u8);
+        }
+        assert_eq!(0.0, dlt_stream_share(&content));
+        let path = tmp_dir.path().join("firmware.bin");
+        fs::write(&path, &content).unwrap();
+        assert_ne!(
+            FileKind::DltStream,
+            discover_format(&path, None).unwrap().kind
+        );
+    }
+
+    #[test]
+    fn test_discover_format_of_files() {
+        let tmp_dir = TempDir::new("discovery").expect("could not create temp dir");
+
+        let dlt = tmp_dir.path().join("storage.dlt");
+        let mut content = b"DLT\x01".to_vec();
+        content.extend_from_slice(&[0u8; 12]);
+        content.extend_from_slice(&dlt_messages(1, false));
+        fs::write(&dlt, content).unwrap();
+        assert_eq!(FileKind::Dlt, discover_format(&dlt, None).unwrap().kind);
+
+        let stream = tmp_dir.path().join("stream.bin");
+        fs::write(&stream, dlt_messages(20, true)).unwrap();
+        let discovery = discover_format(&stream, None).unwrap();
+        assert_eq!(
+            (FileKind::DltStream, 1.0),
+            (discovery.kind, discovery.confidence)
+        );
+        assert!(!discovery.kind.is_text());
+
+        let compressed = tmp_dir.path().join("app.log.gz");
+        let mut encoder = GzEncoder::new(
+            fs::File::create(&compressed).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder
+            .write_all(b"Jul 30 10:08:02 myhost sshd[42]: auth failed\n")
+            .unwrap();
+        encoder.finish().unwrap();
+        let discovery = discover_format(&compressed, None).unwrap();
+        assert_eq!(
+            (FileKind::Syslog, Compression::Gzip),
+            (discovery.kind, discovery.compression)
+        );
+
+        let utf16 = tmp_dir.path().join("utf16.log");
+        let mut content = vec![0xffu8, 0xfe];
+        for unit in "2019-07-30 10:08:02.100 INFO started\n".encode_utf16() {
+            content.extend_from_slice(&unit.to_le_bytes());
+        }
+        fs::write(&utf16, content).unwrap();
+        let discovery = discover_format(&utf16, None).unwrap();
+        assert_eq!(FileKind::Text, discovery.kind);
+        assert_eq!(
+            Some("YYYY-MM-DD hh:mm:ss.s"),
+            discovery
+                .timestamp_formats
+                .first()
+                .map(|c| c.value.as_str())
+        );
+    }
+
+    #[test]
+    fn test_failed_discovery_is_reported() {
+        use crate::parse::{timespan_in_files, DiscoverItem};
+        use indexer_base::progress::{Notification, Severity};
+        let tmp_dir = TempDir::new("discovery").expect("could not create temp dir");
+        let path = tmp_dir.path().join("app.log");
+        fs::write(&path, "2019-07-30 10:08:02.100 INFO started\n").unwrap();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let item = DiscoverItem {
+            path: path.to_string_lossy().to_string(),
+            encoding: Some("no-such-encoding".to_string()),
+            regex: None,
+        };
+        let _ = timespan_in_files(vec![item], &tx);
+        let warnings: Vec<String> = rx
+            .try_iter()
+            .filter_map(|r| match r {
+                Err(Notification {
+                    severity: Severity::WARNING,
+                    content,
+                    ..
+                }) => Some(content),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            warnings
+                .iter()
+                .any(|w| w.starts_with("could not discover format of") && w.contains("app.log")),
+            "{:?}",
+            warnings
+        );
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::generator::*;
+    use crate::parse::{detect_timestamp_in_string, line_matching_format_expression};
+    use pretty_assertions::assert_eq;
+
+    fn generate(config: &TextLogGeneratorConfig) -> (TextLogStats, String) {
+        let mut content: Vec<u8> = vec![];
+        let stats = generate_text_log(config, &mut content).expect("could not generate");
+        (stats, String::from_utf8(content).expect("not utf8"))
+    }
+
+    #[test]
+    fn test_generated_lines_have_timestamps() {
+        let (stats, content) = generate(&TextLogGeneratorConfig {
+            target_size: 10 * 1024,
+            ..Default::default()
+        });
+        assert_eq!(stats.bytes, content.len() as u64);
+        assert_eq!(stats.lines, content.lines().count());
+        let mut last_timestamp = 0;
+        for line in content.lines() {
+            assert!(line_matching_format_expression(GENERATED_TIMESTAMP_FORMAT, line).unwrap());
+            let (timestamp, _, _) = detect_timestamp_in_string(line, Some(0)).unwrap();
+            assert!(timestamp >= last_timestamp);
+            last_timestamp = timestamp;
+        }
+    }
+
+    #[test]
+    fn test_corrupted_lines() {
+        let config = TextLogGeneratorConfig {
+            target_size: 10 * 1024,
+            corruption_ratio: 0.2,
+            seed: 7,
+            ..Default::default()
+        };
+        let (stats, content) = generate(&config);
+        assert!(stats.corrupted > 0);
+        let without_timestamp = content
+            .lines()
+            .filter(|line| detect_timestamp_in_string(line, Some(0)).is_err())
+            .count();
+        assert_eq!(stats.corrupted, without_timestamp);
+        // same seed, same content
+        assert_eq!(content, generate(&config).1);
+    }
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests {
+    use crate::inference::*;
+    use crate::parse::{extract_posix_timestamp, lookup_regex_for_format_str};
+    use pretty_assertions::assert_eq;
+
+    const MINUTE: i64 = 60 * 1_000_000_000;
+
+    /// timestamps (ns) of syslog lines without a year, taken as 2019
+    fn timestamps(lines: &[&str]) -> Vec<i64> {
+        let regex = lookup_regex_for_format_str("MMM DD hh:mm:ss").unwrap();
+        lines
+            .iter()
+            .map(|line| {
+                let (timestamp, year_missing) =
+                    extract_posix_timestamp(line, &regex, Some(2019), Some(0)).unwrap();
+                assert!(year_missing);
+                timestamp
+            })
+            .collect()
+    }
+
+    /// adjusted timestamps and the lines that were reported
+    fn adjust_all(timestamps: &[i64]) -> (Vec<i64>, Vec<usize>) {
+        let mut inference: TimestampInference = Default::default();
+        let mut reported = vec![];
+        let adjusted = timestamps
+            .iter()
+            .enumerate()
+            .map(|(line_nr, t)| {
+                let (adjusted, notification) = inference.adjust(*t, line_nr);
+                if let Some(n) = notification {
+                    assert_eq!(Some(line_nr), n.line);
+                    reported.push(line_nr);
+                }
+                adjusted
+            })
+            .collect();
+        (adjusted, reported)
+    }
+
+    #[test]
+    fn test_year_rollover() {
+        let input = timestamps(&[
+            "Dec 31 23:59:58 a",
+            "Jan  1 00:00:01 b",
+            "Jan  1 00:00:02 c",
+        ]);
+        let expected = timestamps(&["Dec 31 23:59:58 a", "Dec 31 23:59:59 b"]);
+        let (adjusted, reported) = adjust_all(&input);
+        assert_eq!(expected[0], adjusted[0]);
+        assert_eq!(expected[1] + 2_000_000_000, adjusted[1]);
+        assert_eq!(adjusted[1] + 1_000_000_000, adjusted[2]);
+        assert_eq!(vec![1], reported);
+    }
+
+    #[test]
+    fn test_no_adjustments_for_ordered_lines() {
+        let input = timestamps(&[
+            "Jul 30 10:08:02 a",
+            "Jul 30 10:08:02 b",
+            "Jul 30 10:08:05 c",
+        ]);
+        let (adjusted, reported) = adjust_all(&input);
+        assert_eq!(input, adjusted);
+        assert!(reported.is_empty());
+        // small backward steps are not taken as clock jumps
+        let (adjusted, reported) = adjust_all(&[10 * MINUTE, 10 * MINUTE - 1_000_000_000]);
+        assert_eq!(vec![10 * MINUTE, 10 * MINUTE - 1_000_000_000], adjusted);
+        assert!(reported.is_empty());
+    }
+
+    #[test]
+    fn test_backward_clock_jump_is_compensated() {
+        let input = vec![
+            60 * MINUTE,
+            61 * MINUTE,
+            // NTP sets the clock back by 30 minutes
+            31 * MINUTE,
+            32 * MINUTE,
+        ];
+        let (adjusted, reported) = adjust_all(&input);
+        assert_eq!(
+            vec![60 * MINUTE, 61 * MINUTE, 61 * MINUTE, 62 * MINUTE],
+            adjusted
+        );
+        assert_eq!(vec![2], reported);
+    }
+
+    #[test]
+    fn test_clock_restored_after_backward_jump() {
+        let input = vec![60 * MINUTE, 20 * MINUTE, 21 * MINUTE, 62 * MINUTE];
+        let (adjusted, reported) = adjust_all(&input);
+        assert_eq!(
+            vec![60 * MINUTE, 60 * MINUTE, 61 * MINUTE, 62 * MINUTE],
+            adjusted
+        );
+        assert_eq!(vec![1, 3], reported);
+    }
+
+    #[test]
+    fn test_forward_clock_jump_is_reported() {
+        let day = 24 * 60 * MINUTE;
+        let input = vec![MINUTE, 2 * MINUTE, 3 * day, 3 * day + MINUTE];
+        let (adjusted, reported) = adjust_all(&input);
+        assert_eq!(input, adjusted);
+        assert_eq!(vec![2], reported);
+    }
+
+    #[test]
+    fn test_clock_set_back_over_year_rollover() {
+        let input = timestamps(&[
+            "Dec 31 23:59:57 a",
+            "Jan  1 00:00:02 b",
+            "Dec 31 23:50:04 c",
+            "Dec 31 23:50:05 d",
+        ]);
+        let (adjusted, reported) = adjust_all(&input);
+        assert_eq!(input[0] + 5_000_000_000, adjusted[1]);
+        assert_eq!(adjusted[1], adjusted[2]);
+        assert_eq!(adjusted[2] + 1_000_000_000, adjusted[3]);
+        assert_eq!(vec![1, 2], reported);
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod tests {
+    use crate::json_log::*;
+    use pretty_assertions::assert_eq;
+
+    // 2019-07-30T10:08:02Z
+    const SECOND: i64 = 1_564_481_282_000_000_000;
+
+    fn entry(json: &JsonLog, line: &str) -> (Option<i64>, Vec<String>) {
+        match json.parse_line(line) {
+            JsonLine::Entry { timestamp, content } => (
+                timestamp,
+                content.split('\u{0004}').map(String::from).collect(),
+            ),
+            JsonLine::FilteredOut => panic!("{} was filtered out", line),
+            JsonLine::Text => panic!("{} is no JSON object", line),
+        }
+    }
+
+    fn default_log() -> JsonLog {
+        JsonLog::new(JsonLogConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_json_line_columns() {
+        let (timestamp, columns) = entry(
+            &default_log(),
+            r#"{"msg":"started","level":"info","time":"2019-07-30T10:08:02.555Z","pid":42,"http":{"status":200,"path":"/a"},"tags":["x","y"],"user":null}"#,
+        );
+        assert_eq!(Some(SECOND + 555_000_000), timestamp);
+        assert_eq!(
+            vec![
+                "2019-07-30T10:08:02.555Z",
+                "info",
+                "started",
+                "http.path=/a",
+                "http.status=200",
+                "pid=42",
+                r#"tags=["x","y"]"#,
+                "user=",
+            ],
+            columns
+        );
+    }
+
+    #[test]
+    fn test_json_configured_keys_and_columns() {
+        let json = JsonLog::new(JsonLogConfig {
+            timestamp_keys: vec!["meta.at".to_string()],
+            message_keys: vec!["text".to_string()],
+            columns: vec!["service".to_string(), "missing".to_string()],
+            ..Default::default()
+        })
+        .unwrap();
+        let (timestamp, columns) = entry(
+            &json,
+            r#"{"meta":{"at":1564481282123456},"text":"a\nb","service":"db","x":1}"#,
+        );
+        assert_eq!(Some(SECOND + 123_456_000), timestamp);
+        // the newline of the message is encoded
+        assert_eq!(
+            vec!["1564481282123456", "", "a\u{0006}b", "db", "", "x=1"],
+            columns
+        );
+    }
+
+    #[test]
+    fn test_json_numeric_timestamps() {
+        let json = default_log();
+        let timestamp = |line: &str| entry(&json, line).0;
+        assert_eq!(Some(SECOND), timestamp(r#"{"ts":1564481282}"#));
+        assert_eq!(
+            Some(SECOND + 250_000_000),
+            timestamp(r#"{"ts":1564481282.25}"#)
+        );
+        assert_eq!(
+            Some(SECOND + 123_000_000),
+            timestamp(r#"{"ts":1564481282123}"#)
+        );
+        assert_eq!(
+            Some(SECOND + 123_456_789),
+            timestamp(r#"{"ts":1564481282123456789}"#)
+        );
+        assert_eq!(
+            Some(SECOND),
+            timestamp(r#"{"timestamp":"2019-07-30 10:08:02.000"}"#)
+        );
+        assert_eq!(None, timestamp(r#"{"ts":"yesterday"}"#));
+    }
+
+    #[test]
+    fn test_json_filters() {
+        let json = JsonLog::new(JsonLogConfig {
+            min_level: Some("warn".to_string()),
+            filters: vec![JsonFieldFilter {
+                key: "ctx.service".to_string(),
+                pattern: "^(db|net)$".to_string(),
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+        let selected = |line: &str| match json.parse_line(line) {
+            JsonLine::Entry { .. } => true,
+            JsonLine::FilteredOut => false,
+            JsonLine::Text => panic!("{} is no JSON object", line),
+        };
+        assert!(selected(r#"{"level":"ERROR","ctx":{"service":"db"}}"#));
+        assert!(selected(r#"{"level":"warning","ctx":{"service":"net"}}"#));
+        assert!(!selected(r#"{"level":"info","ctx":{"service":"db"}}"#));
+        assert!(!selected(r#"{"level":"error","ctx":{"service":"ui"}}"#));
+        assert!(!selected(r#"{"level":"error"}"#));
+        // bunyan and pino levels
+        assert!(selected(r#"{"level":50,"ctx":{"service":"db"}}"#));
+        assert!(!selected(r#"{"level":30,"ctx":{"service":"db"}}"#));
+        // without a known level
+        assert!(selected(r#"{"ctx":{"service":"db"}}"#));
+
+        assert!(JsonLog::new(JsonLogConfig {
+            min_level: Some("loud".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_json_lines_detection() {
+        assert!(is_json_lines(
+            vec![r#"{"a":1}"#, "", r#"  {"b":"x"}"#, r#"{"c":[1]}"#].into_iter()
+        ));
+        // a few lines that are no objects are tolerated
+        let mut lines = vec![r#"{"a":1}"#; 9];
+        lines.push("starting service");
+        assert!(is_json_lines(lines.into_iter()));
+        assert!(!is_json_lines(
+            vec!["2019-07-30 10:08:02.555 start", r#"{"a":1}"#].into_iter()
+        ));
+        assert!(!is_json_lines(vec!["[1,2]", "{broken"].into_iter()));
+        assert!(!is_json_lines(vec![""].into_iter()));
+        match default_log().parse_line("starting service") {
+            JsonLine::Text => (),
+            _ => panic!("text line should not be parsed"),
+        }
+    }
+}
@@ -1,3 +1,8 @@
 #[macro_use]
 mod parse_tests;
+mod discovery_tests;
+mod generator_tests;
+mod inference_tests;
+mod json_log_tests;
 mod processor_tests;
+mod stats_tests;
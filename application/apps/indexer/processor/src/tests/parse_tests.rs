@@ -29,6 +29,27 @@ mod tests {
             Ok(("23", FormatPiece::AbsoluteMilliseconds))
         );
         assert_eq!(any_date_format("MM23"), Ok(("23", FormatPiece::Month)));
+        assert_eq!(
+            any_date_format("MMMM23"),
+            Ok(("23", FormatPiece::MonthFullName))
+        );
+        assert_eq!(
+            any_date_format("SSS23"),
+            Ok(("23", FormatPiece::FixedFraction(3)))
+        );
+        assert_eq!(
+            any_date_format("SSSSSS23"),
+            Ok(("23", FormatPiece::FixedFraction(6)))
+        );
+        assert_eq!(
+            any_date_format("EPOCH23"),
+            Ok(("23", FormatPiece::EpochSeconds))
+        );
+        assert_eq!(
+            any_date_format("EPOCH_US23"),
+            Ok(("23", FormatPiece::EpochMicroseconds))
+        );
+        assert_eq!(any_date_format("UPTIME]"), Ok(("]", FormatPiece::Uptime)));
         assert_eq!(any_date_format("DDMM"), Ok(("MM", FormatPiece::Day)));
         assert_eq!(any_date_format("YYYY-"), Ok(("-", FormatPiece::Year)));
         assert_eq!(any_date_format("yy-"), Ok(("-", FormatPiece::YearShort)));
@@ -181,7 +202,7 @@ mod tests {
         let (timestamp, _) = extract_posix_timestamp(input, &regex, Some(2017), None)
             .expect("convert to limed line should work");
         println!("timestamp: {}", timestamp);
-        assert_eq!(1_491_299_570_229, timestamp);
+        assert_eq!(1_491_299_570_229_000_000, timestamp);
     }
     #[test]
     fn test_parse_date_line_no_year_no_millis() {
@@ -190,7 +211,7 @@ mod tests {
             lookup_regex_for_format_str("MM-DD hh:mm:ss TZD").expect("should be parsed");
         let (timestamp, _) = extract_posix_timestamp(input, &regex_to_use, Some(2017), None)
             .expect("convert to limed line should work");
-        assert_eq!(1_491_299_570_000, timestamp);
+        assert_eq!(1_491_299_570_000_000_000, timestamp);
     }
 
     const TWO_HOURS_IN_MS: i64 = 2 * 3600 * 1000;
@@ -202,7 +223,7 @@ mod tests {
             .expect("format string should produce regex");
         let (timestamp, _) =
             extract_posix_timestamp(input, &regex, None, Some(TWO_HOURS_IN_MS)).unwrap();
-        assert_eq!(1_491_299_570_229, timestamp);
+        assert_eq!(1_491_299_570_229_000_000, timestamp);
     }
     #[test]
     fn test_parse_date_line_with_short_month_str() {
@@ -211,7 +232,7 @@ mod tests {
             .expect("format string should produce regex");
         let (timestamp, _) =
             extract_posix_timestamp(input, &regex, None, Some(TWO_HOURS_IN_MS)).unwrap();
-        assert_eq!(1_491_299_570_000, timestamp);
+        assert_eq!(1_491_299_570_000_000_000, timestamp);
     }
 
     #[test]
@@ -221,7 +242,7 @@ mod tests {
             .expect("format string should produce regex");
         let (timestamp, _) =
             extract_posix_timestamp(input, &regex, None, Some(TWO_HOURS_IN_MS)).unwrap();
-        assert_eq!(1_564_474_082_555, timestamp);
+        assert_eq!(1_564_474_082_555_000_000, timestamp);
     }
 
     #[test]
@@ -229,10 +250,10 @@ mod tests {
         let input = "1559831467577 some logging here...";
         let regex = lookup_regex_for_format_str("sss").expect("format string should produce regex");
         let (timestamp, _) = extract_posix_timestamp(input, &regex, None, None).unwrap();
-        assert_eq!(1_559_831_467_577, timestamp);
+        assert_eq!(1_559_831_467_577_000_000, timestamp);
         let (timestamp_with_offset, _) =
             extract_posix_timestamp(input, &regex, None, Some(-TWO_HOURS_IN_MS)).unwrap();
-        assert_eq!(1_559_838_667_577, timestamp_with_offset);
+        assert_eq!(1_559_838_667_577_000_000, timestamp_with_offset);
     }
     macro_rules! derive_format_and_check {
         ($input:expr, $exp:expr) => {
@@ -350,7 +371,278 @@ mod tests {
             "2020-03-12T12:31:17.316631+01:00",
             "YYYY-MM-DDThh:mm:ss.s TZD"
         );
-        derive_format_and_check!("2019-07-30T09:38:02.555Z", "YYYY-MM-DDThh:mm:ss.s");
+        derive_format_and_check!("2019-07-30T09:38:02.555Z", "YYYY-MM-DDThh:mm:ss.s TZD");
+    }
+
+    #[test]
+    fn test_detect_system_log_formats() {
+        // RFC3164 syslog
+        derive_format_and_check!(
+            "Oct 11 22:14:15 mymachine su: 'su root' failed",
+            "MMM DD hh:mm:ss"
+        );
+        derive_format_and_check!(
+            "Mar  4 08:00:01 host CRON[1234]: session opened",
+            "MMM DD hh:mm:ss"
+        );
+        // RFC5424 syslog
+        derive_format_and_check!(
+            "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47",
+            "YYYY-MM-DDThh:mm:ss.s TZD"
+        );
+        // journalctl -o short-iso
+        derive_format_and_check!(
+            "2020-03-17T12:15:03+0100 host systemd[1]: Started Session 1.",
+            "YYYY-MM-DDThh:mm:ss TZD"
+        );
+        // android logcat
+        derive_format_and_check!(
+            "03-17 12:15:03.555  1234  1234 I ActivityManager: Start proc",
+            "MM-DD hh:mm:ss.s"
+        );
+        match_format!(
+            "03-17 12:15:03.555  1234  1234 I ActivityManager",
+            "MM-DD hh:mm:ss.SSS"
+        );
+        // dmesg
+        derive_format_and_check!("[    0.000000] Linux version 5.4.0", "[UPTIME]");
+        derive_format_and_check!(
+            "[12345.678901] usb 1-1: new high-speed USB device",
+            "[UPTIME]"
+        );
+        // epoch
+        derive_format_and_check!("1584443703 some message", "EPOCH");
+        derive_format_and_check!("1584443703.123456 some message", "EPOCH");
+        derive_format_and_check!("1584443703123 some message", "EPOCH_MS");
+        derive_format_and_check!("1584443703123456 some message", "EPOCH_US");
+        derive_format_and_check!("1584443703123456789 some message", "EPOCH_NS");
+        // localized month names
+        derive_format_and_check!(
+            "17. März 2020 12:15:03 Dienst gestartet",
+            "DD. MMMM YYYY hh:mm:ss"
+        );
+        derive_format_and_check!("17 janvier 2020 12:15:03 service", "DD MMMM YYYY hh:mm:ss");
+        derive_format_and_check!("17 janv. 2020 12:15:03 service", "DD MMM YYYY hh:mm:ss");
+        derive_format_and_check!("17-dic-2020 12:15:03 servicio", "DD-MMM-YYYY hh:mm:ss");
+
+        no_match_format!("EPOCH", "12345678901 is too long");
+        match_format!("ts=1584443703 some message", "EPOCH");
+        match_format!("[1584443703] some message", "[EPOCH]");
+        // ids within the text are no timestamps
+        no_match_format!("EPOCH", "user 1584443703 logged in");
+        no_match_format!("EPOCH_MS", "order 1584443703123 shipped");
+        // single digit days have to be padded
+        no_match_format!("DD-MM", "4-05");
+        no_match_format!("DD.MM.YYYY", "v1.03.2020");
+        no_match_format!("DD-MM", "36-05");
+    }
+
+    #[test]
+    fn test_extract_system_log_timestamps() {
+        let extract = |format: &str, line: &str, year: Option<i32>, offset: Option<i64>| {
+            let regex = lookup_regex_for_format_str(format).expect("format should produce regex");
+            extract_posix_timestamp(line, &regex, year, offset)
+                .expect("timestamp should be extracted")
+                .0
+        };
+        // 2020-03-04T08:00:01Z
+        let expected = 1_583_308_801_000_000_000;
+        assert_eq!(
+            expected,
+            extract(
+                "MMM DD hh:mm:ss",
+                "Mar  4 08:00:01 host cron",
+                Some(2020),
+                Some(0)
+            )
+        );
+        assert_eq!(
+            expected,
+            extract(
+                "MMM DD hh:mm:ss",
+                "Mar 04 08:00:01 host cron",
+                Some(2020),
+                Some(0)
+            )
+        );
+        assert_eq!(
+            expected,
+            extract(
+                "YYYY-MM-DDThh:mm:ss TZD",
+                "2020-03-04T08:00:01Z host",
+                None,
+                None
+            )
+        );
+        assert_eq!(
+            expected + 555_000_000,
+            extract(
+                "MM-DD hh:mm:ss.SSS",
+                "03-04 09:00:01.555 1 2 I",
+                Some(2020),
+                Some(3_600_000)
+            )
+        );
+        assert_eq!(
+            expected + 123_456_000,
+            extract(
+                "YYYY-MM-DD hh:mm:ss.SSSSSS",
+                "2020-03-04 08:00:01.123456",
+                None,
+                Some(0)
+            )
+        );
+        assert_eq!(
+            expected,
+            extract(
+                "DD. MMMM YYYY hh:mm:ss",
+                "04. März 2020 08:00:01",
+                None,
+                Some(0)
+            )
+        );
+        assert_eq!(
+            expected,
+            extract(
+                "DD MMM YYYY hh:mm:ss",
+                "04 MAR 2020 08:00:01",
+                None,
+                Some(0)
+            )
+        );
+        assert_eq!(
+            expected,
+            extract(
+                "MMMM DD, YYYY hh:mm:ss",
+                "marzo 04, 2020 08:00:01",
+                None,
+                Some(0)
+            )
+        );
+        // epoch values are not shifted by an offset
+        assert_eq!(
+            expected,
+            extract("EPOCH", "1583308801 x", None, Some(3_600_000))
+        );
+        assert_eq!(
+            expected + 500_000_000,
+            extract("EPOCH", "1583308801.5 x", None, None)
+        );
+        assert_eq!(
+            expected + 12_000_000,
+            extract("EPOCH_MS", "1583308801012 x", None, None)
+        );
+        assert_eq!(
+            expected + 12_345_000,
+            extract("EPOCH_US", "1583308801012345 x", None, None)
+        );
+        assert_eq!(
+            expected + 12_345_678,
+            extract("EPOCH_NS", "1583308801012345678 x", None, None)
+        );
+        // uptime is relative to the boot
+        assert_eq!(
+            12_345_678_000,
+            extract("[UPTIME]", "[   12.345678] usb", None, None)
+        );
+        assert_eq!(
+            13_345_678_000,
+            extract("[UPTIME]", "[   12.345678] usb", None, Some(-1000))
+        );
+    }
+
+    #[test]
+    fn test_sub_millisecond_precision() {
+        let extract = |format: &str, line: &str| {
+            let regex = lookup_regex_for_format_str(format).expect("format should produce regex");
+            extract_posix_timestamp(line, &regex, None, Some(0))
+                .expect("timestamp should be extracted")
+                .0
+        };
+        // 2019-07-30T10:08:02Z
+        let second = 1_564_481_282_000_000_000;
+        assert_eq!(
+            second + 123_456_789,
+            extract("YYYY-MM-DD hh:mm:ss.s", "2019-07-30 10:08:02.123456789")
+        );
+        assert_eq!(
+            second + 123_456_789,
+            extract("YYYY-MM-DD hh:mm:ss.s", "2019-07-30 10:08:02.1234567891")
+        );
+        assert_eq!(
+            second + 123_456_000,
+            extract("YYYY-MM-DD hh:mm:ss.s", "2019-07-30 10:08:02.123456")
+        );
+        assert_eq!(
+            second + 450_000_000,
+            extract("YYYY-MM-DD hh:mm:ss.s", "2019-07-30 10:08:02.45")
+        );
+        assert_eq!(
+            second + 123_456_789,
+            extract(
+                "YYYY-MM-DD hh:mm:ss.SSSSSSSSS",
+                "2019-07-30 10:08:02.123456789"
+            )
+        );
+        assert_eq!(
+            "2019-07-30 10:08:02.123456789 UTC",
+            posix_timestamp_as_string(second + 123_456_789)
+        );
+    }
+
+    #[test]
+    fn test_custom_regex() {
+        let extract = |pattern: &str, line: &str, offset: Option<i64>| {
+            let regex = custom_regex_to_regex(pattern).expect("pattern should produce regex");
+            extract_posix_timestamp(line, &regex, Some(2019), offset)
+                .expect("timestamp should be extracted")
+                .0
+        };
+        // 2019-07-30T10:08:02Z
+        let second = 1_564_481_282_000_000_000;
+        let pattern = r"^\[(?P<node>\w+)\] (?P<level>[A-Z]+) (?P<day>\d{2})\.(?P<month>\d{2})\.(?P<year>\d{4}) (?P<hour>\d{2}):(?P<minute>\d{2}):(?P<second>\d{2})\.(?P<fraction>\d+)(?P<tz>[+-]\d{4})";
+        assert_eq!(
+            second + 555_000_000,
+            extract(
+                pattern,
+                "[ecu1] WARN 30.07.2019 12:08:02.555+0200 text",
+                None
+            )
+        );
+        assert_eq!(
+            vec!["node".to_string(), "level".to_string()],
+            extra_columns(&custom_regex_to_regex(pattern).unwrap())
+        );
+        // timestamp in the middle of the line with a month name and no year
+        assert_eq!(
+            second,
+            extract(
+                r"id=\d+ at (?P<month>\w{3}) (?P<day>\d+) (?P<hour>\d+):(?P<minute>\d+):(?P<second>\d+)",
+                "xx id=17 at Jul 30 10:08:02 msg",
+                Some(0)
+            )
+        );
+        assert_eq!(
+            second + 250_000_000,
+            extract(r"t=(?P<epoch>\d+\.\d+)", "[x] t=1564481282.25 msg", None)
+        );
+        assert!(
+            extra_columns(&lookup_regex_for_format_str("YYYY-MM-DD hh:mm:ss.s TZD").unwrap())
+                .is_empty()
+        );
+        assert!(custom_regex_to_regex(r"(?P<hour>\d+):(?P<minute>\d+)").is_err());
+        assert!(custom_regex_to_regex(r"(?P<epoch>\d+").is_err());
+    }
+
+    #[test]
+    fn test_infer_year() {
+        use chrono::NaiveDate;
+        let today = NaiveDate::from_ymd(2020, 1, 2);
+        assert_eq!(2020, infer_year(1, 2, today));
+        assert_eq!(2020, infer_year(1, 3, today));
+        assert_eq!(2019, infer_year(1, 4, today));
+        assert_eq!(2019, infer_year(12, 31, today));
+        assert_eq!(2020, infer_year(12, 31, NaiveDate::from_ymd(2020, 12, 31)));
     }
 
     #[test]
@@ -376,11 +668,11 @@ mod tests {
     #[test]
     fn test_detect_timestamp_in_string_simple() {
         match detect_timestamp_in_string("2019-07-30 10:08:02.555", Some(0)) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string("2019-07-30 09:38:02.555 -00:30", None) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
     }
@@ -388,7 +680,7 @@ mod tests {
     fn test_detect_timestamp_with_timezone_indicated_but_missing() {
         match detect_timestamp_in_string("[2019-07-30T10:08:02.555][DEBUG][indexing]: xyz", Some(0))
         {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
     }
@@ -396,61 +688,61 @@ mod tests {
     #[test]
     fn test_detect_timestamp_in_string_with_t() {
         match detect_timestamp_in_string("2019-07-30T10:08:02.555", Some(0)) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string("2019-07-30T04:38:02.555 -05:30", None) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string("2019-07-30T11:08:02.555+01:00", None) {
             Ok((timestamp, _, _)) => {
                 use chrono::{NaiveDate, NaiveDateTime};
                 let date_time: NaiveDateTime = NaiveDate::from_ymd(2019, 7, 30).and_hms(10, 8, 2);
-                let ts_in_ms = date_time.timestamp() * 1000 + 555;
-                assert_eq!(ts_in_ms, timestamp)
+                let ts_in_ns = date_time.timestamp_nanos() + 555_000_000;
+                assert_eq!(ts_in_ns, timestamp)
             }
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
     }
     #[test]
     fn test_detect_timestamp_in_string_no_year() {
-        use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
-        let year = Utc::now().date().year();
+        use chrono::{NaiveDate, NaiveDateTime, Utc};
+        let year = infer_year(7, 30, Utc::today().naive_utc());
         match detect_timestamp_in_string("07-30 10:08:02.555", Some(0)) {
             Ok((timestamp, _, _)) => {
                 let date_time: NaiveDateTime = NaiveDate::from_ymd(year, 7, 30).and_hms(10, 8, 2);
-                let ts_in_ms = date_time.timestamp() * 1000 + 555;
-                assert_eq!(ts_in_ms, timestamp)
+                let ts_in_ns = date_time.timestamp_nanos() + 555_000_000;
+                assert_eq!(ts_in_ns, timestamp)
             }
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string("07-30 12:08:02.555 +0200", None) {
             Ok((timestamp, _, _)) => {
                 let date_time: NaiveDateTime = NaiveDate::from_ymd(year, 7, 30).and_hms(10, 8, 2);
-                let ts_in_ms = date_time.timestamp() * 1000 + 555;
-                assert_eq!(ts_in_ms, timestamp)
+                let ts_in_ns = date_time.timestamp_nanos() + 555_000_000;
+                assert_eq!(ts_in_ns, timestamp)
             }
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
     }
     #[test]
     fn test_detect_timestamp_in_string_no_year_with_t() {
-        use chrono::{Datelike, NaiveDate, NaiveDateTime, Utc};
-        let year = Utc::now().date().year();
+        use chrono::{NaiveDate, NaiveDateTime, Utc};
+        let year = infer_year(7, 30, Utc::today().naive_utc());
         match detect_timestamp_in_string("07-30T10:08:02.555", Some(0)) {
             Ok((timestamp, _, _)) => {
                 let date_time: NaiveDateTime = NaiveDate::from_ymd(year, 7, 30).and_hms(10, 8, 2);
-                let ts_in_ms = date_time.timestamp() * 1000 + 555;
-                assert_eq!(ts_in_ms, timestamp)
+                let ts_in_ns = date_time.timestamp_nanos() + 555_000_000;
+                assert_eq!(ts_in_ns, timestamp)
             }
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string("07-30T15:08:02.555 +05:00", None) {
             Ok((timestamp, _, _)) => {
                 let date_time: NaiveDateTime = NaiveDate::from_ymd(year, 7, 30).and_hms(10, 8, 2);
-                let ts_in_ms = date_time.timestamp() * 1000 + 555;
-                assert_eq!(ts_in_ms, timestamp)
+                let ts_in_ns = date_time.timestamp_nanos() + 555_000_000;
+                assert_eq!(ts_in_ns, timestamp)
             }
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
@@ -458,11 +750,11 @@ mod tests {
     #[test]
     fn test_detect_timestamp_in_string_year_last() {
         match detect_timestamp_in_string("07-30-2019 10:08:02.555", Some(0)) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string("07-30-2019 08:08:02.555 -0200", None) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_555_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
     }
@@ -470,23 +762,42 @@ mod tests {
     fn test_detect_timestamp_in_string_short_month_name() {
         match detect_timestamp_in_string("109.169.248.247 - - [30/Jul/2019:10:08:02] xyz", Some(0))
         {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_000, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_000_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
         match detect_timestamp_in_string(
             "109.169.248.247 - - [30/Jul/2019:12:08:02 +0200] xyz",
             None,
         ) {
-            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_000, timestamp),
+            Ok((timestamp, _, _)) => assert_eq!(1_564_481_282_000_000_000, timestamp),
             Err(e) => panic!(format!("error happened in detection: {}", e)),
         }
     }
+    #[test]
+    fn test_timestamps_out_of_range() {
+        let regex = lookup_regex_for_format_str("YYYY-MM-DD hh:mm:ss.s").unwrap();
+        // nanoseconds since 1970 only fit into an i64 for the years 1677 to 2262
+        assert!(
+            extract_posix_timestamp("2999-03-14 10:00:00.000 x", &regex, None, Some(0)).is_err()
+        );
+        assert!(
+            extract_posix_timestamp("0001-03-14 10:00:00.000 x", &regex, None, Some(0)).is_err()
+        );
+        assert!(
+            extract_posix_timestamp("2262-03-14 10:00:00.000 x", &regex, None, Some(0)).is_ok()
+        );
+        // the offset can move a timestamp out of range as well
+        let max = "2262-04-11 23:47:16.854 x";
+        assert!(extract_posix_timestamp(max, &regex, None, Some(0)).is_ok());
+        assert!(extract_posix_timestamp(max, &regex, None, Some(-3_600_000)).is_err());
+    }
 
     test_generator::test_expand_paths! { test_detect_regex; "processor/test_samples/detecting/*" }
 
     fn test_detect_regex(dir_name: &str) {
         let in_path = PathBuf::from("..").join(&dir_name).join("in.log");
-        let res = detect_timestamp_format_in_file(&in_path).expect("could not detect regex type");
+        let res =
+            detect_timestamp_format_in_file(&in_path, None).expect("could not detect regex type");
 
         let mut format_path = PathBuf::from("..").join(&dir_name);
         format_path.push("expected.format");
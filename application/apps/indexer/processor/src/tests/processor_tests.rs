@@ -9,6 +9,7 @@ mod tests {
     use indexer_base::chunks::Chunk;
     use indexer_base::chunks::ChunkResults;
     use indexer_base::config::IndexingConfig;
+    use indexer_base::encoding::detect_encoding_in_sample;
     use indexer_base::progress::{IndexingProgress, Notification};
     use pretty_assertions::assert_eq;
     use std::fs;
@@ -41,6 +42,9 @@ mod tests {
             },
             source_file_size,
             false,
+            None,
+            None,
+            None,
             tx,
             None,
         )
@@ -126,6 +130,9 @@ mod tests {
             },
             source_file_size,
             false,
+            None,
+            None,
+            None,
             tx,
             None,
         )
@@ -189,6 +196,9 @@ mod tests {
             },
             source_file_size,
             false,
+            None,
+            None,
+            None,
             tx,
             None,
         )
@@ -328,6 +338,9 @@ mod tests {
             },
             fs::metadata(&in_path).expect("metadata not found").len() as usize,
             false,
+            None,
+            None,
+            None,
             tx,
             None,
         )
@@ -380,7 +393,8 @@ mod tests {
             }
         }
         if !append_use_case {
-            if let Ok(()) = restore_original_from_indexed_file(&out_file_path, &restored_file_path)
+            if let Ok(()) =
+                restore_original_from_indexed_file(&out_file_path, &restored_file_path, false)
             {
                 let restored_file_content_bytes =
                     fs::read(restored_file_path).expect("could not read file");
@@ -389,7 +403,9 @@ mod tests {
 
                 let original_content_bytes =
                     fs::read(in_path).expect("could not read expected file");
-                let original_string = String::from_utf8_lossy(&original_content_bytes[..]);
+                // the indexed file contains the decoded content
+                let (original_string, _, _) = detect_encoding_in_sample(&original_content_bytes)
+                    .decode(&original_content_bytes);
                 let original_content_lines: Vec<&str> = original_string.lines().collect();
                 trace!(
                     "comparing\n{:?}\nto expected:\n{:?}",
@@ -400,4 +416,312 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_index_compressed_file() {
+        use std::io::Write;
+        let tmp_dir = TempDir::new("compressed").expect("could not create temp dir");
+        let content: String = (0..500)
+            .map(|i| format!("2020-03-14 10:{:02}:00 line {}\n", i % 60, i))
+            .collect();
+        let plain_path = tmp_dir.path().join("plain.log");
+        let gz_path = tmp_dir.path().join("compressed.log.gz");
+        fs::write(&plain_path, &content).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        fs::write(&gz_path, encoder.finish().unwrap()).unwrap();
+
+        let index = |in_file: &PathBuf| -> (String, Vec<(usize, usize)>) {
+            let out_path = PathBuf::from(format!("{}.out", in_file.display()));
+            let source_file_size = fs::metadata(in_file).unwrap().len() as usize;
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            create_index_and_mapping(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 100,
+                    in_file: in_file.clone(),
+                    out_path: &out_path,
+                    append: false,
+                },
+                source_file_size,
+                false,
+                None,
+                None,
+                None,
+                tx,
+                None,
+            )
+            .expect("indexing failed");
+            let ticks = rx
+                .try_iter()
+                .filter_map(|r| match r {
+                    Ok(IndexingProgress::Progress { ticks }) => Some(ticks),
+                    _ => None,
+                })
+                .collect();
+            (fs::read_to_string(out_path).unwrap(), ticks)
+        };
+        let (plain_index, _) = index(&plain_path);
+        let (compressed_index, ticks) = index(&gz_path);
+        assert_eq!(plain_index, compressed_index);
+        // progress is reported in compressed bytes
+        let compressed_size = fs::metadata(&gz_path).unwrap().len() as usize;
+        assert!(!ticks.is_empty());
+        assert!(ticks
+            .iter()
+            .all(|(done, total)| *total == compressed_size && *done <= compressed_size));
+    }
+
+    #[test]
+    fn test_index_grouped_records() {
+        use crate::grouping::RecordStart;
+        let tmp_dir = TempDir::new("grouped").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("trace.log");
+        // enough timestamps for the format detection
+        let content = "\
+2020-03-14 10:00:00.000 INFO started
+2020-03-14 10:00:00.100 INFO connecting
+2020-03-14 10:00:00.200 INFO connecting
+2020-03-14 10:00:00.300 INFO connecting
+2020-03-14 10:00:00.400 INFO connecting
+2020-03-14 10:00:00.500 INFO connecting
+2020-03-14 10:00:00.600 INFO connecting
+2020-03-14 10:00:00.700 INFO connecting
+2020-03-14 10:00:01.000 ERROR request failed
+java.lang.IllegalStateException: no connection
+\tat com.example.Client.send(Client.java:42)
+\tat com.example.Main.main(Main.java:7)
+2020-03-14 10:00:02.000 INFO retrying
+";
+        fs::write(&in_path, content).unwrap();
+        let index = |out_name: &str, record_start: Option<RecordStart>| -> (String, Vec<Chunk>) {
+            let out_path = tmp_dir.path().join(out_name);
+            let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            create_index_and_mapping(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 1,
+                    in_file: in_path.clone(),
+                    out_path: &out_path,
+                    append: false,
+                },
+                content.len(),
+                false,
+                record_start,
+                None,
+                None,
+                tx,
+                None,
+            )
+            .expect("indexing failed");
+            let chunks = rx
+                .try_iter()
+                .filter_map(|r| match r {
+                    Ok(IndexingProgress::GotItem { item }) => Some(item),
+                    _ => None,
+                })
+                .collect();
+            (fs::read_to_string(out_path).unwrap(), chunks)
+        };
+
+        let (ungrouped, chunks) = index("ungrouped.out", None);
+        assert_eq!(13, ungrouped.lines().count());
+        assert_eq!(13, chunks.len());
+
+        let record_starts = vec![
+            RecordStart::Timestamp,
+            RecordStart::Pattern(r"^\d{4}-\d{2}-\d{2} ".to_string()),
+        ];
+        for (i, record_start) in record_starts.into_iter().enumerate() {
+            let out_name = format!("grouped_{}.out", i);
+            let (grouped, chunks) = index(&out_name, Some(record_start));
+            assert_eq!(10, grouped.lines().count());
+            assert_eq!(
+                (0..10).map(|i| (i, i)).collect::<Vec<_>>(),
+                chunks.iter().map(|c| c.r).collect::<Vec<_>>()
+            );
+            assert!(grouped.lines().nth(8).unwrap().contains("Main.java:7"));
+            // the original lines are restored from the grouped record
+            let restored_path = tmp_dir.path().join("restored.log");
+            restore_original_from_indexed_file(
+                &tmp_dir.path().join(&out_name),
+                &restored_path,
+                true,
+            )
+            .unwrap();
+            assert_eq!(content, fs::read_to_string(&restored_path).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_index_json_lines() {
+        use crate::json_log::JsonLogConfig;
+        let tmp_dir = TempDir::new("json").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("app.log");
+        let content = r#"{"time":"2020-03-14T10:00:00.000Z","level":"info","msg":"started","pid":7}
+{"time":"2020-03-14T10:00:00.100Z","level":"debug","msg":"connecting\nto db","pid":7}
+{"time":"2020-03-14T10:00:01.000Z","level":"error","msg":"request failed","pid":7}
+"#;
+        fs::write(&in_path, content).unwrap();
+        let index = |out_name: &str, json: Option<JsonLogConfig>| -> String {
+            let out_path = tmp_dir.path().join(out_name);
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            create_index_and_mapping(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 1,
+                    in_file: in_path.clone(),
+                    out_path: &out_path,
+                    append: false,
+                },
+                content.len(),
+                true,
+                None,
+                None,
+                json,
+                tx,
+                None,
+            )
+            .expect("indexing failed");
+            fs::read_to_string(out_path).unwrap()
+        };
+
+        // without a configuration the objects are indexed as text
+        let text = index("text.out", None);
+        assert_eq!(3, text.lines().count());
+        assert!(text.starts_with(r#"{"time":"2020-03-14T10:00:00.000Z","level":"info""#));
+
+        // the timestamps (ms) are appended
+        let columns = index("columns.out", Some(JsonLogConfig::default()));
+        assert_eq!(
+            vec![
+                "2020-03-14T10:00:00.000Z\u{4}info\u{4}started\u{4}pid=7\u{3}TAG\u{3}\u{2}0\u{2}1584180000000\u{2}",
+                "2020-03-14T10:00:00.100Z\u{4}debug\u{4}connecting\u{6}to db\u{4}pid=7\u{3}TAG\u{3}\u{2}1\u{2}1584180000100\u{2}",
+                "2020-03-14T10:00:01.000Z\u{4}error\u{4}request failed\u{4}pid=7\u{3}TAG\u{3}\u{2}2\u{2}1584180001000\u{2}",
+            ],
+            columns.lines().collect::<Vec<_>>()
+        );
+
+        let filtered = index(
+            "filtered.out",
+            Some(JsonLogConfig {
+                min_level: Some("info".to_string()),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(2, filtered.lines().count());
+        assert!(!filtered.contains("connecting"));
+    }
+
+    #[test]
+    fn test_index_encoded_files() {
+        use indexer_base::encoding::{encoding_for_label, Encoding, UTF_8};
+        let tmp_dir = TempDir::new("encoded").expect("could not create temp dir");
+        let content: String = (0..20)
+            .map(|i| format!("2020-03-14 10:00:{:02}.000 接続 {} Größe\n", i, i))
+            .collect();
+        let index = |name: &str, bytes: &[u8], encoding: Option<&'static Encoding>| -> String {
+            let in_path = tmp_dir.path().join(name);
+            let out_path = tmp_dir.path().join(format!("{}.out", name));
+            fs::write(&in_path, bytes).unwrap();
+            let (tx, _rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+            create_index_and_mapping(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 5,
+                    in_file: in_path,
+                    out_path: &out_path,
+                    append: false,
+                },
+                bytes.len(),
+                true,
+                None,
+                encoding,
+                None,
+                tx,
+                None,
+            )
+            .expect("indexing failed");
+            fs::read_to_string(out_path).unwrap()
+        };
+        let expected = index("utf8.log", content.as_bytes(), None);
+        assert_eq!(20, expected.lines().count());
+        let utf16: Vec<u8> = content
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(expected, index("utf16.log", &utf16, None));
+        let utf16le = encoding_for_label("utf-16le").unwrap();
+        assert_eq!(expected, index("utf16_label.log", &utf16, Some(utf16le)));
+        // "Größe" cannot be encoded as Shift_JIS
+        let japanese = content.replace(" Größe", "");
+        let (sjis, _, _) = encoding_for_label("shift_jis").unwrap().encode(&japanese);
+        assert_eq!(
+            index("utf8_japanese.log", japanese.as_bytes(), None),
+            index("sjis.log", &sjis, None)
+        );
+        // invalid UTF-8 is replaced
+        let invalid = index(
+            "invalid.log",
+            b"2020-03-14 10:00:00.000 a\xffb\n",
+            Some(UTF_8),
+        );
+        assert!(invalid.starts_with("2020-03-14 10:00:00.000 a\u{FFFD}b"));
+    }
+
+    #[test]
+    fn test_follow_file() {
+        use std::io::Write;
+        use std::time::Duration;
+        let tmp_dir = TempDir::new("follow").expect("could not create temp dir");
+        let in_path = tmp_dir.path().join("growing.log");
+        let out_path = tmp_dir.path().join("growing.log.out");
+        fs::write(&in_path, "line 0\nline 1\nline").unwrap();
+        let (tx, rx): (cc::Sender<ChunkResults>, cc::Receiver<ChunkResults>) = unbounded();
+        let (shutdown_tx, shutdown_rx) = cc::bounded(1);
+        let (in_file, out_file) = (in_path.clone(), out_path.clone());
+        let handle = std::thread::spawn(move || {
+            follow_file(
+                IndexingConfig {
+                    tag: "TAG",
+                    chunk_size: 100,
+                    in_file,
+                    out_path: &out_file,
+                    append: false,
+                },
+                false,
+                Duration::from_millis(10),
+                tx,
+                Some(shutdown_rx),
+            )
+        });
+        let next_lines = || loop {
+            match rx.recv_timeout(Duration::from_secs(10)) {
+                Ok(Ok(IndexingProgress::GotItem { item })) => return item.r,
+                Ok(Err(Notification { content, .. })) => trace!("notification: {}", content),
+                other => panic!("expected a chunk but got {:?}", other.map(|r| r.is_ok())),
+            }
+        };
+        // the incomplete last line waits for its newline
+        assert_eq!((0, 1), next_lines());
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&in_path)
+            .unwrap()
+            .write_all(b" 2\nline 3\n")
+            .unwrap();
+        assert_eq!((2, 3), next_lines());
+        // truncated and written again
+        fs::write(&in_path, "new 0\n").unwrap();
+        assert_eq!((4, 4), next_lines());
+
+        shutdown_tx.send(()).unwrap();
+        handle.join().unwrap().expect("follow failed");
+        let lines: Vec<String> = fs::read_to_string(&out_path)
+            .unwrap()
+            .lines()
+            .map(|l| indexer_base::utils::restore_line(l).to_string())
+            .collect();
+        assert_eq!(vec!["line 0", "line 1", "line 2", "line 3", "new 0"], lines);
+    }
 }
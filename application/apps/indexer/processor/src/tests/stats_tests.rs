@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod tests {
+    extern crate tempdir;
+    use crate::stats::*;
+    use crossbeam_channel::unbounded;
+    use indexer_base::progress::IndexingProgress;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempdir::TempDir;
+
+    fn detect(line: &str) -> (Option<TextLevel>, Option<String>) {
+        LevelDetector::new(&TextStatsConfig::default())
+            .unwrap()
+            .detect(line)
+    }
+
+    #[test]
+    fn test_detect_builtin_levels() {
+        let cases = vec![
+            (
+                "2019-07-30 10:08:02.555 INFO  com.example.Main - started",
+                Some(TextLevel::Info),
+                Some("com.example.Main"),
+            ),
+            (
+                "2019-07-30 10:08:02.555 ERROR [worker-1] Db: connection lost",
+                Some(TextLevel::Error),
+                Some("Db"),
+            ),
+            (
+                "2019-07-30 10:08:02.555 WARN low memory: 12MB",
+                Some(TextLevel::Warning),
+                None,
+            ),
+            (
+                "07-30 10:08:02.555  1234  1240 W ActivityManager: slow operation",
+                Some(TextLevel::Warning),
+                Some("ActivityManager"),
+            ),
+            (
+                "E/Zygote(  123): process crashed",
+                Some(TextLevel::Error),
+                Some("Zygote"),
+            ),
+            (
+                "07-30 10:08:02.555 D/dalvikvm( 123): GC done",
+                Some(TextLevel::Debug),
+                Some("dalvikvm"),
+            ),
+            (
+                "<34>Jul 30 10:08:02 myhost sshd[42]: auth failed",
+                Some(TextLevel::Fatal),
+                Some("sshd"),
+            ),
+            (
+                "<14>Jul 30 10:08:02 myhost cron[7]: job done",
+                Some(TextLevel::Info),
+                Some("cron"),
+            ),
+            (
+                "ts=2019-07-30T10:08:02Z level=debug msg=\"cache hit\"",
+                Some(TextLevel::Debug),
+                None,
+            ),
+            (
+                "Jul 30 10:08:02 myhost kernel: usb 1-1: new device",
+                None,
+                None,
+            ),
+            ("an error that is not a level", None, None),
+        ];
+        for (line, level, source) in cases {
+            assert_eq!(
+                (level, source.map(String::from)),
+                detect(line),
+                "line: {}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_configured_patterns() {
+        let detector = LevelDetector::new(&TextStatsConfig {
+            level_pattern: Some(r"^\S+ <(?P<level>\w+)>".to_string()),
+            source_pattern: Some(r"\{(\w+)\}".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            (Some(TextLevel::Warning), Some("net".to_string())),
+            detector.detect("10:08:02 <warn> {net} ERROR in retry")
+        );
+        // the built-in patterns are not used with a level pattern
+        assert_eq!((None, None), detector.detect("10:08:02 ERROR failed"));
+        assert!(LevelDetector::new(&TextStatsConfig {
+            level_pattern: Some("(".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_level_tokens() {
+        assert_eq!(Some(TextLevel::Fatal), level_from_token("A"));
+        assert_eq!(Some(TextLevel::Fatal), level_from_token("Critical"));
+        assert_eq!(Some(TextLevel::Error), level_from_token("50"));
+        assert_eq!(Some(TextLevel::Warning), level_from_token("Warning"));
+        assert_eq!(Some(TextLevel::Verbose), level_from_token("trace"));
+        assert_eq!(None, level_from_token("x"));
+    }
+
+    #[test]
+    fn test_text_file_info() {
+        let tmp_dir = TempDir::new("stats").expect("could not create temp dir");
+        let path = tmp_dir.path().join("app.log");
+        let content = "\
+2019-07-30 10:08:02.100 INFO  Main - started
+2019-07-30 10:08:02.200 DEBUG Db - connecting
+2019-07-30 10:08:02.300 ERROR Db - connection lost
+
+2019-07-30 10:08:03.000 WARN  Db - retrying
+2019-07-30 10:08:04.000 INFO  Net - connected
+    at com.example.Db.connect(Db.java:12)
+";
+        fs::write(&path, content).unwrap();
+        let (tx, rx) = unbounded();
+        get_text_file_info(
+            &path,
+            &TextStatsConfig {
+                top_sources: 2,
+                ..Default::default()
+            },
+            &tx,
+            None,
+        )
+        .unwrap();
+        let info = rx
+            .try_iter()
+            .find_map(|r| match r {
+                Ok(IndexingProgress::GotItem { item }) => Some(item),
+                _ => None,
+            })
+            .expect("no statistics");
+        assert_eq!(7, info.lines);
+        assert_eq!(1, info.empty_lines);
+        assert_eq!(5, info.lines_with_timestamp);
+        assert_eq!(Some("YYYY-MM-DD hh:mm:ss.s".to_string()), info.format);
+        assert_eq!(
+            Some("2019-07-30 10:08:02.100 UTC".to_string()),
+            info.min_time
+        );
+        assert_eq!(Some("2019-07-30 10:08:04 UTC".to_string()), info.max_time);
+        assert_eq!(
+            LevelDistribution {
+                no_level: 1,
+                log_error: 1,
+                log_warning: 1,
+                log_info: 2,
+                log_debug: 1,
+                ..Default::default()
+            },
+            info.levels
+        );
+        assert_eq!(3, info.source_count);
+        assert_eq!(
+            vec!["Db", "Main"],
+            info.top_sources
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(3, info.top_sources[0].1.total());
+    }
+
+    #[test]
+    fn test_text_file_info_progress_of_compressed_file() {
+        use flate2::write::GzEncoder;
+        use std::io::Write;
+        let tmp_dir = TempDir::new("stats").expect("could not create temp dir");
+        let path = tmp_dir.path().join("app.log.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        for i in 0..260_000u32 {
+            writeln!(encoder, "2019-07-30 10:08:02.100 INFO  Main - value {}", i).unwrap();
+        }
+        fs::write(&path, encoder.finish().unwrap()).unwrap();
+        let (tx, rx) = unbounded();
+        get_text_file_info(&path, &TextStatsConfig::default(), &tx, None).unwrap();
+        let ticks: Vec<(usize, usize)> = rx
+            .try_iter()
+            .filter_map(|r| match r {
+                Ok(IndexingProgress::Progress { ticks }) => Some(ticks),
+                _ => None,
+            })
+            .collect();
+        // the progress is based on the compressed bytes, not on the decompressed ones
+        assert!(!ticks.is_empty());
+        assert!(ticks.iter().all(|(processed, total)| processed < total));
+    }
+
+    #[test]
+    fn test_text_file_info_stopped() {
+        let tmp_dir = TempDir::new("stats").expect("could not create temp dir");
+        let path = tmp_dir.path().join("app.log");
+        // the stop request is checked every 250000 lines (empty ones are fast)
+        fs::write(&path, "\n".repeat(250_000)).unwrap();
+        let (tx, rx) = unbounded();
+        let (shutdown_tx, shutdown_rx) = crossbeam_channel::bounded(1);
+        shutdown_tx.send(()).unwrap();
+        get_text_file_info(&path, &TextStatsConfig::default(), &tx, Some(shutdown_rx)).unwrap();
+        let events: Vec<&str> = rx
+            .try_iter()
+            .filter_map(|r| match r {
+                Ok(IndexingProgress::GotItem { .. }) => Some("item"),
+                Ok(IndexingProgress::Stopped) => Some("stopped"),
+                Ok(IndexingProgress::Finished) => Some("finished"),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["stopped"], events);
+    }
+}
@@ -12,6 +12,16 @@ export interface IIndexSection {
 	first_line: number;
 	last_line: number;
 }
+// udp addresses of a dlt export as pcapng
+export interface IPcapExportOptions {
+	// sources of ECUs like "ECU1=192.168.0.10:3490"
+	endpoints?: Array<string>;
+	// destination of all messages like "239.255.42.99:3490"
+	destination?: string;
+}
 export interface IFileSaveParams {
 	sections: Array<IIndexSection>;
+	pcap?: IPcapExportOptions;
+	// the session was indexed with a recordStart, the lines of its records are restored
+	grouped_records?: boolean;
 }
\ No newline at end of file
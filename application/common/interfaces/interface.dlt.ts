@@ -49,11 +49,31 @@ export interface LevelDistribution {
 	log_invalid: number;
 }
 
+export interface LostMessagesInfo {
+	lost: number;
+	gaps: number;
+	duplicates: number;
+	wraparounds: number;
+}
+
+export interface Lifecycle {
+	id: number;
+	ecu_id: string;
+	first_line: number;
+	last_line: number;
+	message_count: number;
+	start_uptime: number;
+	end_uptime: number;
+	start_time_us?: number;
+}
+
 export interface StatisticInfo {
 	app_ids: Array<[string, LevelDistribution]>;
 	context_ids: Array<[string, LevelDistribution]>;
 	ecu_ids: Array<[string, LevelDistribution]>;
 	contained_non_verbose: boolean;
+	lost_messages: Array<[string, LostMessagesInfo]>;
+	lifecycles: Array<Lifecycle>;
 }
 
 export interface IFibexConfig {
@@ -71,9 +71,11 @@ export class DLTConnectionController extends EventEmitter {
                 };
             }
             const socket: DLT.ISocketConfig = {
-                bind_addr: this._connection.bindingAddress,
-                port: this._connection.bindingPort,
-                multicast_addr: multicast,
+                endpoints: [{
+                    bind_addr: this._connection.bindingAddress,
+                    port: this._connection.bindingPort,
+                    multicast_addr: multicast,
+                }],
             };
             // Creating source alias
             const sourceName: string = `${this._connection.ecu}::${this._connection.bindingAddress}:${this._connection.bindingPort}`;